@@ -1122,7 +1122,10 @@ impl<'a> TryFrom<&'a mut AMitem> for &'a mut AMchange {
         if let Some(item) = Rc::get_mut(&mut item.0) {
             item.try_into()
         } else {
-            Err(Self::Error::Fail)
+            Err(Self::Error::InvalidValueType {
+                expected: "an item with no other references".to_string(),
+                unexpected: "a shared item".to_string(),
+            })
         }
     }
 }
@@ -1134,7 +1137,10 @@ impl<'a> TryFrom<&'a mut AMitem> for &'a mut AMdoc {
         if let Some(item) = Rc::get_mut(&mut item.0) {
             item.try_into()
         } else {
-            Err(Self::Error::Fail)
+            Err(Self::Error::InvalidValueType {
+                expected: "an item with no other references".to_string(),
+                unexpected: "a shared item".to_string(),
+            })
         }
     }
 }
@@ -1170,7 +1176,10 @@ impl<'a> TryFrom<&'a mut AMitem> for &'a mut AMsyncState {
         if let Some(item) = Rc::get_mut(&mut item.0) {
             item.try_into()
         } else {
-            Err(Self::Error::Fail)
+            Err(Self::Error::InvalidValueType {
+                expected: "an item with no other references".to_string(),
+                unexpected: "a shared item".to_string(),
+            })
         }
     }
 }
@@ -333,6 +333,9 @@ impl TryFrom<JS> for am::sync::State {
             in_flight,
             have_responded,
             their_capabilities,
+            stats: Default::default(),
+            ephemeral: None,
+            ephemeral_handler: None,
         })
     }
 }
@@ -435,6 +438,11 @@ impl TryFrom<JS> for am::sync::Message {
             .try_into()
             .map_err(error::BadSyncMessage::BadJSChanges)?;
 
+        let ephemeral = js_get(&value.0, "ephemeral")?
+            .0
+            .dyn_ref::<Uint8Array>()
+            .map(|payload| payload.to_vec());
+
         Ok(am::sync::Message {
             heads,
             need,
@@ -442,6 +450,7 @@ impl TryFrom<JS> for am::sync::Message {
             changes,
             supported_capabilities,
             version,
+            ephemeral,
         })
     }
 }
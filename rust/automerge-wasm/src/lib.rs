@@ -906,7 +906,11 @@ impl Automerge {
     #[wasm_bindgen(js_name = emptyChange)]
     pub fn empty_change(&mut self, message: Option<String>, time: Option<f64>) -> JsValue {
         let time = time.map(|f| f as i64);
-        let options = CommitOptions { message, time };
+        let options = CommitOptions {
+            message,
+            time,
+            extra_bytes: None,
+        };
         let hash = self.doc.empty_change(options);
         JsValue::from_str(&hex::encode(hash))
     }
@@ -1182,6 +1186,10 @@ pub fn decode_sync_message(msg: Uint8Array) -> Result<JsValue, error::BadSyncMes
         js_set(&obj, "supportedCapabilities", caps).unwrap();
     }
 
+    if let Some(payload) = msg.ephemeral {
+        js_set(&obj, "ephemeral", Uint8Array::from(payload.as_slice())).unwrap();
+    }
+
     Ok(obj)
 }
 
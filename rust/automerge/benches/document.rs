@@ -0,0 +1,73 @@
+use automerge::{
+    generators::DocumentGenerator, transaction::Transactable, Automerge, ReadDoc, ROOT,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+/// A document with `changes` changes made by several actors, see [`DocumentGenerator`]
+fn generated(changes: usize) -> Automerge {
+    DocumentGenerator::new().changes(changes).seed(1).generate()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let sizes = [100, 1_000, 5_000];
+
+    let mut group = c.benchmark_group("document");
+    for size in &sizes {
+        group.throughput(criterion::Throughput::Elements(*size as u64));
+        // generating the documents is much slower than the operations being measured, so only do
+        // it once for each size
+        let doc = generated(*size);
+        let bytes = doc.save();
+        let changes = doc
+            .get_changes(&[])
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let half = doc.fork_at(&[changes[changes.len() / 2].hash()]).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("save", size), &doc, |b, doc| {
+            b.iter(|| doc.save())
+        });
+        group.bench_with_input(BenchmarkId::new("load", size), &bytes, |b, bytes| {
+            b.iter(|| Automerge::load(bytes).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("apply changes", size),
+            &changes,
+            |b, changes| {
+                b.iter_batched(
+                    || changes.clone(),
+                    |changes| Automerge::new().apply_changes(changes).unwrap(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("merge", size), &doc, |b, doc| {
+            b.iter_batched(
+                || (half.clone(), doc.clone()),
+                |(mut half, mut doc)| half.merge(&mut doc).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("splice text", size), &doc, |b, doc| {
+            let (_, text) = doc.get(ROOT, "text").unwrap().unwrap();
+            b.iter_batched(
+                || doc.clone(),
+                |mut doc| {
+                    let mut tx = doc.transaction();
+                    let middle = tx.length(&text) / 2;
+                    for i in 0..100 {
+                        tx.splice_text(&text, middle + i, 0, "a").unwrap();
+                    }
+                    tx.commit();
+                    doc
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
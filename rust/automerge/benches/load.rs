@@ -0,0 +1,65 @@
+use automerge::{transaction::Transactable, AutoCommit, Automerge, ObjType, ROOT};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// A document with `n` changes made by several actors who regularly merge each other's work, so
+/// the history has many concurrent branches
+fn concurrent_edits(n: usize) -> Vec<u8> {
+    let mut base = AutoCommit::new();
+    let text = base.put_object(ROOT, "text", ObjType::Text).unwrap();
+    base.commit();
+    let mut docs = (0..4).map(|_| base.fork()).collect::<Vec<_>>();
+    for i in 0..n {
+        let actor = i % docs.len();
+        if i % 5 == 0 {
+            let other = (actor + 1) % docs.len();
+            let mut other = docs[other].fork();
+            docs[actor].merge(&mut other).unwrap();
+        }
+        let doc = &mut docs[actor];
+        doc.splice_text(&text, 0, 0, "abc").unwrap();
+        doc.put(ROOT, format!("key{}", i % 100), i as i64).unwrap();
+        doc.commit();
+    }
+    for doc in docs.iter_mut() {
+        base.merge(doc).unwrap();
+    }
+    base.save()
+}
+
+/// A document with `n` changes made one after the other by a single actor
+fn sequential_edits(n: usize) -> Vec<u8> {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    for i in 0..n {
+        doc.splice_text(&text, 0, 0, "abc").unwrap();
+        doc.put(ROOT, format!("key{}", i % 100), i as i64).unwrap();
+        doc.commit();
+    }
+    doc.save()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let sizes = [100, 1_000, 5_000];
+
+    let mut group = c.benchmark_group("load");
+    for size in &sizes {
+        group.throughput(criterion::Throughput::Elements(*size as u64));
+        // generating the documents is much slower than loading them, so only do it once
+        let sequential = sequential_edits(*size);
+        group.bench_with_input(
+            BenchmarkId::new("sequential changes", size),
+            &sequential,
+            |b, bytes| b.iter(|| Automerge::load(bytes).unwrap()),
+        );
+        let concurrent = concurrent_edits(*size);
+        group.bench_with_input(
+            BenchmarkId::new("concurrent changes", size),
+            &concurrent,
+            |b, bytes| b.iter(|| Automerge::load(bytes).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+use crate::ChangeHash;
+
+/// The latest change applied to a document by a single actor, returned by
+/// [`crate::Automerge::actor_frontier()`]
+///
+/// The changes made by each actor form a sequence numbered from 1, so `seq` is also the number
+/// of changes from the actor which have been applied. Comparing the frontiers of two documents
+/// tells a replication layer which actors it is behind on, and the missing changes can be
+/// requested with [`crate::Automerge::get_changes_by_actor()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActorFrontier {
+    /// The sequence number of the latest change from this actor
+    pub seq: u64,
+    /// The largest op counter in the latest change from this actor
+    pub max_op: u64,
+    /// The hash of the latest change from this actor
+    pub hash: ChangeHash,
+}
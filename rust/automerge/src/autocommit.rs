@@ -1,19 +1,28 @@
+use std::collections::HashMap;
 use std::ops::RangeBounds;
+use std::time::SystemTime;
 
 use crate::automerge::SaveOptions;
 use crate::automerge::{current_state, diff};
 use crate::exid::ExId;
-use crate::iter::{Keys, ListRange, MapRange, Values};
+use crate::iter::{History, Keys, ListRange, MapRange, Values};
 use crate::marks::{ExpandMark, Mark, MarkSet};
-use crate::patches::{PatchLog, TextRepresentation};
+use crate::patches::{PatchBatch, PatchLog, TextRepresentation};
 use crate::sync::SyncDoc;
 use crate::transaction::{CommitOptions, Transactable};
 use crate::types::Clock;
-use crate::{hydrate, OnPartialLoad};
-use crate::{sync, ObjType, Parents, Patch, ReadDoc, ScalarValue};
 use crate::{
-    transaction::TransactionInner, ActorId, Automerge, AutomergeError, Change, ChangeHash, Cursor,
-    Prop, Value,
+    hydrate, ActorFrontier, AutoSerdePaths, ChangeApplied, ChangeSkipped, CounterIncrement,
+    DebugBundle, DocumentStats, DocumentSummary, HeadMetadata, MergePreview, ObjectStats,
+    OnPartialLoad, ResolvedOp, TextWithConflicts, WriteLimits,
+};
+use crate::{
+    sync, ApplyProgress, ObjType, OpObserver, Parents, Patch, ReadDoc, ReadGuard, ScalarValue,
+    Signer, Subtree, Verifier,
+};
+use crate::{
+    transaction::TransactionInner, ActorId, Automerge, AutomergeError, Change, ChangeCache,
+    ChangeHash, Cursor, DeletedElement, Prop, Value,
 };
 use crate::{LoadOptions, VerificationMode};
 
@@ -28,17 +37,29 @@ use crate::{LoadOptions, VerificationMode};
 /// [`Self::merge()`].
 ///
 /// If you have a document you want to split into two concurrent threads of execution you can use
-/// [`Self::fork()`]. If you want to split a document from ealier in its history you can use
+/// [`Self::fork()`]. If you want to split a document from earlier in its history you can use
 /// [`Self::fork_at()`].
 ///
 /// ## Reading values
 ///
 /// [`Self`] implements [`ReadDoc`], which provides methods for reading values from the document.
+/// To read the document as it was at some heads use [`Self::at()`], which returns a read only
+/// view implementing [`ReadDoc`], or [`Self::fork_at()`] for an owned copy, see
+/// [`Automerge`](Automerge#reading-historical-state).
 ///
 /// ## Modifying a document
 ///
 /// This type implements [`Transactable`] directly, so you can modify it using methods from [`Transactable`].
 ///
+/// The operations are not committed one at a time. The first modification opens a transaction and
+/// every subsequent modification is added to it, so a batch of edits becomes a single change
+/// rather than one change per operation. The transaction is committed when you call
+/// [`Self::commit()`] or [`Self::commit_with()`], or implicitly by any method which needs the
+/// document's history to be complete, such as [`Self::save()`], [`Self::get_heads()`],
+/// [`Self::merge()`], [`Self::fork()`] and the methods of [`Self::sync()`]. These methods take
+/// `&mut self` for this reason. Use [`Self::pending_ops()`] to see how many operations are
+/// waiting to be committed and [`Self::rollback()`] to discard them.
+///
 /// ## Synchronization
 ///
 /// To synchronise call [`Self::sync()`] which returns an implementation of [`SyncDoc`]
@@ -61,6 +82,7 @@ pub struct AutoCommit {
     diff_cache: Option<(OpRange, Vec<Patch>)>,
     save_cursor: Vec<ChangeHash>,
     isolation: Option<Vec<ChangeHash>>,
+    patch_batch_seq: u64,
 }
 
 /// An autocommit document with an inactive [`PatchLog`]
@@ -76,6 +98,7 @@ impl Default for AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            patch_batch_seq: 0,
         }
     }
 }
@@ -85,6 +108,40 @@ impl AutoCommit {
         AutoCommit::default()
     }
 
+    /// Collect the state of this document for a bug report, see [`Automerge::debug_bundle()`]
+    ///
+    /// The bundle also includes the patches which have not yet been returned by
+    /// [`Self::diff_incremental()`], formatted for reading.
+    pub fn debug_bundle(&mut self) -> DebugBundle {
+        self.ensure_transaction_closed();
+        let heads = self.doc.get_heads();
+        let patches = self
+            .doc
+            .diff(&self.diff_cursor, &heads, self.patch_log.text_rep())
+            .iter()
+            .map(|patch| format!("{:?}", patch))
+            .collect();
+        DebugBundle {
+            patches,
+            ..self.doc.debug_bundle()
+        }
+    }
+
+    /// The encoded [`Self::debug_bundle()`], see [`Automerge::export_debug_bundle()`]
+    pub fn export_debug_bundle(&mut self) -> Vec<u8> {
+        self.debug_bundle().to_bytes()
+    }
+
+    /// Create a new document containing `value` in a single change, see
+    /// [`Automerge::from_json()`]
+    pub fn from_json(value: &hydrate::Value) -> Result<Self, AutomergeError> {
+        let doc = Automerge::from_json(value)?;
+        Ok(Self {
+            doc,
+            ..Default::default()
+        })
+    }
+
     pub fn load(data: &[u8]) -> Result<Self, AutomergeError> {
         let doc = Automerge::load(data)?;
         Ok(Self {
@@ -95,6 +152,7 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            patch_batch_seq: 0,
         })
     }
 
@@ -108,6 +166,7 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            patch_batch_seq: 0,
         })
     }
 
@@ -138,6 +197,7 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            patch_batch_seq: 0,
         })
     }
 
@@ -145,7 +205,9 @@ impl AutoCommit {
     /// longer indexes changes to the document.
     pub fn reset_diff_cursor(&mut self) {
         self.ensure_transaction_closed();
+        let obj_types = self.patch_log.obj_types().map(|t| t.to_vec());
         self.patch_log = PatchLog::inactive(TextRepresentation::default());
+        self.patch_log.set_obj_types(obj_types);
         self.diff_cursor = Vec::new();
     }
 
@@ -225,7 +287,7 @@ impl AutoCommit {
         {
             self.patch_log.make_patches(&self.doc)
         } else if range.before().is_empty() && range.after() == heads {
-            let mut patch_log = PatchLog::active(self.patch_log.text_rep());
+            let mut patch_log = self.patch_log.fresh(true);
             // This if statement is only active if the current heads are the same as `after`
             // so we don't need to tell the patch log to target a specific heads and consequently
             // it wll be able to generate patches very fast as it doesn't need to make any clocks
@@ -235,7 +297,7 @@ impl AutoCommit {
         } else {
             let before_clock = self.doc.clock_at(range.before());
             let after_clock = self.doc.clock_at(range.after());
-            let mut patch_log = PatchLog::active(self.patch_log.text_rep());
+            let mut patch_log = self.patch_log.fresh(true);
             patch_log.heads = Some(range.after().to_vec());
             diff::log_diff(&self.doc, &before_clock, &after_clock, &mut patch_log);
             patch_log.make_patches(&self.doc)
@@ -263,29 +325,58 @@ impl AutoCommit {
         patches
     }
 
+    /// Like [`Self::diff_incremental()`] but wraps the patches in a [`PatchBatch`]
+    ///
+    /// Each call returns a batch with a sequence number one greater than the previous call, along
+    /// with the diff cursor before the call and the heads of the document after it. A consumer
+    /// can check [`PatchBatch::follows()`] against the last batch it saw to detect gaps or
+    /// re-ordering. The sequence number is not persisted and starts again from `1` for a loaded or
+    /// forked document.
+    pub fn diff_incremental_batch(&mut self) -> PatchBatch {
+        self.ensure_transaction_closed();
+        let before = self.diff_cursor();
+        let after = self.doc.get_heads();
+        let patches = self.diff_incremental();
+        self.patch_batch_seq += 1;
+        PatchBatch {
+            seq: self.patch_batch_seq,
+            before,
+            after,
+            patches,
+        }
+    }
+
     pub fn fork(&mut self) -> Self {
         self.ensure_transaction_closed();
         Self {
             doc: self.doc.fork(),
             transaction: self.transaction.clone(),
-            patch_log: PatchLog::inactive(self.patch_log.text_rep()),
+            patch_log: self.patch_log.fresh(false),
             diff_cursor: vec![],
             diff_cache: None,
             save_cursor: vec![],
             isolation: None,
+            patch_batch_seq: 0,
         }
     }
 
+    /// Fork this document at the current point for use by `actor`, see
+    /// [`Automerge::fork_with_actor()`]
+    pub fn fork_with_actor(&mut self, actor: ActorId) -> Self {
+        self.fork().with_actor(actor)
+    }
+
     pub fn fork_at(&mut self, heads: &[ChangeHash]) -> Result<Self, AutomergeError> {
         self.ensure_transaction_closed();
         Ok(Self {
             doc: self.doc.fork_at(heads)?,
             transaction: self.transaction.clone(),
-            patch_log: PatchLog::inactive(self.patch_log.text_rep()),
+            patch_log: self.patch_log.fresh(false),
             diff_cursor: vec![],
             diff_cache: None,
             save_cursor: vec![],
             isolation: None,
+            patch_batch_seq: 0,
         })
     }
 
@@ -328,18 +419,41 @@ impl AutoCommit {
         if self.transaction.is_none() {
             let args = self.doc.transaction_args(self.isolation.as_deref());
             let inner = TransactionInner::new(args);
-            self.transaction = Some((self.patch_log.branch(), inner))
+            let mut patch_log = self.patch_log.branch();
+            if self.doc.has_op_observer() {
+                patch_log.set_active(true);
+            }
+            self.transaction = Some((patch_log, inner))
         }
     }
 
     fn ensure_transaction_closed(&mut self) {
         if let Some((patch_log, tx)) = self.transaction.take() {
+            self.close_transaction(patch_log, tx, CommitOptions::default());
+        }
+    }
+
+    fn close_transaction(
+        &mut self,
+        mut patch_log: PatchLog,
+        tx: TransactionInner,
+        options: CommitOptions,
+    ) -> Option<ChangeHash> {
+        let hash = tx.commit(&mut self.doc, options);
+        if self.doc.has_op_observer() {
+            // the observer sees every change, whatever the patch log is filtered to
+            let obj_types = patch_log.obj_types().map(<[_]>::to_vec);
+            patch_log.set_obj_types(None);
+            self.doc.notify_op_observer(&mut patch_log, true);
+            patch_log.set_obj_types(obj_types);
+        }
+        if self.patch_log.is_active() {
             self.patch_log.merge(patch_log);
-            let hash = tx.commit(&mut self.doc, None, None);
-            if self.isolation.is_some() && hash.is_some() {
-                self.isolation = hash.map(|h| vec![h])
-            }
         }
+        if self.isolation.is_some() && hash.is_some() {
+            self.isolation = hash.map(|h| vec![h])
+        }
+        hash
     }
 
     /// Load an incremental save of a document.
@@ -360,6 +474,24 @@ impl AutoCommit {
         }
     }
 
+    /// Load a document saved with [`Self::save_encrypted()`], and any changes saved with
+    /// [`Self::save_incremental_encrypted()`] appended to it, see [`Automerge::load_encrypted()`]
+    #[cfg(feature = "encryption")]
+    pub fn load_encrypted(data: &[u8], key: &[u8; 32]) -> Result<Self, AutomergeError> {
+        Self::load(&crate::encryption::decrypt(key, data)?)
+    }
+
+    /// Load data written by [`Self::save_encrypted()`] or [`Self::save_incremental_encrypted()`]
+    /// into this document using `key`, see [`Self::load_incremental()`]
+    #[cfg(feature = "encryption")]
+    pub fn load_incremental_encrypted(
+        &mut self,
+        data: &[u8],
+        key: &[u8; 32],
+    ) -> Result<usize, AutomergeError> {
+        self.load_incremental(&crate::encryption::decrypt(key, data)?)
+    }
+
     pub fn apply_changes(
         &mut self,
         changes: impl IntoIterator<Item = Change>,
@@ -374,6 +506,39 @@ impl AutoCommit {
         }
     }
 
+    /// Like [`Self::apply_changes()`] but call `on_progress` after each change is dealt with, see
+    /// [`Automerge::apply_changes_with_progress()`]
+    pub fn apply_changes_with_progress<F: FnMut(ApplyProgress)>(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+        mut on_progress: F,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_closed();
+        if self.isolation.is_some() {
+            self.doc
+                .apply_changes_with(changes, &mut PatchLog::null(), &mut on_progress)
+        } else {
+            self.doc
+                .apply_changes_with(changes, &mut self.patch_log, &mut on_progress)
+        }
+    }
+
+    /// Like [`Self::apply_changes()`] but either all of `changes` are applied or none of them are,
+    /// see [`Automerge::apply_changes_atomic()`]
+    pub fn apply_changes_atomic(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_closed();
+        if self.isolation.is_some() {
+            self.doc
+                .apply_changes_atomic_log_patches(changes, &mut PatchLog::null())
+        } else {
+            self.doc
+                .apply_changes_atomic_log_patches(changes, &mut self.patch_log)
+        }
+    }
+
     /// Takes all the changes in `other` which are not in `self` and applies them
     pub fn merge(&mut self, other: &mut AutoCommit) -> Result<Vec<ChangeHash>, AutomergeError> {
         self.ensure_transaction_closed();
@@ -387,6 +552,34 @@ impl AutoCommit {
         }
     }
 
+    /// Like [`Self::merge()`] but consumes `other`, see [`Automerge::merge_owned()`]
+    pub fn merge_owned(
+        &mut self,
+        mut other: AutoCommit,
+    ) -> Result<Vec<ChangeHash>, AutomergeError> {
+        self.ensure_transaction_closed();
+        other.ensure_transaction_closed();
+        if self.isolation.is_some() {
+            self.doc
+                .merge_owned_and_log_patches(other.doc, &mut PatchLog::null())
+        } else {
+            self.doc
+                .merge_owned_and_log_patches(other.doc, &mut self.patch_log)
+        }
+    }
+
+    /// Work out what [`Self::merge()`] would do with `other` without changing either document, see
+    /// [`Automerge::merge_preview()`]
+    pub fn merge_preview(
+        &mut self,
+        other: &mut AutoCommit,
+    ) -> Result<MergePreview, AutomergeError> {
+        self.ensure_transaction_closed();
+        other.ensure_transaction_closed();
+        self.doc
+            .merge_preview(&other.doc, self.patch_log.text_rep())
+    }
+
     /// Save the entirety of this document in a compact form.
     pub fn save(&mut self) -> Vec<u8> {
         self.save_with_options(SaveOptions::default())
@@ -401,6 +594,46 @@ impl AutoCommit {
         bytes
     }
 
+    /// Save the document to `writer`, see [`Automerge::save_to_writer()`]
+    pub fn save_to_writer<W: std::io::Write>(&mut self, writer: &mut W) -> std::io::Result<usize> {
+        self.save_with_options_to_writer(SaveOptions::default(), writer)
+    }
+
+    /// Save the document to `writer` with the given options, see
+    /// [`Automerge::save_to_writer()`]
+    pub fn save_with_options_to_writer<W: std::io::Write>(
+        &mut self,
+        options: SaveOptions,
+        writer: &mut W,
+    ) -> std::io::Result<usize> {
+        self.ensure_transaction_closed();
+        let written = self.doc.save_with_options_to_writer(options, writer)?;
+        if written > 0 {
+            self.save_cursor = self.doc.get_heads()
+        }
+        Ok(written)
+    }
+
+    /// Save the entirety of this document and encrypt it with `key`, see
+    /// [`Automerge::save_encrypted()`]
+    #[cfg(feature = "encryption")]
+    pub fn save_encrypted(&mut self, key: &[u8; 32]) -> Vec<u8> {
+        let bytes = self.save();
+        crate::encryption::encrypt(key, &bytes)
+    }
+
+    /// Save the changes since the last save, as [`Self::save_incremental()`] does, and encrypt
+    /// them with `key`
+    ///
+    /// The output can be appended to the output of [`Self::save_encrypted()`] and the whole
+    /// loaded with [`Self::load_encrypted()`]. Returns an empty vector if there are no new
+    /// changes.
+    #[cfg(feature = "encryption")]
+    pub fn save_incremental_encrypted(&mut self, key: &[u8; 32]) -> Vec<u8> {
+        let bytes = self.save_incremental();
+        crate::encryption::encrypt(key, &bytes)
+    }
+
     /// Save the document and attempt to load it before returning - slow!
     pub fn save_and_verify(&mut self) -> Result<Vec<u8>, AutomergeError> {
         let bytes = self.save();
@@ -431,12 +664,68 @@ impl AutoCommit {
         bytes
     }
 
+    /// Save the changes since the last save, as [`Self::save_incremental()`] does, but only if
+    /// they take up more than `bytes` bytes
+    ///
+    /// Returns `None` and leaves the last save where it is if there are `bytes` bytes of
+    /// unsaved changes or fewer. This makes it possible to flush to storage once enough changes
+    /// have built up without encoding the changes after every commit just to measure them.
+    pub fn save_incremental_if_larger_than(&mut self, bytes: usize) -> Option<Vec<u8>> {
+        self.ensure_transaction_closed();
+        if self.unsaved_bytes_estimate() > bytes {
+            Some(self.save_incremental())
+        } else {
+            None
+        }
+    }
+
+    /// The number of bytes [`Self::save_incremental()`] would currently return
+    ///
+    /// Committed changes are already encoded, so this just adds up their sizes. Operations in
+    /// the current transaction are not counted as they have not been encoded yet.
+    pub fn unsaved_bytes_estimate(&self) -> usize {
+        self.doc
+            .get_changes(&self.save_cursor)
+            .iter()
+            .map(|c| c.raw_bytes().len())
+            .sum()
+    }
+
+    /// Write the changes since the last save to `writer` one change at a time, see
+    /// [`Self::save_incremental()`]
+    ///
+    /// The last save is only updated if writing succeeds, so if this returns an error the same
+    /// changes will be written by the next incremental save.
+    pub fn save_incremental_to_writer<W: std::io::Write>(
+        &mut self,
+        writer: &mut W,
+    ) -> std::io::Result<usize> {
+        self.ensure_transaction_closed();
+        let written = self.doc.save_after_to_writer(&self.save_cursor, writer)?;
+        if written > 0 {
+            self.save_cursor = self.doc.get_heads()
+        }
+        Ok(written)
+    }
+
     /// Save everything which is not a (transitive) dependency of `heads`
     pub fn save_after(&mut self, heads: &[ChangeHash]) -> Vec<u8> {
         self.ensure_transaction_closed();
         self.doc.save_after(heads)
     }
 
+    /// Save the changes which are waiting for their dependencies, see
+    /// [`Automerge::export_queue()`]
+    pub fn export_queue(&self) -> Vec<u8> {
+        self.doc.export_queue()
+    }
+
+    /// Restore changes saved with [`Self::export_queue()`], see [`Automerge::import_queue()`]
+    pub fn import_queue(&mut self, data: &[u8]) -> Result<usize, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.import_queue(data)
+    }
+
     pub fn get_missing_deps(&mut self, heads: &[ChangeHash]) -> Vec<ChangeHash> {
         self.ensure_transaction_closed();
         self.doc.get_missing_deps(heads)
@@ -453,11 +742,42 @@ impl AutoCommit {
         self.doc.get_changes(have_deps)
     }
 
+    /// Create a new document containing the current state of this one in a single change, see
+    /// [`Automerge::compact()`]
+    pub fn compact(&mut self) -> Result<Self, AutomergeError> {
+        self.ensure_transaction_closed();
+        Ok(Self {
+            doc: self.doc.compact()?,
+            ..Default::default()
+        })
+    }
+
+    /// Iterate over the changes in this document, see [`Automerge::history()`]
+    pub fn history(&mut self) -> History<'_> {
+        self.ensure_transaction_closed();
+        self.doc.history()
+    }
+
     pub fn get_change_by_hash(&mut self, hash: &ChangeHash) -> Option<&Change> {
         self.ensure_transaction_closed();
         self.doc.get_change_by_hash(hash)
     }
 
+    /// Whether the change with `hash` has been applied to this document, see
+    /// [`Automerge::has_change()`]
+    pub fn has_change(&self, hash: &ChangeHash) -> bool {
+        self.doc.has_change(hash)
+    }
+
+    /// The hashes in `hashes` which do not correspond to a change in this document, see
+    /// [`Automerge::filter_unknown_hashes()`]
+    pub fn filter_unknown_hashes<I: IntoIterator<Item = ChangeHash>>(
+        &self,
+        hashes: I,
+    ) -> Vec<ChangeHash> {
+        self.doc.filter_unknown_hashes(hashes)
+    }
+
     /// Get changes in `other` that are not in `self`
     pub fn get_changes_added<'a>(&mut self, other: &'a mut Self) -> Vec<&'a Change> {
         self.ensure_transaction_closed();
@@ -492,6 +812,33 @@ impl AutoCommit {
         self.doc.visualise_optree(objects)
     }
 
+    /// Get a [`ReadGuard`] which reads this document as at [`Self::get_heads()`]
+    ///
+    /// This closes the transaction first, if one is in progress.
+    pub fn read_guard(&mut self) -> ReadGuard<'_, Self> {
+        let heads = self.get_heads();
+        ReadGuard::new(self, heads)
+    }
+
+    /// Compare two changes by their max op and actor ID, see [`Automerge::cmp_changes()`]
+    pub fn cmp_changes(
+        &mut self,
+        left: &ChangeHash,
+        right: &ChangeHash,
+    ) -> Result<std::cmp::Ordering, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.cmp_changes(left, right)
+    }
+
+    /// Get a [`ReadGuard`] which reads this document as at `heads`, see [`Automerge::at()`]
+    ///
+    /// This closes the transaction first, if one is in progress.
+    pub fn at(&mut self, heads: &[ChangeHash]) -> Result<ReadGuard<'_, Self>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.at(heads)?;
+        Ok(ReadGuard::new(self, heads.to_vec()))
+    }
+
     /// Get the current heads of the document.
     ///
     /// This closes the transaction first, if one is in progress.
@@ -517,6 +864,24 @@ impl AutoCommit {
         self
     }
 
+    /// Only generate patches for objects of the given types, or for all objects if `None`
+    ///
+    /// This applies to the patches returned by [`Self::diff()`] and [`Self::diff_incremental()`],
+    /// see [`PatchLog::with_obj_types()`] for details.
+    pub fn set_patch_obj_types(&mut self, obj_types: Option<&[ObjType]>) {
+        self.diff_cache = None;
+        self.patch_log.set_obj_types(obj_types.map(|t| t.to_vec()))
+    }
+
+    pub fn get_patch_obj_types(&self) -> Option<&[ObjType]> {
+        self.patch_log.obj_types()
+    }
+
+    pub fn with_patch_obj_types(mut self, obj_types: &[ObjType]) -> Self {
+        self.set_patch_obj_types(Some(obj_types));
+        self
+    }
+
     /// Commit any uncommitted changes
     ///
     /// Returns [`None`] if there were no operations to commit
@@ -545,12 +910,7 @@ impl AutoCommit {
         // ensure that even no changes triggers a change
         self.ensure_transaction_open();
         let (patch_log, tx) = self.transaction.take().unwrap();
-        self.patch_log.merge(patch_log);
-        let hash = tx.commit(&mut self.doc, options.message, options.time);
-        if self.isolation.is_some() && hash.is_some() {
-            self.isolation = hash.map(|h| vec![h])
-        }
-        hash
+        self.close_transaction(patch_log, tx, options)
     }
 
     /// Remove any changes that have been made in the current transaction from the document
@@ -561,6 +921,51 @@ impl AutoCommit {
             .unwrap_or(0)
     }
 
+    /// Serialize the operations of the current transaction without committing them
+    ///
+    /// This allows an application to persist unsaved edits, for example across a restart,
+    /// without committing a half finished change into the document's history. The draft can be
+    /// restored with [`Self::load_draft()`] onto a document in the same state as this one.
+    /// Returns `None` if there are no pending operations.
+    pub fn save_draft(&self) -> Option<Vec<u8>> {
+        let (_, tx) = self.transaction.as_ref()?;
+        if tx.pending_ops() == 0 {
+            return None;
+        }
+        Some(tx.clone().export(self.doc.osd()).raw_bytes().to_vec())
+    }
+
+    /// Restore a draft created by [`Self::save_draft()`] as the current transaction
+    ///
+    /// Any outstanding transaction is committed first. The operations of the draft are not
+    /// committed, so they can be continued, committed or rolled back as though they had been
+    /// made in this session. The actor ID of this document is set to the actor ID of the draft.
+    ///
+    /// Returns the number of operations restored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidDraft`] if the draft was not saved from the current
+    /// state of this document, for example because changes have been applied since.
+    pub fn load_draft(&mut self, draft: &[u8]) -> Result<usize, AutomergeError> {
+        let change = Change::from_bytes(draft.to_vec())?;
+        self.ensure_transaction_closed();
+        let actor = self.doc.get_actor().clone();
+        self.doc.set_actor(change.actor_id().clone());
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        let restored = if tx.matches_draft(&change) {
+            tx.restore_draft(&mut self.doc, patch_log, &change)
+        } else {
+            Err(AutomergeError::InvalidDraft)
+        };
+        if restored.is_err() {
+            self.rollback();
+            self.doc.set_actor(actor);
+        }
+        restored
+    }
+
     /// Generate an empty change
     ///
     /// The main reason to do this is if you wish to create a "merge commit" which has all the
@@ -573,7 +978,7 @@ impl AutoCommit {
     pub fn empty_change(&mut self, options: CommitOptions) -> ChangeHash {
         self.ensure_transaction_closed();
         let args = self.doc.transaction_args(None);
-        TransactionInner::empty(&mut self.doc, args, options.message, options.time)
+        TransactionInner::empty(&mut self.doc, args, options)
     }
 
     /// An implementation of [`crate::sync::SyncDoc`] for this autocommit
@@ -585,6 +990,256 @@ impl AutoCommit {
         SyncWrapper { inner: self }
     }
 
+    /// Set a function to be called after each change received from another peer is applied, see
+    /// [`Automerge::set_change_hook()`]
+    pub fn set_change_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&ChangeApplied) + Send + Sync + 'static,
+    {
+        self.doc.set_change_hook(hook)
+    }
+
+    /// Remove the hook set with [`Self::set_change_hook()`]
+    pub fn clear_change_hook(&mut self) {
+        self.doc.clear_change_hook()
+    }
+
+    /// Notify `observer` of every change to the current state of this document, see
+    /// [`Automerge::set_op_observer()`]
+    ///
+    /// Changes made through this document are reported when the transaction they are in is
+    /// committed, which happens implicitly before most operations other than reads.
+    pub fn set_op_observer<O: OpObserver + 'static>(&mut self, observer: O) {
+        self.doc.set_op_observer(observer)
+    }
+
+    /// Remove the observer set with [`Self::set_op_observer()`]
+    pub fn clear_op_observer(&mut self) {
+        self.doc.clear_op_observer()
+    }
+
+    /// Sign every change made on this document with `signer`, see [`Automerge::set_signer()`]
+    pub fn set_signer<S: Signer + 'static>(&mut self, signer: S) {
+        self.doc.set_signer(signer)
+    }
+
+    /// Remove the signer set with [`Self::set_signer()`]
+    pub fn clear_signer(&mut self) {
+        self.doc.clear_signer()
+    }
+
+    /// Check the signature of every change received by this document with `verifier`, see
+    /// [`Automerge::set_verifier()`]
+    pub fn set_verifier<V: Verifier + 'static>(&mut self, verifier: V) {
+        self.doc.set_verifier(verifier)
+    }
+
+    /// Remove the verifier set with [`Self::set_verifier()`]
+    pub fn clear_verifier(&mut self) {
+        self.doc.clear_verifier()
+    }
+
+    /// Set a function to be called for each received change which is not applied, see
+    /// [`Automerge::set_skipped_change_hook()`]
+    pub fn set_skipped_change_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&ChangeSkipped) + Send + Sync + 'static,
+    {
+        self.doc.set_skipped_change_hook(hook)
+    }
+
+    /// Remove the hook set with [`Self::set_skipped_change_hook()`]
+    pub fn clear_skipped_change_hook(&mut self) {
+        self.doc.clear_skipped_change_hook()
+    }
+
+    /// Make this document read only, see [`Automerge::freeze()`]
+    ///
+    /// This closes the transaction first, if one is in progress.
+    pub fn freeze(&mut self) {
+        self.ensure_transaction_closed();
+        self.doc.freeze()
+    }
+
+    /// Allow changes to a document frozen with [`Self::freeze()`] again
+    pub fn thaw(&mut self) {
+        self.doc.thaw()
+    }
+
+    /// Whether this document has been frozen with [`Self::freeze()`]
+    pub fn is_frozen(&self) -> bool {
+        self.doc.is_frozen()
+    }
+
+    /// Limit what local operations may write to this document, see
+    /// [`Automerge::set_write_limits()`]
+    pub fn set_write_limits(&mut self, limits: WriteLimits) {
+        self.doc.set_write_limits(limits)
+    }
+
+    /// The limits set with [`Self::set_write_limits()`]
+    pub fn write_limits(&self) -> &WriteLimits {
+        self.doc.write_limits()
+    }
+
+    /// Check every op of the changes applied to this document, see
+    /// [`Automerge::set_strict_validation()`]
+    pub fn set_strict_validation(&mut self, enabled: bool) {
+        self.doc.set_strict_validation(enabled)
+    }
+
+    /// Whether strict validation was enabled with [`Self::set_strict_validation()`]
+    pub fn strict_validation(&self) -> bool {
+        self.doc.strict_validation()
+    }
+
+    /// Set a function which provides the timestamp of each local change, see
+    /// [`Automerge::set_timestamp_source()`]
+    pub fn set_timestamp_source<F>(&mut self, source: F)
+    where
+        F: Fn() -> i64 + Send + Sync + 'static,
+    {
+        self.doc.set_timestamp_source(source)
+    }
+
+    /// Remove the function set with [`Self::set_timestamp_source()`]
+    pub fn clear_timestamp_source(&mut self) {
+        self.doc.clear_timestamp_source()
+    }
+
+    /// Whether the change `ancestor` is `descendant` or one of its transitive dependencies, see
+    /// [`Automerge::is_ancestor()`]
+    pub fn is_ancestor(
+        &mut self,
+        ancestor: &ChangeHash,
+        descendant: &ChangeHash,
+    ) -> Result<bool, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.is_ancestor(ancestor, descendant)
+    }
+
+    /// The heads of the latest version of the document which every one of `hashes` includes,
+    /// see [`Automerge::common_ancestors()`]
+    pub fn common_ancestors(
+        &mut self,
+        hashes: &[ChangeHash],
+    ) -> Result<Vec<ChangeHash>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.common_ancestors(hashes)
+    }
+
+    /// The latest change applied from each actor, see [`Automerge::actor_frontier()`]
+    pub fn actor_frontier(&mut self) -> HashMap<ActorId, ActorFrontier> {
+        self.ensure_transaction_closed();
+        self.doc.actor_frontier()
+    }
+
+    /// Get the changes made by `actor` after `after_seq`, see
+    /// [`Automerge::get_changes_by_actor()`]
+    pub fn get_changes_by_actor(&mut self, actor: &ActorId, after_seq: u64) -> Vec<&Change> {
+        self.ensure_transaction_closed();
+        self.doc.get_changes_by_actor(actor, after_seq)
+    }
+
+    /// Read the state this document would have after applying `changes`, without modifying it,
+    /// see [`Automerge::with_changes()`]
+    pub fn with_changes<F, R>(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+        f: F,
+    ) -> Result<R, AutomergeError>
+    where
+        F: FnOnce(&Automerge) -> R,
+    {
+        self.ensure_transaction_closed();
+        self.doc.with_changes(changes, f)
+    }
+
+    /// Get the operations of the change with hash `hash`, see [`Automerge::change_ops()`]
+    pub fn change_ops(&mut self, hash: &ChangeHash) -> Result<Vec<ResolvedOp>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.change_ops(hash)
+    }
+
+    /// Get the merged content of the text object `obj` along with the regions where concurrent
+    /// insertions met, see [`Automerge::text_with_conflicts()`]
+    pub fn text_with_conflicts<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+    ) -> Result<TextWithConflicts, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.text_with_conflicts(obj)
+    }
+
+    /// Get the elements of the sequence `obj` which existed at `since` and have been deleted since,
+    /// see [`Automerge::deleted_elements()`]
+    pub fn deleted_elements<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        since: &[ChangeHash],
+    ) -> Result<Vec<DeletedElement<'_>>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.deleted_elements(obj, since)
+    }
+
+    /// Share the data of the changes in this document with other documents using `cache`, see
+    /// [`Automerge::intern_changes()`]
+    pub fn intern_changes(&mut self, cache: &ChangeCache) {
+        self.ensure_transaction_closed();
+        self.doc.intern_changes(cache)
+    }
+
+    /// Count the live values, tombstones, conflicts and total operations stored for `obj`, see
+    /// [`Automerge::object_stats()`]
+    pub fn object_stats<O: AsRef<ExId>>(&mut self, obj: O) -> Result<ObjectStats, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.object_stats(obj)
+    }
+
+    /// What the most recent search of the op tree did, see [`Automerge::last_query_stats()`]
+    ///
+    /// Unlike most methods this does not commit the current transaction, so it reports on the
+    /// last operation even if that operation is still pending.
+    #[cfg(feature = "query-tracing")]
+    pub fn last_query_stats(&self) -> Option<crate::QueryStats> {
+        self.doc.last_query_stats()
+    }
+
+    /// The size of this document and the approximate memory used by its internal structures, see
+    /// [`Automerge::stats()`]
+    pub fn stats(&mut self) -> DocumentStats {
+        self.ensure_transaction_closed();
+        self.doc.stats()
+    }
+
+    /// Every key or list element in the document which currently has more than one value, see
+    /// [`Automerge::conflicts()`]
+    pub fn conflicts(&mut self) -> impl Iterator<Item = (ExId, Prop, Vec<(Value<'_>, ExId)>)> + '_ {
+        self.ensure_transaction_closed();
+        self.doc.conflicts()
+    }
+
+    /// Every increment of the counter at `prop` in `obj`, see [`Automerge::counter_increments()`]
+    pub fn counter_increments<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+    ) -> Result<Vec<CounterIncrement>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.counter_increments(obj, prop)
+    }
+
+    /// The index and time of every timestamp in the list `obj` which is within `range`, see
+    /// [`Automerge::timestamps_in_range()`]
+    pub fn timestamps_in_range<O: AsRef<ExId>, R: RangeBounds<SystemTime>>(
+        &mut self,
+        obj: O,
+        range: R,
+    ) -> Result<Vec<(usize, SystemTime)>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.timestamps_in_range(obj, range)
+    }
+
     /// Get the hash of the change that contains the given `opid`.
     ///
     /// Returns [`None`] if the `opid`:
@@ -599,6 +1254,42 @@ impl AutoCommit {
         self.doc.hydrate(heads)
     }
 
+    /// Copy the object `obj` and everything nested in it, see [`Automerge::export_subtree()`]
+    pub fn export_subtree<O: AsRef<ExId>>(&self, obj: O) -> Result<Subtree, AutomergeError> {
+        Subtree::export(self, obj.as_ref())
+    }
+
+    /// The whole current state of the document, see [`Automerge::materialize()`]
+    pub fn materialize(&self) -> hydrate::Value {
+        self.doc.materialize()
+    }
+
+    /// The state of the document as at `heads`, see [`Automerge::materialize()`]
+    pub fn materialize_at(&self, heads: &[ChangeHash]) -> hydrate::Value {
+        self.doc.materialize_at(heads)
+    }
+
+    /// An overview of this document for listings, see [`Automerge::summary()`]
+    pub fn summary(&mut self, root_keys: &[&str]) -> DocumentSummary {
+        self.ensure_transaction_closed();
+        self.doc.summary(root_keys)
+    }
+
+    /// The metadata of each of the heads returned by [`Self::get_heads()`], see
+    /// [`Automerge::heads_with_metadata()`]
+    pub fn heads_with_metadata(&mut self) -> Vec<HeadMetadata> {
+        self.get_heads()
+            .iter()
+            .filter_map(|hash| self.doc.get_change_by_hash(hash))
+            .map(HeadMetadata::from)
+            .collect()
+    }
+
+    /// Serialize only the values found at `paths`, see [`Automerge::export_paths()`]
+    pub fn export_paths<'a>(&'a self, paths: &'a [Vec<Prop>]) -> AutoSerdePaths<'a, Self> {
+        AutoSerdePaths::new(self, paths)
+    }
+
     fn get_scope(&self, heads: Option<&[ChangeHash]>) -> Option<Clock> {
         // heads arg takes priority
         if let Some(h) = heads {
@@ -702,6 +1393,26 @@ impl ReadDoc for AutoCommit {
             .length_for(obj.as_ref(), self.get_scope(Some(heads)))
     }
 
+    fn contains_key<O: AsRef<ExId>>(&self, obj: O, key: &str) -> bool {
+        self.doc
+            .contains_key_for(obj.as_ref(), key, self.get_scope(None))
+    }
+
+    fn contains_key_at<O: AsRef<ExId>>(&self, obj: O, key: &str, heads: &[ChangeHash]) -> bool {
+        self.doc
+            .contains_key_for(obj.as_ref(), key, self.get_scope(Some(heads)))
+    }
+
+    fn has_index<O: AsRef<ExId>>(&self, obj: O, index: usize) -> bool {
+        self.doc
+            .has_index_for(obj.as_ref(), index, self.get_scope(None))
+    }
+
+    fn has_index_at<O: AsRef<ExId>>(&self, obj: O, index: usize, heads: &[ChangeHash]) -> bool {
+        self.doc
+            .has_index_for(obj.as_ref(), index, self.get_scope(Some(heads)))
+    }
+
     fn object_type<O: AsRef<ExId>>(&self, obj: O) -> Result<ObjType, AutomergeError> {
         self.doc.object_type(obj)
     }
@@ -895,6 +1606,18 @@ impl Transactable for AutoCommit {
         tx.splice(&mut self.doc, patch_log, obj.as_ref(), pos, del, vals)
     }
 
+    fn splice_objects<O: AsRef<ExId>, V: IntoIterator<Item = ObjType>>(
+        &mut self,
+        obj: O,
+        pos: usize,
+        del: isize,
+        objs: V,
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        tx.splice_objects(&mut self.doc, patch_log, obj.as_ref(), pos, del, objs)
+    }
+
     fn splice_text<O: AsRef<ExId>>(
         &mut self,
         obj: O,
@@ -3,15 +3,17 @@ use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::Debug;
 use std::num::NonZeroU64;
 use std::ops::RangeBounds;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use itertools::Itertools;
 
 use crate::change_graph::ChangeGraph;
 use crate::columnar::Key as EncodedKey;
 use crate::exid::ExId;
-use crate::iter::{Keys, ListRange, MapRange, Values};
+use crate::iter::{History, Keys, ListRange, MapRange, Values};
 use crate::marks::{Mark, MarkAccumulator, MarkSet, MarkStateMachine};
-use crate::op_set::{OpSet, OpSetData};
+use crate::op_set::{OpIdx, OpSet, OpSetData};
 use crate::parents::Parents;
 use crate::patches::{Patch, PatchLog, TextRepresentation};
 use crate::query;
@@ -24,14 +26,38 @@ use crate::types::{
     OpBuilder, OpId, OpIds, OpType, Value,
 };
 use crate::{hydrate, ScalarValue};
-use crate::{AutomergeError, Change, Cursor, ObjType, Prop, ReadDoc};
+use crate::{
+    op_observer::OpObserverHandle,
+    signing::{SignerHandle, VerifierHandle},
+    ActorFrontier, AutoSerdePaths, AutomergeError, Change, ChangeCache, CounterIncrement, Cursor,
+    DebugBundle, DebugStats, DeletedElement, DocumentStats, DocumentSummary, HeadMetadata,
+    HeapStats, MergePreview, ObjType, ObjectStats, OpObserver, Prop, ReadDoc, ReadGuard,
+    ResolvedOp, Signer, Subtree, TextConflict, TextWithConflicts, Verifier,
+};
 
+mod change_hook;
 pub(crate) mod current_state;
 pub(crate) mod diff;
+#[cfg(any(test, feature = "debug-invariants"))]
+mod invariants;
+mod progress;
+mod timestamp_source;
+mod validate;
+mod write_limits;
 
 #[cfg(test)]
 mod tests;
 
+pub use change_hook::{ChangeApplied, ChangeSkipped};
+use change_hook::{ChangeHook, SkippedChangeHook, Timer};
+pub use progress::ApplyProgress;
+use progress::ProgressFn;
+use timestamp_source::TimestampSource;
+pub use write_limits::WriteLimits;
+
+#[cfg(any(test, feature = "debug-invariants"))]
+pub use invariants::{InvariantReport, InvariantViolation};
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Actor {
     Unused(ActorId),
@@ -62,6 +88,7 @@ pub struct LoadOptions<'a> {
     verification_mode: VerificationMode,
     string_migration: StringMigration,
     patch_log: Option<&'a mut PatchLog>,
+    on_progress: Option<ProgressFn<'a>>,
 }
 
 impl<'a> LoadOptions<'a> {
@@ -99,6 +126,18 @@ impl<'a> LoadOptions<'a> {
         }
     }
 
+    /// A function to call as the changes in the document are applied, see [`ApplyProgress`]
+    ///
+    /// A document saved with [`Automerge::save()`] is rebuilt in one step, which is reported as
+    /// a single call once it is done. Changes after that, such as those appended by
+    /// [`Automerge::save_incremental()`], are reported one at a time.
+    pub fn on_progress<F: FnMut(ApplyProgress) + 'a>(self, on_progress: F) -> Self {
+        Self {
+            on_progress: Some(ProgressFn(Box::new(on_progress))),
+            ..self
+        }
+    }
+
     /// Whether to convert [`ScalarValue::Str`]s in the loaded document to [`ObjType::Text`]
     ///
     /// Until version 2.1.0 of the javascript library strings (as in, the native string of the JS
@@ -131,6 +170,7 @@ impl std::default::Default for LoadOptions<'static> {
             verification_mode: VerificationMode::Check,
             patch_log: None,
             string_migration: StringMigration::NoMigration,
+            on_progress: None,
         }
     }
 }
@@ -153,6 +193,33 @@ impl std::default::Default for LoadOptions<'static> {
 ///
 /// [`Self`] implements [`ReadDoc`], which provides methods for reading values from the document.
 ///
+/// ## Reading historical state
+///
+/// Every [`ReadDoc`] method has an `_at` variant which reads the document as it was at some heads.
+/// To hand a historical snapshot to code written against [`ReadDoc`] use [`Self::at()`], which
+/// returns a cheap read only view of the document at those heads. If you need an owned document,
+/// for example to make changes on top of the old state, use [`Self::fork_at()`].
+///
+/// ```
+/// # use automerge::{transaction::Transactable, Automerge, ReadDoc, ROOT};
+/// fn title<R: ReadDoc>(doc: &R) -> Option<String> {
+///     doc.get_string(ROOT, "title").unwrap()
+/// }
+///
+/// let mut doc = Automerge::new();
+/// let mut tx = doc.transaction();
+/// tx.put(ROOT, "title", "draft").unwrap();
+/// tx.commit();
+/// let heads = doc.get_heads();
+/// let mut tx = doc.transaction();
+/// tx.put(ROOT, "title", "final").unwrap();
+/// tx.commit();
+///
+/// assert_eq!(title(&doc.at(&heads).unwrap()).as_deref(), Some("draft"));
+/// assert_eq!(title(&doc.fork_at(&heads).unwrap()).as_deref(), Some("draft"));
+/// assert_eq!(title(&doc).as_deref(), Some("final"));
+/// ```
+///
 /// ## Modifying a document (Transactions)
 ///
 /// [`Automerge`] provides an interface for viewing and modifying automerge documents which does
@@ -183,6 +250,24 @@ pub struct Automerge {
     actor: Actor,
     /// The maximum operation counter this document has seen.
     max_op: u64,
+    /// Called after each change is applied, see [`Self::set_change_hook()`]
+    change_hook: Option<ChangeHook>,
+    /// Called for each change which is not applied, see [`Self::set_skipped_change_hook()`]
+    skipped_change_hook: Option<SkippedChangeHook>,
+    /// Notified of changes to the current state, see [`Self::set_op_observer()`]
+    op_observer: Option<OpObserverHandle>,
+    /// Signs local changes, see [`Self::set_signer()`]
+    signer: Option<SignerHandle>,
+    /// Checks the signatures of received changes, see [`Self::set_verifier()`]
+    verifier: Option<VerifierHandle>,
+    /// Provides the time of local changes, see [`Self::set_timestamp_source()`]
+    timestamp_source: Option<TimestampSource>,
+    /// Whether changes to this document are rejected, see [`Self::freeze()`]
+    frozen: bool,
+    /// Limits on local operations, see [`Self::set_write_limits()`]
+    write_limits: WriteLimits,
+    /// Whether received changes are fully checked, see [`Self::set_strict_validation()`]
+    strict_validation: bool,
 }
 
 impl Automerge {
@@ -198,7 +283,53 @@ impl Automerge {
             deps: Default::default(),
             actor: Actor::Unused(ActorId::random()),
             max_op: 0,
+            change_hook: None,
+            skipped_change_hook: None,
+            op_observer: None,
+            signer: None,
+            verifier: None,
+            timestamp_source: None,
+            frozen: false,
+            write_limits: WriteLimits::default(),
+            strict_validation: false,
+        }
+    }
+
+    /// Create a new document containing `value` in a single change
+    ///
+    /// `value` is usually deserialized from JSON or another serde format, see
+    /// [`Transactable::set_json()`] for how it is written.
+    ///
+    /// ```
+    /// # use automerge::{hydrate, Automerge, ReadDoc, ROOT};
+    /// let value: hydrate::Value =
+    ///     serde_json::from_str(r#"{"name": "config", "tags": ["a", "b"]}"#).unwrap();
+    /// let doc = Automerge::from_json(&value).unwrap();
+    /// assert_eq!(doc.get_changes(&[]).len(), 1);
+    /// assert_eq!(doc.materialize(), value);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidValueType`] if `value` is not a map, as the root of a
+    /// document is always a map
+    pub fn from_json(value: &hydrate::Value) -> Result<Self, AutomergeError> {
+        let map = match value {
+            hydrate::Value::Map(map) => map,
+            hydrate::Value::List(_) | hydrate::Value::Text(_) | hydrate::Value::Scalar(_) => {
+                return Err(AutomergeError::InvalidValueType {
+                    expected: "map".to_string(),
+                    unexpected: format!("{:?}", Value::from(value)),
+                })
+            }
+        };
+        let mut doc = Self::new();
+        let mut tx = doc.transaction();
+        for (key, value) in map.iter().collect::<std::collections::BTreeMap<_, _>>() {
+            tx.set_json(ExId::Root, key.as_str(), value.value())?;
         }
+        tx.commit();
+        Ok(doc)
     }
 
     pub(crate) fn ops_mut(&mut self) -> &mut OpSet {
@@ -218,13 +349,6 @@ impl Automerge {
         self.history.is_empty() && self.queue.is_empty()
     }
 
-    pub(crate) fn actor_id(&self) -> ActorId {
-        match &self.actor {
-            Actor::Unused(id) => id.clone(),
-            Actor::Cached(idx) => self.ops.osd.actors[*idx].clone(),
-        }
-    }
-
     /// Set the actor id for this document.
     pub fn with_actor(mut self, actor: ActorId) -> Self {
         self.actor = Actor::Unused(actor);
@@ -324,6 +448,34 @@ impl Automerge {
 
     /// Run a transaction on this document in a closure, automatically handling commit or rollback
     /// afterwards.
+    ///
+    /// If `f` returns `Ok` the transaction is committed and the [`Success`] holds both the value
+    /// returned by `f` and the hash of the new change (`None` if `f` made no changes). If `f`
+    /// returns `Err` every operation it made is rolled back and the [`Failure`] holds the error,
+    /// so an early return with `?` never leaves half of an edit in the document.
+    ///
+    /// ```
+    /// # use automerge::{Automerge, AutomergeError, ReadDoc, ROOT, transaction::Transactable};
+    /// let mut doc = Automerge::new();
+    /// let success = doc
+    ///     .transact(|tx| {
+    ///         tx.put(ROOT, "count", 1)?;
+    ///         Ok::<_, AutomergeError>("done")
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(success.result, "done");
+    /// assert_eq!(success.hash, Some(doc.get_heads()[0]));
+    ///
+    /// let failure = doc
+    ///     .transact(|tx| {
+    ///         tx.put(ROOT, "count", 2)?;
+    ///         tx.put(ROOT, "", 3)?; // empty keys are an error
+    ///         Ok::<_, AutomergeError>(())
+    ///     })
+    ///     .unwrap_err();
+    /// assert_eq!(failure.cancelled, 1);
+    /// assert_eq!(doc.get(ROOT, "count").unwrap().unwrap().0.to_i64(), Some(1));
+    /// ```
     pub fn transact<F, O, E>(&mut self, f: F) -> transaction::Result<O, E>
     where
         F: FnOnce(&mut Transaction<'_>) -> Result<O, E>,
@@ -446,6 +598,21 @@ impl Automerge {
     pub fn fork(&self) -> Self {
         let mut f = self.clone();
         f.set_actor(ActorId::random());
+        f.frozen = false;
+        f
+    }
+
+    /// Fork this document at the current point for use by `actor`
+    ///
+    /// This is the same as [`Self::fork()`] followed by [`Self::set_actor()`], for when the actor
+    /// of the fork must be known in advance, for example when it is derived from the ID of a
+    /// draft branch so that the branch can be reopened later with the same actor. `actor` must
+    /// not be used by any other fork or peer, or their changes will have conflicting sequence
+    /// numbers. To fork a historical version of the document use [`Self::fork_at()`] and then
+    /// [`Self::set_actor()`].
+    pub fn fork_with_actor(&self, actor: ActorId) -> Self {
+        let mut f = self.fork();
+        f.set_actor(actor);
         f
     }
 
@@ -485,7 +652,7 @@ impl Automerge {
                 } else if let Some(backup_idx) = self.ops.osd.actors.lookup(actor) {
                     OpId::new(*ctr, backup_idx)
                 } else {
-                    return Err(AutomergeError::InvalidObjId(id.to_string()));
+                    return Err(AutomergeError::UnknownActor(actor.clone()));
                 };
                 Ok(opid)
             }
@@ -498,7 +665,7 @@ impl Automerge {
         } else if let Some((typ, encoding)) = self.ops.type_and_encoding(&id) {
             Ok(ObjMeta { id, typ, encoding })
         } else {
-            Err(AutomergeError::NotAnObject)
+            Err(AutomergeError::NotAnObject(self.id_to_exid(id.0)))
         }
     }
 
@@ -536,6 +703,11 @@ impl Automerge {
     }
 
     /// Load a document.
+    ///
+    /// A document saved with [`Self::save()`] is loaded straight from its columnar encoding into
+    /// the op set, rather than by applying each change in turn. The changes are still
+    /// reconstructed from the operations, to check the heads of the document and to keep the
+    /// history, but their operations are not applied again.
     pub fn load(data: &[u8]) -> Result<Self, AutomergeError> {
         Self::load_with_options(data, Default::default())
     }
@@ -596,9 +768,7 @@ impl Automerge {
         tracing::trace!("loading first chunk");
         let (remaining, first_chunk) = storage::Chunk::parse(storage::parse::Input::new(data))
             .map_err(|e| load::Error::Parse(Box::new(e)))?;
-        if !first_chunk.checksum_valid() {
-            return Err(load::Error::BadChecksum.into());
-        }
+        load::check_checksum(&first_chunk)?;
 
         let mut change: Option<Change> = None;
         let mut first_chunk_was_doc = false;
@@ -629,9 +799,33 @@ impl Automerge {
             }
         };
         tracing::trace!("loading change chunks");
+        let mut on_progress = options.on_progress;
         match load::load_changes(remaining.reset()) {
             load::LoadedChanges::Complete(c) => {
-                am.apply_changes(change.into_iter().chain(c))?;
+                let changes = change.into_iter().chain(c);
+                let mut patch_log = PatchLog::inactive(TextRepresentation::default());
+                match on_progress.as_mut() {
+                    Some(ProgressFn(f)) => {
+                        // report the document chunk as one step and offset the rest by it
+                        let changes: Vec<_> = changes.collect();
+                        let base_changes = am.history.len();
+                        let base_ops = am.ops.len();
+                        let total_changes = base_changes + changes.len();
+                        f(ApplyProgress {
+                            changes_processed: base_changes,
+                            total_changes,
+                            ops_applied: base_ops,
+                        });
+                        am.apply_changes_with(changes, &mut patch_log, &mut |p| {
+                            f(ApplyProgress {
+                                changes_processed: base_changes + p.changes_processed,
+                                total_changes,
+                                ops_applied: base_ops + p.ops_applied,
+                            })
+                        })?;
+                    }
+                    None => am.apply_changes_with(changes, &mut patch_log, &mut |_| {})?,
+                }
                 // Only allow missing deps if the first chunk was a document chunk
                 // See https://github.com/automerge/automerge/pull/599#issuecomment-1549667472
                 if !am.queue.is_empty()
@@ -676,6 +870,29 @@ impl Automerge {
         patch_log.make_patches(self)
     }
 
+    /// Load a document saved with [`Self::save_encrypted()`], and any changes saved with
+    /// [`Self::save_after_encrypted()`] appended to it, using `key`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::Decryption`] if `key` is not the key the data was encrypted with
+    /// or the data has been modified.
+    #[cfg(feature = "encryption")]
+    pub fn load_encrypted(data: &[u8], key: &[u8; 32]) -> Result<Self, AutomergeError> {
+        Self::load(&crate::encryption::decrypt(key, data)?)
+    }
+
+    /// Load data written by [`Self::save_encrypted()`] or [`Self::save_after_encrypted()`] into
+    /// this document using `key`, see [`Self::load_incremental()`]
+    #[cfg(feature = "encryption")]
+    pub fn load_incremental_encrypted(
+        &mut self,
+        data: &[u8],
+        key: &[u8; 32],
+    ) -> Result<usize, AutomergeError> {
+        self.load_incremental(&crate::encryption::decrypt(key, data)?)
+    }
+
     /// Load an incremental save of a document.
     ///
     /// Unlike [`Self::load()`] this imports changes into an existing document. It will work with
@@ -697,18 +914,23 @@ impl Automerge {
         data: &[u8],
         patch_log: &mut PatchLog,
     ) -> Result<usize, AutomergeError> {
-        if self.is_empty() {
-            let mut doc = Self::load_with_options(
+        self.check_frozen()?;
+        if self.is_empty() && !self.applies_changes_individually() {
+            let doc = Self::load_with_options(
                 data,
                 LoadOptions::new()
                     .on_partial_load(OnPartialLoad::Ignore)
                     .verification_mode(VerificationMode::Check),
             )?;
-            doc = doc.with_actor(self.actor_id());
+            self.replace_state(doc);
             if patch_log.is_active() {
-                current_state::log_current_state_patches(&doc, patch_log);
+                current_state::log_current_state_patches(self, patch_log);
+            }
+            if self.op_observer.is_some() {
+                let mut observed = PatchLog::active(patch_log.text_rep());
+                current_state::log_current_state_patches(self, &mut observed);
+                self.notify_op_observer(&mut observed, false);
             }
-            *self = doc;
             return Ok(self.ops.len());
         }
         let changes = match load::load_changes(storage::parse::Input::new(data)) {
@@ -724,6 +946,41 @@ impl Automerge {
         Ok(delta)
     }
 
+    /// Whether received changes have to be applied one at a time because they are verified,
+    /// validated or reported to a hook, rather than loading them as a whole document
+    fn applies_changes_individually(&self) -> bool {
+        self.verifier.is_some()
+            || self.strict_validation
+            || self.change_hook.is_some()
+            || self.skipped_change_hook.is_some()
+    }
+
+    /// Replace the contents of this document with those of `loaded`, keeping the actor and all
+    /// of the configuration of this document
+    fn replace_state(&mut self, loaded: Automerge) {
+        let actor = self.get_actor().clone();
+        let Automerge {
+            queue,
+            history,
+            history_index,
+            change_graph,
+            states,
+            deps,
+            ops,
+            max_op,
+            ..
+        } = loaded;
+        self.queue = queue;
+        self.history = history;
+        self.history_index = history_index;
+        self.change_graph = change_graph;
+        self.states = states;
+        self.deps = deps;
+        self.ops = ops;
+        self.max_op = max_op;
+        self.actor = Actor::Unused(actor);
+    }
+
     fn duplicate_seq(&self, change: &Change) -> bool {
         let mut dup = false;
         if let Some(actor_index) = self.ops.osd.actors.lookup(change.actor_id()) {
@@ -748,20 +1005,215 @@ impl Automerge {
         )
     }
 
+    /// Read the state this document would have after applying `changes`, without modifying it
+    ///
+    /// The changes are applied to a temporary copy of this document which is passed to `f` and
+    /// dropped once `f` returns. This is useful for previewing what an update from a peer would
+    /// do, or for validating the result of an update before accepting it. Changes whose
+    /// dependencies are missing are queued in the copy as they would be by
+    /// [`Self::apply_changes()`], so they don't show up in the state `f` sees.
+    ///
+    /// Copying the document is linear in its size, so this is much more expensive than
+    /// [`Self::apply_changes()`]. Hooks set with [`Self::set_change_hook()`] or
+    /// [`Self::set_skipped_change_hook()`] are not called for the changes applied to the copy.
+    pub fn with_changes<F, R>(
+        &self,
+        changes: impl IntoIterator<Item = Change>,
+        f: F,
+    ) -> Result<R, AutomergeError>
+    where
+        F: FnOnce(&Automerge) -> R,
+    {
+        let mut overlay = self.clone();
+        overlay.change_hook = None;
+        overlay.skipped_change_hook = None;
+        overlay.op_observer = None;
+        overlay.frozen = false;
+        overlay.apply_changes(changes)?;
+        Ok(f(&overlay))
+    }
+
+    /// Like [`Self::apply_changes()`] but either all of `changes` are applied or none of them are
+    ///
+    /// [`Self::apply_changes()`] stops at the first change which fails, for example with
+    /// [`AutomergeError::DuplicateSeqNumber`] or [`AutomergeError::InvalidSignature`], leaving
+    /// the changes before it applied. This method applies the changes to a copy of the document
+    /// and only replaces the document with the copy if every change succeeded, so on error the
+    /// document is unchanged, including the queue of changes waiting for their dependencies.
+    ///
+    /// Copying the document is linear in its size, so this is much more expensive than
+    /// [`Self::apply_changes()`]. The observer set with [`Self::set_op_observer()`] is only
+    /// notified if the changes are applied, but the hooks set with [`Self::set_change_hook()`]
+    /// and [`Self::set_skipped_change_hook()`] are called as each change is applied to the copy,
+    /// so on error they may have been called for changes which were then discarded.
+    pub fn apply_changes_atomic(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+    ) -> Result<(), AutomergeError> {
+        self.apply_changes_atomic_log_patches(
+            changes,
+            &mut PatchLog::inactive(TextRepresentation::default()),
+        )
+    }
+
+    /// Like [`Self::apply_changes_atomic()`] but log the resulting changes to the current state
+    /// of the document to `patch_log`
+    ///
+    /// Nothing is logged to `patch_log` if the changes are not applied.
+    pub fn apply_changes_atomic_log_patches<I: IntoIterator<Item = Change>>(
+        &mut self,
+        changes: I,
+        patch_log: &mut PatchLog,
+    ) -> Result<(), AutomergeError> {
+        self.check_frozen()?;
+        let mut staged = self.clone();
+        let observer = staged.op_observer.take();
+        let mut staged_log = if observer.is_some() {
+            let mut log = patch_log.fresh(true);
+            log.set_obj_types(None);
+            log
+        } else {
+            patch_log.branch()
+        };
+        staged.apply_changes_inner(changes, &mut staged_log, &mut |_| {})?;
+        staged.op_observer = observer;
+        *self = staged;
+        self.notify_op_observer(&mut staged_log, false);
+        if patch_log.is_active() {
+            patch_log.merge(staged_log);
+        }
+        Ok(())
+    }
+
     /// Like [`Self::apply_changes()`] but log the resulting changes to the current state of the
     /// document to `patch_log`
+    ///
+    /// The logged changes are turned into [`Patch`]es describing which keys and indices were
+    /// put, inserted, deleted, incremented or became conflicted with [`Self::make_patches()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use automerge::{Automerge, PatchAction, PatchLog, ROOT, transaction::Transactable};
+    /// # use automerge::patches::TextRepresentation;
+    /// # fn main() -> Result<(), automerge::AutomergeError> {
+    /// let mut doc = Automerge::new();
+    /// let mut other = doc.fork();
+    /// other.transact::<_, _, automerge::AutomergeError>(|tx| {
+    ///     tx.put(ROOT, "key", "value")?;
+    ///     Ok(())
+    /// }).unwrap();
+    /// let changes = other.get_changes(&[]).into_iter().cloned();
+    ///
+    /// let mut patch_log = PatchLog::active(TextRepresentation::String);
+    /// doc.apply_changes_log_patches(changes, &mut patch_log)?;
+    /// let patches = doc.make_patches(&mut patch_log);
+    /// assert_eq!(patches.len(), 1);
+    /// assert!(matches!(&patches[0].action, PatchAction::PutMap { key, .. } if key == "key"));
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn apply_changes_log_patches<I: IntoIterator<Item = Change>>(
         &mut self,
         changes: I,
         patch_log: &mut PatchLog,
+    ) -> Result<(), AutomergeError> {
+        self.apply_changes_with(changes, patch_log, &mut |_| {})
+    }
+
+    /// Like [`Self::apply_changes()`] but call `on_progress` after each change is dealt with
+    ///
+    /// This is for showing progress while merging a large number of changes, the callback is
+    /// called on the same thread, so it should be cheap.
+    ///
+    /// ```
+    /// # use automerge::{transaction::Transactable, ApplyProgress, Automerge, ROOT};
+    /// # fn main() -> Result<(), automerge::AutomergeError> {
+    /// let mut doc = Automerge::new();
+    /// for i in 0..3 {
+    ///     let mut tx = doc.transaction();
+    ///     tx.put(ROOT, "count", i)?;
+    ///     tx.commit();
+    /// }
+    /// let changes = doc.get_changes(&[]).into_iter().cloned().collect::<Vec<_>>();
+    ///
+    /// let mut other = Automerge::new();
+    /// let mut last = None;
+    /// other.apply_changes_with_progress(changes, |progress| last = Some(progress))?;
+    /// assert_eq!(
+    ///     last,
+    ///     Some(ApplyProgress { changes_processed: 3, total_changes: 3, ops_applied: 3 })
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_changes_with_progress<F: FnMut(ApplyProgress)>(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+        mut on_progress: F,
+    ) -> Result<(), AutomergeError> {
+        self.apply_changes_with(
+            changes,
+            &mut PatchLog::inactive(TextRepresentation::default()),
+            &mut on_progress,
+        )
+    }
+
+    pub(crate) fn apply_changes_with<I: IntoIterator<Item = Change>>(
+        &mut self,
+        changes: I,
+        patch_log: &mut PatchLog,
+        on_progress: &mut dyn FnMut(ApplyProgress),
+    ) -> Result<(), AutomergeError> {
+        if self.op_observer.is_none() {
+            return self.apply_changes_inner(changes, patch_log, on_progress);
+        }
+        let mut observed = patch_log.fresh(true);
+        observed.set_obj_types(None);
+        let result = self.apply_changes_inner(changes, &mut observed, on_progress);
+        self.notify_op_observer(&mut observed, false);
+        if patch_log.is_active() {
+            patch_log.merge(observed);
+        }
+        result
+    }
+
+    fn apply_changes_inner<I: IntoIterator<Item = Change>>(
+        &mut self,
+        changes: I,
+        patch_log: &mut PatchLog,
+        on_progress: &mut dyn FnMut(ApplyProgress),
     ) -> Result<(), AutomergeError> {
         // Record this so we can avoid observing each individual change and instead just observe
         // the final state after all the changes have been applied. We can only do this for an
         // empty document right now, once we have logic to produce the diffs between arbitrary
         // states of the OpSet we can make this cleaner.
+        self.check_frozen()?;
+        let changes: Vec<_> = changes.into_iter().collect();
+        let start_ops = self.ops.len();
+        let mut progress = ApplyProgress {
+            changes_processed: 0,
+            total_changes: changes.len(),
+            ops_applied: 0,
+        };
         for c in changes {
+            progress.changes_processed += 1;
             if !self.history_index.contains_key(&c.hash()) {
+                if let Some(verifier) = &self.verifier {
+                    if !verifier.verify(&c) {
+                        self.skipped(|| ChangeSkipped::Invalid {
+                            hash: c.hash(),
+                            reason: "invalid signature".to_string(),
+                        });
+                        return Err(AutomergeError::InvalidSignature(c.hash()));
+                    }
+                }
                 if self.duplicate_seq(&c) {
+                    self.skipped(|| ChangeSkipped::DuplicateSeq {
+                        hash: c.hash(),
+                        actor: c.actor_id().clone(),
+                        seq: c.seq(),
+                    });
                     return Err(AutomergeError::DuplicateSeqNumber(
                         c.seq(),
                         c.actor_id().clone(),
@@ -769,14 +1221,29 @@ impl Automerge {
                 }
                 if self.is_causally_ready(&c) {
                     self.apply_change(c, patch_log)?;
+                    progress.ops_applied = self.ops.len() - start_ops;
                 } else {
+                    self.skipped(|| ChangeSkipped::Queued {
+                        hash: c.hash(),
+                        missing_deps: c
+                            .deps()
+                            .iter()
+                            .filter(|d| !self.history_index.contains_key(d))
+                            .copied()
+                            .collect(),
+                    });
                     self.queue.push(c);
                 }
+            } else {
+                self.skipped(|| ChangeSkipped::AlreadyApplied { hash: c.hash() });
             }
+            on_progress(progress);
         }
         while let Some(c) = self.pop_next_causally_ready_change() {
             if !self.history_index.contains_key(&c.hash()) {
                 self.apply_change(c, patch_log)?;
+                progress.ops_applied = self.ops.len() - start_ops;
+                on_progress(progress);
             }
         }
         Ok(())
@@ -787,14 +1254,209 @@ impl Automerge {
         change: Change,
         patch_log: &mut PatchLog,
     ) -> Result<(), AutomergeError> {
+        let timer = self.change_hook.as_ref().map(|_| Timer::start());
+        let hash = change.hash();
         let ops = self.import_ops(&change);
-        self.update_history(change, ops.len());
-        for (obj, op, pred) in ops {
-            self.insert_op(&obj, op, &pred, patch_log)?;
+        let checked = self.check_op_references(&change, &ops).and_then(|_| {
+            if self.strict_validation {
+                validate::check_ops(self, &change, &ops)
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(e) = checked {
+            self.skipped(|| ChangeSkipped::Invalid {
+                hash,
+                reason: e.to_string(),
+            });
+            return Err(e);
+        }
+        let num_ops = ops.len();
+        self.update_history(change, num_ops);
+        self.insert_ops(ops, patch_log)?;
+        if let (Some(hook), Some(timer)) = (&self.change_hook, timer) {
+            (hook.0)(&ChangeApplied {
+                hash,
+                ops: num_ops,
+                duration: timer.elapsed(),
+                queued: self.queue.len(),
+            });
         }
         Ok(())
     }
 
+    /// Set a function to be called after each change received from another peer is applied
+    ///
+    /// This is called for changes applied by [`Self::apply_changes()`] and the methods built on
+    /// it, such as [`Self::merge()`], [`Self::load_incremental()`] and receiving sync messages.
+    /// It is not called for local changes or when loading a document. The hook is shared with
+    /// forks of this document.
+    ///
+    /// This is intended for profiling, for example to find peers whose changes are slow to apply.
+    pub fn set_change_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&ChangeApplied) + Send + Sync + 'static,
+    {
+        self.change_hook = Some(ChangeHook(Arc::new(hook)));
+    }
+
+    /// Remove the hook set with [`Self::set_change_hook()`]
+    pub fn clear_change_hook(&mut self) {
+        self.change_hook = None;
+    }
+
+    /// Notify `observer` of every change to the current state of this document, see
+    /// [`OpObserver`]
+    ///
+    /// Local changes are reported when the transaction which made them is committed, remote
+    /// changes when they are applied, including by [`Self::merge()`],
+    /// [`Self::load_incremental()`] and receiving sync messages. While an observer is set the
+    /// work of logging patches is done for every change, so this costs about as much as using an
+    /// active [`PatchLog`]. The observer is shared with forks of this document. Only one observer
+    /// can be set, setting another replaces it.
+    pub fn set_op_observer<O: OpObserver + 'static>(&mut self, observer: O) {
+        self.op_observer = Some(OpObserverHandle(Arc::new(observer)));
+    }
+
+    /// Remove the observer set with [`Self::set_op_observer()`]
+    pub fn clear_op_observer(&mut self) {
+        self.op_observer = None;
+    }
+
+    /// Sign every change made on this document with `signer`
+    ///
+    /// The signature covers the whole change, including its actor ID, and is stored at the end of
+    /// the change's [`Change::extra_bytes()`], after any extra bytes set with
+    /// [`CommitOptions::with_extra_bytes()`]. It can be read back with [`Change::signature()`]
+    /// and checked by peers with [`Self::set_verifier()`]. The signer is shared with forks of
+    /// this document.
+    pub fn set_signer<S: Signer + 'static>(&mut self, signer: S) {
+        self.signer = Some(SignerHandle(Arc::new(signer)));
+    }
+
+    /// Remove the signer set with [`Self::set_signer()`]
+    pub fn clear_signer(&mut self) {
+        self.signer = None;
+    }
+
+    pub(crate) fn signer(&self) -> Option<&SignerHandle> {
+        self.signer.as_ref()
+    }
+
+    /// Check the signature of every change received by this document with `verifier`
+    ///
+    /// This applies to changes applied by [`Self::apply_changes()`] and the methods built on it,
+    /// such as [`Self::merge()`], [`Self::load_incremental()`] and receiving sync messages. A
+    /// change which the verifier rejects is not applied and the method returns
+    /// [`AutomergeError::InvalidSignature`]. This is intended for sync servers which need to
+    /// reject changes claiming to be from another actor.
+    ///
+    /// Documents loaded with [`Self::load()`] are not verified, as they are usually read from
+    /// the application's own storage.
+    ///
+    /// ```
+    /// # use automerge::{transaction::Transactable, ActorId, AutoCommit, AutomergeError, Signer, Verifier, ROOT};
+    /// // a toy scheme for illustration, use a real signature algorithm
+    /// struct Checksum;
+    ///
+    /// impl Signer for Checksum {
+    ///     fn sign(&self, actor: &ActorId, payload: &[u8]) -> Vec<u8> {
+    ///         let sum = payload.iter().chain(actor.to_bytes()).fold(0u8, |a, b| a.wrapping_add(*b));
+    ///         vec![sum]
+    ///     }
+    /// }
+    ///
+    /// impl Verifier for Checksum {
+    ///     fn verify(&self, actor: &ActorId, payload: &[u8], signature: Option<&[u8]>) -> bool {
+    ///         signature == Some(&self.sign(actor, payload)[..])
+    ///     }
+    /// }
+    ///
+    /// let mut signed = AutoCommit::new();
+    /// signed.set_signer(Checksum);
+    /// signed.put(ROOT, "a", 1).unwrap();
+    /// let mut unsigned = AutoCommit::new();
+    /// unsigned.put(ROOT, "b", 2).unwrap();
+    ///
+    /// let mut server = AutoCommit::new();
+    /// server.set_verifier(Checksum);
+    /// server.merge(&mut signed).unwrap();
+    /// assert!(matches!(
+    ///     server.merge(&mut unsigned),
+    ///     Err(AutomergeError::InvalidSignature(_))
+    /// ));
+    /// ```
+    pub fn set_verifier<V: Verifier + 'static>(&mut self, verifier: V) {
+        self.verifier = Some(VerifierHandle(Arc::new(verifier)));
+    }
+
+    /// Remove the verifier set with [`Self::set_verifier()`]
+    pub fn clear_verifier(&mut self) {
+        self.verifier = None;
+    }
+
+    /// Whether an observer has been set with [`Self::set_op_observer()`]
+    pub(crate) fn has_op_observer(&self) -> bool {
+        self.op_observer.is_some()
+    }
+
+    /// Make patches from the events in `patch_log` and pass them to the observer, if there is one
+    pub(crate) fn notify_op_observer(&self, patch_log: &mut PatchLog, local: bool) {
+        if let Some(observer) = &self.op_observer {
+            observer.notify(patch_log.make_patches(self), local);
+        }
+    }
+
+    /// Set a function to be called for each change passed to [`Self::apply_changes()`] which is
+    /// not applied when it is received
+    ///
+    /// The hook receives a [`ChangeSkipped`] describing why the change was ignored, rejected or
+    /// queued, so operators can trace why an edit from a peer never appeared. Like
+    /// [`Self::set_change_hook()`] this covers the methods built on [`Self::apply_changes()`] and
+    /// is shared with forks of this document. Queued changes are reported to the hook set with
+    /// [`Self::set_change_hook()`] once they are applied.
+    pub fn set_skipped_change_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&ChangeSkipped) + Send + Sync + 'static,
+    {
+        self.skipped_change_hook = Some(SkippedChangeHook(Arc::new(hook)));
+    }
+
+    /// Remove the hook set with [`Self::set_skipped_change_hook()`]
+    pub fn clear_skipped_change_hook(&mut self) {
+        self.skipped_change_hook = None;
+    }
+
+    fn skipped<F: FnOnce() -> ChangeSkipped>(&self, skipped: F) {
+        if let Some(hook) = &self.skipped_change_hook {
+            (hook.0)(&skipped());
+        }
+    }
+
+    /// Set a function which provides the timestamp of each local change
+    ///
+    /// Changes are committed with a timestamp of `0` unless one is given in the
+    /// [`CommitOptions`]. Once a timestamp source is set it is called whenever a change is
+    /// committed without an explicit time, so embedders can record the current time (typically
+    /// milliseconds since the unix epoch) automatically and tests can use a fixed time. The
+    /// source is shared with forks of this document.
+    pub fn set_timestamp_source<F>(&mut self, source: F)
+    where
+        F: Fn() -> i64 + Send + Sync + 'static,
+    {
+        self.timestamp_source = Some(TimestampSource(Arc::new(source)));
+    }
+
+    /// Remove the function set with [`Self::set_timestamp_source()`]
+    pub fn clear_timestamp_source(&mut self) {
+        self.timestamp_source = None;
+    }
+
+    /// The timestamp for a local change, if a timestamp source has been set
+    pub(crate) fn timestamp(&self) -> Option<i64> {
+        self.timestamp_source.as_ref().map(|source| (source.0)())
+    }
+
     fn is_causally_ready(&self, change: &Change) -> bool {
         change
             .deps()
@@ -813,6 +1475,64 @@ impl Automerge {
         None
     }
 
+    /// Check that every op referenced by `ops` (as the object, the list element or a
+    /// predecessor) is either an earlier op in the same change or could be in this document
+    ///
+    /// An op which references an op beyond the largest counter seen from that actor refers to an
+    /// op which does not exist. This can only happen if the change was constructed maliciously or
+    /// by a buggy implementation, so it is rejected rather than inserting a dangling reference
+    /// into the op set.
+    fn check_op_references(
+        &self,
+        change: &Change,
+        ops: &[(ObjId, OpBuilder, OpIds)],
+    ) -> Result<(), AutomergeError> {
+        let mut max_ops = HashMap::new();
+        let mut exists = |id: &OpId, referrer: &OpId| {
+            if id.actor() == referrer.actor() && id.counter() >= change.start_op().get() {
+                return id.counter() < referrer.counter();
+            }
+            let max_op = *max_ops.entry(id.actor()).or_insert_with(|| {
+                self.states
+                    .get(&id.actor())
+                    .and_then(|s| s.last())
+                    .map(|index| self.history[*index].max_op())
+                    .unwrap_or(0)
+            });
+            id.counter() <= max_op
+        };
+        for (obj, op, pred) in ops {
+            let elem = match op.key {
+                Key::Seq(e) if !e.is_head() => Some(e.0),
+                _ => None,
+            };
+            let references = std::iter::once(obj.0)
+                .filter(|o| *o != crate::types::ROOT)
+                .chain(elem)
+                .chain(pred.iter().copied());
+            for id in references {
+                if !exists(&id, &op.id) {
+                    return Err(AutomergeError::InvalidOpReference {
+                        change: change.hash(),
+                        counter: id.counter(),
+                        actor: self.ops.osd.actors[id.actor()].clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The ops of `change`, which has not been committed, checked as if it were being applied
+    pub(crate) fn import_draft(
+        &mut self,
+        change: &Change,
+    ) -> Result<Vec<(ObjId, OpBuilder, OpIds)>, AutomergeError> {
+        let ops = self.import_ops(change);
+        self.check_op_references(change, &ops)?;
+        Ok(ops)
+    }
+
     fn import_ops(&mut self, change: &Change) -> Vec<(ObjId, OpBuilder, OpIds)> {
         let actor = self.ops.osd.actors.cache(change.actor_id().clone());
         let mut actors = Vec::with_capacity(change.other_actor_ids().len() + 1);
@@ -894,36 +1614,188 @@ impl Automerge {
         Ok(self.get_heads())
     }
 
+    /// Like [`Self::merge()`] but consumes `other`
+    ///
+    /// The changes which are not in `self` are moved out of `other` rather than cloned, which
+    /// avoids copying the change data when merging a fork back into the document it was forked
+    /// from. Any changes in `other` which are still waiting for their dependencies are moved
+    /// across too, so they are not lost when `other` is dropped.
+    pub fn merge_owned(&mut self, other: Self) -> Result<Vec<ChangeHash>, AutomergeError> {
+        self.merge_owned_and_log_patches(
+            other,
+            &mut PatchLog::inactive(TextRepresentation::default()),
+        )
+    }
+
+    /// Like [`Self::merge_and_log_patches()`] but consumes `other`, see [`Self::merge_owned()`]
+    pub fn merge_owned_and_log_patches(
+        &mut self,
+        mut other: Self,
+        patch_log: &mut PatchLog,
+    ) -> Result<Vec<ChangeHash>, AutomergeError> {
+        let added = self
+            .get_changes_added(&other)
+            .into_iter()
+            .map(|c| c.hash())
+            .collect::<Vec<_>>();
+        let mut history = std::mem::take(&mut other.history)
+            .into_iter()
+            .map(|c| (c.hash(), c))
+            .collect::<HashMap<_, _>>();
+        let changes = added
+            .into_iter()
+            .filter_map(|h| history.remove(&h))
+            .chain(other.queue.drain(..))
+            .collect::<Vec<_>>();
+        tracing::trace!(changes=?changes.iter().map(|c| c.hash()).collect::<Vec<_>>(), "merging new changes");
+        self.apply_changes_log_patches(changes, patch_log)?;
+        Ok(self.get_heads())
+    }
+
+    /// Work out what [`Self::merge()`] would do with `other` without changing either document
+    ///
+    /// This is for reviewing a merge before making it. See [`MergePreview`] for what is
+    /// reported. The patches use `text_rep` for text objects.
+    ///
+    /// The preview merges a copy of this document, so it takes as long as the merge itself plus
+    /// the time to copy the document.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Self::merge()`] would
+    pub fn merge_preview(
+        &self,
+        other: &Self,
+        text_rep: TextRepresentation,
+    ) -> Result<MergePreview, AutomergeError> {
+        let ours = self.get_heads();
+        let theirs = other.get_heads();
+        let changes = self
+            .get_changes_added(other)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let incoming = changes.iter().map(|c| c.hash()).collect();
+        self.with_changes(changes, |merged| {
+            let base = merged.common_ancestors(&[ours.as_slice(), theirs.as_slice()].concat())?;
+            Ok(MergePreview::new(
+                base.clone(),
+                incoming,
+                merged.diff(&base, &ours, text_rep),
+                merged.diff(&base, &theirs, text_rep),
+                merged.diff(&ours, &merged.get_heads(), text_rep),
+            ))
+        })?
+    }
+
     /// Save the entirety of this document in a compact form.
     pub fn save_with_options(&self, options: SaveOptions) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.save_with_options_to_writer(options, &mut bytes)
+            .expect("writing to a Vec can't fail");
+        bytes
+    }
+
+    /// Save the entirety of this document to `writer`, returning the number of bytes written
+    ///
+    /// The output is the same as [`Self::save()`]. This is useful for large documents, as the
+    /// saved document is written out as it is rather than being handed over as a new `Vec<u8>`
+    /// which the caller must then write somewhere itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error `writer` returns
+    pub fn save_to_writer<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+        self.save_with_options_to_writer(SaveOptions::default(), writer)
+    }
+
+    /// Save the entirety of this document to `writer` with the given options, see
+    /// [`Self::save_to_writer()`]
+    pub fn save_with_options_to_writer<W: std::io::Write>(
+        &self,
+        options: SaveOptions,
+        writer: &mut W,
+    ) -> std::io::Result<usize> {
         let heads = self.get_heads();
-        let c = self.history.iter();
-        let compress = if options.deflate {
-            None
-        } else {
-            Some(CompressConfig::None)
-        };
-        let mut bytes = crate::storage::save::save_document(
-            c,
+        let history = self.sorted_history();
+        let compress = Some(options.compress_config());
+        let doc = crate::storage::save::encode_document(
+            history.iter().copied(),
             self.ops.iter().map(|(objid, _, op)| (objid, op)),
             &self.ops.osd.actors,
             &self.ops.osd.props,
             &heads,
             compress,
         );
+        let mut written = doc.write_to(writer)?;
+        drop(doc);
         if options.retain_orphans {
-            for orphaned in self.queue.iter() {
-                bytes.extend(orphaned.raw_bytes());
+            written += write_changes(self.queue.iter(), writer)?;
+        }
+        Ok(written)
+    }
+
+    /// The history sorted topologically, with ties broken by hash so that the order does not
+    /// depend on the order in which changes were applied
+    fn sorted_history(&self) -> Vec<&Change> {
+        let mut children = vec![Vec::new(); self.history.len()];
+        let mut pending = vec![0; self.history.len()];
+        for (index, change) in self.history.iter().enumerate() {
+            for dep in change.deps() {
+                if let Some(dep_index) = self.history_index.get(dep) {
+                    children[*dep_index].push(index);
+                    pending[index] += 1;
+                }
             }
         }
-        bytes
+        let mut ready = pending
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| **p == 0)
+            .map(|(index, _)| (self.history[index].hash(), index))
+            .collect::<BTreeSet<_>>();
+        let mut sorted = Vec::with_capacity(self.history.len());
+        while let Some((hash, index)) = ready.iter().next().copied() {
+            ready.remove(&(hash, index));
+            sorted.push(&self.history[index]);
+            for child in &children[index] {
+                pending[*child] -= 1;
+                if pending[*child] == 0 {
+                    ready.insert((self.history[*child].hash(), *child));
+                }
+            }
+        }
+        sorted
     }
 
     /// Save the entirety of this document in a compact form.
+    ///
+    /// The output depends only on the changes in the document and not on the order in which they
+    /// were applied, so two documents with the same heads produce identical bytes.
     pub fn save(&self) -> Vec<u8> {
         self.save_with_options(SaveOptions::default())
     }
 
+    /// Save the entirety of this document and encrypt it with `key`
+    ///
+    /// The output can be loaded with [`Self::load_encrypted()`] using the same key. The encryption
+    /// is authenticated, so loading data which has been tampered with fails rather than producing
+    /// a different document.
+    #[cfg(feature = "encryption")]
+    pub fn save_encrypted(&self, key: &[u8; 32]) -> Vec<u8> {
+        crate::encryption::encrypt(key, &self.save())
+    }
+
+    /// Save the changes since the given heads and encrypt them with `key`, see
+    /// [`Self::save_after()`]
+    ///
+    /// The output can be appended to the output of [`Self::save_encrypted()`], or of previous calls
+    /// to this method, and the whole loaded with [`Self::load_encrypted()`].
+    #[cfg(feature = "encryption")]
+    pub fn save_after_encrypted(&self, heads: &[ChangeHash], key: &[u8; 32]) -> Vec<u8> {
+        crate::encryption::encrypt(key, &self.save_after(heads))
+    }
+
     /// Save the document and attempt to load it before returning - slow!
     pub fn save_and_verify(&self) -> Result<Vec<u8>, AutomergeError> {
         let bytes = self.save();
@@ -954,11 +1826,83 @@ impl Automerge {
         bytes
     }
 
-    /// Filter the changes down to those that are not transitive dependencies of the heads.
+    /// Write the changes since the given heads to `writer` one change at a time, returning the
+    /// number of bytes written
     ///
-    /// Thus a graph with these heads has not seen the remaining changes.
-    pub(crate) fn filter_changes(
-        &self,
+    /// The output is the same as [`Self::save_after()`], but the changes are never copied into a
+    /// single buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error `writer` returns
+    pub fn save_after_to_writer<W: std::io::Write>(
+        &self,
+        heads: &[ChangeHash],
+        writer: &mut W,
+    ) -> std::io::Result<usize> {
+        write_changes(self.get_changes(heads).into_iter(), writer)
+    }
+
+    /// Save the changes which have been received but which are not yet causally ready
+    ///
+    /// Changes whose dependencies we have not seen are held in a queue until those dependencies
+    /// arrive. This queue is only held in memory, so a process which restarts before the missing
+    /// dependencies arrive would otherwise lose these changes. The output of this method is a
+    /// series of change chunks which can be restored with [`Self::import_queue()`].
+    pub fn export_queue(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for c in &self.queue {
+            bytes.extend(c.raw_bytes());
+        }
+        bytes
+    }
+
+    /// Restore changes saved with [`Self::export_queue()`]
+    ///
+    /// Any of the imported changes which are now causally ready are applied, the rest are added
+    /// to the queue. Changes which are already in the document or the queue are ignored.
+    ///
+    /// Returns the number of changes which are still waiting for dependencies after the import.
+    pub fn import_queue(&mut self, data: &[u8]) -> Result<usize, AutomergeError> {
+        let changes = match load::load_changes(storage::parse::Input::new(data)) {
+            load::LoadedChanges::Complete(c) => c,
+            load::LoadedChanges::Partial { error, .. } => return Err(error.into()),
+        };
+        let queued = self.queue.iter().map(|c| c.hash()).collect::<HashSet<_>>();
+        self.apply_changes(changes.into_iter().filter(|c| !queued.contains(&c.hash())))?;
+        Ok(self.queue.len())
+    }
+
+    /// Collect the state of this document for a bug report, see [`DebugBundle`]
+    ///
+    /// The bundle contains the whole document, so it should only be shared with people who may
+    /// see its contents.
+    pub fn debug_bundle(&self) -> DebugBundle {
+        DebugBundle {
+            document: self.save(),
+            queue: self.export_queue(),
+            actors: self.ops.osd.actors.cache.clone(),
+            stats: DebugStats {
+                heads: self.get_heads(),
+                changes: self.history.len() as u64,
+                ops: self.ops.len() as u64,
+                queued: self.queue.len() as u64,
+            },
+            patches: Vec::new(),
+        }
+    }
+
+    /// The encoded [`Self::debug_bundle()`], a single artifact which can be attached to a bug
+    /// report and read with [`DebugBundle::from_bytes()`]
+    pub fn export_debug_bundle(&self) -> Vec<u8> {
+        self.debug_bundle().to_bytes()
+    }
+
+    /// Filter the changes down to those that are not transitive dependencies of the heads.
+    ///
+    /// Thus a graph with these heads has not seen the remaining changes.
+    pub(crate) fn filter_changes(
+        &self,
         heads: &[ChangeHash],
         changes: &mut BTreeSet<ChangeHash>,
     ) -> Result<(), AutomergeError> {
@@ -1001,6 +1945,92 @@ impl Automerge {
             .collect()
     }
 
+    /// Whether the change `ancestor` is `descendant` or one of its transitive dependencies
+    ///
+    /// In other words, whether any version of the document which includes `descendant` also
+    /// includes `ancestor`. This can be used to tell whether one version supersedes another.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::MissingHash`] if either change is not in this document
+    pub fn is_ancestor(
+        &self,
+        ancestor: &ChangeHash,
+        descendant: &ChangeHash,
+    ) -> Result<bool, AutomergeError> {
+        self.check_hashes(&[*ancestor, *descendant])?;
+        self.change_graph
+            .is_ancestor(ancestor, descendant)
+            .ok_or(AutomergeError::MissingHash(*ancestor))
+    }
+
+    /// The heads of the latest version of the document which every one of `hashes` includes
+    ///
+    /// This is the set of changes which are ancestors (see [`Self::is_ancestor()`]) of all of
+    /// `hashes`, minus any change which is an ancestor of another change in the set, sorted by
+    /// hash. It is empty if `hashes` is empty or the changes have no history in common.
+    ///
+    /// This walks the history of each change so it is linear in the size of the history.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::MissingHash`] if any of the changes is not in this document
+    pub fn common_ancestors(
+        &self,
+        hashes: &[ChangeHash],
+    ) -> Result<Vec<ChangeHash>, AutomergeError> {
+        self.check_hashes(hashes)?;
+        Ok(self
+            .change_graph
+            .common_ancestors(hashes)
+            .expect("the hashes were checked above"))
+    }
+
+    fn check_hashes(&self, hashes: &[ChangeHash]) -> Result<(), AutomergeError> {
+        match hashes.iter().find(|h| !self.history_index.contains_key(h)) {
+            Some(missing) => Err(AutomergeError::MissingHash(*missing)),
+            None => Ok(()),
+        }
+    }
+
+    /// The latest change applied from each actor which has made changes to this document
+    ///
+    /// See [`ActorFrontier`] for details.
+    pub fn actor_frontier(&self) -> HashMap<ActorId, ActorFrontier> {
+        self.states
+            .iter()
+            .filter_map(|(actor_index, changes)| {
+                let change = &self.history[*changes.last()?];
+                let frontier = ActorFrontier {
+                    seq: changes.len() as u64,
+                    max_op: change.max_op(),
+                    hash: change.hash(),
+                };
+                Some((self.ops.osd.actors[*actor_index].clone(), frontier))
+            })
+            .collect()
+    }
+
+    /// Get the changes made by `actor` with a sequence number greater than `after_seq`, in order
+    ///
+    /// Passing the `seq` from another document's [`Self::actor_frontier()`] returns the changes
+    /// from `actor` which that document is missing.
+    pub fn get_changes_by_actor(&self, actor: &ActorId, after_seq: u64) -> Vec<&Change> {
+        self.ops
+            .osd
+            .actors
+            .lookup(actor)
+            .and_then(|index| self.states.get(&index))
+            .map(|changes| {
+                changes
+                    .iter()
+                    .skip(after_seq as usize)
+                    .map(|i| &self.history[*i])
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Get the last change this actor made to the document.
     pub fn get_last_local_change(&self) -> Option<&Change> {
         return self
@@ -1213,9 +2243,70 @@ impl Automerge {
         pred: &OpIds,
         patch_log: &mut PatchLog,
     ) -> Result<(), AutomergeError> {
-        let is_delete = op.is_delete();
         let idx = self.ops.load(*obj, op);
+        self.insert_loaded_op(obj, idx, pred, patch_log)
+    }
+
+    /// Insert the ops of a change, in order
+    ///
+    /// Ops which insert directly after the element inserted by the op before them, which is what
+    /// typing produces, go immediately after that element as it can't have any successors or
+    /// children yet. Their position is known without searching the tree, so a long run of typed
+    /// characters is inserted in one pass. This is only done when patches are not being logged,
+    /// as logging needs the visible index which the search computes.
+    pub(crate) fn insert_ops(
+        &mut self,
+        ops: Vec<(ObjId, OpBuilder, OpIds)>,
+        patch_log: &mut PatchLog,
+    ) -> Result<(), AutomergeError> {
+        if patch_log.is_active() {
+            for (obj, op, pred) in ops {
+                self.insert_op(&obj, op, &pred, patch_log)?;
+            }
+            return Ok(());
+        }
+        // the object, ID and position of the element the previous op inserted
+        let mut last_insert: Option<(ObjId, OpId, usize)> = None;
+        for (obj, op, pred) in ops {
+            let after = match last_insert {
+                Some((last_obj, last_id, pos))
+                    if last_obj == obj && op.insert && op.key == Key::Seq(ElemId(last_id)) =>
+                {
+                    Some(pos + 1)
+                }
+                _ => None,
+            };
+            let (id, insert) = (op.id, op.insert);
+            let idx = self.ops.load(obj, op);
+            let op = idx.as_op(&self.ops.osd);
+            let is_delete = op.is_delete();
+            let found = match after {
+                Some(pos) => self.ops.found_op_without_patch_log(&obj, op, &pred, pos),
+                None => self.ops.find_op_without_patch_log(&obj, op, &pred),
+            };
+            self.ops.add_succ(&obj, &found.succ, idx);
+            if !is_delete {
+                self.ops.insert(found.pos, &obj, idx);
+            }
+            last_insert = if insert {
+                Some((obj, id, found.pos))
+            } else {
+                None
+            };
+        }
+        Ok(())
+    }
+
+    /// Insert an op which has already been loaded into the op set data
+    pub(crate) fn insert_loaded_op(
+        &mut self,
+        obj: &ObjId,
+        idx: OpIdx,
+        pred: &OpIds,
+        patch_log: &mut PatchLog,
+    ) -> Result<(), AutomergeError> {
         let op = idx.as_op(&self.ops.osd);
+        let is_delete = op.is_delete();
 
         let (pos, succ) = if patch_log.is_active() {
             let obj = self.get_obj_meta(*obj)?;
@@ -1252,17 +2343,247 @@ impl Automerge {
         patch_log.make_patches(self)
     }
 
-    /// Get the heads of this document.
+    /// Make this document read only
+    ///
+    /// While a document is frozen every method which would change its contents returns
+    /// [`AutomergeError::Frozen`]. This includes operations in transactions as well as applying,
+    /// merging or loading changes from elsewhere, so it can be used to serve archived versions of
+    /// a document or to enforce read only access. Empty changes created with
+    /// [`crate::AutoCommit::empty_change()`] are not prevented as they have no contents. Reading
+    /// the document, saving it and generating sync messages are unaffected.
+    ///
+    /// Forks of a frozen document are not frozen.
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    /// Allow changes to a document frozen with [`Self::freeze()`] again
+    pub fn thaw(&mut self) {
+        self.frozen = false;
+    }
+
+    /// Whether this document has been frozen with [`Self::freeze()`]
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub(crate) fn check_frozen(&self) -> Result<(), AutomergeError> {
+        if self.frozen {
+            Err(AutomergeError::Frozen)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Limit what local operations may write to this document
+    ///
+    /// Operations which would exceed one of the limits return an error such as
+    /// [`AutomergeError::KeyTooLong`] before they are added to the transaction, so a server
+    /// which accepts edits from untrusted clients can reject them before they are committed and
+    /// synced to every peer. Changes applied from elsewhere are not checked. The limits are
+    /// shared with forks of this document.
+    pub fn set_write_limits(&mut self, limits: WriteLimits) {
+        self.write_limits = limits;
+    }
+
+    /// The limits set with [`Self::set_write_limits()`]
+    pub fn write_limits(&self) -> &WriteLimits {
+        &self.write_limits
+    }
+
+    /// Check every op of the changes applied to this document against its current state
+    ///
+    /// By default changes from other peers are only checked for references to ops which can't
+    /// exist. With strict validation each op must also be in an object which exists, use a map
+    /// key or list element which matches the type of the object, refer to list elements and
+    /// predecessors which exist and only increment counters. A change which fails these checks
+    /// is rejected with [`AutomergeError::InvalidChangeOp`] describing the first invalid op,
+    /// rather than being applied with dangling references. This is intended for servers which
+    /// accept changes from clients which aren't trusted.
+    ///
+    /// This makes applying changes slower, as each reference is looked up in the document. The
+    /// setting is shared with forks of this document.
+    pub fn set_strict_validation(&mut self, enabled: bool) {
+        self.strict_validation = enabled;
+    }
+
+    /// Whether strict validation was enabled with [`Self::set_strict_validation()`]
+    pub fn strict_validation(&self) -> bool {
+        self.strict_validation
+    }
+
+    /// The number of objects between `obj` and the root of the document
+    pub(crate) fn object_depth(&self, obj: &ObjId) -> usize {
+        let mut depth = 0;
+        let mut obj = *obj;
+        while let Some(parent) = self.ops.parent_object(&obj, None) {
+            depth += 1;
+            obj = parent.obj;
+        }
+        depth
+    }
+
+    /// Create a new document containing the current state of this one in a single change
+    ///
+    /// The new document has none of the history of this one: deleted values, overwritten
+    /// values, conflicting values which lost and the metadata of every change are all dropped, so
+    /// it is usually much smaller when saved. It has a new random actor ID, as the compacted change
+    /// starts again at sequence number 1 and would otherwise clash with this document's changes.
+    ///
+    /// Because the history is different the new document should not be merged or synced with
+    /// copies of this document, doing so would duplicate every object in it. Every peer must
+    /// replace its copy with the compacted one. Marks are
+    /// recreated with [`ExpandMark::After`](crate::marks::ExpandMark::After) as the expand
+    /// setting of the original marks is not visible in the current state.
+    ///
+    /// Compacting only the history before some heads is not supported, as the changes after the
+    /// heads refer to the operations which would be dropped.
+    ///
+    /// ```
+    /// # use automerge::{transaction::Transactable, AutoCommit, ReadDoc, ROOT};
+    /// let mut doc = AutoCommit::new();
+    /// for i in 0..100 {
+    ///     doc.put(ROOT, "count", i).unwrap();
+    ///     doc.commit();
+    /// }
+    /// let compacted = doc.document().compact().unwrap();
+    /// assert_eq!(compacted.get_changes(&[]).len(), 1);
+    /// assert_eq!(compacted.get_int(ROOT, "count").unwrap(), Some(99));
+    /// assert!(compacted.save().len() < doc.save().len());
+    /// ```
+    pub fn compact(&self) -> Result<Self, AutomergeError> {
+        let mut doc = Self::new();
+        let mut tx = doc.transaction();
+        Subtree::export(self, &ExId::Root)?.write_contents(&mut tx, &ExId::Root)?;
+        tx.commit();
+        Ok(doc)
+    }
+
+    /// Get a [`ReadGuard`] which reads this document as at its current heads
+    pub fn read_guard(&self) -> ReadGuard<'_, Self> {
+        ReadGuard::new(self, self.get_heads())
+    }
+
+    /// Get a [`ReadGuard`] which reads this document as at `heads`
+    ///
+    /// Every [`ReadDoc`] method of the guard reads the historical state, so code written against
+    /// [`ReadDoc`] works with old versions of the document without using the `_at` variant of each
+    /// method.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::MissingHash`] if any of `heads` is not in this document
+    pub fn at(&self, heads: &[ChangeHash]) -> Result<ReadGuard<'_, Self>, AutomergeError> {
+        self.check_hashes(heads)?;
+        Ok(ReadGuard::new(self, heads.to_vec()))
+    }
+
+    /// Get the heads of this document, sorted by hash.
     pub fn get_heads(&self) -> Vec<ChangeHash> {
         let mut deps: Vec<_> = self.deps.iter().copied().collect();
         deps.sort_unstable();
         deps
     }
 
+    /// The actor, sequence number, timestamp and message of each of the heads of this document,
+    /// in the same order as [`Self::get_heads()`]
+    pub fn heads_with_metadata(&self) -> Vec<HeadMetadata> {
+        self.get_heads()
+            .iter()
+            .filter_map(|hash| self.get_change_by_hash(hash))
+            .map(HeadMetadata::from)
+            .collect()
+    }
+
+    /// Put `heads` in the canonical order used by [`Self::get_heads()`]
+    ///
+    /// Heads are sorted by the bytes of their hash with duplicates removed, so two sets of heads
+    /// describe the same version exactly when their ordered forms are equal. Use this before
+    /// storing or comparing heads which came from elsewhere.
+    pub fn order_heads(heads: &[ChangeHash]) -> Vec<ChangeHash> {
+        let mut heads = heads.to_vec();
+        heads.sort_unstable();
+        heads.dedup();
+        heads
+    }
+
+    /// Compare two changes in this document by the largest op counter in each change and then
+    /// by actor ID
+    ///
+    /// This is the same lamport ordering used to order operations, so a change which contains
+    /// operations is always ordered after its dependencies. A change with no operations has the
+    /// same max op as the dependency with the largest max op, so it may be ordered before that
+    /// dependency depending on their actor IDs and hashes. Changes with the same max op and
+    /// actor are ordered by hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::MissingHash`] if either change is not in this document
+    pub fn cmp_changes(
+        &self,
+        left: &ChangeHash,
+        right: &ChangeHash,
+    ) -> Result<Ordering, AutomergeError> {
+        let key = |hash: &ChangeHash| {
+            self.get_change_by_hash(hash)
+                .map(|c| (c.max_op(), c.actor_id(), *hash))
+                .ok_or(AutomergeError::MissingHash(*hash))
+        };
+        Ok(key(left)?.cmp(&key(right)?))
+    }
+
+    /// Get the changes which are not transitive dependencies of `have_deps`
+    ///
+    /// The changes are returned in the order in which they were applied to this document, which
+    /// is a topological order but may differ between documents with the same heads.
     pub fn get_changes(&self, have_deps: &[ChangeHash]) -> Vec<&Change> {
         self.get_changes_clock(have_deps)
     }
 
+    /// Iterate over the changes in this document, in the order they were applied
+    ///
+    /// The order is topological, every change comes after its dependencies. Changes which are
+    /// waiting for missing dependencies are not included. The iterator can be narrowed with the
+    /// filters on [`History`].
+    ///
+    /// ```
+    /// # use automerge::{transaction::{CommitOptions, Transactable}, AutoCommit, ROOT};
+    /// let mut doc = AutoCommit::new();
+    /// doc.put(ROOT, "a", 1).unwrap();
+    /// doc.commit_with(CommitOptions::default().with_message("add a").with_time(10));
+    /// doc.put(ROOT, "b", 2).unwrap();
+    /// doc.commit_with(CommitOptions::default().with_message("add b").with_time(20));
+    ///
+    /// let later: Vec<_> = doc.history().in_time_range(15..).collect();
+    /// assert_eq!(later.len(), 1);
+    /// assert_eq!(later[0].1.message().unwrap(), "add b");
+    /// assert_eq!(doc.history().message_contains("add").count(), 2);
+    /// ```
+    pub fn history(&self) -> History<'_> {
+        History::new(&self.history)
+    }
+
+    /// Whether the change with `hash` has been applied to this document
+    ///
+    /// Changes which are waiting for their dependencies are not included.
+    pub fn has_change(&self, hash: &ChangeHash) -> bool {
+        self.history_index.contains_key(hash)
+    }
+
+    /// The hashes in `hashes` which do not correspond to a change in this document, in the order
+    /// they were given
+    ///
+    /// See [`Self::has_change()`]
+    pub fn filter_unknown_hashes<I: IntoIterator<Item = ChangeHash>>(
+        &self,
+        hashes: I,
+    ) -> Vec<ChangeHash> {
+        hashes
+            .into_iter()
+            .filter(|hash| !self.has_change(hash))
+            .collect()
+    }
+
     /// Get changes in `other` that are not in `self`
     pub fn get_changes_added<'a>(&self, other: &'a Self) -> Vec<&'a Change> {
         // Depth-first traversal from the heads through the dependency graph,
@@ -1289,6 +2610,346 @@ impl Automerge {
             .collect()
     }
 
+    /// Get the operations of the change with hash `hash`
+    ///
+    /// The operations are returned in the order they appear in the change, with their keys
+    /// resolved to strings and their IDs as [`ExId`]s, so that tools which audit the history of a
+    /// document can describe exactly what a change did.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::MissingHash`] if the change has not been applied to this document
+    pub fn change_ops(&self, hash: &ChangeHash) -> Result<Vec<ResolvedOp>, AutomergeError> {
+        let change = self
+            .get_change_by_hash(hash)
+            .ok_or(AutomergeError::MissingHash(*hash))?;
+        Ok(crate::resolved_op::resolve_ops(change, Some(&self.ops.osd.actors)).collect())
+    }
+
+    /// Get the elements of the sequence `obj` which existed at `since` and have been deleted since
+    ///
+    /// The elements are returned in the order they had at `since`, along with the last value they
+    /// had before being deleted. This allows applications to render a placeholder in place of
+    /// content which was removed, rather than having it silently disappear after a merge.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a list or text object
+    pub fn deleted_elements<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        since: &[ChangeHash],
+    ) -> Result<Vec<DeletedElement<'_>>, AutomergeError> {
+        let obj = self.exid_to_obj(obj.as_ref())?;
+        if !obj.typ.is_sequence() {
+            return Err(AutomergeError::InvalidOp(obj.typ));
+        }
+        let clock = self.clock_at(since);
+        Ok(self.ops.deleted_elements(&obj.id, obj.encoding, &clock))
+    }
+
+    /// Get the merged content of the text object `obj` along with the regions where concurrent
+    /// insertions from different actors ended up next to each other
+    ///
+    /// When two actors concurrently type at the same place, or replace the same range of text,
+    /// the merged text contains both of their insertions one after the other. Each such region is
+    /// returned as a [`TextConflict`] with the text each actor inserted, so an application can
+    /// show both versions and let the user choose, much like the conflict markers in git.
+    ///
+    /// Insertions are only reported as conflicting if they are adjacent in the merged text and
+    /// neither actor had seen the other's insertion when they made theirs. Text which was
+    /// concurrently deleted by one actor and kept by another is not reported, see
+    /// [`Self::deleted_elements()`] for a way to find deleted text.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidObjType`] if `obj` is not a text object
+    pub fn text_with_conflicts<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+    ) -> Result<TextWithConflicts, AutomergeError> {
+        let obj = obj.as_ref();
+        let obj_type = self.object_type(obj)?;
+        if obj_type != ObjType::Text {
+            return Err(AutomergeError::InvalidObjType {
+                expected: ObjType::Text,
+                unexpected: obj_type,
+            });
+        }
+
+        // consecutive characters written by the same change, along with one of the operations
+        // which wrote them
+        struct Run {
+            start: usize,
+            text: String,
+            op: OpId,
+        }
+        let mut runs: Vec<Run> = Vec::new();
+        let mut hashes = Vec::new();
+        for item in self.list_range(obj, ..) {
+            let hash = self
+                .hash_for_opid(&item.id)
+                .expect("every op in the document is in a change");
+            let value = item.value.to_str().unwrap_or_default();
+            match runs.last_mut() {
+                Some(run) if hashes.last() == Some(&hash) => run.text.push_str(value),
+                _ => {
+                    runs.push(Run {
+                        start: item.index,
+                        text: value.to_string(),
+                        op: self.exid_to_opid(&item.id)?,
+                    });
+                    hashes.push(hash);
+                }
+            }
+        }
+
+        let mut clocks = HashMap::new();
+        for hash in &hashes {
+            clocks
+                .entry(*hash)
+                .or_insert_with(|| self.clock_at(&[*hash]));
+        }
+        let concurrent = |a: usize, b: usize| {
+            !clocks[&hashes[b]].covers(&runs[a].op) && !clocks[&hashes[a]].covers(&runs[b].op)
+        };
+        let actor = |run: usize| runs[run].op.actor();
+
+        // Wherever two runs from concurrent changes meet, the conflict extends over the
+        // neighbouring runs from the same actors which are also concurrent with the other side.
+        let mut regions: Vec<(usize, usize)> = Vec::new();
+        for left in 0..runs.len().saturating_sub(1) {
+            let right = left + 1;
+            if !concurrent(left, right) {
+                continue;
+            }
+            let mut lo = left;
+            while lo > 0 && actor(lo - 1) == actor(left) && concurrent(lo - 1, right) {
+                lo -= 1;
+            }
+            let mut hi = right;
+            while hi + 1 < runs.len() && actor(hi + 1) == actor(right) && concurrent(left, hi + 1) {
+                hi += 1;
+            }
+            match regions.last_mut() {
+                Some((_, last_hi)) if lo <= *last_hi => *last_hi = (*last_hi).max(hi),
+                _ => regions.push((lo, hi)),
+            }
+        }
+
+        let end = self.length(obj);
+        let conflicts = regions
+            .into_iter()
+            .map(|(lo, hi)| {
+                let mut alternatives: Vec<(ActorId, String)> = Vec::new();
+                for run in &runs[lo..=hi] {
+                    let actor = &self.ops.osd.actors[run.op.actor()];
+                    match alternatives.iter_mut().find(|(a, _)| a == actor) {
+                        Some((_, text)) => text.push_str(&run.text),
+                        None => alternatives.push((actor.clone(), run.text.clone())),
+                    }
+                }
+                let region_end = runs.get(hi + 1).map(|next| next.start).unwrap_or(end);
+                TextConflict {
+                    range: runs[lo].start..region_end,
+                    alternatives,
+                }
+            })
+            .collect();
+        Ok(TextWithConflicts {
+            text: self.text(obj)?,
+            conflicts,
+        })
+    }
+
+    /// Share the data of the changes in this document with other documents using `cache`
+    ///
+    /// See [`ChangeCache`] for details.
+    pub fn intern_changes(&mut self, cache: &ChangeCache) {
+        for change in self.history.iter_mut().chain(self.queue.iter_mut()) {
+            change.intern(cache);
+        }
+    }
+
+    /// Count the live values, tombstones, conflicts and total operations stored for `obj`
+    ///
+    /// This walks every operation in the object so it is linear in the size of the object's
+    /// history. It is intended for diagnosing objects which have grown large rather than for
+    /// use on a hot path.
+    pub fn object_stats<O: AsRef<ExId>>(&self, obj: O) -> Result<ObjectStats, AutomergeError> {
+        let obj = self.exid_to_obj(obj.as_ref())?;
+        Ok(self.ops.object_stats(&obj.id, obj.typ))
+    }
+
+    /// What the most recent search of the op tree of any object in this document did, or `None`
+    /// if nothing has searched it yet, see [`crate::QueryStats`]
+    ///
+    /// This is for diagnosing slow list or text access, for example by calling it after each
+    /// read or write of a list in a test. The stats belong to the document rather than the
+    /// thread, so with a [`crate::SharedAutomerge`] they may be from another thread's search. A
+    /// copy of the document made with `clone()` or [`Self::fork()`] starts with no stats.
+    #[cfg(feature = "query-tracing")]
+    pub fn last_query_stats(&self) -> Option<crate::QueryStats> {
+        self.ops.osd.tracer.last()
+    }
+
+    /// The size of this document and the approximate memory used by its internal structures, see
+    /// [`DocumentStats`]
+    pub fn stats(&self) -> DocumentStats {
+        let changes_size = |changes: &[Change]| {
+            changes
+                .iter()
+                .map(|c| std::mem::size_of::<Change>() + c.raw_bytes().len())
+                .sum::<usize>()
+        };
+        DocumentStats {
+            ops: self.ops.len(),
+            objects: self.ops.num_objects(),
+            actors: self.ops.osd.actors.len(),
+            queued: self.queue.len(),
+            tree_depth: self.ops.max_tree_depth(),
+            heap: HeapStats {
+                ops: self.ops.osd.ops_heap_size(),
+                op_trees: self.ops.trees_heap_size(),
+                caches: self.ops.osd.caches_heap_size(),
+                history: changes_size(&self.history)
+                    + self.history_index.capacity() * std::mem::size_of::<(ChangeHash, usize)>(),
+                change_graph: self.change_graph.heap_size(),
+                queue: changes_size(&self.queue),
+            },
+        }
+    }
+
+    /// Every key or list element in the document which currently has more than one value
+    ///
+    /// Each item is the object, the prop within it and all of the conflicting values, as returned
+    /// by [`ReadDoc::get_all()`]. Objects are visited in the order they were created and the
+    /// props of each object in order, so the iteration is stable for a given document. Objects
+    /// which have been deleted are skipped.
+    ///
+    /// This walks every operation in the document, which is much cheaper than calling
+    /// [`ReadDoc::get_all()`] on every key, but is still linear in the size of the document's
+    /// history.
+    pub fn conflicts(&self) -> impl Iterator<Item = (ExId, Prop, Vec<(Value<'_>, ExId)>)> + '_ {
+        self.ops
+            .iter_objs()
+            .filter(move |(obj, _, _)| self.ops.parents(**obj, None).visible_path().is_some())
+            .flat_map(move |(obj, _, _)| {
+                let exid = self.ops.id_to_exid(obj.0);
+                let encoding = self
+                    .ops
+                    .type_and_encoding(obj)
+                    .map(|(_, encoding)| encoding)
+                    .unwrap_or_default();
+                self.ops
+                    .conflicted_props(obj, encoding)
+                    .into_iter()
+                    .map(move |prop| {
+                        let values = self
+                            .get_all_for(&exid, prop.clone(), None)
+                            .unwrap_or_default();
+                        (exid.clone(), prop, values)
+                    })
+            })
+    }
+
+    /// Every increment of the counter at `prop` in `obj`, in the order of their operation IDs
+    ///
+    /// This is the history behind the value returned by [`ReadDoc::get_counter()`]. If the
+    /// counter has conflicting values only the increments of the value returned by
+    /// [`ReadDoc::get()`] are included. Returns an empty vector if there is no value at `prop`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidValueType`] if the value at `prop` is not a counter.
+    pub fn counter_increments<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Vec<CounterIncrement>, AutomergeError> {
+        let obj = self.exid_to_obj(obj.as_ref())?;
+        let found = self
+            .ops
+            .seek_ops_by_prop(&obj.id, prop.into(), obj.encoding, None);
+        let counter = match found.ops.last() {
+            Some(op) if op.is_counter() => op,
+            Some(op) => {
+                return Err(AutomergeError::InvalidValueType {
+                    expected: "a counter".to_string(),
+                    unexpected: op.value().to_string(),
+                })
+            }
+            None => return Ok(Vec::new()),
+        };
+        let mut increments = counter
+            .succ()
+            .filter_map(|op| {
+                let by = op.get_increment_value()?;
+                let id = op.exid();
+                let change = self.hash_for_opid(&id)?;
+                let time = self.get_change_by_hash(&change)?.timestamp();
+                Some((
+                    *op.id(),
+                    CounterIncrement {
+                        actor: self.ops.osd.actors[op.id().actor()].clone(),
+                        id,
+                        by,
+                        change,
+                        time,
+                    },
+                ))
+            })
+            .collect::<Vec<_>>();
+        increments.sort_by(|(a, _), (b, _)| self.ops.osd.lamport_cmp(*a, *b));
+        Ok(increments.into_iter().map(|(_, inc)| inc).collect())
+    }
+
+    /// The index and time of every timestamp in the list `obj` which is within `range`
+    ///
+    /// Elements which are not timestamps are ignored. This is for lists of dates, such as the
+    /// events of a calendar, and scans the whole list.
+    ///
+    /// ```
+    /// # use std::time::{Duration, SystemTime};
+    /// # use automerge::{transaction::Transactable, Automerge, ObjType, ROOT};
+    /// let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    /// let mut doc = Automerge::new();
+    /// let mut tx = doc.transaction();
+    /// let events = tx.put_object(ROOT, "events", ObjType::List).unwrap();
+    /// for day in 0..10 {
+    ///     let time = start + Duration::from_secs(day * 24 * 60 * 60);
+    ///     tx.insert(&events, day as usize, time).unwrap();
+    /// }
+    /// tx.commit();
+    ///
+    /// let week = start..start + Duration::from_secs(7 * 24 * 60 * 60);
+    /// let found = doc.timestamps_in_range(&events, week).unwrap();
+    /// assert_eq!(found.len(), 7);
+    /// assert_eq!(found[0], (0, start));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a list or text object
+    pub fn timestamps_in_range<O: AsRef<ExId>, R: RangeBounds<SystemTime>>(
+        &self,
+        obj: O,
+        range: R,
+    ) -> Result<Vec<(usize, SystemTime)>, AutomergeError> {
+        let obj = obj.as_ref();
+        let typ = self.exid_to_obj(obj)?.typ;
+        if !typ.is_sequence() {
+            return Err(AutomergeError::InvalidOp(typ));
+        }
+        Ok(self
+            .list_range(obj, ..)
+            .filter_map(|item| {
+                let time = item.value.to_system_time()?;
+                range.contains(&time).then(|| (item.index, time))
+            })
+            .collect())
+    }
+
     /// Get the hash of the change that contains the given `opid`.
     ///
     /// Returns [`None`] if the `opid`:
@@ -1374,6 +3035,87 @@ impl Automerge {
         let clock = heads.map(|heads| self.clock_at(heads));
         self.hydrate_map(&ObjId::root(), clock.as_ref())
     }
+
+    /// Copy the object `obj` and everything nested in it, so it can be written into this or
+    /// another document with [`Transactable::import_subtree()`]
+    ///
+    /// ```
+    /// # use automerge::{transaction::Transactable, AutoCommit, Automerge, ObjType, ReadDoc, ROOT};
+    /// let mut template = AutoCommit::new();
+    /// let card = template.put_object(ROOT, "card", ObjType::Map).unwrap();
+    /// let title = template.put_object(&card, "title", ObjType::Text).unwrap();
+    /// template.splice_text(&title, 0, 0, "untitled").unwrap();
+    /// let subtree = template.export_subtree(&card).unwrap();
+    ///
+    /// let mut doc = AutoCommit::new();
+    /// let copy = doc.import_subtree(ROOT, "first", &subtree).unwrap();
+    /// let (_, title) = doc.get(&copy, "title").unwrap().unwrap();
+    /// assert_eq!(doc.text(&title).unwrap(), "untitled");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::NotAnObject`] or [`AutomergeError::UnknownActor`] if `obj` is
+    /// not an object in this document
+    pub fn export_subtree<O: AsRef<ExId>>(&self, obj: O) -> Result<Subtree, AutomergeError> {
+        Subtree::export(self, obj.as_ref())
+    }
+
+    /// The whole current state of the document as a nested [`hydrate::Value`], built in a single
+    /// traversal
+    ///
+    /// The result implements [`serde::Serialize`], so it can be converted to JSON or any other
+    /// serde format. Text objects serialize as strings.
+    ///
+    /// ```
+    /// # use automerge::{transaction::Transactable, AutoCommit, ObjType, ROOT};
+    /// let mut doc = AutoCommit::new();
+    /// let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    /// doc.insert(&list, 0, 1).unwrap();
+    /// let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    /// doc.splice_text(&text, 0, 0, "hello").unwrap();
+    /// assert_eq!(
+    ///     serde_json::to_string(&doc.materialize()).unwrap(),
+    ///     r#"{"list":[1],"text":"hello"}"#
+    /// );
+    /// ```
+    pub fn materialize(&self) -> hydrate::Value {
+        self.hydrate(None)
+    }
+
+    /// The state of the document as at `heads`, see [`Self::materialize()`]
+    pub fn materialize_at(&self, heads: &[ChangeHash]) -> hydrate::Value {
+        self.hydrate(Some(heads))
+    }
+
+    /// An overview of this document for listings, see [`DocumentSummary`]
+    ///
+    /// Only the values of the root keys in `root_keys` are hydrated, the rest of the summary is
+    /// computed from the change history.
+    pub fn summary(&self, root_keys: &[&str]) -> DocumentSummary {
+        let root = root_keys
+            .iter()
+            .filter_map(|key| {
+                let value = self.hydrate_key(&ObjId::root(), key, None)?;
+                Some((key.to_string(), value))
+            })
+            .collect();
+        DocumentSummary {
+            heads: self.get_heads(),
+            last_modified: self.history.iter().map(|c| c.timestamp()).max(),
+            actors: self.states.len(),
+            changes: self.history.len(),
+            root,
+        }
+    }
+
+    /// Serialize only the values found at `paths`
+    ///
+    /// Each path is a sequence of properties starting from the root of the document. The returned
+    /// value implements [`serde::Serialize`], see [`AutoSerdePaths`] for the format.
+    pub fn export_paths<'a>(&'a self, paths: &'a [Vec<Prop>]) -> AutoSerdePaths<'a, Self> {
+        AutoSerdePaths::new(self, paths)
+    }
 }
 
 impl Automerge {
@@ -1430,6 +3172,22 @@ impl Automerge {
             .unwrap_or(0)
     }
 
+    pub(crate) fn contains_key_for(&self, obj: &ExId, key: &str, clock: Option<Clock>) -> bool {
+        match self.exid_to_obj(obj) {
+            Ok(obj) if !obj.typ.is_sequence() => self.ops.has_map_key(&obj.id, key, clock.as_ref()),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn has_index_for(&self, obj: &ExId, index: usize, clock: Option<Clock>) -> bool {
+        match self.exid_to_obj(obj) {
+            Ok(obj) if obj.typ.is_sequence() => {
+                index < self.ops.length(&obj.id, obj.encoding, clock)
+            }
+            _ => false,
+        }
+    }
+
     pub(crate) fn text_for(
         &self,
         obj: &ExId,
@@ -1475,6 +3233,33 @@ impl Automerge {
         Ok(found.index)
     }
 
+    /// The index of the element of the sequence `obj` which was inserted by the operation `elem`
+    /// and whether the element is visible, a deleted element has the index it would have if it
+    /// were visible
+    pub(crate) fn element_index(
+        &self,
+        obj: &ExId,
+        elem: &ExId,
+        clock: Option<Clock>,
+    ) -> Result<(usize, bool), AutomergeError> {
+        let obj = self.exid_to_obj(obj)?;
+        let opid = self.exid_to_opid(elem)?;
+        let found = self
+            .ops
+            .seek_list_opid(&obj.id, opid, clock.as_ref())
+            .ok_or_else(|| AutomergeError::InvalidObjId(elem.to_string()))?;
+        // `found.visible` is whether the op which inserted the element is visible, the element is
+        // also visible if that op has been overwritten by one which is
+        let visible = found.visible
+            || self
+                .ops
+                .seek_ops_by_prop(&obj.id, found.index.into(), obj.encoding, clock.as_ref())
+                .ops
+                .last()
+                .map_or(false, |op| op.elemid_or_key() == Key::Seq(ElemId(opid)));
+        Ok((found.index, visible))
+    }
+
     pub(crate) fn marks_for(
         &self,
         obj: &ExId,
@@ -1670,6 +3455,24 @@ impl ReadDoc for Automerge {
         self.length_for(obj.as_ref(), Some(clock))
     }
 
+    fn contains_key<O: AsRef<ExId>>(&self, obj: O, key: &str) -> bool {
+        self.contains_key_for(obj.as_ref(), key, None)
+    }
+
+    fn contains_key_at<O: AsRef<ExId>>(&self, obj: O, key: &str, heads: &[ChangeHash]) -> bool {
+        let clock = self.clock_at(heads);
+        self.contains_key_for(obj.as_ref(), key, Some(clock))
+    }
+
+    fn has_index<O: AsRef<ExId>>(&self, obj: O, index: usize) -> bool {
+        self.has_index_for(obj.as_ref(), index, None)
+    }
+
+    fn has_index_at<O: AsRef<ExId>>(&self, obj: O, index: usize, heads: &[ChangeHash]) -> bool {
+        let clock = self.clock_at(heads);
+        self.has_index_for(obj.as_ref(), index, Some(clock))
+    }
+
     fn text<O: AsRef<ExId>>(&self, obj: O) -> Result<String, AutomergeError> {
         self.text_for(obj.as_ref(), None)
     }
@@ -1811,6 +3614,21 @@ pub struct SaveOptions {
     pub deflate: bool,
     /// Whether to save changes which we do not have the dependencies for
     pub retain_orphans: bool,
+    /// Columns smaller than this many bytes are not compressed even if [`Self::deflate`] is
+    /// `true`, as compressing small columns costs more time than it saves space
+    ///
+    /// The default is 256. Use `0` to compress every column.
+    pub deflate_threshold: usize,
+}
+
+impl SaveOptions {
+    fn compress_config(&self) -> CompressConfig {
+        if self.deflate {
+            CompressConfig::Threshold(self.deflate_threshold)
+        } else {
+            CompressConfig::None
+        }
+    }
 }
 
 impl std::default::Default for SaveOptions {
@@ -1818,6 +3636,7 @@ impl std::default::Default for SaveOptions {
         Self {
             deflate: true,
             retain_orphans: true,
+            deflate_threshold: storage::change::DEFLATE_MIN_SIZE,
         }
     }
 }
@@ -1863,5 +3682,29 @@ pub(crate) fn reconstruct_document<'a>(
         deps: heads.into_iter().collect(),
         actor: Actor::Unused(ActorId::random()),
         max_op,
+        change_hook: None,
+        skipped_change_hook: None,
+        op_observer: None,
+        signer: None,
+        verifier: None,
+        timestamp_source: None,
+        frozen: false,
+        write_limits: WriteLimits::default(),
+        strict_validation: false,
     })
 }
+
+/// Write the raw bytes of each of `changes` to `writer`, returning the number of bytes written
+fn write_changes<'a, I, W>(changes: I, writer: &mut W) -> std::io::Result<usize>
+where
+    I: Iterator<Item = &'a Change>,
+    W: std::io::Write,
+{
+    let mut written = 0;
+    for change in changes {
+        let bytes = change.raw_bytes();
+        writer.write_all(bytes)?;
+        written += bytes.len();
+    }
+    Ok(written)
+}
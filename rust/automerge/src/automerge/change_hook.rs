@@ -0,0 +1,100 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{ActorId, ChangeHash};
+
+/// Information about a change passed to the hook set with
+/// [`crate::Automerge::set_change_hook()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeApplied {
+    /// The hash of the change which was applied
+    pub hash: ChangeHash,
+    /// The number of operations in the change
+    pub ops: usize,
+    /// How long it took to apply the change. This is always zero on `wasm` targets, where there
+    /// is no monotonic clock available.
+    pub duration: Duration,
+    /// The number of changes still waiting for their dependencies after this change was applied
+    pub queued: usize,
+}
+
+/// A change passed to [`crate::Automerge::apply_changes()`] which was not applied when it was
+/// received, passed to the hook set with [`crate::Automerge::set_skipped_change_hook()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeSkipped {
+    /// The change was ignored because it has already been applied
+    AlreadyApplied { hash: ChangeHash },
+    /// The change was rejected because a different change with the same sequence number from
+    /// the same actor has already been applied
+    DuplicateSeq {
+        hash: ChangeHash,
+        actor: ActorId,
+        seq: u64,
+    },
+    /// The change was queued until its missing dependencies are applied
+    Queued {
+        hash: ChangeHash,
+        missing_deps: Vec<ChangeHash>,
+    },
+    /// The change was rejected because it is invalid
+    Invalid { hash: ChangeHash, reason: String },
+}
+
+impl ChangeSkipped {
+    /// The hash of the change which was skipped
+    pub fn hash(&self) -> ChangeHash {
+        match self {
+            Self::AlreadyApplied { hash }
+            | Self::DuplicateSeq { hash, .. }
+            | Self::Queued { hash, .. }
+            | Self::Invalid { hash, .. } => *hash,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SkippedChangeHook(pub(crate) Arc<dyn Fn(&ChangeSkipped) + Send + Sync>);
+
+impl fmt::Debug for SkippedChangeHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SkippedChangeHook").finish()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ChangeHook(pub(crate) Arc<dyn Fn(&ChangeApplied) + Send + Sync>);
+
+impl fmt::Debug for ChangeHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangeHook").finish()
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub(crate) struct Timer(std::time::Instant);
+
+#[cfg(not(target_family = "wasm"))]
+impl Timer {
+    pub(crate) fn start() -> Self {
+        Self(std::time::Instant::now())
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+#[cfg(target_family = "wasm")]
+pub(crate) struct Timer;
+
+#[cfg(target_family = "wasm")]
+impl Timer {
+    pub(crate) fn start() -> Self {
+        Self
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+
+use crate::exid::ExId;
+use crate::{ActorId, ChangeHash};
+
+use super::Automerge;
+
+/// A single inconsistency found by [`Automerge::check_invariants()`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvariantViolation {
+    /// `op` lists `other` as a successor but `other` does not list `op` as a predecessor, or
+    /// the other way round
+    SuccPredMismatch { op: ExId, other: ExId },
+    /// The cached number of visible elements in a sequence does not match the visible ops
+    IndexLength {
+        obj: ExId,
+        indexed: usize,
+        actual: usize,
+    },
+    /// The total op count of the op set does not match the number of ops in its objects
+    OpCount { recorded: usize, actual: usize },
+    /// A change in the history has no entry in the history index
+    MissingHistoryIndex(ChangeHash),
+    /// The history index points to the wrong position in the history
+    WrongHistoryIndex {
+        hash: ChangeHash,
+        indexed: usize,
+        actual: usize,
+    },
+    /// The history index has a different number of entries to the history
+    HistoryIndexLength { history: usize, index: usize },
+    /// The per actor state does not point at the change with the expected actor and seq
+    ActorState { actor: ActorId, seq: u64 },
+    /// One of the heads of the document is not in the history
+    UnknownHead(ChangeHash),
+    /// One of the heads of the document is a dependency of another change
+    HeadIsDependency(ChangeHash),
+    /// A change in the history has a larger op counter than the document
+    MaxOp { hash: ChangeHash, max_op: u64 },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SuccPredMismatch { op, other } => write!(
+                f,
+                "op {} and op {} disagree about their succ/pred relationship",
+                op, other
+            ),
+            Self::IndexLength {
+                obj,
+                indexed,
+                actual,
+            } => write!(
+                f,
+                "index of {} has length {} but {} elements are visible",
+                obj, indexed, actual
+            ),
+            Self::OpCount { recorded, actual } => {
+                write!(f, "op set records {} ops but has {}", recorded, actual)
+            }
+            Self::MissingHistoryIndex(hash) => {
+                write!(f, "change {} is missing from the history index", hash)
+            }
+            Self::WrongHistoryIndex {
+                hash,
+                indexed,
+                actual,
+            } => write!(
+                f,
+                "history index for {} is {} but the change is at {}",
+                hash, indexed, actual
+            ),
+            Self::HistoryIndexLength { history, index } => write!(
+                f,
+                "history has {} changes but the index has {} entries",
+                history, index
+            ),
+            Self::ActorState { actor, seq } => {
+                write!(f, "state for actor {} seq {} is inconsistent", actor, seq)
+            }
+            Self::UnknownHead(hash) => write!(f, "head {} is not in the history", hash),
+            Self::HeadIsDependency(hash) => {
+                write!(f, "head {} is a dependency of another change", hash)
+            }
+            Self::MaxOp { hash, max_op } => write!(
+                f,
+                "change {} has max op {} which exceeds the document",
+                hash, max_op
+            ),
+        }
+    }
+}
+
+/// The result of [`Automerge::check_invariants()`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InvariantReport {
+    pub violations: Vec<InvariantViolation>,
+}
+
+impl InvariantReport {
+    /// Whether no violations were found
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for InvariantReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "no invariant violations");
+        }
+        for violation in &self.violations {
+            writeln!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl Automerge {
+    /// Check the internal data structures of this document for consistency
+    ///
+    /// This walks every op and change in the document so it is expensive. It is intended to be
+    /// run in tests, or after a crash, to detect corruption early. Requires the
+    /// `debug-invariants` feature.
+    pub fn check_invariants(&self) -> InvariantReport {
+        let mut violations = self.ops.check_invariants();
+
+        if self.history_index.len() != self.history.len() {
+            violations.push(InvariantViolation::HistoryIndexLength {
+                history: self.history.len(),
+                index: self.history_index.len(),
+            });
+        }
+        for (actual, change) in self.history.iter().enumerate() {
+            let hash = change.hash();
+            match self.history_index.get(&hash) {
+                None => violations.push(InvariantViolation::MissingHistoryIndex(hash)),
+                Some(&indexed) if indexed != actual => {
+                    violations.push(InvariantViolation::WrongHistoryIndex {
+                        hash,
+                        indexed,
+                        actual,
+                    })
+                }
+                _ => {}
+            }
+            if change.max_op() > self.max_op {
+                violations.push(InvariantViolation::MaxOp {
+                    hash,
+                    max_op: change.max_op(),
+                });
+            }
+        }
+
+        for (actor_index, indices) in &self.states {
+            let actor = self.ops.osd.actors.get(*actor_index);
+            for (i, history_index) in indices.iter().enumerate() {
+                let seq = i as u64 + 1;
+                let consistent = self
+                    .history
+                    .get(*history_index)
+                    .map(|c| c.actor_id() == actor && c.seq() == seq)
+                    .unwrap_or(false);
+                if !consistent {
+                    violations.push(InvariantViolation::ActorState {
+                        actor: actor.clone(),
+                        seq,
+                    });
+                }
+            }
+        }
+
+        for head in self.get_heads() {
+            if !self.history_index.contains_key(&head) {
+                violations.push(InvariantViolation::UnknownHead(head));
+            } else if self.history.iter().any(|c| c.deps().contains(&head)) {
+                violations.push(InvariantViolation::HeadIsDependency(head));
+            }
+        }
+
+        InvariantReport { violations }
+    }
+}
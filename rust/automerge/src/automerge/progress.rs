@@ -0,0 +1,27 @@
+use std::fmt;
+
+/// How far through applying a batch of changes a document is, see
+/// [`crate::Automerge::apply_changes_with_progress()`] and
+/// [`crate::LoadOptions::on_progress()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApplyProgress {
+    /// The number of changes which have been dealt with so far
+    ///
+    /// This includes changes which were skipped because they had already been applied and
+    /// changes which were queued because their dependencies are missing, so it reaches
+    /// `total_changes` once every change has been looked at.
+    pub changes_processed: usize,
+    /// The number of changes in the batch
+    pub total_changes: usize,
+    /// The number of operations which have been applied so far
+    pub ops_applied: usize,
+}
+
+/// A function to report the progress of a load to
+pub(crate) struct ProgressFn<'a>(pub(crate) Box<dyn FnMut(ApplyProgress) + 'a>);
+
+impl<'a> fmt::Debug for ProgressFn<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressFn").finish()
+    }
+}
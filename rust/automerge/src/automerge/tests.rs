@@ -1587,6 +1587,18 @@ fn parents_iterator() {
     assert_eq!(parents.next(), None);
 }
 
+/// The length of `s` in the units text is indexed by, see the `utf8-indexing` and
+/// `utf16-indexing` features
+fn text_len(s: &str) -> usize {
+    if cfg!(feature = "utf8-indexing") {
+        s.len()
+    } else if cfg!(feature = "utf16-indexing") {
+        s.encode_utf16().count()
+    } else {
+        s.chars().count()
+    }
+}
+
 #[test]
 fn can_insert_a_grapheme_into_text() {
     let mut doc = Automerge::new();
@@ -1598,7 +1610,7 @@ fn can_insert_a_grapheme_into_text() {
     let s = doc.text(&text).unwrap();
     assert_eq!(s, polar_bear);
     let len = doc.length(&text);
-    assert_eq!(len, 4); // 4 utf8 chars
+    assert_eq!(len, text_len(polar_bear));
 }
 
 #[test]
@@ -1613,11 +1625,11 @@ fn long_strings_spliced_into_text_get_segmented_by_utf8_chars() {
     let s = doc.text(&text).unwrap();
     assert_eq!(s, polar_bear_army);
     let len = doc.length(&text);
-    assert_eq!(len, polar_bear.chars().count() * 100);
-    assert_eq!(len, 400);
+    assert_eq!(len, text_len(polar_bear) * 100);
 }
 
 #[test]
+#[cfg(not(any(feature = "utf8-indexing", feature = "utf16-indexing")))]
 fn splice_text_uses_unicode_scalars() {
     let mut doc = Automerge::new();
     let mut tx = doc.transaction();
@@ -1785,3 +1797,715 @@ fn hash_for_opid() {
     assert_eq!(doc.hash_for_opid(&id1), hash1);
     assert_eq!(doc.hash_for_opid(&id2), hash2);
 }
+
+#[test]
+fn export_and_import_queue() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    doc1.commit();
+    let first = doc1.get_last_local_change().unwrap().clone();
+    doc1.put(ROOT, "b", 2).unwrap();
+    doc1.commit();
+    let second = doc1.get_last_local_change().unwrap().clone();
+
+    // receive the second change without its dependency
+    let mut doc2 = Automerge::new();
+    doc2.apply_changes(vec![second]).unwrap();
+    assert_eq!(doc2.get(ROOT, "b").unwrap(), None);
+    let queue = doc2.export_queue();
+    assert!(!queue.is_empty());
+
+    // simulate a restart which loses the in memory queue
+    let mut doc3 = Automerge::new();
+    assert_eq!(doc3.import_queue(&queue).unwrap(), 1);
+    // importing twice doesn't duplicate the queue
+    assert_eq!(doc3.import_queue(&queue).unwrap(), 1);
+
+    doc3.apply_changes(vec![first]).unwrap();
+    assert!(doc3.export_queue().is_empty());
+    assert_eq!(doc3.get(ROOT, "b").unwrap().unwrap().0, Value::int(2));
+}
+
+#[test]
+fn diff_incremental_batch_sequence() {
+    let mut doc = AutoCommit::new();
+    doc.update_diff_cursor();
+    doc.put(ROOT, "a", 1).unwrap();
+    let first = doc.diff_incremental_batch();
+    assert_eq!(first.seq, 1);
+    assert!(first.before.is_empty());
+    assert_eq!(first.after, doc.get_heads());
+    assert_eq!(first.patches.len(), 1);
+
+    doc.put(ROOT, "b", 2).unwrap();
+    let second = doc.diff_incremental_batch();
+    assert_eq!(second.seq, 2);
+    assert!(second.follows(&first));
+    assert!(!first.follows(&second));
+
+    doc.put(ROOT, "c", 3).unwrap();
+    let _missed = doc.diff_incremental_batch();
+    let fourth = doc.diff_incremental_batch();
+    assert_eq!(fourth.seq, 4);
+    assert!(fourth.patches.is_empty());
+    assert!(!fourth.follows(&second));
+}
+
+#[test]
+fn object_stats() {
+    let mut doc1 = AutoCommit::new();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    for i in 0..5 {
+        doc1.insert(&list, i, i as i64).unwrap();
+    }
+    doc1.delete(&list, 0).unwrap();
+    doc1.delete(&list, 0).unwrap();
+    doc1.put(&list, 0, "updated").unwrap();
+    doc1.put(ROOT, "key", 1).unwrap();
+
+    let mut doc2 = doc1.fork();
+    doc1.put(ROOT, "key", 2).unwrap();
+    doc2.put(ROOT, "key", 3).unwrap();
+    doc2.put(ROOT, "other", 3).unwrap();
+    doc2.delete(ROOT, "other").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    let stats = doc1.object_stats(&list).unwrap();
+    assert_eq!(stats.obj_type, ObjType::List);
+    assert_eq!(stats.live, 3);
+    assert_eq!(stats.tombstones, 2);
+    assert_eq!(stats.conflicts, 0);
+    // five inserts and one put, deletes are recorded as successors rather than stored
+    assert_eq!(stats.ops, 6);
+
+    let stats = doc1.object_stats(ROOT).unwrap();
+    assert_eq!(stats.obj_type, ObjType::Map);
+    assert_eq!(stats.live, 2);
+    assert_eq!(stats.tombstones, 1);
+    assert_eq!(stats.conflicts, 1);
+    assert_eq!(stats.ops, 5);
+}
+
+#[test]
+fn check_invariants() {
+    let mut doc1 = AutoCommit::new();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    let text = doc1.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "hello").unwrap();
+    for i in 0..10 {
+        doc1.insert(&list, i, i as i64).unwrap();
+    }
+    doc1.delete(&list, 3).unwrap();
+    doc1.put(ROOT, "counter", ScalarValue::counter(1)).unwrap();
+    doc1.increment(ROOT, "counter", 2).unwrap();
+    let mut doc2 = doc1.fork();
+    doc1.put(ROOT, "key", "a").unwrap();
+    doc2.put(ROOT, "key", "b").unwrap();
+    doc2.splice_text(&text, 2, 1, "LL").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    doc1.commit();
+
+    let report = doc1.doc.check_invariants();
+    assert!(report.is_ok(), "{}", report);
+    let loaded = Automerge::load(&doc1.save()).unwrap();
+    assert!(loaded.check_invariants().is_ok());
+
+    let mut broken = doc1.doc.clone();
+    let hash = broken.history[0].hash();
+    broken.history_index.insert(hash, 5);
+    broken.deps.insert(hash);
+    let report = broken.check_invariants();
+    assert!(report
+        .violations
+        .contains(&InvariantViolation::WrongHistoryIndex {
+            hash,
+            indexed: 5,
+            actual: 0
+        }));
+    assert!(report
+        .violations
+        .contains(&InvariantViolation::HeadIsDependency(hash)));
+}
+
+#[test]
+fn merge_owned() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    let mut doc2 = doc1.fork();
+    doc2.put(ROOT, "b", 2).unwrap();
+    doc2.commit();
+    let mut doc3 = doc2.fork();
+    doc3.put(ROOT, "c", 3).unwrap();
+    doc3.commit();
+    // a document holding the change from doc3 in its queue as its dependency is missing
+    let mut queued = Automerge::new();
+    queued
+        .apply_changes(vec![doc3.get_last_local_change().unwrap().clone()])
+        .unwrap();
+    doc1.put(ROOT, "d", 4).unwrap();
+
+    let mut expected = doc1.fork();
+    expected.merge(&mut doc3).unwrap();
+
+    doc1.merge_owned(doc2).unwrap();
+    assert_eq!(doc1.get(ROOT, "b").unwrap().unwrap().0, Value::int(2));
+    doc1.doc.merge_owned(queued).unwrap();
+    assert_eq!(doc1.get_heads(), expected.get_heads());
+    assert_eq!(doc1.save(), expected.save());
+}
+
+#[test]
+fn save_is_independent_of_application_order() {
+    let mut base = AutoCommit::new();
+    base.put(ROOT, "base", 0).unwrap();
+    let mut forks = (0..4).map(|_| base.fork()).collect::<Vec<_>>();
+    for (i, fork) in forks.iter_mut().enumerate() {
+        fork.put(ROOT, "key", i as i64).unwrap();
+        fork.commit();
+    }
+    let changes = forks
+        .iter_mut()
+        .map(|f| f.get_last_local_change().unwrap().clone())
+        .collect::<Vec<_>>();
+
+    let mut doc1 = base.fork();
+    doc1.apply_changes(changes.clone()).unwrap();
+    let mut doc2 = base.fork();
+    doc2.apply_changes(changes.into_iter().rev()).unwrap();
+
+    assert_ne!(
+        doc1.get_changes(&[])
+            .iter()
+            .map(|c| c.hash())
+            .collect::<Vec<_>>(),
+        doc2.get_changes(&[])
+            .iter()
+            .map(|c| c.hash())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(doc1.save(), doc2.save());
+    assert_eq!(doc1.save(), Automerge::load(&doc2.save()).unwrap().save());
+}
+
+#[test]
+fn intern_changes_shares_change_bytes() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "a", 1).unwrap();
+    doc.commit();
+    doc.put(ROOT, "b", 2).unwrap();
+    let bytes = doc.save();
+
+    let ptrs = |doc: &Automerge| {
+        doc.get_changes(&[])
+            .iter()
+            .map(|c| c.raw_bytes().as_ptr())
+            .collect::<Vec<_>>()
+    };
+
+    // forking shares the change data
+    let forked = doc.doc.fork();
+    assert_eq!(ptrs(&forked), ptrs(&doc.doc));
+
+    let mut doc1 = Automerge::load(&bytes).unwrap();
+    let mut doc2 = Automerge::load(&bytes).unwrap();
+    assert_ne!(ptrs(&doc1), ptrs(&doc2));
+
+    let cache = ChangeCache::new();
+    doc1.intern_changes(&cache);
+    doc2.intern_changes(&cache);
+    assert_eq!(ptrs(&doc1), ptrs(&doc2));
+    assert_eq!(cache.len(), 2);
+    assert_eq!(doc1.save(), doc2.save());
+
+    drop(doc1);
+    drop(doc2);
+    assert!(cache.is_empty());
+    cache.prune();
+}
+
+#[test]
+fn change_hook_is_called_for_applied_changes() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    doc1.put(ROOT, "b", 2).unwrap();
+    doc1.commit();
+    let first = doc1.get_last_local_change().unwrap().clone();
+    doc1.put(ROOT, "c", 3).unwrap();
+    doc1.commit();
+    let second = doc1.get_last_local_change().unwrap().clone();
+
+    let applied = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut doc2 = Automerge::new();
+    let log = applied.clone();
+    doc2.set_change_hook(move |change| log.lock().unwrap().push(change.clone()));
+
+    // local changes don't trigger the hook
+    doc2.transact::<_, _, AutomergeError>(|tx| tx.put(ROOT, "local", 1))
+        .unwrap();
+    assert!(applied.lock().unwrap().is_empty());
+
+    doc2.apply_changes(vec![second.clone()]).unwrap();
+    assert!(applied.lock().unwrap().is_empty());
+    doc2.apply_changes(vec![first.clone()]).unwrap();
+    {
+        let applied = applied.lock().unwrap();
+        assert_eq!(
+            applied.iter().map(|c| c.hash).collect::<Vec<_>>(),
+            vec![first.hash(), second.hash()]
+        );
+        assert_eq!(applied[0].ops, 2);
+        // the second change is still queued when the first is applied
+        assert_eq!(applied[0].queued, 1);
+        assert_eq!(applied[1].ops, 1);
+        assert_eq!(applied[1].queued, 0);
+    }
+
+    doc2.clear_change_hook();
+    doc1.put(ROOT, "d", 4).unwrap();
+    doc2.merge(&mut doc1.doc).unwrap();
+    assert_eq!(applied.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn change_hooks_are_called_when_loading_into_an_empty_document() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    doc1.commit();
+    let first = doc1.get_last_local_change().unwrap().clone();
+    let bytes = doc1.save();
+
+    let applied = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let skipped = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut doc2 = Automerge::new();
+    let log = applied.clone();
+    doc2.set_change_hook(move |change| log.lock().unwrap().push(change.hash));
+    let log = skipped.clone();
+    doc2.set_skipped_change_hook(move |change| log.lock().unwrap().push(change.clone()));
+
+    doc2.load_incremental(&bytes).unwrap();
+    assert_eq!(*applied.lock().unwrap(), vec![first.hash()]);
+
+    // the hooks are still set for changes applied afterwards
+    doc2.apply_changes(vec![first.clone()]).unwrap();
+    assert_eq!(
+        *skipped.lock().unwrap(),
+        vec![ChangeSkipped::AlreadyApplied { hash: first.hash() }]
+    );
+    doc1.put(ROOT, "b", 2).unwrap();
+    doc1.commit();
+    doc2.merge(&mut doc1.doc).unwrap();
+    assert_eq!(applied.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn skipped_change_hook_is_called_for_changes_which_are_not_applied() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    doc1.commit();
+    let first = doc1.get_last_local_change().unwrap().clone();
+    doc1.put(ROOT, "b", 2).unwrap();
+    doc1.commit();
+    let second = doc1.get_last_local_change().unwrap().clone();
+    let mut other = AutoCommit::new().with_actor(doc1.get_actor().clone());
+    other.put(ROOT, "a", 3).unwrap();
+    other.commit();
+    let same_seq = other.get_last_local_change().unwrap().clone();
+
+    let skipped = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut doc2 = Automerge::new();
+    let log = skipped.clone();
+    doc2.set_skipped_change_hook(move |change| log.lock().unwrap().push(change.clone()));
+
+    doc2.apply_changes(vec![second.clone()]).unwrap();
+    doc2.apply_changes(vec![first.clone(), first.clone()])
+        .unwrap();
+    assert!(doc2.apply_changes(vec![same_seq.clone()]).is_err());
+    assert_eq!(
+        *skipped.lock().unwrap(),
+        vec![
+            ChangeSkipped::Queued {
+                hash: second.hash(),
+                missing_deps: vec![first.hash()],
+            },
+            ChangeSkipped::AlreadyApplied { hash: first.hash() },
+            ChangeSkipped::DuplicateSeq {
+                hash: same_seq.hash(),
+                actor: doc1.get_actor().clone(),
+                seq: 1,
+            },
+        ]
+    );
+
+    doc2.clear_skipped_change_hook();
+    doc2.apply_changes(vec![first]).unwrap();
+    assert_eq!(skipped.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn read_guard_reads_at_pinned_heads() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.insert(&list, 0, 1).unwrap();
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+    doc.put(ROOT, "key", "old").unwrap();
+    let old_heads = doc.get_heads();
+    {
+        let guard = doc.read_guard();
+        assert_eq!(guard.heads(), &old_heads[..]);
+        assert_eq!(
+            guard.get(ROOT, "key").unwrap().unwrap().0,
+            Value::str("old")
+        );
+    }
+
+    doc.insert(&list, 1, 2).unwrap();
+    doc.splice_text(&text, 5, 0, " world").unwrap();
+    doc.put(ROOT, "key", "new").unwrap();
+    doc.put(ROOT, "other", 1).unwrap();
+    let new_heads = doc.get_heads();
+
+    let guard = ReadGuard::new(&doc.doc, old_heads);
+    assert_eq!(
+        guard.get(ROOT, "key").unwrap().unwrap().0,
+        Value::str("old")
+    );
+    assert_eq!(guard.get_all(ROOT, "key").unwrap().len(), 1);
+    assert_eq!(
+        guard.keys(ROOT).collect::<Vec<_>>(),
+        vec!["key", "list", "text"]
+    );
+    assert_eq!(guard.map_range(ROOT, ..).count(), 3);
+    assert_eq!(guard.values(ROOT).count(), 3);
+    assert_eq!(guard.length(&list), 1);
+    assert_eq!(guard.list_range(&list, ..).count(), 1);
+    assert_eq!(guard.text(&text).unwrap(), "hello");
+    assert_eq!(guard.text_at(&text, &new_heads).unwrap(), "hello world");
+    let cursor = guard.get_cursor(&text, 4, None).unwrap();
+    assert_eq!(guard.get_cursor_position(&text, &cursor, None).unwrap(), 4);
+}
+
+#[test]
+fn deleted_elements_since_heads() {
+    let mut doc1 = AutoCommit::new();
+    let list = doc1.put_object(ROOT, "messages", ObjType::List).unwrap();
+    for msg in ["a", "b", "c", "d"] {
+        let len = doc1.length(&list);
+        doc1.insert(&list, len, msg).unwrap();
+    }
+    let since = doc1.get_heads();
+    assert!(doc1.deleted_elements(&list, &since).unwrap().is_empty());
+
+    let mut doc2 = doc1.fork();
+    doc1.put(&list, 1, "b edited").unwrap();
+    doc1.delete(&list, 1).unwrap();
+    doc2.delete(&list, 3).unwrap();
+    // elements inserted and deleted after `since` are not reported
+    doc2.insert(&list, 0, "new").unwrap();
+    doc2.delete(&list, 0).unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    let (_, b_id) = doc1.get_at(&list, 1, &since).unwrap().unwrap();
+
+    let deleted = doc1.deleted_elements(&list, &since).unwrap();
+    assert_eq!(
+        deleted
+            .iter()
+            .map(|d| (d.index, d.value.clone()))
+            .collect::<Vec<_>>(),
+        vec![(1, Value::str("b edited")), (3, Value::str("d"))]
+    );
+    assert_eq!(deleted[0].id, b_id);
+    assert_ne!(deleted[0].value_id, b_id);
+
+    let heads = doc1.get_heads();
+    assert!(doc1.deleted_elements(&list, &heads).unwrap().is_empty());
+    assert_eq!(
+        doc1.deleted_elements(ROOT, &since),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    );
+}
+
+#[test]
+fn changes_referencing_nonexistent_ops_are_rejected() {
+    let mut doc1 = AutoCommit::new();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc1.insert(&list, 0, "a").unwrap();
+    doc1.put(ROOT, "key", "value").unwrap();
+    doc1.commit();
+    let mut doc2 = doc1.fork();
+    doc2.put(ROOT, "key", "other").unwrap();
+    doc2.insert(&list, 1, "b").unwrap();
+    doc2.commit();
+    let valid = doc2.get_last_local_change().unwrap().decode();
+    let bad_actor = doc1.get_actor().clone();
+
+    let tamper = |f: &dyn Fn(&mut legacy::Op)| {
+        let mut change = valid.clone();
+        change.hash = None;
+        for op in change.operations.iter_mut() {
+            f(op);
+        }
+        Change::from(change)
+    };
+    let bad_pred = tamper(&|op| {
+        if !op.pred.is_empty() {
+            op.pred = vec![legacy::OpId(1000, bad_actor.clone())].into();
+        }
+    });
+    let bad_elem = tamper(&|op| {
+        if let legacy::Key::Seq(legacy::ElementId::Id(_)) = op.key {
+            op.key = legacy::Key::Seq(legacy::ElementId::Id(legacy::OpId(1000, bad_actor.clone())));
+        }
+    });
+    let bad_obj = tamper(&|op| {
+        if op.obj != legacy::ObjectId::Root {
+            op.obj = legacy::ObjectId::Id(legacy::OpId(1000, bad_actor.clone()));
+        }
+    });
+
+    let heads = doc1.get_heads();
+    for bad in [bad_pred, bad_elem, bad_obj] {
+        let hash = bad.hash();
+        match doc1.apply_changes(vec![bad]) {
+            Err(AutomergeError::InvalidOpReference {
+                change,
+                counter,
+                actor,
+            }) => {
+                assert_eq!(change, hash);
+                assert_eq!(counter, 1000);
+                assert_eq!(actor, bad_actor);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(doc1.get_heads(), heads);
+        assert!(doc1.get_change_by_hash(&hash).is_none());
+    }
+
+    doc1.apply_changes(vec![Change::from(valid)]).unwrap();
+    assert_eq!(
+        doc1.get(ROOT, "key").unwrap().unwrap().0,
+        Value::from("other")
+    );
+    assert_eq!(doc1.length(&list), 2);
+}
+
+#[test]
+fn strict_validation_rejects_ops_with_dangling_references() {
+    let mut doc1 = AutoCommit::new();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc1.insert(&list, 0, "a").unwrap();
+    doc1.put(ROOT, "key", "value").unwrap();
+    doc1.commit();
+    let mut doc2 = doc1.fork();
+    doc2.put(ROOT, "key", "other").unwrap();
+    doc2.insert(&list, 1, "b").unwrap();
+    doc2.commit();
+    let valid = doc2.get_last_local_change().unwrap().decode();
+    let actor = doc1.get_actor().clone();
+    // ops which exist in doc1 but are not where the tampered ops say they are
+    let make_list = legacy::OpId(1, actor.clone());
+    let insert_a = legacy::OpId(2, actor.clone());
+    let put_key = legacy::OpId(3, actor.clone());
+
+    let tamper = |f: &dyn Fn(&mut legacy::Op)| {
+        let mut change = valid.clone();
+        change.hash = None;
+        for op in change.operations.iter_mut() {
+            f(op);
+        }
+        Change::from(change)
+    };
+    let put_op = |op: &legacy::Op| op.key == legacy::Key::Map("key".into());
+    let cases = vec![
+        (
+            tamper(&|op| {
+                if put_op(op) {
+                    op.pred = vec![insert_a.clone()].into();
+                }
+            }),
+            InvalidOpReason::MissingPred(ExId::Id(2, actor.clone(), 0)),
+        ),
+        (
+            tamper(&|op| {
+                if op.insert {
+                    op.key = legacy::Key::Seq(legacy::ElementId::Id(put_key.clone()));
+                }
+            }),
+            InvalidOpReason::MissingElement(ExId::Id(3, actor.clone(), 0)),
+        ),
+        (
+            tamper(&|op| {
+                if op.insert {
+                    op.obj = legacy::ObjectId::Id(put_key.clone());
+                }
+            }),
+            InvalidOpReason::MissingObject(ExId::Id(3, actor.clone(), 0)),
+        ),
+        (
+            tamper(&|op| {
+                if put_op(op) {
+                    op.obj = legacy::ObjectId::Id(make_list.clone());
+                }
+            }),
+            InvalidOpReason::KeyTypeMismatch {
+                obj: ExId::Id(1, actor.clone(), 0),
+                obj_type: ObjType::List,
+            },
+        ),
+        (
+            tamper(&|op| {
+                if put_op(op) {
+                    op.action = legacy::OpType::Increment(1);
+                }
+            }),
+            InvalidOpReason::IncrementNonCounter(ExId::Id(3, actor.clone(), 0)),
+        ),
+    ];
+
+    let heads = doc1.get_heads();
+    let mut lenient = doc1.fork();
+    doc1.set_strict_validation(true);
+    for (bad, expected) in cases {
+        let hash = bad.hash();
+        match doc1.apply_changes(vec![bad.clone()]) {
+            Err(AutomergeError::InvalidChangeOp { change, reason, .. }) => {
+                assert_eq!(change, hash);
+                assert_eq!(reason.to_string(), expected.to_string());
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(doc1.get_heads(), heads);
+        assert!(doc1.get_change_by_hash(&hash).is_none());
+        // the other references are only caught by strict validation, a map key in a list can't
+        // be applied at all
+        if !matches!(expected, InvalidOpReason::KeyTypeMismatch { .. }) {
+            let mut lenient = lenient.fork();
+            lenient.apply_changes(vec![bad]).unwrap();
+        }
+    }
+    lenient
+        .apply_changes(vec![Change::from(valid.clone())])
+        .unwrap();
+
+    doc1.apply_changes(vec![Change::from(valid)]).unwrap();
+    assert_eq!(doc1.length(&list), 2);
+    assert_eq!(doc1.get_heads(), lenient.get_heads());
+}
+
+#[test]
+fn strict_validation_applies_when_loading_into_an_empty_document() {
+    let mut source = AutoCommit::new();
+    source.put(ROOT, "key", "value").unwrap();
+    let list = source.put_object(ROOT, "list", ObjType::List).unwrap();
+    source.insert(&list, 0, "a").unwrap();
+    source.commit();
+    // insert into the value at "key", which is not an object
+    let mut change = source.get_last_local_change().unwrap().decode();
+    change.hash = None;
+    for op in change.operations.iter_mut().filter(|op| op.insert) {
+        op.obj = legacy::ObjectId::Id(legacy::OpId(1, source.get_actor().clone()));
+    }
+    let bad = Change::from(change);
+
+    let mut doc = AutoCommit::new();
+    doc.fork().load_incremental(bad.raw_bytes()).unwrap();
+    doc.set_strict_validation(true);
+    assert!(matches!(
+        doc.load_incremental(bad.raw_bytes()),
+        Err(AutomergeError::InvalidChangeOp { .. })
+    ));
+    assert!(doc.strict_validation());
+    assert!(doc.get_heads().is_empty());
+
+    let mut doc = Automerge::new();
+    doc.set_strict_validation(true);
+    assert!(matches!(
+        doc.load_incremental(bad.raw_bytes()),
+        Err(AutomergeError::InvalidChangeOp { .. })
+    ));
+    assert!(doc.strict_validation());
+    assert!(doc.get_heads().is_empty());
+}
+
+#[test]
+fn patches_filtered_by_obj_type() {
+    let mut doc = AutoCommit::new().with_text_rep(TextRepresentation::String);
+    doc.set_patch_obj_types(Some(&[ObjType::Text]));
+    assert_eq!(doc.get_patch_obj_types(), Some(&[ObjType::Text][..]));
+    doc.update_diff_cursor();
+
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+    doc.put(ROOT, "count", 1).unwrap();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, 1).unwrap();
+    // a text object which is only reported as part of a newly created map
+    let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+    let nested = doc.put_object(&map, "text", ObjType::Text).unwrap();
+    doc.splice_text(&nested, 0, 0, "nested").unwrap();
+
+    let patches = doc.diff_incremental();
+    assert!(patches
+        .iter()
+        .all(|p| matches!(p.action, PatchAction::SpliceText { .. })));
+    let mut objs = patches.iter().map(|p| p.obj.clone()).collect::<Vec<_>>();
+    objs.dedup();
+    assert_eq!(objs, vec![text.clone(), nested.clone()]);
+
+    // the unindexed diff is filtered the same way
+    let heads = doc.get_heads();
+    let patches = doc.diff(&[], &heads);
+    assert!(patches.iter().all(|p| p.obj == text || p.obj == nested));
+    assert_eq!(patches.len(), 2);
+
+    doc.set_patch_obj_types(None);
+    let patches = doc.diff(&[], &heads);
+    assert!(patches.iter().any(|p| p.obj == ROOT));
+    assert!(patches.iter().any(|p| p.obj == list));
+}
+
+#[test]
+fn change_ops_resolve_keys_and_ids() {
+    let mut doc1 = AutoCommit::new();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc1.insert(&list, 0, "a").unwrap();
+    doc1.commit();
+    let first_heads = doc1.get_heads();
+    let mut doc2 = doc1.fork();
+    doc2.put(ROOT, "title", "doc").unwrap();
+    doc2.insert(&list, 1, "b").unwrap();
+    doc2.delete(&list, 0).unwrap();
+    doc2.commit();
+    doc1.merge(&mut doc2).unwrap();
+
+    let hash = doc2.get_heads()[0];
+    let ops = doc1.change_ops(&hash).unwrap();
+    let (_, a) = doc1.get_at(&list, 0, &first_heads).unwrap().unwrap();
+    let (_, b) = doc1.get(&list, 0).unwrap().unwrap();
+    assert_eq!(ops.len(), 3);
+
+    assert_eq!(ops[0].obj, ROOT);
+    assert_eq!(ops[0].key, ResolvedKey::Map("title".to_string()));
+    assert_eq!(ops[0].action, OpType::Put("doc".into()));
+    assert!(ops[0].pred.is_empty());
+
+    assert_eq!(ops[1].obj, list);
+    assert_eq!(ops[1].key, ResolvedKey::Elem(a.clone()));
+    assert!(ops[1].insert);
+    assert_eq!(ops[1].id, b);
+
+    assert_eq!(ops[2].key, ResolvedKey::Elem(a.clone()));
+    assert_eq!(ops[2].action, OpType::Delete);
+    assert_eq!(ops[2].pred, vec![a]);
+
+    let first = doc1.change_ops(&first_heads[0]).unwrap();
+    assert_eq!(first[1].key, ResolvedKey::Head);
+    assert_eq!(first[0].action, OpType::Make(ObjType::List));
+    assert_eq!(first[0].id, list);
+
+    assert_eq!(
+        doc1.change_ops(&ChangeHash([0; 32])),
+        Err(AutomergeError::MissingHash(ChangeHash([0; 32])))
+    );
+}
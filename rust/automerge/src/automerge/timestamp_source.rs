@@ -0,0 +1,12 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// The function set with [`crate::Automerge::set_timestamp_source()`]
+#[derive(Clone)]
+pub(crate) struct TimestampSource(pub(crate) Arc<dyn Fn() -> i64 + Send + Sync>);
+
+impl fmt::Debug for TimestampSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimestampSource").finish()
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use crate::error::InvalidOpReason;
+use crate::types::{ElemId, Key, ObjId, OpBuilder, OpId, OpIds, OpType};
+use crate::{AutomergeError, Change, ObjType};
+
+use super::Automerge;
+
+/// What is known about an op earlier in the change being checked
+struct Pending {
+    obj: ObjId,
+    key: Key,
+    counter: bool,
+    make: Option<ObjType>,
+}
+
+/// Check that every op of `change` refers to objects, list elements and predecessors which
+/// exist, either in `doc` or earlier in the change, see [`Automerge::set_strict_validation()`]
+pub(super) fn check_ops(
+    doc: &Automerge,
+    change: &Change,
+    ops: &[(ObjId, OpBuilder, OpIds)],
+) -> Result<(), AutomergeError> {
+    let mut pending: HashMap<OpId, Pending> = HashMap::new();
+    for (obj, op, pred) in ops {
+        let invalid = |reason| AutomergeError::InvalidChangeOp {
+            change: change.hash(),
+            op: doc.ops.id_to_exid(op.id),
+            reason: Box::new(reason),
+        };
+        let obj_type = if obj.is_root() {
+            ObjType::Map
+        } else if let Some(obj_type) = doc.ops.object_type(obj) {
+            obj_type
+        } else if let Some(obj_type) = pending.get(&obj.0).and_then(|p| p.make) {
+            obj_type
+        } else {
+            return Err(invalid(InvalidOpReason::MissingObject(
+                doc.ops.id_to_exid(obj.0),
+            )));
+        };
+
+        match op.key {
+            Key::Map(_) if !obj_type.is_sequence() => {}
+            Key::Seq(ElemId(elem)) if obj_type.is_sequence() => {
+                let exists = ElemId(elem).is_head()
+                    || pending
+                        .get(&elem)
+                        .map_or(false, |p| p.obj == *obj && p.key == Key::Seq(ElemId(elem)))
+                    || doc.ops.seek_op_at_key(obj, &op.key, elem).is_some();
+                if !exists {
+                    return Err(invalid(InvalidOpReason::MissingElement(
+                        doc.ops.id_to_exid(elem),
+                    )));
+                }
+            }
+            _ => {
+                return Err(invalid(InvalidOpReason::KeyTypeMismatch {
+                    obj: doc.ops.id_to_exid(obj.0),
+                    obj_type,
+                }))
+            }
+        }
+
+        let key = op.elemid_or_key();
+        for p in pred.iter() {
+            let counter = match pending.get(p) {
+                Some(pending) if pending.obj == *obj && pending.key == key => Some(pending.counter),
+                _ => doc
+                    .ops
+                    .seek_op_at_key(obj, &key, *p)
+                    .map(|o| o.is_counter()),
+            };
+            match counter {
+                None => {
+                    return Err(invalid(InvalidOpReason::MissingPred(
+                        doc.ops.id_to_exid(*p),
+                    )))
+                }
+                Some(false) if matches!(op.action, OpType::Increment(_)) => {
+                    return Err(invalid(InvalidOpReason::IncrementNonCounter(
+                        doc.ops.id_to_exid(*p),
+                    )))
+                }
+                Some(_) => {}
+            }
+        }
+
+        let make = match op.action {
+            OpType::Make(obj_type) => Some(obj_type),
+            _ => None,
+        };
+        pending.insert(
+            op.id,
+            Pending {
+                obj: *obj,
+                key,
+                counter: op.is_counter(),
+                make,
+            },
+        );
+    }
+    Ok(())
+}
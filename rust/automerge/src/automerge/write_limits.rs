@@ -0,0 +1,72 @@
+use crate::{AutomergeError, Prop, ScalarValue};
+
+/// Limits on what local operations may write to a document, see
+/// [`crate::Automerge::set_write_limits()`]
+///
+/// Each limit is `None`, meaning unlimited, by default.
+///
+/// ```
+/// # use automerge::{transaction::Transactable, AutoCommit, AutomergeError, WriteLimits, ROOT};
+/// let mut doc = AutoCommit::new();
+/// doc.set_write_limits(WriteLimits::default().with_max_key_len(8));
+/// assert_eq!(
+///     doc.put(ROOT, "a very long key", 1),
+///     Err(AutomergeError::KeyTooLong { len: 15, max: 8 })
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteLimits {
+    /// The maximum depth of nested objects, objects in the root map have a depth of one
+    pub max_depth: Option<usize>,
+    /// The maximum length of a map key in bytes
+    pub max_key_len: Option<usize>,
+    /// The maximum length of a string value in bytes, this does not apply to text objects
+    pub max_string_len: Option<usize>,
+}
+
+impl WriteLimits {
+    pub fn with_max_depth(mut self, max: usize) -> Self {
+        self.max_depth = Some(max);
+        self
+    }
+
+    pub fn with_max_key_len(mut self, max: usize) -> Self {
+        self.max_key_len = Some(max);
+        self
+    }
+
+    pub fn with_max_string_len(mut self, max: usize) -> Self {
+        self.max_string_len = Some(max);
+        self
+    }
+
+    pub(crate) fn check_prop(&self, prop: &Prop) -> Result<(), AutomergeError> {
+        match (prop, self.max_key_len) {
+            (Prop::Map(key), Some(max)) if key.len() > max => Err(AutomergeError::KeyTooLong {
+                len: key.len(),
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_value(&self, value: &ScalarValue) -> Result<(), AutomergeError> {
+        match (value, self.max_string_len) {
+            (ScalarValue::Str(s), Some(max)) if s.len() > max => {
+                Err(AutomergeError::StringTooLong { len: s.len(), max })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that an object may be created in an object at `parent_depth`
+    pub(crate) fn check_depth(&self, parent_depth: usize) -> Result<(), AutomergeError> {
+        match self.max_depth {
+            Some(max) if parent_depth + 1 > max => Err(AutomergeError::TooDeep {
+                depth: parent_depth + 1,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
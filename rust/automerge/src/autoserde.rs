@@ -1,6 +1,6 @@
 use serde::ser::{SerializeMap, SerializeSeq};
 
-use crate::{ObjId, ObjType, ReadDoc, Value};
+use crate::{ObjId, ObjType, Prop, ReadDoc, Value};
 
 /// A wrapper type which implements [`serde::Serialize`] for a [`ReadDoc`].
 ///
@@ -40,6 +40,76 @@ impl<'a, R: crate::ReadDoc> serde::Serialize for AutoSerde<'a, R> {
     }
 }
 
+/// A wrapper type which implements [`serde::Serialize`] for a selection of paths in a
+/// [`ReadDoc`].
+///
+/// The selection serializes as a sequence with one element per requested path, in the order the
+/// paths were given. Each element is the value found at that path, with objects serialized the
+/// same way as [`AutoSerde`] serializes the whole document. Paths which don't resolve to a value
+/// serialize as `null`. Only the objects under the requested paths are visited, so this is much
+/// cheaper than serializing the whole document when only a small part of it is needed.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use automerge::{AutoCommit, ObjType, Prop, transaction::Transactable};
+/// let mut doc = AutoCommit::new();
+/// let config = doc.put_object(automerge::ROOT, "config", ObjType::Map)?;
+/// doc.put(&config, "theme", "dark")?;
+/// doc.put(automerge::ROOT, "large", "...")?;
+///
+/// let paths = vec![vec![Prop::from("config")], vec![Prop::from("missing")]];
+/// let serialized = serde_json::to_string(&doc.export_paths(&paths)).unwrap();
+///
+/// assert_eq!(serialized, r#"[{"theme":"dark"},null]"#);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AutoSerdePaths<'a, R: crate::ReadDoc> {
+    doc: &'a R,
+    paths: &'a [Vec<Prop>],
+}
+
+impl<'a, R: ReadDoc> AutoSerdePaths<'a, R> {
+    pub fn new(doc: &'a R, paths: &'a [Vec<Prop>]) -> Self {
+        AutoSerdePaths { doc, paths }
+    }
+
+    fn resolve(&self, path: &[Prop]) -> Option<(Value<'a>, ObjId)> {
+        let mut current = (Value::Object(ObjType::Map), ObjId::Root);
+        for prop in path {
+            let obj = match current {
+                (Value::Object(_), obj) => obj,
+                (Value::Scalar(_), _) => return None,
+            };
+            current = self.doc.get(&obj, prop.clone()).ok().flatten()?;
+        }
+        Some(current)
+    }
+}
+
+impl<'a, R: crate::ReadDoc> serde::Serialize for AutoSerdePaths<'a, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut seq_ser = serializer.serialize_seq(Some(self.paths.len()))?;
+        for path in self.paths {
+            match self.resolve(path) {
+                Some((val, obj)) => seq_ser.serialize_element(&AutoSerdeVal {
+                    doc: self.doc,
+                    val,
+                    obj,
+                })?,
+                None => seq_ser.serialize_element(&None::<()>)?,
+            }
+        }
+        seq_ser.end()
+    }
+}
+
 struct AutoSerdeMap<'a, R> {
     doc: &'a R,
     obj: ObjId,
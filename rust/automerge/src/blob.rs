@@ -0,0 +1,89 @@
+use std::io::{self, Read};
+
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ObjType, Prop, ReadDoc};
+
+/// The number of bytes in each element of a blob, except the last
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Write everything `reader` produces to a new blob at `prop` in `obj`, see
+/// [`Transactable::set_blob()`]
+pub(crate) fn write<T: Transactable + ?Sized, R: io::Read>(
+    tx: &mut T,
+    obj: &ExId,
+    prop: Prop,
+    mut reader: R,
+) -> Result<ExId, AutomergeError> {
+    let blob = tx.put_object(obj, prop, ObjType::List)?;
+    for index in 0.. {
+        let mut chunk = Vec::new();
+        (&mut reader)
+            .take(CHUNK_SIZE as u64)
+            .read_to_end(&mut chunk)
+            .map_err(AutomergeError::BlobRead)?;
+        if chunk.is_empty() {
+            break;
+        }
+        let last = chunk.len() < CHUNK_SIZE;
+        tx.insert(&blob, index, chunk)?;
+        if last {
+            break;
+        }
+    }
+    Ok(blob)
+}
+
+/// Reads the bytes of a blob, created by [`ReadDoc::read_blob()`]
+///
+/// The chunks of the blob are read from the document one at a time as they are needed.
+#[derive(Debug)]
+pub struct BlobReader<'a, D: ?Sized> {
+    doc: &'a D,
+    obj: ExId,
+    len: usize,
+    next_chunk: usize,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl<'a, D: ReadDoc + ?Sized> BlobReader<'a, D> {
+    pub(crate) fn new(doc: &'a D, obj: ExId) -> Result<Self, AutomergeError> {
+        let typ = doc.object_type(&obj)?;
+        if typ != ObjType::List {
+            return Err(AutomergeError::InvalidObjType {
+                expected: ObjType::List,
+                unexpected: typ,
+            });
+        }
+        Ok(Self {
+            len: doc.length(&obj),
+            doc,
+            obj,
+            next_chunk: 0,
+            chunk: Vec::new(),
+            pos: 0,
+        })
+    }
+}
+
+impl<'a, D: ReadDoc + ?Sized> Read for BlobReader<'a, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            if self.next_chunk == self.len {
+                return Ok(0);
+            }
+            self.chunk = self
+                .doc
+                .get_bytes(&self.obj, self.next_chunk)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                .unwrap_or_default();
+            self.next_chunk += 1;
+            self.pos = 0;
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
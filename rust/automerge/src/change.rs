@@ -1,4 +1,4 @@
-use std::{borrow::Cow, num::NonZeroU64};
+use std::{borrow::Cow, num::NonZeroU64, sync::Arc};
 
 use crate::{
     columnar::Key as StoredKey,
@@ -7,11 +7,13 @@ use crate::{
         parse, Change as StoredChange, ChangeOp, Chunk, Compressed, ReadChangeOpError,
     },
     types::{ActorId, ChangeHash, ElemId},
+    ChangeCache,
 };
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Change {
-    stored: StoredChange<'static, Verified>,
+    // shared so that cloning a change, e.g. when forking a document, doesn't copy its bytes
+    stored: Arc<StoredChange<'static, Verified>>,
     compression: CompressionState,
     len: usize,
 }
@@ -20,7 +22,7 @@ impl Change {
     pub(crate) fn new(stored: StoredChange<'static, Verified>) -> Self {
         let len = stored.len();
         Self {
-            stored,
+            stored: Arc::new(stored),
             len,
             compression: CompressionState::NotCompressed,
         }
@@ -38,7 +40,7 @@ impl Change {
             CompressionState::NotCompressed
         };
         Ok(Self {
-            stored,
+            stored: Arc::new(stored),
             len,
             compression,
         })
@@ -88,6 +90,27 @@ impl Change {
         self.stored.timestamp()
     }
 
+    /// Iterate over the operations in this change
+    ///
+    /// Unlike [`Self::decode()`] this decodes each operation as it is reached, so a history
+    /// browser can look at the first few operations of a large change without decoding all of
+    /// them.
+    ///
+    /// ```
+    /// # use automerge::{transaction::Transactable, AutoCommit, OpType, ResolvedKey, ROOT};
+    /// let mut doc = AutoCommit::new();
+    /// doc.put(ROOT, "title", "notes").unwrap();
+    /// doc.commit();
+    /// let change = doc.get_last_local_change().unwrap();
+    /// let op = change.iter_decoded_ops().next().unwrap();
+    /// assert_eq!(op.obj, ROOT);
+    /// assert_eq!(op.key, ResolvedKey::Map("title".to_string()));
+    /// assert_eq!(op.action, OpType::Put("notes".into()));
+    /// ```
+    pub fn iter_decoded_ops(&self) -> impl Iterator<Item = crate::ResolvedOp> + '_ {
+        crate::resolved_op::resolve_ops(self, None)
+    }
+
     pub fn bytes(&mut self) -> Cow<'_, [u8]> {
         if let CompressionState::NotCompressed = self.compression {
             if let Some(compressed) = self.stored.compress() {
@@ -104,10 +127,23 @@ impl Change {
         }
     }
 
+    /// The uncompressed bytes of this change, exactly as they are hashed
     pub fn raw_bytes(&self) -> &[u8] {
         self.stored.bytes()
     }
 
+    /// The checksum stored in the header of this change
+    ///
+    /// This is the first four bytes of [`Self::hash()`] and is checked by [`verify_change()`].
+    pub fn checksum(&self) -> [u8; 4] {
+        self.stored.checksum().bytes()
+    }
+
+    /// Replace the data of this change with the copy held in `cache`, if there is one
+    pub(crate) fn intern(&mut self, cache: &ChangeCache) {
+        self.stored = cache.intern(&self.stored);
+    }
+
     pub(crate) fn iter_ops(&self) -> impl Iterator<Item = ChangeOp> + '_ {
         self.stored.iter_ops()
     }
@@ -116,6 +152,13 @@ impl Change {
         self.stored.extra_bytes()
     }
 
+    /// The signature added to this change by a [`crate::Signer`], if it has one
+    ///
+    /// The signature is stored at the end of [`Self::extra_bytes()`].
+    pub fn signature(&self) -> Option<&[u8]> {
+        crate::signing::split_signature(self.extra_bytes()).map(|(_, signature)| signature)
+    }
+
     // TODO replace all uses of this with TryFrom<&[u8]>
     pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, LoadError> {
         Self::try_from(&bytes[..])
@@ -144,7 +187,7 @@ impl AsRef<StoredChange<'static, Verified>> for Change {
 
 impl From<Change> for StoredChange<'static, Verified> {
     fn from(c: Change) -> Self {
-        c.stored
+        Arc::try_unwrap(c.stored).unwrap_or_else(|stored| (*stored).clone())
     }
 }
 
@@ -156,6 +199,52 @@ pub enum LoadError {
     LeftoverData,
     #[error("wrong chunk type")]
     WrongChunkType,
+    #[error("checksum does not match the contents of the change")]
+    BadChecksum,
+    #[error("change was hashed with {found} but this process hashes changes with {expected}")]
+    HashAlgorithmMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+/// Check that `bytes` is a single well formed change whose checksum matches its contents
+///
+/// This is intended for storage systems which need to check the integrity of change records
+/// without loading them into a document. Both compressed and uncompressed changes are accepted.
+/// On success the hash of the change is returned, which can be compared with the hash the
+/// change was stored under.
+///
+/// # Errors
+///
+/// Returns [`LoadError::BadChecksum`] if the change has been corrupted,
+/// [`LoadError::HashAlgorithmMismatch`] if it was hashed with a different [`crate::ChangeHasher`],
+/// or one of the other
+/// variants of [`LoadError`] if the bytes are not a change at all.
+pub fn verify_change(bytes: &[u8]) -> Result<ChangeHash, LoadError> {
+    let input = parse::Input::new(bytes);
+    let (remaining, chunk) = Chunk::parse(input).map_err(|e| LoadError::Parse(Box::new(e)))?;
+    if !remaining.is_empty() {
+        return Err(LoadError::LeftoverData);
+    }
+    if !chunk.checksum_valid() {
+        return Err(match chunk.other_hash_algorithm() {
+            Some(found) => LoadError::HashAlgorithmMismatch {
+                expected: crate::hash::change_hasher().name(),
+                found,
+            },
+            None => LoadError::BadChecksum,
+        });
+    }
+    let change = match chunk {
+        Chunk::Change(c) => c,
+        Chunk::CompressedChange(c, _) => c,
+        _ => return Err(LoadError::WrongChunkType),
+    };
+    let change = change
+        .verify_ops(|_| ())
+        .map_err(|e| LoadError::Parse(Box::new(e)))?;
+    Ok(change.hash())
 }
 
 impl<'a> TryFrom<&'a [u8]> for Change {
@@ -208,6 +297,28 @@ impl From<crate::ExpandedChange> for Change {
     }
 }
 
+/// An arbitrary change, for fuzzing
+///
+/// The change is well formed, so it can be encoded and decoded, but its operations refer to
+/// arbitrary objects and its dependencies are arbitrary hashes, so most of them are rejected or
+/// queued by [`crate::Automerge::apply_changes()`].
+#[cfg(feature = "derive-arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Change {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut change = crate::ExpandedChange::arbitrary(u)?;
+        // changes whose ops have counters which don't fit in a u32 are rejected when decoded
+        let max_start = u64::from(u32::MAX) - change.operations.len() as u64;
+        change.start_op = u
+            .int_in_range(1..=max_start)
+            .map(|start| std::num::NonZeroU64::new(start).unwrap())?;
+        // an empty message is encoded the same way as no message
+        if change.message.as_deref() == Some("") {
+            change.message = None;
+        }
+        Ok(Change::from(change))
+    }
+}
+
 mod convert_expanded {
     use std::borrow::Cow;
 
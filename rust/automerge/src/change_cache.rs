@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::storage::{change::Verified, Change as StoredChange};
+use crate::ChangeHash;
+
+/// The smallest number of entries at which dead entries are removed when a change is interned
+const MIN_PRUNE_AT: usize = 64;
+
+#[derive(Debug, Default)]
+struct Entries {
+    changes: HashMap<ChangeHash, Weak<StoredChange<'static, Verified>>>,
+    /// Dead entries are removed when a change is interned and there are this many entries
+    prune_at: usize,
+}
+
+impl Entries {
+    fn prune(&mut self) {
+        self.changes.retain(|_, c| c.strong_count() > 0);
+        // waiting for the number of live entries to double keeps the cost of pruning constant
+        // per interned change
+        self.prune_at = std::cmp::max(self.changes.len() * 2, MIN_PRUNE_AT);
+    }
+}
+
+/// A cache which lets many documents in a process share the bytes of the changes they have in
+/// common
+///
+/// Cloning a document (for example with [`crate::Automerge::fork()`]) already shares the change
+/// data between the clones. Documents which were loaded separately hold their own copies though,
+/// which can add up when a process holds many copies of the same base document. Passing the same
+/// cache to [`crate::Automerge::intern_changes()`] for each of those documents replaces the
+/// duplicate copies with a single shared one.
+///
+/// The cache holds weak references so it does not keep changes alive once all the documents
+/// referencing them have been dropped. The entries for those changes are removed as more changes
+/// are interned, or by [`Self::prune()`]. Cloning the cache produces a handle to the same cache.
+///
+/// Actor IDs are not interned. An actor ID of up to 16 bytes, which includes the random IDs
+/// automerge generates, is stored inline without a separate allocation, so sharing it would not
+/// save any memory.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeCache {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl ChangeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of changes currently shared through the cache
+    pub fn len(&self) -> usize {
+        self.lock()
+            .changes
+            .values()
+            .filter(|c| c.strong_count() > 0)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove entries for changes which are no longer referenced by any document
+    pub fn prune(&self) {
+        self.lock().prune();
+    }
+
+    pub(crate) fn intern(
+        &self,
+        change: &Arc<StoredChange<'static, Verified>>,
+    ) -> Arc<StoredChange<'static, Verified>> {
+        let mut entries = self.lock();
+        let hash = change.hash();
+        if let Some(existing) = entries.changes.get(&hash).and_then(Weak::upgrade) {
+            existing
+        } else {
+            if entries.changes.len() >= entries.prune_at {
+                entries.prune();
+            }
+            entries.changes.insert(hash, Arc::downgrade(change));
+            change.clone()
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        // the entries are always left in a consistent state so it's safe to ignore poisoning
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, ROOT};
+
+    #[test]
+    fn dead_entries_are_removed_as_changes_are_interned() {
+        let cache = ChangeCache::new();
+        let mut kept = AutoCommit::new();
+        kept.put(ROOT, "kept", true).unwrap();
+        kept.intern_changes(&cache);
+        for i in 0..1000 {
+            let mut doc = AutoCommit::new();
+            doc.put(ROOT, "i", i).unwrap();
+            doc.intern_changes(&cache);
+        }
+        assert_eq!(cache.len(), 1);
+        assert!(cache.lock().changes.len() <= MIN_PRUNE_AT);
+
+        cache.prune();
+        assert_eq!(cache.lock().changes.len(), 1);
+    }
+}
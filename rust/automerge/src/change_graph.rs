@@ -58,6 +58,20 @@ impl ChangeGraph {
         }
     }
 
+    /// The approximate number of bytes allocated by the graph
+    pub(crate) fn heap_size(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<ChangeNode>()
+            + self.edges.capacity() * std::mem::size_of::<Edge>()
+            + self.hashes.capacity() * std::mem::size_of::<ChangeHash>()
+            + self.nodes_by_hash.len() * std::mem::size_of::<(ChangeHash, NodeIdx)>()
+            + self.clock_cache.capacity() * std::mem::size_of::<Clock>()
+            + self
+                .clock_cache
+                .iter()
+                .map(|c| c.num_actors() * std::mem::size_of::<(usize, ClockData)>())
+                .sum::<usize>()
+    }
+
     pub(crate) fn add_change(
         &mut self,
         change: &Change,
@@ -162,8 +176,21 @@ impl ChangeGraph {
 
     fn calculate_clock(&self, nodes: Vec<NodeIdx>) -> Clock {
         let mut clock = Clock::new();
+        // The union of the cached clocks merged into `clock` so far. A cached clock includes all
+        // the ancestors of the node it was cached for, so any node it covers has already been
+        // accounted for along with all of its ancestors and we don't need to visit them again.
+        // Without this the traversal of a history with many concurrent branches visits most of
+        // the graph for every clock it calculates.
+        let mut covered = Clock::new();
 
         self.traverse_ancestors(nodes, |node, idx| {
+            if covered
+                .get_for_actor(&node.actor_index)
+                .map(|data| data.seq >= node.seq)
+                .unwrap_or(false)
+            {
+                return false;
+            }
             clock.include(
                 node.actor_index,
                 ClockData {
@@ -175,6 +202,7 @@ impl ChangeGraph {
                 if cached_idx < self.clock_cache.len() {
                     let ancestor_clock = &self.clock_cache[cached_idx];
                     clock = Clock::merge(&clock, ancestor_clock);
+                    covered = Clock::merge(&covered, ancestor_clock);
                     return false; // dont look at ancestors
                 }
             }
@@ -197,6 +225,54 @@ impl ChangeGraph {
         });
     }
 
+    /// Whether `ancestor` is `descendant` or one of its transitive dependencies
+    ///
+    /// Returns `None` if either hash is not in the graph
+    pub(crate) fn is_ancestor(
+        &self,
+        ancestor: &ChangeHash,
+        descendant: &ChangeHash,
+    ) -> Option<bool> {
+        let ancestor = &self.nodes[self.nodes_by_hash.get(ancestor)?.0 as usize];
+        let descendant = *self.nodes_by_hash.get(descendant)?;
+        let clock = self.calculate_clock(vec![descendant]);
+        Some(
+            clock
+                .get_for_actor(&ancestor.actor_index)
+                .map(|data| data.seq >= ancestor.seq)
+                .unwrap_or(false),
+        )
+    }
+
+    /// The heads of the set of changes which are ancestors of every one of `hashes`
+    ///
+    /// Returns `None` if any of the hashes is not in the graph
+    pub(crate) fn common_ancestors(&self, hashes: &[ChangeHash]) -> Option<Vec<ChangeHash>> {
+        let mut common: Option<BTreeSet<NodeIdx>> = None;
+        for hash in hashes {
+            let node = *self.nodes_by_hash.get(hash)?;
+            let mut ancestors = BTreeSet::new();
+            self.traverse_ancestors(vec![node], |_, idx| {
+                if common.as_ref().map(|c| c.contains(&idx)).unwrap_or(true) {
+                    ancestors.insert(idx);
+                }
+                true
+            });
+            common = Some(ancestors);
+        }
+        let common = common.unwrap_or_default();
+        let parents = common
+            .iter()
+            .flat_map(|idx| self.parents(*idx))
+            .collect::<BTreeSet<_>>();
+        let mut heads = common
+            .difference(&parents)
+            .map(|idx| self.hashes[self.nodes[idx.0 as usize].hash_idx.0 as usize])
+            .collect::<Vec<_>>();
+        heads.sort();
+        Some(heads)
+    }
+
     /// Call `f` for each (node, hash) in the graph, starting from the given heads
     ///
     /// No guarantees are made about the order of traversal but each node will only be visited
@@ -264,6 +340,45 @@ mod tests {
         assert_eq!(clock, expected_clock);
     }
 
+    #[test]
+    fn cached_clocks_with_concurrent_branches() {
+        let mut builder = TestGraphBuilder::new();
+        let actors = (0..4).map(|_| builder.actor()).collect::<Vec<_>>();
+        let first = builder.change(&actors[0], 1, &[]);
+        let mut last = vec![first; actors.len()];
+        let mut heads = Vec::new();
+        for round in 0..(CACHE_STEP as usize * 3) {
+            for (i, actor) in actors.iter().enumerate() {
+                let mut deps = vec![last[i]];
+                // merge in another actor's work every few rounds
+                if round % 3 == i % 3 {
+                    let other = last[(i + 1) % actors.len()];
+                    if other != last[i] {
+                        deps.push(other);
+                    }
+                }
+                last[i] = builder.change(actor, 1, &deps);
+                heads.push(last[i]);
+            }
+        }
+        let graph = builder.build();
+
+        for head in heads.iter().step_by(7) {
+            let mut expected = Clock::new();
+            graph.traverse_ancestors(graph.heads_to_nodes(&[*head]), |node, _| {
+                expected.include(
+                    node.actor_index,
+                    ClockData {
+                        max_op: node.max_op,
+                        seq: node.seq,
+                    },
+                );
+                true
+            });
+            assert_eq!(graph.clock_for_heads(&[*head]), expected);
+        }
+    }
+
     #[test]
     fn remove_ancestors() {
         let mut builder = TestGraphBuilder::new();
@@ -287,6 +402,42 @@ mod tests {
         assert_eq!(changes, expected_changes);
     }
 
+    #[test]
+    fn ancestry() {
+        let mut builder = TestGraphBuilder::new();
+        let actor1 = builder.actor();
+        let actor2 = builder.actor();
+        let actor3 = builder.actor();
+        let change1 = builder.change(&actor1, 10, &[]);
+        let change2 = builder.change(&actor2, 20, &[change1]);
+        let change3 = builder.change(&actor3, 30, &[change1]);
+        let change4 = builder.change(&actor1, 10, &[change2, change3]);
+        let change5 = builder.change(&actor2, 10, &[change2]);
+        let graph = builder.build();
+
+        assert_eq!(graph.is_ancestor(&change1, &change4), Some(true));
+        assert_eq!(graph.is_ancestor(&change3, &change4), Some(true));
+        assert_eq!(graph.is_ancestor(&change4, &change4), Some(true));
+        assert_eq!(graph.is_ancestor(&change4, &change1), Some(false));
+        assert_eq!(graph.is_ancestor(&change3, &change5), Some(false));
+        assert_eq!(graph.is_ancestor(&ChangeHash([0; 32]), &change5), None);
+
+        assert_eq!(
+            graph.common_ancestors(&[change4, change5]),
+            Some(vec![change2])
+        );
+        assert_eq!(
+            graph.common_ancestors(&[change3, change5]),
+            Some(vec![change1])
+        );
+        assert_eq!(graph.common_ancestors(&[change4]), Some(vec![change4]));
+        assert_eq!(graph.common_ancestors(&[]), Some(vec![]));
+        assert_eq!(
+            graph.common_ancestors(&[change4, ChangeHash([0; 32])]),
+            None
+        );
+    }
+
     struct TestGraphBuilder {
         actors: Vec<ActorId>,
         changes: Vec<Change>,
@@ -47,6 +47,11 @@ impl Clock {
         Clock(Default::default())
     }
 
+    /// The number of actors in the clock
+    pub(crate) fn num_actors(&self) -> usize {
+        self.0.len()
+    }
+
     pub(crate) fn merge(a: &Clock, b: &Clock) -> Clock {
         if a.0.len() > b.0.len() {
             Self::merge(b, a)
@@ -169,12 +169,15 @@ impl<'a> KeyIter<'a> {
             (Some(None) | None, Some(Some(0)), Some(None) | None) => {
                 Ok(Some(Key::Elem(ElemId(OpId::new(0, 0)))))
             }
-            (Some(Some(actor)), Some(Some(ctr)), Some(None) | None) => match ctr.try_into() {
+            (Some(Some(actor)), Some(Some(ctr)), Some(None) | None) => match u32::try_from(ctr) {
                 //Ok(ctr) => Some(Ok(Key::Elem(ElemId(OpId(ctr, actor as usize))))),
-                Ok(ctr) => Ok(Some(Key::Elem(ElemId(OpId::new(ctr, actor as usize))))),
+                Ok(ctr) => Ok(Some(Key::Elem(ElemId(OpId::new(
+                    ctr as u64,
+                    actor as usize,
+                ))))),
                 Err(_) => Err(DecodeColumnError::invalid_value(
                     "counter",
-                    "negative value for counter",
+                    "negative or large value for counter",
                 )),
             },
             (None | Some(None), None | Some(None), None | Some(None)) => Ok(None),
@@ -133,7 +133,13 @@ impl<'a> ObjIdIter<'a> {
             .map_err(|e| DecodeColumnError::decode_raw("counter", e))?;
         match (actor, counter) {
             (None | Some(None), None | Some(None)) => Ok(Some(ObjId::root())),
-            (Some(Some(a)), Some(Some(c))) => Ok(Some(ObjId(OpId::new(c, a as usize)))),
+            (Some(Some(a)), Some(Some(c))) => match u32::try_from(c) {
+                Ok(c) => Ok(Some(ObjId(OpId::new(c as u64, a as usize)))),
+                Err(_) => Err(DecodeColumnError::invalid_value(
+                    "counter",
+                    "negative or large value encountered",
+                )),
+            },
             (_, Some(Some(0))) => Ok(Some(ObjId::root())),
             (Some(None) | None, _) => Err(DecodeColumnError::unexpected_null("actor")),
             (_, Some(None) | None) => Err(DecodeColumnError::unexpected_null("counter")),
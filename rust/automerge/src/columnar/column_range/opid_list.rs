@@ -207,12 +207,12 @@ impl<'a> OpIdListIter<'a> {
                 .transpose()
                 .map_err(|e| DecodeColumnError::decode_raw("counter", e))?;
             match (actor, counter) {
-                (Some(Some(a)), Some(Some(ctr))) => match ctr.try_into() {
-                    Ok(ctr) => p.push(OpId::new(ctr, a as usize)),
+                (Some(Some(a)), Some(Some(ctr))) => match u32::try_from(ctr) {
+                    Ok(ctr) => p.push(OpId::new(ctr as u64, a as usize)),
                     Err(_e) => {
                         return Err(DecodeColumnError::invalid_value(
                             "counter",
-                            "negative value for counter",
+                            "negative or large value for counter",
                         ))
                     }
                 },
@@ -0,0 +1,73 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::exid::ExId;
+use crate::ActorId;
+
+type Compare = dyn Fn(&ExId, &ExId) -> Ordering + Send + Sync;
+
+/// A policy for ordering the conflicting values of a property, used by
+/// [`ReadDoc::get_ordered()`](crate::ReadDoc::get_ordered) and
+/// [`ReadDoc::get_all_ordered()`](crate::ReadDoc::get_all_ordered)
+///
+/// Conflicting values are ordered from the lowest to the highest priority and the value with the
+/// highest priority is the one returned as the value of the property. The ordering used by
+/// [`ReadDoc::get()`](crate::ReadDoc::get) is [`ConflictOrder::OpId`]. Every peer which uses the
+/// same policy will choose the same value, but different policies can be used for different
+/// reads without changing the document.
+#[derive(Clone)]
+pub enum ConflictOrder {
+    /// Order values by the ID of the operation which set them, comparing the counter and then the
+    /// actor ID
+    ///
+    /// This is the ordering used internally so it is what [`ReadDoc::get()`](crate::ReadDoc::get)
+    /// returns.
+    OpId,
+    /// Give values set by this actor priority over values set by anyone else, and otherwise order
+    /// values as in [`ConflictOrder::OpId`]
+    PreferActor(ActorId),
+    /// Order values with a custom comparison of the IDs of the operations which set them
+    ///
+    /// The comparison must be a total order for the result to be deterministic.
+    Custom(Arc<Compare>),
+}
+
+impl ConflictOrder {
+    /// Create a [`ConflictOrder::Custom`] from a comparison function
+    pub fn custom<F>(compare: F) -> Self
+    where
+        F: Fn(&ExId, &ExId) -> Ordering + Send + Sync + 'static,
+    {
+        ConflictOrder::Custom(Arc::new(compare))
+    }
+
+    /// Compare the IDs of the operations which set two conflicting values
+    pub fn compare(&self, a: &ExId, b: &ExId) -> Ordering {
+        match self {
+            ConflictOrder::OpId => a.cmp(b),
+            ConflictOrder::PreferActor(actor) => {
+                let preferred =
+                    |id: &ExId| matches!(id, ExId::Id(_, id_actor, _) if id_actor == actor);
+                preferred(a).cmp(&preferred(b)).then_with(|| a.cmp(b))
+            }
+            ConflictOrder::Custom(compare) => compare(a, b),
+        }
+    }
+}
+
+impl Default for ConflictOrder {
+    fn default() -> Self {
+        ConflictOrder::OpId
+    }
+}
+
+impl fmt::Debug for ConflictOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictOrder::OpId => write!(f, "OpId"),
+            ConflictOrder::PreferActor(actor) => f.debug_tuple("PreferActor").field(actor).finish(),
+            ConflictOrder::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
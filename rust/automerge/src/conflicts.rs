@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use crate::{exid::ExId, ActorId, Value};
+
+/// The conflicting values of a property, returned by [`crate::ReadDoc::get_conflicts()`]
+///
+/// The values are keyed by the ID of the operation which set them, formatted as
+/// `counter@actor` as in the JavaScript `getConflicts`, and the value which
+/// [`crate::ReadDoc::get()`] returns is marked as the winner.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Conflicts {
+    values: BTreeMap<String, ConflictingValue>,
+    winner: Option<String>,
+}
+
+/// One of the values in [`Conflicts`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictingValue {
+    /// The value
+    pub value: Value<'static>,
+    /// The ID of the operation which set the value
+    pub id: ExId,
+    /// The actor which set the value
+    pub actor: ActorId,
+    /// Whether this is the value [`crate::ReadDoc::get()`] returns
+    pub winner: bool,
+}
+
+impl Conflicts {
+    pub(crate) fn new(values: Vec<(Value<'_>, ExId)>, winner: Option<&ExId>) -> Self {
+        let mut conflicts = Conflicts::default();
+        for (value, id) in values {
+            let actor = match &id {
+                ExId::Id(_, actor, _) => actor.clone(),
+                // the root is never the value of a property
+                ExId::Root => continue,
+            };
+            let key = id.to_string();
+            let is_winner = Some(&id) == winner;
+            if is_winner {
+                conflicts.winner = Some(key.clone());
+            }
+            conflicts.values.insert(
+                key,
+                ConflictingValue {
+                    value: value.into_owned(),
+                    id,
+                    actor,
+                    winner: is_winner,
+                },
+            );
+        }
+        conflicts
+    }
+
+    /// The number of values, if this is greater than one there is a conflict
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether the property has no value at all
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Whether more than one value was set concurrently
+    pub fn is_conflicted(&self) -> bool {
+        self.values.len() > 1
+    }
+
+    /// The value which was set by the operation with the ID `op_id`, formatted as
+    /// `counter@actor`
+    pub fn get(&self, op_id: &str) -> Option<&ConflictingValue> {
+        self.values.get(op_id)
+    }
+
+    /// The value which [`crate::ReadDoc::get()`] returns
+    pub fn winner(&self) -> Option<&ConflictingValue> {
+        self.winner.as_ref().and_then(|w| self.values.get(w))
+    }
+
+    /// The values keyed by the ID of the operation which set them, in order of the keys
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ConflictingValue)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
@@ -0,0 +1,17 @@
+use crate::exid::ExId;
+use crate::{ActorId, ChangeHash};
+
+/// An increment of a counter, returned by [`crate::Automerge::counter_increments()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterIncrement {
+    /// The ID of the increment operation
+    pub id: ExId,
+    /// The actor which made the increment
+    pub actor: ActorId,
+    /// The amount the counter was incremented by, which is negative for a decrement
+    pub by: i64,
+    /// The hash of the change containing the increment
+    pub change: ChangeHash,
+    /// The time of the change containing the increment, see [`crate::Change::timestamp()`]
+    pub time: i64,
+}
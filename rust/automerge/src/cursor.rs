@@ -14,6 +14,11 @@ use std::fmt;
 ///
 /// This can be persisted using [`Self::to_bytes()`] and [`TryFrom<&[u8]>`][TryFrom].
 ///
+/// Because a cursor refers to an element rather than an index it remains stable when changes
+/// (including concurrent ones from other actors) insert or delete elements before it. If the
+/// element the cursor refers to is itself deleted, the cursor resolves to the position the
+/// element would have occupied.
+///
 /// A cursor is obtained from [`ReadDoc::get_cursor()`] and dereferenced with
 /// [`ReadDoc::get_cursor_position()`].
 #[derive(Clone, PartialEq, Debug)]
@@ -0,0 +1,154 @@
+use std::convert::TryFrom;
+
+use crate::{ActorId, Automerge, AutomergeError, ChangeHash};
+
+const MAGIC: &[u8] = b"AMDB";
+const VERSION: u8 = 1;
+
+/// Everything needed to reproduce a problem with a document, see
+/// [`crate::Automerge::debug_bundle()`]
+///
+/// A bundle is written to a single artifact with [`Self::to_bytes()`] which can be attached to a
+/// bug report, and read back with [`Self::from_bytes()`]. [`Self::load()`] then rebuilds the
+/// document, including the changes which were waiting for their dependencies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugBundle {
+    /// The document, as saved by [`crate::Automerge::save()`]
+    pub document: Vec<u8>,
+    /// The changes waiting for missing dependencies, as exported by
+    /// [`crate::Automerge::export_queue()`]
+    pub queue: Vec<u8>,
+    /// The actors the document knows about
+    pub actors: Vec<ActorId>,
+    /// Counts describing the document
+    pub stats: DebugStats,
+    /// Patches the application had not yet observed, formatted for reading
+    ///
+    /// This is only filled in by [`crate::AutoCommit::debug_bundle()`].
+    pub patches: Vec<String>,
+}
+
+/// Counts describing a document in a [`DebugBundle`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebugStats {
+    /// The heads of the document
+    pub heads: Vec<ChangeHash>,
+    /// The number of changes in the document
+    pub changes: u64,
+    /// The number of operations in the document
+    pub ops: u64,
+    /// The number of changes waiting for missing dependencies
+    pub queued: u64,
+}
+
+/// An error reading a [`DebugBundle`]
+#[derive(Debug, thiserror::Error)]
+pub enum DebugBundleError {
+    #[error("data is not a debug bundle")]
+    NotABundle,
+    #[error("unsupported debug bundle version {0}")]
+    UnsupportedVersion(u8),
+    #[error("debug bundle is truncated")]
+    Truncated,
+    #[error("debug bundle contains invalid UTF-8")]
+    InvalidUtf8,
+}
+
+impl DebugBundle {
+    /// Encode the bundle as a single artifact
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        write_bytes(&mut out, &self.document);
+        write_bytes(&mut out, &self.queue);
+        write_u64(&mut out, self.actors.len() as u64);
+        for actor in &self.actors {
+            write_bytes(&mut out, actor.to_bytes());
+        }
+        write_u64(&mut out, self.stats.heads.len() as u64);
+        for head in &self.stats.heads {
+            write_bytes(&mut out, head.as_bytes());
+        }
+        write_u64(&mut out, self.stats.changes);
+        write_u64(&mut out, self.stats.ops);
+        write_u64(&mut out, self.stats.queued);
+        write_u64(&mut out, self.patches.len() as u64);
+        for patch in &self.patches {
+            write_bytes(&mut out, patch.as_bytes());
+        }
+        out
+    }
+
+    /// Read a bundle written by [`Self::to_bytes()`]
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DebugBundleError> {
+        let mut input = match data.strip_prefix(MAGIC) {
+            Some(rest) => rest,
+            None => return Err(DebugBundleError::NotABundle),
+        };
+        match input.split_first() {
+            Some((&VERSION, rest)) => input = rest,
+            Some((&version, _)) => return Err(DebugBundleError::UnsupportedVersion(version)),
+            None => return Err(DebugBundleError::Truncated),
+        }
+        let document = read_bytes(&mut input)?.to_vec();
+        let queue = read_bytes(&mut input)?.to_vec();
+        let actors = (0..read_u64(&mut input)?)
+            .map(|_| Ok(ActorId::from(read_bytes(&mut input)?)))
+            .collect::<Result<_, _>>()?;
+        let heads = (0..read_u64(&mut input)?)
+            .map(|_| {
+                ChangeHash::try_from(read_bytes(&mut input)?)
+                    .map_err(|_| DebugBundleError::Truncated)
+            })
+            .collect::<Result<_, _>>()?;
+        let stats = DebugStats {
+            heads,
+            changes: read_u64(&mut input)?,
+            ops: read_u64(&mut input)?,
+            queued: read_u64(&mut input)?,
+        };
+        let patches = (0..read_u64(&mut input)?)
+            .map(|_| {
+                String::from_utf8(read_bytes(&mut input)?.to_vec())
+                    .map_err(|_| DebugBundleError::InvalidUtf8)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(DebugBundle {
+            document,
+            queue,
+            actors,
+            stats,
+            patches,
+        })
+    }
+
+    /// Rebuild the document, with the changes which were waiting for dependencies queued again
+    pub fn load(&self) -> Result<Automerge, AutomergeError> {
+        let mut doc = Automerge::load(&self.document)?;
+        doc.import_queue(&self.queue)?;
+        Ok(doc)
+    }
+}
+
+fn write_u64(out: &mut Vec<u8>, n: u64) {
+    leb128::write::unsigned(out, n).unwrap();
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_u64(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn read_u64(input: &mut &[u8]) -> Result<u64, DebugBundleError> {
+    leb128::read::unsigned(input).map_err(|_| DebugBundleError::Truncated)
+}
+
+fn read_bytes<'a>(input: &mut &'a [u8]) -> Result<&'a [u8], DebugBundleError> {
+    let len = usize::try_from(read_u64(input)?).map_err(|_| DebugBundleError::Truncated)?;
+    if input.len() < len {
+        return Err(DebugBundleError::Truncated);
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
@@ -0,0 +1,16 @@
+use crate::exid::ExId;
+use crate::Value;
+
+/// An element of a sequence which has been deleted, returned by
+/// [`crate::Automerge::deleted_elements()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeletedElement<'a> {
+    /// The index of the element in the sequence as at the heads it was deleted since
+    pub index: usize,
+    /// The ID of the element, this is the same as the ID of the operation which inserted it
+    pub id: ExId,
+    /// The last value the element had before it was deleted
+    pub value: Value<'a>,
+    /// The ID of the operation which set [`Self::value`]
+    pub value_id: ExId,
+}
@@ -0,0 +1,49 @@
+/// Statistics about a whole document, returned by [`crate::Automerge::stats()`]
+///
+/// These are intended for capacity planning, for example deciding how many documents a server
+/// can keep loaded at once. Computing them walks the internal structures of the document but not
+/// every operation, so it is much cheaper than loading or saving the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocumentStats {
+    /// The number of operations stored in the document, including deleted and overwritten ones
+    pub ops: usize,
+    /// The number of objects in the document, including the root and deleted objects
+    pub objects: usize,
+    /// The number of distinct actors referred to by the operations in the document
+    pub actors: usize,
+    /// The number of changes waiting for missing dependencies
+    pub queued: usize,
+    /// The depth of the deepest op tree, a tree containing only a root node has depth 1
+    pub tree_depth: usize,
+    /// The approximate memory used by each internal structure
+    pub heap: HeapStats,
+}
+
+/// The approximate number of bytes allocated on the heap for each of the internal structures of
+/// a document, see [`DocumentStats`]
+///
+/// The sizes are computed from the capacity of the collections making up each structure and
+/// don't include allocator overhead or memory owned by individual values, such as the contents
+/// of long strings, so they are a lower bound.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// The operations themselves and the links between operations and their predecessors
+    pub ops: usize,
+    /// The trees which order the operations of each object and the indexes within them
+    pub op_trees: usize,
+    /// The actor IDs and map keys referred to by operations
+    pub caches: usize,
+    /// The encoded changes which make up the history of the document
+    pub history: usize,
+    /// The graph of dependencies between changes
+    pub change_graph: usize,
+    /// The encoded changes waiting for missing dependencies
+    pub queue: usize,
+}
+
+impl HeapStats {
+    /// The sum of all of the structures
+    pub fn total(&self) -> usize {
+        self.ops + self.op_trees + self.caches + self.history + self.change_graph + self.queue
+    }
+}
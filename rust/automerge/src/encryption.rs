@@ -0,0 +1,68 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+use crate::AutomergeError;
+
+/// Starts every envelope written by [`encrypt()`]
+const MAGIC: &[u8] = b"AMEC";
+const NONCE_LEN: usize = 12;
+
+/// Encrypt `data` with ChaCha20-Poly1305 under `key` using a random nonce
+///
+/// The output is an envelope made of [`MAGIC`], the nonce, the length of the ciphertext and the
+/// ciphertext. Envelopes can be concatenated, as the output of several incremental saves
+/// appended to one file would be, and [`decrypt()`] will decrypt each in turn. Empty data is
+/// left empty, so an incremental save with no changes in it still writes nothing.
+pub(crate) fn encrypt(key: &[u8; 32], data: &[u8]) -> Vec<u8> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+    let cipher = ChaCha20Poly1305::new(key.into());
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: data,
+                aad: MAGIC,
+            },
+        )
+        .expect("encryption only fails for messages larger than 256 GiB");
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + 10 + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    leb128::write::unsigned(&mut out, ciphertext.len() as u64).unwrap();
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+/// Decrypt a sequence of envelopes written by [`encrypt()`] and concatenate their contents
+pub(crate) fn decrypt(key: &[u8; 32], mut data: &[u8]) -> Result<Vec<u8>, AutomergeError> {
+    let cipher = ChaCha20Poly1305::new(key.into());
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let rest = data.strip_prefix(MAGIC).ok_or(AutomergeError::Decryption)?;
+        if rest.len() < NONCE_LEN {
+            return Err(AutomergeError::Decryption);
+        }
+        let (nonce, mut rest) = rest.split_at(NONCE_LEN);
+        let len = leb128::read::unsigned(&mut rest).map_err(|_| AutomergeError::Decryption)?;
+        let len = usize::try_from(len).map_err(|_| AutomergeError::Decryption)?;
+        if rest.len() < len {
+            return Err(AutomergeError::Decryption);
+        }
+        let (ciphertext, rest) = rest.split_at(len);
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: MAGIC,
+                },
+            )
+            .map_err(|_| AutomergeError::Decryption)?;
+        out.extend(plaintext);
+        data = rest;
+    }
+    Ok(out)
+}
@@ -1,3 +1,4 @@
+use crate::exid::ExId;
 use crate::storage::load::Error as LoadError;
 use crate::types::{ActorId, ScalarValue};
 use crate::value::DataType;
@@ -8,14 +9,23 @@ use thiserror::Error;
 pub enum AutomergeError {
     #[error(transparent)]
     ChangeGraph(#[from] crate::change_graph::MissingDep),
+    #[error("failed to read blob data: {0}")]
+    BlobRead(#[source] std::io::Error),
+    /// Only returned when the `encryption` feature is enabled
+    #[error("unable to decrypt the data, the key is wrong or the data is corrupt")]
+    Decryption,
     #[error("failed to load compressed data: {0}")]
     Deflate(#[source] std::io::Error),
     #[error("duplicate seq {0} found for actor {1}")]
     DuplicateSeqNumber(u64, ActorId),
     #[error("key must not be an empty string")]
     EmptyStringKey,
-    #[error("general failure")]
-    Fail,
+    #[error("incrementing the counter would overflow")]
+    CounterOverflow,
+    #[error("the document is frozen")]
+    Frozen,
+    #[error("counter bounds are invalid, min {min} is greater than max {max}")]
+    InvalidCounterBounds { min: i64, max: i64 },
     #[error("invalid actor ID `{0}`")]
     InvalidActorId(String),
     #[error(transparent)]
@@ -26,14 +36,37 @@ pub enum AutomergeError {
     InvalidHash(ChangeHash),
     #[error("index {0} is out of bounds")]
     InvalidIndex(usize),
+    #[error("the draft was not saved from the current state of this document")]
+    InvalidDraft,
     #[error("invalid obj id `{0}`")]
     InvalidObjId(String),
     #[error("invalid obj id format `{0}`")]
     InvalidObjIdFormat(String),
+    #[error("expected an object of type `{expected}` but found `{unexpected}`")]
+    InvalidObjType {
+        expected: ObjType,
+        unexpected: ObjType,
+    },
+    #[error("change {change} references op {counter}@{actor} which does not exist")]
+    InvalidOpReference {
+        change: ChangeHash,
+        counter: u64,
+        actor: ActorId,
+    },
     #[error("invalid op for object of type `{0}`")]
     InvalidOp(ObjType),
+    #[error("invalid path `{0}`")]
+    InvalidPath(String),
     #[error("seq {0} is out of bounds")]
     InvalidSeq(u64),
+    #[error("change {change} contains an invalid op {op}: {reason}")]
+    InvalidChangeOp {
+        change: ChangeHash,
+        op: ExId,
+        reason: Box<InvalidOpReason>,
+    },
+    #[error("change {0} does not have a valid signature")]
+    InvalidSignature(ChangeHash),
     #[error("cursor {0} is invalid")]
     InvalidCursor(Cursor),
     #[error("cursor format is invalid")]
@@ -43,6 +76,8 @@ pub enum AutomergeError {
         expected: String,
         unexpected: String,
     },
+    #[error("key is {len} bytes long, the limit is {max}")]
+    KeyTooLong { len: usize, max: usize },
     #[error(transparent)]
     Load(#[from] LoadError),
     #[error(transparent)]
@@ -55,8 +90,16 @@ pub enum AutomergeError {
     MissingDeps,
     #[error("compressed chunk was not a change")]
     NonChangeCompressed,
-    #[error("id was not an object id")]
-    NotAnObject,
+    #[error("`{0}` is not the id of an object")]
+    NotAnObject(ExId),
+    #[error("object `{0}` is not an ordered map")]
+    NotAnOrderedMap(ExId),
+    #[error("actor {0} has not made any changes to this document")]
+    UnknownActor(ActorId),
+    #[error("string is {len} bytes long, the limit is {max}")]
+    StringTooLong { len: usize, max: usize },
+    #[error("object would be nested {depth} deep, the limit is {max}")]
+    TooDeep { depth: usize, max: usize },
     #[error(transparent)]
     HydrateError(#[from] HydrateError),
 }
@@ -74,6 +117,21 @@ impl From<AutomergeError> for wasm_bindgen::JsValue {
     }
 }
 
+/// Why an op in a change was rejected by [`crate::Automerge::set_strict_validation()`]
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum InvalidOpReason {
+    #[error("object {0} does not exist")]
+    MissingObject(ExId),
+    #[error("the key does not match the type of object {obj}, which is a {obj_type}")]
+    KeyTypeMismatch { obj: ExId, obj_type: ObjType },
+    #[error("list element {0} does not exist")]
+    MissingElement(ExId),
+    #[error("predecessor {0} does not exist")]
+    MissingPred(ExId),
+    #[error("predecessor {0} of an increment is not a counter")]
+    IncrementNonCounter(ExId),
+}
+
 #[derive(Error, Debug)]
 #[error("Invalid actor ID: {0}")]
 pub struct InvalidActorId(pub String);
@@ -115,8 +173,6 @@ pub enum InvalidOpType {
 pub enum HydrateError {
     //#[error(transparent)]
     //ChangeGraph(#[from] crate::change_graph::MissingDep),
-    #[error("general failure")]
-    Fail,
     #[error("invalid index {0} for sequence")]
     InvalidIndex(usize),
     #[error("invalid key {0} for map")]
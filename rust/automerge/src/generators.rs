@@ -0,0 +1,275 @@
+//! Generate synthetic documents for load testing
+//!
+//! This module is only available with the `generators` feature. A [`DocumentGenerator`] produces
+//! a document with the history you would expect from several actors editing it concurrently: each
+//! actor works on its own copy of the document, occasionally merges in the work of another actor,
+//! and makes changes which are a mix of text edits, list edits and map updates. The result can be
+//! saved as bytes to benchmark storage, sync or load times with a representative corpus.
+//!
+//! Generation is deterministic, the same settings always produce the same document.
+//!
+//! ```
+//! # use automerge::{generators::DocumentGenerator, Automerge};
+//! let bytes = DocumentGenerator::new()
+//!     .actors(4)
+//!     .changes(100)
+//!     .ops_per_change(5)
+//!     .seed(42)
+//!     .generate_bytes();
+//! let doc = Automerge::load(&bytes).unwrap();
+//! assert_eq!(doc.get_changes(&[]).len(), 101);
+//! ```
+
+use crate::transaction::{CommitOptions, Transactable};
+use crate::{ActorId, AutoCommit, Automerge, ObjId, ObjType, ReadDoc, ScalarValue, ROOT};
+
+const WORDS: &[&str] = &[
+    "the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog", "lorem", "ipsum", "dolor",
+    "sit", "amet", "merge", "sync", "change",
+];
+
+/// Settings for generating a synthetic document, see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct DocumentGenerator {
+    actors: usize,
+    changes: usize,
+    ops_per_change: usize,
+    keys: usize,
+    merge_every: usize,
+    weights: [u32; 3],
+    seed: u64,
+}
+
+impl Default for DocumentGenerator {
+    fn default() -> Self {
+        Self {
+            actors: 3,
+            changes: 1000,
+            ops_per_change: 10,
+            keys: 100,
+            merge_every: 5,
+            weights: [1, 1, 1],
+            seed: 0,
+        }
+    }
+}
+
+impl DocumentGenerator {
+    /// Settings for a document with 3 actors making 1000 changes of 10 operations each
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of actors editing the document
+    pub fn actors(mut self, actors: usize) -> Self {
+        self.actors = actors.max(1);
+        self
+    }
+
+    /// The number of changes to make, not counting the change which creates the initial objects
+    pub fn changes(mut self, changes: usize) -> Self {
+        self.changes = changes;
+        self
+    }
+
+    /// The number of operations in each change
+    pub fn ops_per_change(mut self, ops: usize) -> Self {
+        self.ops_per_change = ops.max(1);
+        self
+    }
+
+    /// The number of distinct keys to put in the map
+    pub fn keys(mut self, keys: usize) -> Self {
+        self.keys = keys.max(1);
+        self
+    }
+
+    /// On average, how many changes an actor makes between merging the work of another actor
+    ///
+    /// Higher values produce more concurrent changes.
+    pub fn merge_every(mut self, changes: usize) -> Self {
+        self.merge_every = changes.max(1);
+        self
+    }
+
+    /// The relative frequency of text, list and map operations
+    pub fn weights(mut self, text: u32, list: u32, map: u32) -> Self {
+        self.weights = [text, list, map];
+        if self.weights.iter().all(|w| *w == 0) {
+            self.weights = [1, 1, 1];
+        }
+        self
+    }
+
+    /// The seed for the random choices made while generating
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Generate the document
+    ///
+    /// The root of the document has a `text` object, a `list` object and a `map` object which
+    /// the generated operations edit.
+    pub fn generate(&self) -> Automerge {
+        let mut rng = Rng::new(self.seed);
+        let mut base = AutoCommit::new().with_actor(rng.actor());
+        let text = base.put_object(ROOT, "text", ObjType::Text).unwrap();
+        let list = base.put_object(ROOT, "list", ObjType::List).unwrap();
+        let map = base.put_object(ROOT, "map", ObjType::Map).unwrap();
+        base.commit_with(CommitOptions::default().with_time(0));
+        let objs = Objects { text, list, map };
+
+        let mut docs = (0..self.actors)
+            .map(|_| base.fork().with_actor(rng.actor()))
+            .collect::<Vec<_>>();
+        for change in 0..self.changes {
+            let actor = rng.below(docs.len());
+            if docs.len() > 1 && rng.below(self.merge_every) == 0 {
+                let other = (actor + 1 + rng.below(docs.len() - 1)) % docs.len();
+                let changes = docs[actor]
+                    .doc
+                    .get_changes_added(&docs[other].doc)
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                docs[actor].apply_changes(changes).unwrap();
+            }
+            let doc = &mut docs[actor];
+            for _ in 0..self.ops_per_change {
+                self.random_op(doc, &objs, &mut rng);
+            }
+            let time = (change as i64 + 1) * 1000;
+            doc.commit_with(CommitOptions::default().with_time(time));
+        }
+
+        let mut result = base;
+        for doc in docs.iter_mut() {
+            result.merge(doc).unwrap();
+        }
+        result.doc
+    }
+
+    /// Generate the document and save it, see [`Self::generate()`]
+    pub fn generate_bytes(&self) -> Vec<u8> {
+        self.generate().save()
+    }
+
+    fn random_op(&self, doc: &mut AutoCommit, objs: &Objects, rng: &mut Rng) {
+        // summed as u64 so that large weights can't overflow
+        let total = self.weights.iter().map(|w| u64::from(*w)).sum::<u64>();
+        let mut choice = rng.next() % total;
+        let kind = self
+            .weights
+            .iter()
+            .position(|w| {
+                let w = u64::from(*w);
+                if choice < w {
+                    true
+                } else {
+                    choice -= w;
+                    false
+                }
+            })
+            .unwrap_or(2);
+        match kind {
+            0 => {
+                let len = doc.length(&objs.text);
+                if len > 0 && rng.below(4) == 0 {
+                    let pos = rng.below(len);
+                    doc.splice_text(&objs.text, pos, 1, "").unwrap();
+                } else {
+                    let pos = rng.below(len + 1);
+                    let word = format!("{} ", WORDS[rng.below(WORDS.len())]);
+                    doc.splice_text(&objs.text, pos, 0, &word).unwrap();
+                }
+            }
+            1 => {
+                let len = doc.length(&objs.list);
+                let value = ScalarValue::Int(rng.next() as i64 % 1000);
+                match rng.below(4) {
+                    0 if len > 0 => doc.delete(&objs.list, rng.below(len)).unwrap(),
+                    1 if len > 0 => doc.put(&objs.list, rng.below(len), value).unwrap(),
+                    _ => doc.insert(&objs.list, rng.below(len + 1), value).unwrap(),
+                }
+            }
+            _ => {
+                let key = format!("key{}", rng.below(self.keys));
+                if rng.below(2) == 0 {
+                    let value = WORDS[rng.below(WORDS.len())];
+                    doc.put(&objs.map, key, value).unwrap();
+                } else {
+                    doc.put(&objs.map, key, rng.next() as i64 % 1000).unwrap();
+                }
+            }
+        }
+    }
+}
+
+struct Objects {
+    text: ObjId,
+    list: ObjId,
+    map: ObjId,
+}
+
+/// A small deterministic random number generator (splitmix64)
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn actor(&mut self) -> ActorId {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.next().to_be_bytes());
+        bytes[8..].copy_from_slice(&self.next().to_be_bytes());
+        ActorId::from(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generation_is_deterministic() {
+        let generator = DocumentGenerator::new().actors(3).changes(50).seed(7);
+        let doc = generator.generate();
+        assert_eq!(doc.get_changes(&[]).len(), 51);
+        assert!(!doc.get_heads().is_empty());
+        assert!(doc.length(doc.get(ROOT, "list").unwrap().unwrap().1) > 0);
+        assert_eq!(generator.generate_bytes(), doc.save());
+        assert_ne!(generator.clone().seed(8).generate_bytes(), doc.save());
+
+        let text_only = DocumentGenerator::new()
+            .changes(10)
+            .weights(1, 0, 0)
+            .generate();
+        assert_eq!(
+            text_only
+                .keys(text_only.get(ROOT, "map").unwrap().unwrap().1)
+                .count(),
+            0
+        );
+
+        // weights which overflow a u32 when summed
+        let doc = DocumentGenerator::new()
+            .changes(10)
+            .weights(u32::MAX, u32::MAX, 1)
+            .generate();
+        assert_eq!(doc.get_changes(&[]).len(), 11);
+    }
+}
@@ -0,0 +1,41 @@
+//! Typed handles to objects in a document
+//!
+//! Rather than passing an [`ExId`](crate::ObjId) to every method on a document, a handle wraps a
+//! mutable reference to the document along with the ID of an object of a particular type. Creating
+//! a handle checks the type of the object, so the methods on the handle can only be used with
+//! objects for which they make sense.
+//!
+//! Handles are obtained from any [`Transactable`](crate::transaction::Transactable), for example
+//! [`Transactable::text_obj()`](crate::transaction::Transactable::text_obj).
+mod kv;
+mod list;
+mod map;
+mod ordered_map;
+mod table;
+mod text;
+
+pub use kv::{Kv, KvError, KvValue};
+pub use list::List;
+pub use map::Map;
+pub use ordered_map::OrderedMap;
+pub use table::Table;
+pub use text::Text;
+
+use crate::exid::ExId;
+use crate::{AutomergeError, ObjType, ReadDoc};
+
+pub(crate) fn check_type<D: ReadDoc + ?Sized>(
+    doc: &D,
+    obj: &ExId,
+    expected: ObjType,
+) -> Result<(), AutomergeError> {
+    let actual = doc.object_type(obj)?;
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(AutomergeError::InvalidObjType {
+            expected,
+            unexpected: actual,
+        })
+    }
+}
@@ -0,0 +1,182 @@
+use std::convert::TryFrom;
+
+use crate::exid::ExId;
+use crate::iter::Keys;
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ReadDoc, ScalarValue, Value};
+
+/// A flat key-value view of the root map of a document, obtained from [`Transactable::kv()`]
+///
+/// This is for documents which are just a replicated map of settings, where every value is a
+/// scalar. Values are read as Rust types rather than as [`Value`]s, and reading a key which holds
+/// a value of a different type is a [`KvError`] rather than a value to match on.
+///
+/// ```
+/// # use automerge::{transaction::Transactable, AutoCommit};
+/// let mut doc = AutoCommit::new();
+/// let mut kv = doc.kv();
+/// kv.put("retries", 3).unwrap();
+/// kv.put("host", "example.com").unwrap();
+/// assert_eq!(kv.get::<i64>("retries").unwrap(), Some(3));
+/// assert_eq!(kv.get::<String>("host").unwrap().as_deref(), Some("example.com"));
+/// assert!(kv.get::<bool>("host").is_err());
+/// assert_eq!(kv.get::<bool>("missing").unwrap(), None);
+/// ```
+#[derive(Debug)]
+pub struct Kv<'a, D: ?Sized> {
+    doc: &'a mut D,
+}
+
+/// An error reading or writing a [`Kv`]
+#[derive(Debug, thiserror::Error)]
+pub enum KvError {
+    #[error("expected `{key}` to be {expected} but found {found}")]
+    WrongType {
+        key: String,
+        expected: &'static str,
+        found: Value<'static>,
+    },
+    #[error(transparent)]
+    Automerge(#[from] AutomergeError),
+}
+
+/// A type which can be stored in a [`Kv`]
+pub trait KvValue: Into<ScalarValue> + Sized {
+    /// A description of the type for [`KvError::WrongType`]
+    const EXPECTED: &'static str;
+
+    /// Convert `value` to this type, if it holds a value of this type
+    fn from_scalar(value: &ScalarValue) -> Option<Self>;
+}
+
+impl KvValue for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_scalar(value: &ScalarValue) -> Option<Self> {
+        value.to_str().map(String::from)
+    }
+}
+
+impl KvValue for bool {
+    const EXPECTED: &'static str = "a boolean";
+
+    fn from_scalar(value: &ScalarValue) -> Option<Self> {
+        value.to_bool()
+    }
+}
+
+impl KvValue for i64 {
+    const EXPECTED: &'static str = "an integer";
+
+    fn from_scalar(value: &ScalarValue) -> Option<Self> {
+        match value {
+            ScalarValue::Int(n) => Some(*n),
+            ScalarValue::Uint(n) => i64::try_from(*n).ok(),
+            ScalarValue::Counter(c) => Some(c.into()),
+            _ => None,
+        }
+    }
+}
+
+impl KvValue for u64 {
+    const EXPECTED: &'static str = "an unsigned integer";
+
+    fn from_scalar(value: &ScalarValue) -> Option<Self> {
+        match value {
+            ScalarValue::Uint(n) => Some(*n),
+            ScalarValue::Int(n) => u64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl KvValue for f64 {
+    const EXPECTED: &'static str = "a number";
+
+    fn from_scalar(value: &ScalarValue) -> Option<Self> {
+        match value {
+            ScalarValue::F64(n) => Some(*n),
+            ScalarValue::Int(n) => Some(*n as f64),
+            ScalarValue::Uint(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+}
+
+impl KvValue for Vec<u8> {
+    const EXPECTED: &'static str = "bytes";
+
+    fn from_scalar(value: &ScalarValue) -> Option<Self> {
+        value.to_bytes().map(Vec::from)
+    }
+}
+
+impl KvValue for ScalarValue {
+    const EXPECTED: &'static str = "a scalar";
+
+    fn from_scalar(value: &ScalarValue) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl<'a, D: ReadDoc + ?Sized> Kv<'a, D> {
+    pub(crate) fn new(doc: &'a mut D) -> Self {
+        Self { doc }
+    }
+
+    /// The value of `key` as a `T`, or [`None`] if there is no value for `key`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KvError::WrongType`] if `key` holds an object or a scalar of a different type
+    pub fn get<T: KvValue>(&self, key: &str) -> Result<Option<T>, KvError> {
+        let value = match self.doc.get(ExId::Root, key)? {
+            Some((value, _)) => value,
+            None => return Ok(None),
+        };
+        let converted = match &value {
+            Value::Scalar(s) => T::from_scalar(s),
+            Value::Object(_) => None,
+        };
+        match converted {
+            Some(v) => Ok(Some(v)),
+            None => Err(KvError::WrongType {
+                key: key.to_string(),
+                expected: T::EXPECTED,
+                found: value.into_owned(),
+            }),
+        }
+    }
+
+    /// Whether there is a value for `key`
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.doc.contains_key(ExId::Root, key)
+    }
+
+    /// Iterate over the keys which have values
+    pub fn keys(&self) -> Keys<'_> {
+        self.doc.keys(ExId::Root)
+    }
+
+    /// The number of keys which have values
+    pub fn len(&self) -> usize {
+        self.doc.length(ExId::Root)
+    }
+
+    /// Whether there are no keys
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a, D: Transactable + ?Sized> Kv<'a, D> {
+    /// Set `key` to `value`
+    pub fn put<V: Into<ScalarValue>>(&mut self, key: &str, value: V) -> Result<(), KvError> {
+        Ok(self.doc.put(ExId::Root, key, value)?)
+    }
+
+    /// Remove `key`
+    pub fn delete(&mut self, key: &str) -> Result<(), KvError> {
+        Ok(self.doc.delete(ExId::Root, key)?)
+    }
+}
@@ -0,0 +1,120 @@
+use std::ops::RangeFull;
+
+use crate::exid::ExId;
+use crate::iter::ListRange;
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ChangeHash, ObjType, ReadDoc, ScalarValue, Value};
+
+/// A handle to an [`ObjType::List`] object, obtained from [`Transactable::list()`]
+#[derive(Debug)]
+pub struct List<'a, D: ?Sized> {
+    doc: &'a mut D,
+    obj: ExId,
+}
+
+impl<'a, D: ReadDoc + ?Sized> List<'a, D> {
+    pub(crate) fn new(doc: &'a mut D, obj: ExId) -> Result<Self, AutomergeError> {
+        super::check_type(doc, &obj, ObjType::List)?;
+        Ok(Self { doc, obj })
+    }
+
+    /// The ID of the list object
+    pub fn id(&self) -> &ExId {
+        &self.obj
+    }
+
+    /// The number of elements in the list
+    pub fn len(&self) -> usize {
+        self.doc.length(&self.obj)
+    }
+
+    /// Whether the list is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The element at `index`, see [`ReadDoc::get()`]
+    pub fn get(&self, index: usize) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        self.doc.get(&self.obj, index)
+    }
+
+    /// The element at `index` as it was at `heads`
+    pub fn get_at(
+        &self,
+        index: usize,
+        heads: &[ChangeHash],
+    ) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        self.doc.get_at(&self.obj, index, heads)
+    }
+
+    /// Iterate over the elements of the list
+    pub fn iter(&self) -> ListRange<'_, RangeFull> {
+        self.doc.list_range(&self.obj, ..)
+    }
+}
+
+impl<'a, D: Transactable + ?Sized> List<'a, D> {
+    /// Append `value` to the end of the list
+    pub fn push<V: Into<ScalarValue>>(&mut self, value: V) -> Result<(), AutomergeError> {
+        let len = self.len();
+        self.doc.insert(&self.obj, len, value)
+    }
+
+    /// Append a new object of type `obj_type` to the end of the list
+    pub fn push_object(&mut self, obj_type: ObjType) -> Result<ExId, AutomergeError> {
+        let len = self.len();
+        self.doc.insert_object(&self.obj, len, obj_type)
+    }
+
+    /// Insert `value` at `index`
+    pub fn insert<V: Into<ScalarValue>>(
+        &mut self,
+        index: usize,
+        value: V,
+    ) -> Result<(), AutomergeError> {
+        self.doc.insert(&self.obj, index, value)
+    }
+
+    /// Insert a new object of type `obj_type` at `index`
+    pub fn insert_object(
+        &mut self,
+        index: usize,
+        obj_type: ObjType,
+    ) -> Result<ExId, AutomergeError> {
+        self.doc.insert_object(&self.obj, index, obj_type)
+    }
+
+    /// Overwrite the element at `index` with `value`
+    pub fn put<V: Into<ScalarValue>>(
+        &mut self,
+        index: usize,
+        value: V,
+    ) -> Result<(), AutomergeError> {
+        self.doc.put(&self.obj, index, value)
+    }
+
+    /// Overwrite the element at `index` with a new object of type `obj_type`
+    pub fn put_object(&mut self, index: usize, obj_type: ObjType) -> Result<ExId, AutomergeError> {
+        self.doc.put_object(&self.obj, index, obj_type)
+    }
+
+    /// Increment the counter at `index` by `value`
+    pub fn increment(&mut self, index: usize, value: i64) -> Result<(), AutomergeError> {
+        self.doc.increment(&self.obj, index, value)
+    }
+
+    /// Remove the element at `index`
+    pub fn delete(&mut self, index: usize) -> Result<(), AutomergeError> {
+        self.doc.delete(&self.obj, index)
+    }
+
+    /// Delete `del` elements at `pos` and insert `vals`, see [`Transactable::splice()`]
+    pub fn splice<V: IntoIterator<Item = ScalarValue>>(
+        &mut self,
+        pos: usize,
+        del: isize,
+        vals: V,
+    ) -> Result<(), AutomergeError> {
+        self.doc.splice(&self.obj, pos, del, vals)
+    }
+}
@@ -0,0 +1,87 @@
+use std::ops::RangeFull;
+
+use crate::exid::ExId;
+use crate::iter::{Keys, MapRange};
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ChangeHash, ObjType, ReadDoc, ScalarValue, Value};
+
+/// A handle to an [`ObjType::Map`] object, obtained from [`Transactable::map()`]
+#[derive(Debug)]
+pub struct Map<'a, D: ?Sized> {
+    doc: &'a mut D,
+    obj: ExId,
+}
+
+impl<'a, D: ReadDoc + ?Sized> Map<'a, D> {
+    pub(crate) fn new(doc: &'a mut D, obj: ExId) -> Result<Self, AutomergeError> {
+        super::check_type(doc, &obj, ObjType::Map)?;
+        Ok(Self { doc, obj })
+    }
+
+    /// The ID of the map object
+    pub fn id(&self) -> &ExId {
+        &self.obj
+    }
+
+    /// The number of keys in the map
+    pub fn len(&self) -> usize {
+        self.doc.length(&self.obj)
+    }
+
+    /// Whether the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value of `key`, see [`ReadDoc::get()`]
+    pub fn get(&self, key: &str) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        self.doc.get(&self.obj, key)
+    }
+
+    /// The value of `key` as it was at `heads`
+    pub fn get_at(
+        &self,
+        key: &str,
+        heads: &[ChangeHash],
+    ) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        self.doc.get_at(&self.obj, key, heads)
+    }
+
+    /// Whether the map currently has a value for `key`
+    pub fn contains_key(&self, key: &str) -> Result<bool, AutomergeError> {
+        self.doc.object_type(&self.obj)?;
+        Ok(self.doc.contains_key(&self.obj, key))
+    }
+
+    /// Iterate over the keys of the map
+    pub fn keys(&self) -> Keys<'_> {
+        self.doc.keys(&self.obj)
+    }
+
+    /// Iterate over the keys and values of the map
+    pub fn entries(&self) -> MapRange<'_, RangeFull> {
+        self.doc.map_range(&self.obj, ..)
+    }
+}
+
+impl<'a, D: Transactable + ?Sized> Map<'a, D> {
+    /// Set `key` to `value`
+    pub fn put<V: Into<ScalarValue>>(&mut self, key: &str, value: V) -> Result<(), AutomergeError> {
+        self.doc.put(&self.obj, key, value)
+    }
+
+    /// Set `key` to a new object of type `obj_type`
+    pub fn put_object(&mut self, key: &str, obj_type: ObjType) -> Result<ExId, AutomergeError> {
+        self.doc.put_object(&self.obj, key, obj_type)
+    }
+
+    /// Increment the counter at `key` by `value`
+    pub fn increment(&mut self, key: &str, value: i64) -> Result<(), AutomergeError> {
+        self.doc.increment(&self.obj, key, value)
+    }
+
+    /// Remove `key` from the map
+    pub fn delete(&mut self, key: &str) -> Result<(), AutomergeError> {
+        self.doc.delete(&self.obj, key)
+    }
+}
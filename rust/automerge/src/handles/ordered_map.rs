@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ObjType, Prop, ReadDoc, ScalarValue, Value};
+
+/// The property of an ordered map which holds the list of keys in order
+const KEYS: &str = "keys";
+/// The property of an ordered map which holds the map of keys to values
+const VALUES: &str = "values";
+
+/// A handle to a map whose keys are kept in a user controlled order, obtained from
+/// [`Transactable::ordered_map()`]
+///
+/// Ordinary maps always iterate in lexicographic order of their keys. An ordered map is stored
+/// as an [`ObjType::Map`] with two properties: a `values` map holding the entries and a `keys`
+/// list holding the keys in order. The handle keeps the two in step, so the data model doesn't
+/// have to maintain a parallel list itself. New ordered maps are created with
+/// [`Transactable::put_ordered_map()`].
+///
+/// ## Concurrent edits
+///
+/// If two actors concurrently add the same key it will appear twice in the `keys` list, in which
+/// case the first occurrence determines its position. Keys which have a value but are missing
+/// from the `keys` list (for example because one actor moved a key while another deleted it) are
+/// ordered after all the other keys, so no entry is ever hidden.
+#[derive(Debug)]
+pub struct OrderedMap<'a, D: ?Sized> {
+    doc: &'a mut D,
+    obj: ExId,
+    keys: ExId,
+    values: ExId,
+}
+
+impl<'a, D: ReadDoc + ?Sized> OrderedMap<'a, D> {
+    pub(crate) fn new(doc: &'a mut D, obj: ExId) -> Result<Self, AutomergeError> {
+        super::check_type(doc, &obj, ObjType::Map)?;
+        let keys = child(doc, &obj, KEYS, ObjType::List);
+        let values = child(doc, &obj, VALUES, ObjType::Map);
+        match (keys, values) {
+            (Some(keys), Some(values)) => Ok(Self {
+                doc,
+                obj,
+                keys,
+                values,
+            }),
+            _ => Err(AutomergeError::NotAnOrderedMap(obj)),
+        }
+    }
+
+    /// The ID of the ordered map object
+    pub fn id(&self) -> &ExId {
+        &self.obj
+    }
+
+    /// The number of keys in the map
+    pub fn len(&self) -> usize {
+        self.doc.length(&self.values)
+    }
+
+    /// Whether the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value of `key`, see [`ReadDoc::get()`]
+    pub fn get(&self, key: &str) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        self.doc.get(&self.values, key)
+    }
+
+    /// Whether the map currently has a value for `key`
+    pub fn contains_key(&self, key: &str) -> Result<bool, AutomergeError> {
+        self.doc.object_type(&self.values)?;
+        Ok(self.doc.contains_key(&self.values, key))
+    }
+
+    /// The keys of the map in order
+    pub fn keys(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut keys = self
+            .ordered_keys()
+            .filter(|(_, key)| seen.insert(key.clone()))
+            .map(|(_, key)| key)
+            .filter(|key| self.doc.contains_key(&self.values, key))
+            .collect::<Vec<_>>();
+        keys.extend(
+            self.doc
+                .keys(&self.values)
+                .filter(|key| !seen.contains(key)),
+        );
+        keys
+    }
+
+    /// The keys and values of the map in order
+    pub fn entries(&self) -> Vec<(String, Value<'_>, ExId)> {
+        self.keys()
+            .into_iter()
+            .filter_map(|key| {
+                let (value, id) = self.doc.get(&self.values, key.as_str()).ok()??;
+                Some((key, value, id))
+            })
+            .collect()
+    }
+
+    /// The position of `key` in the map, if it is present
+    pub fn index_of(&self, key: &str) -> Option<usize> {
+        self.keys().iter().position(|k| k == key)
+    }
+
+    /// The elements of the `keys` list along with their index in the list
+    fn ordered_keys(&self) -> impl Iterator<Item = (usize, String)> + '_ {
+        self.doc
+            .list_range(&self.keys, ..)
+            .filter_map(|item| Some((item.index, item.value.to_str()?.to_string())))
+    }
+}
+
+impl<'a, D: Transactable + ?Sized> OrderedMap<'a, D> {
+    /// Create a new, empty ordered map at `prop` in `obj`
+    pub(crate) fn create<P: Into<Prop>>(
+        doc: &mut D,
+        obj: &ExId,
+        prop: P,
+    ) -> Result<ExId, AutomergeError> {
+        let map = doc.put_object(obj, prop, ObjType::Map)?;
+        doc.put_object(&map, KEYS, ObjType::List)?;
+        doc.put_object(&map, VALUES, ObjType::Map)?;
+        Ok(map)
+    }
+
+    /// Set `key` to `value`, adding `key` at the end of the map if it is not already present
+    pub fn put<V: Into<ScalarValue>>(&mut self, key: &str, value: V) -> Result<(), AutomergeError> {
+        self.append_key(key)?;
+        self.doc.put(&self.values, key, value)
+    }
+
+    /// Set `key` to a new object of type `obj_type`, adding `key` at the end of the map if it is
+    /// not already present
+    pub fn put_object(&mut self, key: &str, obj_type: ObjType) -> Result<ExId, AutomergeError> {
+        self.append_key(key)?;
+        self.doc.put_object(&self.values, key, obj_type)
+    }
+
+    /// Set `key` to `value` and move `key` to position `index`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidIndex`] if `index` is greater than the number of keys
+    /// in the map, not counting `key`
+    pub fn insert<V: Into<ScalarValue>>(
+        &mut self,
+        index: usize,
+        key: &str,
+        value: V,
+    ) -> Result<(), AutomergeError> {
+        self.check_index(key, index)?;
+        self.doc.put(&self.values, key, value)?;
+        self.move_key(key, index)?;
+        Ok(())
+    }
+
+    /// Move `key` to position `index`, shifting the keys after it along by one
+    ///
+    /// Returns `false` without changing anything if `key` is not in the map.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidIndex`] if `index` is greater than the number of other
+    /// keys in the map
+    pub fn move_key(&mut self, key: &str, index: usize) -> Result<bool, AutomergeError> {
+        if !self.contains_key(key)? {
+            return Ok(false);
+        }
+        let others = self.check_index(key, index)?;
+        self.remove_key(key)?;
+        let list_index = match others.get(index) {
+            Some(next) => self
+                .ordered_keys()
+                .find(|(_, k)| k == next)
+                .map(|(i, _)| i)
+                .unwrap_or_else(|| self.doc.length(&self.keys)),
+            None => self.doc.length(&self.keys),
+        };
+        self.doc.insert(&self.keys, list_index, key)?;
+        Ok(true)
+    }
+
+    /// Remove `key` from the map
+    pub fn delete(&mut self, key: &str) -> Result<(), AutomergeError> {
+        self.remove_key(key)?;
+        self.doc.delete(&self.values, key)
+    }
+
+    /// Check that `key` can be placed at `index`, returning the other keys in order
+    fn check_index(&self, key: &str, index: usize) -> Result<Vec<String>, AutomergeError> {
+        let others = self
+            .keys()
+            .into_iter()
+            .filter(|k| k != key)
+            .collect::<Vec<_>>();
+        if index > others.len() {
+            return Err(AutomergeError::InvalidIndex(index));
+        }
+        Ok(others)
+    }
+
+    fn append_key(&mut self, key: &str) -> Result<(), AutomergeError> {
+        if self.ordered_keys().any(|(_, k)| k == key) {
+            return Ok(());
+        }
+        let end = self.doc.length(&self.keys);
+        self.doc.insert(&self.keys, end, key)
+    }
+
+    fn remove_key(&mut self, key: &str) -> Result<(), AutomergeError> {
+        let indices = self
+            .ordered_keys()
+            .filter(|(_, k)| k == key)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        for index in indices.into_iter().rev() {
+            self.doc.delete(&self.keys, index)?;
+        }
+        Ok(())
+    }
+}
+
+fn child<D: ReadDoc + ?Sized>(doc: &D, obj: &ExId, prop: &str, typ: ObjType) -> Option<ExId> {
+    match doc.get(obj, prop) {
+        Ok(Some((Value::Object(t), id))) if t == typ => Some(id),
+        _ => None,
+    }
+}
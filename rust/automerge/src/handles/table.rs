@@ -0,0 +1,75 @@
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ObjType, ReadDoc, ScalarValue};
+
+/// A handle to an [`ObjType::Table`] object, obtained from [`Transactable::table()`]
+///
+/// A table is a collection of rows, each of which is a map. Rows are added with
+/// [`Self::add_row()`], which gives each row a new random ID (a UUID) in the same way as tables in
+/// the JavaScript implementation, so rows added concurrently by different actors never collide.
+/// The row IDs are the keys of the table object, so the rows can also be read with the ordinary
+/// map methods of [`ReadDoc`].
+///
+/// ## Concurrent edits
+///
+/// If one actor removes a row while another concurrently updates one of its fields, the removal
+/// wins: the update is made to the row object, which is no longer in the table once the changes
+/// are merged.
+#[derive(Debug)]
+pub struct Table<'a, D: ?Sized> {
+    doc: &'a mut D,
+    obj: ExId,
+}
+
+impl<'a, D: ReadDoc + ?Sized> Table<'a, D> {
+    pub(crate) fn new(doc: &'a mut D, obj: ExId) -> Result<Self, AutomergeError> {
+        super::check_type(doc, &obj, ObjType::Table)?;
+        Ok(Self { doc, obj })
+    }
+
+    /// The ID of the table object
+    pub fn id(&self) -> &ExId {
+        &self.obj
+    }
+
+    /// The number of rows in the table
+    pub fn len(&self) -> usize {
+        self.doc.length(&self.obj)
+    }
+
+    /// Whether the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The object ID of the row with ID `id`, see [`ReadDoc::row()`]
+    pub fn row(&self, id: &str) -> Result<Option<ExId>, AutomergeError> {
+        self.doc.row(&self.obj, id)
+    }
+
+    /// The IDs of the rows and their object IDs, see [`ReadDoc::rows()`]
+    pub fn rows(&self) -> Result<Vec<(String, ExId)>, AutomergeError> {
+        self.doc.rows(&self.obj)
+    }
+}
+
+impl<'a, D: Transactable + ?Sized> Table<'a, D> {
+    /// Add a row with the given fields, see [`Transactable::add_row()`]
+    pub fn add_row<I, K, V>(&mut self, fields: I) -> Result<String, AutomergeError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<ScalarValue>,
+    {
+        self.doc.add_row(&self.obj, fields)
+    }
+
+    /// Remove the row with ID `id`, returning `false` if there was no such row
+    pub fn remove_row(&mut self, id: &str) -> Result<bool, AutomergeError> {
+        if self.doc.row(&self.obj, id)?.is_none() {
+            return Ok(false);
+        }
+        self.doc.delete(&self.obj, id)?;
+        Ok(true)
+    }
+}
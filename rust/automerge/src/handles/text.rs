@@ -0,0 +1,161 @@
+use std::ops::{Bound, RangeBounds, RangeFull};
+
+use crate::exid::ExId;
+use crate::iter::ListRange;
+use crate::marks::{ExpandMark, Mark, Span};
+use crate::text_value::TextValue;
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ChangeHash, Cursor, ObjType, ReadDoc};
+
+/// A handle to an [`ObjType::Text`] object, obtained from [`Transactable::text_obj()`]
+///
+/// Positions and lengths are measured in the same units as the rest of the text API, which
+/// depends on the text encoding the crate was compiled with.
+#[derive(Debug)]
+pub struct Text<'a, D: ?Sized> {
+    doc: &'a mut D,
+    obj: ExId,
+}
+
+impl<'a, D: ReadDoc + ?Sized> Text<'a, D> {
+    pub(crate) fn new(doc: &'a mut D, obj: ExId) -> Result<Self, AutomergeError> {
+        super::check_type(doc, &obj, ObjType::Text)?;
+        Ok(Self { doc, obj })
+    }
+
+    /// The ID of the text object
+    pub fn id(&self) -> &ExId {
+        &self.obj
+    }
+
+    /// The length of the text
+    pub fn len(&self) -> usize {
+        self.doc.length(&self.obj)
+    }
+
+    /// Whether the text is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The whole text as a string
+    pub fn text(&self) -> Result<String, AutomergeError> {
+        self.doc.text(&self.obj)
+    }
+
+    /// The text as it was at `heads`
+    pub fn text_at(&self, heads: &[ChangeHash]) -> Result<String, AutomergeError> {
+        self.doc.text_at(&self.obj, heads)
+    }
+
+    /// The portion of the text within `range`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidIndex`] if the range extends beyond the end of the text
+    pub fn get<R: RangeBounds<usize>>(&self, range: R) -> Result<String, AutomergeError> {
+        let text = self.text()?;
+        let start = match range.start_bound() {
+            Bound::Included(n) => *n,
+            Bound::Excluded(n) => n.checked_add(1).ok_or(AutomergeError::InvalidIndex(*n))?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(n) => n.checked_add(1).ok_or(AutomergeError::InvalidIndex(*n))?,
+            Bound::Excluded(n) => *n,
+            Bound::Unbounded => return Ok(text[byte_offset(&text, start)?..].to_string()),
+        };
+        if end < start {
+            return Err(AutomergeError::InvalidIndex(end));
+        }
+        Ok(text[byte_offset(&text, start)?..byte_offset(&text, end)?].to_string())
+    }
+
+    /// Iterate over the elements of the text
+    pub fn iter(&self) -> ListRange<'_, RangeFull> {
+        self.doc.list_range(&self.obj, ..)
+    }
+
+    /// The marks in the text
+    pub fn marks(&self) -> Result<Vec<Mark<'_>>, AutomergeError> {
+        self.doc.marks(&self.obj)
+    }
+
+    /// The marks in the text as it was at `heads`
+    pub fn marks_at(&self, heads: &[ChangeHash]) -> Result<Vec<Mark<'_>>, AutomergeError> {
+        self.doc.marks_at(&self.obj, heads)
+    }
+
+    /// The text split into runs with the same marks, see [`ReadDoc::spans()`]
+    pub fn spans(&self) -> Result<Vec<Span>, AutomergeError> {
+        self.doc.spans(&self.obj)
+    }
+
+    /// Obtain a [`Cursor`] for `position`, see [`ReadDoc::get_cursor()`]
+    pub fn get_cursor(
+        &self,
+        position: usize,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<Cursor, AutomergeError> {
+        self.doc.get_cursor(&self.obj, position, at)
+    }
+
+    /// The current position of `cursor`, see [`ReadDoc::get_cursor_position()`]
+    pub fn get_cursor_position(
+        &self,
+        cursor: &Cursor,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<usize, AutomergeError> {
+        self.doc.get_cursor_position(&self.obj, cursor, at)
+    }
+}
+
+impl<'a, D: Transactable + ?Sized> Text<'a, D> {
+    /// Delete `del` characters at `pos` and insert `text`, see [`Transactable::splice_text()`]
+    pub fn splice(&mut self, pos: usize, del: isize, text: &str) -> Result<(), AutomergeError> {
+        self.doc.splice_text(&self.obj, pos, del, text)
+    }
+
+    /// Append `text` to the end of the text
+    pub fn push_str(&mut self, text: &str) -> Result<(), AutomergeError> {
+        let len = self.len();
+        self.splice(len, 0, text)
+    }
+
+    /// Replace the text with `new_text`, see [`Transactable::update_text()`]
+    pub fn update<S: AsRef<str>>(&mut self, new_text: S) -> Result<(), AutomergeError> {
+        self.doc.update_text(&self.obj, new_text)
+    }
+
+    /// Add a mark to the text, see [`Transactable::mark()`]
+    pub fn mark(&mut self, mark: Mark<'_>, expand: ExpandMark) -> Result<(), AutomergeError> {
+        self.doc.mark(&self.obj, mark, expand)
+    }
+
+    /// Remove a mark from the text, see [`Transactable::unmark()`]
+    pub fn unmark(
+        &mut self,
+        key: &str,
+        start: usize,
+        end: usize,
+        expand: ExpandMark,
+    ) -> Result<(), AutomergeError> {
+        self.doc.unmark(&self.obj, key, start, end, expand)
+    }
+}
+
+// Convert a position in the text encoding to a byte offset in `text`
+fn byte_offset(text: &str, pos: usize) -> Result<usize, AutomergeError> {
+    let mut width = 0;
+    for (offset, c) in text.char_indices() {
+        if width >= pos {
+            return Ok(offset);
+        }
+        width += TextValue::width(c.encode_utf8(&mut [0; 4]));
+    }
+    if width >= pos {
+        Ok(text.len())
+    } else {
+        Err(AutomergeError::InvalidIndex(pos))
+    }
+}
@@ -0,0 +1,78 @@
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use sha2::{Digest, Sha256};
+
+use crate::types::HASH_SIZE;
+
+/// The algorithm used to compute [`crate::ChangeHash`]es and chunk checksums
+///
+/// Automerge uses SHA-256 ([`Sha256Hasher`]) unless a different algorithm is installed with
+/// [`set_change_hasher()`]. Every peer in a network must use the same algorithm, otherwise they
+/// will disagree about the hashes of changes and reject each other's data.
+pub trait ChangeHasher: Send + Sync {
+    /// A name for the algorithm, used in errors which report a mismatch between algorithms
+    fn name(&self) -> &'static str;
+
+    /// Hash `data`
+    fn digest(&self, data: &[u8]) -> [u8; HASH_SIZE];
+}
+
+/// The default [`ChangeHasher`], SHA-256
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256Hasher;
+
+impl ChangeHasher for Sha256Hasher {
+    fn name(&self) -> &'static str {
+        "sha256"
+    }
+
+    fn digest(&self, data: &[u8]) -> [u8; HASH_SIZE] {
+        Sha256::digest(data).into()
+    }
+}
+
+/// Returned by [`set_change_hasher()`] if a hasher has already been installed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("a change hasher has already been installed")]
+pub struct HasherAlreadySet;
+
+static HASHER: AtomicPtr<&'static dyn ChangeHasher> = AtomicPtr::new(ptr::null_mut());
+
+/// Use `hasher` instead of SHA-256 to hash changes in this process
+///
+/// This must be called before any documents are created or loaded, as the hashes of changes
+/// made before it is called will not match the hashes computed afterwards. It can only be
+/// called once.
+///
+/// # Errors
+///
+/// Returns [`HasherAlreadySet`] if a hasher has already been installed
+pub fn set_change_hasher(hasher: &'static dyn ChangeHasher) -> Result<(), HasherAlreadySet> {
+    let boxed = Box::into_raw(Box::new(hasher));
+    match HASHER.compare_exchange(ptr::null_mut(), boxed, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => Ok(()),
+        Err(_) => {
+            // SAFETY: `boxed` was created above and was not stored, so nothing else refers to it
+            drop(unsafe { Box::from_raw(boxed) });
+            Err(HasherAlreadySet)
+        }
+    }
+}
+
+/// The hasher installed with [`set_change_hasher()`], or [`Sha256Hasher`]
+pub fn change_hasher() -> &'static dyn ChangeHasher {
+    let installed = HASHER.load(Ordering::Acquire);
+    if installed.is_null() {
+        &Sha256Hasher
+    } else {
+        // SAFETY: a non null pointer was stored by `set_change_hasher()` from a leaked box and is
+        // never freed or replaced
+        unsafe { *installed }
+    }
+}
+
+/// Whether changes are hashed with [`Sha256Hasher`] because no other hasher has been installed
+pub(crate) fn is_default() -> bool {
+    HASHER.load(Ordering::Acquire).is_null()
+}
@@ -1,5 +1,10 @@
-use crate::types::{Clock, ObjId, Op, OpType};
-use crate::{error::HydrateError, value, ObjType, Patch, PatchAction, Prop, ScalarValue};
+use crate::exid::ExId;
+use crate::subtree::Entry;
+use crate::transaction::Transactable;
+use crate::types::{Clock, ListEncoding, ObjId, Op, OpType};
+use crate::{
+    error::HydrateError, value, AutomergeError, ObjType, Patch, PatchAction, Prop, ScalarValue,
+};
 use std::borrow::Cow;
 use std::collections::HashMap;
 
@@ -58,7 +63,7 @@ impl Value {
             (None, Value::Map(map)) => map.apply(patch),
             (None, Value::List(list)) => list.apply(patch),
             (None, Value::Text(text)) => text.apply(patch),
-            _ => Err(HydrateError::Fail),
+            _ => Err(HydrateError::ApplyInvalidProp(patch)),
         }
     }
 
@@ -77,6 +82,122 @@ impl Value {
     }
 }
 
+/// Objects serialize as the JSON types they correspond to: maps and tables as maps, lists as
+/// sequences and text as a string. Scalars serialize as [`ScalarValue`] does.
+impl serde::Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Value::Scalar(s) => s.serialize(serializer),
+            Value::Map(m) => m.serialize(serializer),
+            Value::List(l) => l.serialize(serializer),
+            Value::Text(t) => t.serialize(serializer),
+        }
+    }
+}
+
+/// JSON-like data deserializes into a tree of maps, lists and scalars, so a document can be
+/// built from any serde format with [`crate::Automerge::from_json()`] or
+/// [`crate::transaction::Transactable::set_json()`]. Strings become [`ScalarValue::Str`] rather
+/// than text objects and integers become [`ScalarValue::Int`] unless they are too large for an
+/// `i64`.
+impl<'de> serde::Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> serde::de::Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a JSON-like value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Scalar(ScalarValue::Boolean(v)))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Scalar(ScalarValue::Int(v)))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        match i64::try_from(v) {
+            Ok(v) => Ok(Value::Scalar(ScalarValue::Int(v))),
+            Err(_) => Ok(Value::Scalar(ScalarValue::Uint(v))),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Scalar(ScalarValue::F64(v)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::Scalar(ScalarValue::Str(v.into())))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Scalar(ScalarValue::Bytes(v.to_vec())))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Scalar(ScalarValue::Null))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Scalar(ScalarValue::Null))
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        serde::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut values = Vec::<Value>::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(values.into())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut values = Map::new();
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            values.insert(key, MapValue::new(value, ExId::Root, false));
+        }
+        Ok(Value::Map(values))
+    }
+}
+
+/// Write `value` to `prop` in `obj`, inserting it before `prop` if `insert` is true, and create
+/// the contents of any objects it contains
+pub(crate) fn write<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    prop: Prop,
+    insert: bool,
+    value: &Value,
+) -> Result<(), AutomergeError> {
+    Entry::from(value).write(tx, obj, prop, insert)
+}
+
 impl From<value::Value<'_>> for Value {
     fn from(value: value::Value<'_>) -> Self {
         match value {
@@ -138,6 +259,19 @@ impl Automerge {
         Value::Map(map)
     }
 
+    /// Hydrate the winning value of `key` in the map `obj`, if there is one
+    pub(crate) fn hydrate_key(
+        &self,
+        obj: &ObjId,
+        key: &str,
+        clock: Option<&Clock>,
+    ) -> Option<Value> {
+        let found =
+            self.ops()
+                .seek_ops_by_prop(obj, Prop::Map(key.to_string()), ListEncoding::List, clock);
+        found.ops.last().map(|op| self.hydrate_op(*op, clock))
+    }
+
     pub(crate) fn hydrate_list(&self, obj: &ObjId, clock: Option<&Clock>) -> Value {
         let mut list = List::new();
         for top in self.ops().top_ops(obj, clock.cloned()) {
@@ -160,7 +294,7 @@ impl Automerge {
             OpType::Make(ObjType::Table) => self.hydrate_map(&op.id().into(), clock),
             OpType::Make(ObjType::List) => self.hydrate_list(&op.id().into(), clock),
             OpType::Make(ObjType::Text) => self.hydrate_text(&op.id().into(), clock),
-            OpType::Put(scalar) => Value::Scalar(scalar.clone()),
+            OpType::Put(_) => op.value_at(clock).into(),
             _ => panic!("invalid op to hydrate"),
         }
     }
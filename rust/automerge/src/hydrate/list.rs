@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use serde::ser::SerializeSeq;
+
 use crate::exid::ExId;
 use crate::types::Prop;
 use crate::{PatchAction, ScalarValue, SequenceTree};
@@ -69,6 +71,11 @@ impl List {
         self.0.get(index).map(|lv| &lv.value)
     }
 
+    /// The values in the list, in order
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        self.0.iter().map(|lv| &lv.value)
+    }
+
     pub(crate) fn push<V: Into<Value>>(&mut self, value: V, _id: ExId, conflict: bool) {
         self.0.push(ListValue::new(value.into(), conflict))
     }
@@ -78,6 +85,19 @@ impl List {
     }
 }
 
+impl serde::Serialize for List {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut seq_ser = serializer.serialize_seq(Some(self.0.len()))?;
+        for item in self.0.iter() {
+            seq_ser.serialize_element(&item.value)?;
+        }
+        seq_ser.end()
+    }
+}
+
 impl ListValue {
     pub(crate) fn increment(&mut self, n: i64) -> Result<(), HydrateError> {
         if let Value::Scalar(ScalarValue::Counter(c)) = &mut self.value {
@@ -1,6 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::{Deref, DerefMut};
 
+use serde::ser::SerializeMap;
+
 use crate::exid::ExId;
 use crate::types::Prop;
 use crate::{PatchAction, ScalarValue};
@@ -64,6 +66,10 @@ impl MapValue {
         Self { value, conflict }
     }
 
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
     pub(crate) fn increment(&mut self, n: i64) -> Result<(), HydrateError> {
         if let Value::Scalar(ScalarValue::Counter(c)) = &mut self.value {
             c.increment(n);
@@ -74,6 +80,21 @@ impl MapValue {
     }
 }
 
+/// Keys are serialized in sorted order, the order [`crate::ReadDoc::keys()`] returns them in
+impl serde::Serialize for Map {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let entries = self.0.iter().collect::<BTreeMap<_, _>>();
+        let mut map_ser = serializer.serialize_map(Some(entries.len()))?;
+        for (key, value) in entries {
+            map_ser.serialize_entry(key, &value.value)?;
+        }
+        map_ser.end()
+    }
+}
+
 impl Deref for Map {
     type Target = HashMap<String, MapValue>;
 
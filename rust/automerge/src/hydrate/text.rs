@@ -38,6 +38,21 @@ impl Text {
     }
 }
 
+impl serde::Serialize for Text {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.value.make_string())
+    }
+}
+
+impl std::fmt::Display for Text {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.value.make_string())
+    }
+}
+
 impl From<TextValue> for Value {
     fn from(text: TextValue) -> Self {
         Value::Text(Text::new(text))
@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::ops::Index;
@@ -29,6 +30,15 @@ where
         }
     }
 
+    /// The approximate number of bytes allocated by the cache, `item_size` is the number of bytes
+    /// allocated by each item
+    pub(crate) fn heap_size<F: Fn(&T) -> usize>(&self, item_size: F) -> usize {
+        self.cache.capacity() * std::mem::size_of::<T>()
+            + self.lookup.capacity() * std::mem::size_of::<(T, usize)>()
+            // every item is stored twice, once in `cache` and once in `lookup`
+            + self.cache.iter().map(item_size).sum::<usize>() * 2
+    }
+
     pub(crate) fn cache(&mut self, item: T) -> usize {
         if let Some(n) = self.lookup.get(&item) {
             *n
@@ -40,7 +50,11 @@ where
         }
     }
 
-    pub(crate) fn lookup(&self, item: &T) -> Option<usize> {
+    pub(crate) fn lookup<Q>(&self, item: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
         self.lookup.get(item).cloned()
     }
 
@@ -1,9 +1,11 @@
+mod history;
 mod keys;
 mod list_range;
 mod map_range;
 mod top_ops;
 mod values;
 
+pub use history::History;
 pub use keys::Keys;
 pub use list_range::{ListRange, ListRangeItem};
 pub use map_range::{MapRange, MapRangeItem};
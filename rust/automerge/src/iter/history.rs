@@ -0,0 +1,85 @@
+use std::ops::{Bound, RangeBounds};
+
+use crate::{ActorId, Change, ChangeHash};
+
+/// Iterator created by [`crate::Automerge::history()`]
+///
+/// Yields the changes of a document in the order they were applied, which is a topological order:
+/// every change comes after the changes it depends on. The filter methods narrow the changes
+/// which are yielded without changing their order.
+#[derive(Debug, Clone)]
+pub struct History<'a> {
+    changes: std::slice::Iter<'a, Change>,
+    actor: Option<ActorId>,
+    start: Bound<i64>,
+    end: Bound<i64>,
+    message: Option<String>,
+}
+
+impl<'a> History<'a> {
+    pub(crate) fn new(changes: &'a [Change]) -> Self {
+        Self {
+            changes: changes.iter(),
+            actor: None,
+            start: Bound::Unbounded,
+            end: Bound::Unbounded,
+            message: None,
+        }
+    }
+
+    /// Only yield changes made by `actor`
+    pub fn by_actor(self, actor: &ActorId) -> Self {
+        Self {
+            actor: Some(actor.clone()),
+            ..self
+        }
+    }
+
+    /// Only yield changes whose [`Change::time()`] is in `range`
+    pub fn in_time_range<R: RangeBounds<i64>>(self, range: R) -> Self {
+        Self {
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+            ..self
+        }
+    }
+
+    /// Only yield changes whose message contains `text`
+    pub fn message_contains(self, text: &str) -> Self {
+        Self {
+            message: Some(text.to_string()),
+            ..self
+        }
+    }
+
+    fn matches(&self, change: &Change) -> bool {
+        if let Some(actor) = &self.actor {
+            if change.actor_id() != actor {
+                return false;
+            }
+        }
+        if !(self.start, self.end).contains(&change.timestamp()) {
+            return false;
+        }
+        if let Some(text) = &self.message {
+            match change.message() {
+                Some(message) if message.contains(text.as_str()) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+impl<'a> Iterator for History<'a> {
+    type Item = (ChangeHash, &'a Change);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let change = self.changes.next()?;
+            if self.matches(change) {
+                return Some((change.hash(), change));
+            }
+        }
+    }
+}
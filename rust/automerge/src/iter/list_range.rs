@@ -1,12 +1,12 @@
 use std::fmt;
-use std::ops::RangeBounds;
+use std::ops::{Bound, RangeBounds};
 use std::sync::Arc;
 
 use crate::exid::ExId;
 use crate::marks::MarkSet;
 use crate::types::Clock;
 use crate::types::ListEncoding;
-use crate::value::Value;
+use crate::value::{Value, ValueRef};
 
 use super::{TopOp, TopOps};
 
@@ -68,6 +68,9 @@ impl<'a, R: RangeBounds<usize>> Iterator for ListRange<'a, R> {
             } in inner.iter.by_ref()
             {
                 let index = inner.state;
+                if past_end(&inner.range, index) {
+                    break;
+                }
                 inner.state += op.width(inner.encoding);
                 let value = op.value_at(inner.clock.as_ref());
                 let id = op.exid();
@@ -86,6 +89,14 @@ impl<'a, R: RangeBounds<usize>> Iterator for ListRange<'a, R> {
     }
 }
 
+fn past_end<R: RangeBounds<usize>>(range: &R, index: usize) -> bool {
+    match range.end_bound() {
+        Bound::Included(end) => index > *end,
+        Bound::Excluded(end) => index >= *end,
+        Bound::Unbounded => false,
+    }
+}
+
 #[derive(Debug)]
 pub struct ListRangeItem<'a> {
     pub index: usize,
@@ -99,4 +110,9 @@ impl<'a> ListRangeItem<'a> {
     pub fn marks(&self) -> Option<&MarkSet> {
         self.marks.as_deref()
     }
+
+    /// The value of this item together with its ID
+    pub fn value_ref(&self) -> ValueRef<'a> {
+        (self.value.clone(), self.id.clone()).into()
+    }
 }
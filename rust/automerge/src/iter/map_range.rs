@@ -1,10 +1,10 @@
 use std::fmt;
-use std::ops::RangeBounds;
+use std::ops::{Bound, RangeBounds};
 
 use crate::exid::ExId;
 use crate::op_set::OpSet;
 use crate::types::{Clock, Key};
-use crate::value::Value;
+use crate::value::{Value, ValueRef};
 
 use super::TopOps;
 
@@ -51,23 +51,36 @@ impl<'a, R: RangeBounds<String>> Iterator for MapRange<'a, R> {
     type Item = MapRangeItem<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.as_mut().and_then(|inner| {
-            for top in inner.iter.by_ref() {
-                if let Key::Map(n) = top.op.key() {
-                    if let Some(prop) = inner.op_set.osd.props.safe_get(*n) {
-                        if inner.range.contains(prop) {
-                            return Some(MapRangeItem {
-                                key: prop.as_str(),
-                                value: top.op.value_at(inner.clock.as_ref()),
-                                id: top.op.exid(),
-                                conflict: top.conflict,
-                            });
-                        }
+        let inner = self.iter.as_mut()?;
+        for top in inner.iter.by_ref() {
+            if let Key::Map(n) = top.op.key() {
+                if let Some(prop) = inner.op_set.osd.props.safe_get(*n) {
+                    // keys are visited in order so there is nothing more to find once we are
+                    // past the end of the range
+                    if past_end(&inner.range, prop) {
+                        self.iter = None;
+                        return None;
+                    }
+                    if inner.range.contains(prop) {
+                        return Some(MapRangeItem {
+                            key: prop.as_str(),
+                            value: top.op.value_at(inner.clock.as_ref()),
+                            id: top.op.exid(),
+                            conflict: top.conflict,
+                        });
                     }
                 }
             }
-            None
-        })
+        }
+        None
+    }
+}
+
+fn past_end<R: RangeBounds<String>>(range: &R, key: &str) -> bool {
+    match range.end_bound() {
+        Bound::Included(end) => key > end.as_str(),
+        Bound::Excluded(end) => key >= end.as_str(),
+        Bound::Unbounded => false,
     }
 }
 
@@ -88,4 +101,9 @@ impl<'a> MapRangeItem<'a> {
             conflict,
         }
     }
+
+    /// The value of this item together with its ID
+    pub fn value_ref(&self) -> ValueRef<'a> {
+        (self.value.clone(), self.id.clone()).into()
+    }
 }
@@ -60,6 +60,7 @@ pub enum ObjectId {
 }
 
 #[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "derive-arbitrary", derive(arbitrary::Arbitrary))]
 pub enum ElementId {
     Head,
     Id(OpId),
@@ -93,6 +94,7 @@ impl ElementId {
 }
 
 #[derive(Serialize, PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "derive-arbitrary", derive(arbitrary::Arbitrary))]
 #[serde(untagged)]
 pub enum Key {
     Map(SmolStr),
@@ -180,6 +182,13 @@ impl<T: Ord> FromIterator<T> for SortedVec<T> {
     }
 }
 
+#[cfg(feature = "derive-arbitrary")]
+impl<'a, T: arbitrary::Arbitrary<'a> + Ord> arbitrary::Arbitrary<'a> for SortedVec<T> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Vec::<T>::arbitrary(u).map(SortedVec::from)
+    }
+}
+
 impl<T> IntoIterator for SortedVec<T> {
     type Item = T;
 
@@ -213,6 +222,7 @@ pub(crate) struct OpTypeParts {
 
 // Like `types::OpType` except using a String for mark names
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "derive-arbitrary", derive(arbitrary::Arbitrary))]
 pub enum OpType {
     Make(ObjType),
     Delete,
@@ -288,6 +298,7 @@ impl OpType {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "derive-arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MarkData {
     pub name: smol_str::SmolStr,
     pub value: ScalarValue,
@@ -295,6 +306,7 @@ pub struct MarkData {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "derive-arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Op {
     pub action: OpType,
     pub obj: ObjectId,
@@ -327,6 +339,7 @@ impl Op {
 
 /// A change represents a group of operations performed by an actor.
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[cfg_attr(feature = "derive-arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Change {
     /// The operations performed in this change.
     #[serde(rename = "ops")]
@@ -85,6 +85,20 @@
 //!
 //! Text is encoded in UTF-8 by default but uses UTF-16 when using the wasm target.
 //!
+//! Indexes into text, for example in [`transaction::Transactable::splice_text()`], in patches and
+//! in [`ReadDoc::length()`], count unicode scalar values by default. On the wasm target they count
+//! UTF-16 code units so that they match JavaScript string indexes. This can be chosen with cargo
+//! features: `utf16-indexing` counts UTF-16 code units on every target, which is useful for
+//! servers which exchange indexes with JavaScript clients, and `utf8-indexing` counts bytes. If
+//! both features are enabled `utf8-indexing` is used.
+//!
+//! ### Encryption
+//!
+//! With the `encryption` feature documents can be encrypted with ChaCha20-Poly1305 when they are
+//! saved, for storing them somewhere which isn't trusted, see [`Automerge::save_encrypted()`].
+//! Incremental saves are encrypted separately so they can still be appended to an encrypted
+//! document and loaded together with [`Automerge::load_encrypted()`].
+//!
 //! ## Sync Protocol
 //!
 //! See the [`sync`] module.
@@ -256,56 +270,114 @@ macro_rules! __log {
      }
  }
 
+mod actor_frontier;
 mod autocommit;
 mod automerge;
 mod autoserde;
+mod blob;
 mod change;
+mod change_cache;
 mod change_graph;
 mod clock;
 mod columnar;
+mod conflict_order;
+mod conflicts;
 mod convert;
+mod counter_increment;
 mod cursor;
+mod debug_bundle;
+mod deleted_element;
+mod document_stats;
+#[cfg(feature = "encryption")]
+mod encryption;
 mod error;
 mod exid;
+#[cfg(feature = "generators")]
+pub mod generators;
+pub mod handles;
+mod hash;
 pub mod hydrate;
 mod indexed_cache;
 pub mod iter;
 mod legacy;
 pub mod marks;
+mod merge_preview;
+mod object_stats;
+mod op_observer;
 mod op_set;
 pub mod op_tree;
 mod parents;
 pub mod patches;
+mod path;
 mod query;
+#[cfg(feature = "query-tracing")]
+mod query_stats;
 mod read;
+mod read_guard;
+mod resolved_op;
 mod sequence_tree;
+mod shared;
+mod signing;
 mod storage;
+mod subtree;
+mod summary;
 pub mod sync;
+mod text_conflict;
 mod text_diff;
 mod text_value;
 pub mod transaction;
 mod types;
+mod undo;
 mod value;
 #[cfg(feature = "optree-visualisation")]
 mod visualisation;
 
-pub use crate::automerge::{Automerge, LoadOptions, OnPartialLoad, SaveOptions, StringMigration};
+pub use crate::automerge::{
+    ApplyProgress, Automerge, ChangeApplied, ChangeSkipped, LoadOptions, OnPartialLoad,
+    SaveOptions, StringMigration, WriteLimits,
+};
+#[cfg(any(test, feature = "debug-invariants"))]
+pub use crate::automerge::{InvariantReport, InvariantViolation};
+pub use actor_frontier::ActorFrontier;
 pub use autocommit::AutoCommit;
-pub use autoserde::AutoSerde;
-pub use change::{Change, LoadError as LoadChangeError};
+pub use autoserde::{AutoSerde, AutoSerdePaths};
+pub use blob::BlobReader;
+pub use change::{verify_change, Change, LoadError as LoadChangeError};
+pub use change_cache::ChangeCache;
+pub use conflict_order::ConflictOrder;
+pub use conflicts::{ConflictingValue, Conflicts};
+pub use counter_increment::CounterIncrement;
 pub use cursor::Cursor;
+pub use debug_bundle::{DebugBundle, DebugBundleError, DebugStats};
+pub use deleted_element::DeletedElement;
+pub use document_stats::{DocumentStats, HeapStats};
 pub use error::AutomergeError;
 pub use error::InvalidActorId;
 pub use error::InvalidChangeHashSlice;
+pub use error::InvalidOpReason;
 pub use exid::{ExId as ObjId, ObjIdFromBytesError};
+pub use hash::{change_hasher, set_change_hasher, ChangeHasher, HasherAlreadySet, Sha256Hasher};
 pub use legacy::Change as ExpandedChange;
+pub use merge_preview::MergePreview;
+pub use object_stats::ObjectStats;
+pub use op_observer::OpObserver;
 pub use parents::{Parent, Parents};
-pub use patches::{Patch, PatchAction, PatchLog};
+pub use patches::{Patch, PatchAction, PatchBatch, PatchLog};
+#[cfg(feature = "query-tracing")]
+pub use query_stats::QueryStats;
 pub use read::ReadDoc;
+pub use read_guard::ReadGuard;
+pub use resolved_op::{ResolvedKey, ResolvedOp};
 pub use sequence_tree::SequenceTree;
+pub use shared::SharedAutomerge;
+pub use signing::{Signer, Verifier};
 pub use storage::VerificationMode;
+pub use subtree::Subtree;
+pub use summary::{DocumentSummary, HeadMetadata};
+pub use text_conflict::{TextConflict, TextWithConflicts};
 pub use types::{ActorId, ChangeHash, ObjType, OpType, ParseChangeHashError, Prop};
-pub use value::{ScalarValue, Value};
+pub use undo::UndoManager;
+pub use value::{ScalarValue, Value, ValueRef};
 
 /// The object ID for the root map of a document
 pub const ROOT: ObjId = ObjId::Root;
@@ -285,6 +285,53 @@ impl<'a> MarkStateMachine<'a> {
     }
 }
 
+/// A run of text which has the same marks throughout, returned by [`crate::ReadDoc::spans()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    /// The text of the span
+    pub text: String,
+    /// The marks which apply to every character of the span
+    pub marks: MarkSet,
+}
+
+/// Split the `(index, text)` elements of a sequence into spans with the same marks
+pub(crate) fn spans<'a, I: Iterator<Item = (usize, &'a str)>>(
+    elems: I,
+    marks: &[Mark<'_>],
+) -> Vec<Span> {
+    let mut boundaries = marks
+        .iter()
+        .flat_map(|m| [m.start, m.end])
+        .collect::<Vec<_>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    let mut boundaries = boundaries.into_iter().peekable();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut current = MarkSet::default();
+    for (index, text) in elems {
+        let mut changed = spans.is_empty();
+        while boundaries.peek().map(|b| *b <= index).unwrap_or(false) {
+            boundaries.next();
+            changed = true;
+        }
+        if changed {
+            let mut active = MarkSet::default();
+            for mark in marks.iter().filter(|m| m.start <= index && index < m.end) {
+                active.insert(mark.data.name.clone(), mark.data.value.clone());
+            }
+            current = active;
+        }
+        match spans.last_mut() {
+            Some(span) if span.marks == current => span.text.push_str(text),
+            _ => spans.push(Span {
+                text: text.to_string(),
+                marks: current.clone(),
+            }),
+        }
+    }
+    spans
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct MarkData {
     pub name: SmolStr,
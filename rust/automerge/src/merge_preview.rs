@@ -0,0 +1,75 @@
+use crate::{ChangeHash, ObjId, Patch, PatchAction, Prop};
+
+/// What merging another document would do, returned by [`crate::Automerge::merge_preview()`]
+///
+/// The report has the three sides of the merge: [`Self::ours`] and [`Self::theirs`] are the
+/// changes each document made since [`Self::base`], the latest version both documents contain,
+/// and [`Self::patches`] are the changes merging would make to this document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergePreview {
+    /// The heads of the latest version of the document which both documents contain
+    ///
+    /// This is empty if the documents have no history in common.
+    pub base: Vec<ChangeHash>,
+    /// The changes in the other document which this document does not have, in the order they
+    /// would be applied. If this is empty merging would do nothing.
+    pub incoming: Vec<ChangeHash>,
+    /// The changes this document made since [`Self::base`]
+    pub ours: Vec<Patch>,
+    /// The changes the other document made since [`Self::base`]
+    pub theirs: Vec<Patch>,
+    /// The changes merging would make to the current state of this document
+    pub patches: Vec<Patch>,
+    /// The properties which would have conflicting values after merging because both documents
+    /// set them, in the order they appear in [`Self::patches`]
+    ///
+    /// The value which would win each conflict is the one in [`Self::patches`], or the current
+    /// value of this document if there is no patch which sets the property.
+    pub conflicts: Vec<(ObjId, Prop)>,
+}
+
+impl MergePreview {
+    pub(crate) fn new(
+        base: Vec<ChangeHash>,
+        incoming: Vec<ChangeHash>,
+        ours: Vec<Patch>,
+        theirs: Vec<Patch>,
+        patches: Vec<Patch>,
+    ) -> Self {
+        let mut conflicts = Vec::new();
+        for patch in &patches {
+            match &patch.action {
+                PatchAction::PutMap {
+                    key,
+                    conflict: true,
+                    ..
+                } => conflicts.push((patch.obj.clone(), Prop::Map(key.clone()))),
+                PatchAction::PutSeq {
+                    index,
+                    conflict: true,
+                    ..
+                } => conflicts.push((patch.obj.clone(), Prop::Seq(*index))),
+                PatchAction::Conflict { prop } => conflicts.push((patch.obj.clone(), prop.clone())),
+                _ => {}
+            }
+        }
+        MergePreview {
+            base,
+            incoming,
+            ours,
+            theirs,
+            patches,
+            conflicts,
+        }
+    }
+
+    /// Whether merging would change this document
+    pub fn is_empty(&self) -> bool {
+        self.incoming.is_empty()
+    }
+
+    /// Whether merging would leave any property with conflicting values
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+}
@@ -0,0 +1,23 @@
+use crate::ObjType;
+
+/// Statistics about the operations stored for a single object, returned by
+/// [`crate::Automerge::object_stats()`]
+///
+/// These are intended to help find objects which have accumulated enough history to cause
+/// performance problems, for example a list which has had millions of elements inserted and
+/// deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectStats {
+    /// The type of the object
+    pub obj_type: ObjType,
+    /// The number of keys (for maps) or elements (for sequences) which currently have a value
+    pub live: usize,
+    /// The number of keys or elements which have been deleted but are still stored in the
+    /// document
+    pub tombstones: usize,
+    /// The number of keys or elements which have more than one conflicting value
+    pub conflicts: usize,
+    /// The total number of operations stored for the object, including deleted, overwritten and
+    /// mark operations
+    pub ops: usize,
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::exid::ExId;
+use crate::{Patch, PatchAction, Prop, Value};
+
+/// Notified of every change to the current state of a document, see
+/// [`crate::Automerge::set_op_observer()`]
+///
+/// Each method is called with the object which changed and whether the change was made locally,
+/// in a transaction on this document, or remotely, by applying changes from another peer. The
+/// calls describe the change to the visible state of the document in the same way as
+/// [`Patch`]es do, so they can be used to keep a materialized view or an index up to date. Every
+/// method does nothing by default, so an observer only needs to implement the ones it is
+/// interested in. Conflicts and marks are not reported.
+///
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use automerge::{transaction::Transactable, AutoCommit, ObjId, OpObserver, Prop, Value, ROOT};
+/// struct RootKeys(Arc<Mutex<Vec<String>>>);
+///
+/// impl OpObserver for RootKeys {
+///     fn put(&self, obj: &ObjId, prop: &Prop, _value: &Value<'_>, _local: bool) {
+///         if obj == &ROOT {
+///             self.0.lock().unwrap().push(prop.to_string());
+///         }
+///     }
+/// }
+///
+/// let keys = Arc::new(Mutex::new(Vec::new()));
+/// let mut doc = AutoCommit::new();
+/// doc.set_op_observer(RootKeys(keys.clone()));
+/// doc.put(ROOT, "title", "notes").unwrap();
+/// doc.commit();
+/// assert_eq!(*keys.lock().unwrap(), vec!["title".to_string()]);
+/// ```
+pub trait OpObserver: Send + Sync {
+    /// `prop` in `obj` was set to `value`
+    fn put(&self, _obj: &ExId, _prop: &Prop, _value: &Value<'_>, _local: bool) {}
+
+    /// `value` was inserted at `index` in the list `obj`
+    fn insert(&self, _obj: &ExId, _index: usize, _value: &Value<'_>, _local: bool) {}
+
+    /// `text` was inserted at `index` in the text object `obj`
+    fn splice_text(&self, _obj: &ExId, _index: usize, _text: &str, _local: bool) {}
+
+    /// The counter at `prop` in `obj` was incremented by `by`
+    fn increment(&self, _obj: &ExId, _prop: &Prop, _by: i64, _local: bool) {}
+
+    /// `prop` was removed from `obj`
+    ///
+    /// Deleting several elements of a sequence is reported as a call for each element, each with
+    /// the index of the first one.
+    fn delete(&self, _obj: &ExId, _prop: &Prop, _local: bool) {}
+}
+
+#[derive(Clone)]
+pub(crate) struct OpObserverHandle(pub(crate) Arc<dyn OpObserver>);
+
+impl fmt::Debug for OpObserverHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpObserverHandle").finish()
+    }
+}
+
+impl OpObserverHandle {
+    /// Call the observer for each of `patches`
+    pub(crate) fn notify(&self, patches: Vec<Patch>, local: bool) {
+        let observer = &*self.0;
+        for Patch { obj, action, .. } in patches {
+            match action {
+                PatchAction::PutMap { key, value, .. } => {
+                    observer.put(&obj, &Prop::Map(key), &value.0, local)
+                }
+                PatchAction::PutSeq { index, value, .. } => {
+                    observer.put(&obj, &Prop::Seq(index), &value.0, local)
+                }
+                PatchAction::Insert { index, values, .. } => {
+                    for (offset, (value, _, _)) in values.iter().enumerate() {
+                        observer.insert(&obj, index + offset, value, local);
+                    }
+                }
+                PatchAction::SpliceText { index, value, .. } => {
+                    observer.splice_text(&obj, index, &value.make_string(), local)
+                }
+                PatchAction::Increment { prop, value } => {
+                    observer.increment(&obj, &prop, value, local)
+                }
+                PatchAction::DeleteMap { key } => observer.delete(&obj, &Prop::Map(key), local),
+                PatchAction::DeleteSeq { index, length } => {
+                    for _ in 0..length {
+                        observer.delete(&obj, &Prop::Seq(index), local);
+                    }
+                }
+                PatchAction::Conflict { .. } | PatchAction::Mark { .. } => {}
+            }
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use crate::clock::Clock;
+use crate::deleted_element::DeletedElement;
 use crate::error::AutomergeError;
 use crate::exid::ExId;
 use crate::indexed_cache::IndexedCache;
 use crate::iter::{Keys, ListRange, MapRange, TopOps};
+use crate::object_stats::ObjectStats;
 use crate::op_tree::OpTreeIter;
 use crate::op_tree::{
     self, FoundOpId, FoundOpWithPatchLog, FoundOpWithoutPatchLog, LastInsert, OpTree,
-    OpTreeInternal, OpsFound,
+    OpTreeInternal, OpTreeNode, OpsFound,
 };
 use crate::parents::Parents;
 use crate::query::{ChangeVisibility, TreeQuery};
@@ -71,6 +73,8 @@ impl OpSetInternal {
                 props: IndexedCache::new(),
                 ops: Vec::new(),
                 op_deps: Vec::new(),
+                #[cfg(feature = "query-tracing")]
+                tracer: Default::default(),
             },
         }
     }
@@ -98,6 +102,32 @@ impl OpSetInternal {
         }
     }
 
+    /// The number of objects in the opset
+    pub(crate) fn num_objects(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// The depth of the deepest op tree
+    pub(crate) fn max_tree_depth(&self) -> usize {
+        self.trees
+            .values()
+            .filter_map(|t| t.internal.root_node.as_ref())
+            .map(|n| n.depth())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The approximate number of bytes allocated by the op trees
+    pub(crate) fn trees_heap_size(&self) -> usize {
+        self.trees.capacity() * std::mem::size_of::<(ObjId, OpTree)>()
+            + self
+                .trees
+                .values()
+                .filter_map(|t| t.internal.root_node.as_ref())
+                .map(|n| std::mem::size_of::<OpTreeNode>() + n.heap_size())
+                .sum::<usize>()
+    }
+
     /// Iterate over objects in the opset in causal order
     pub(crate) fn iter_objs(&self) -> impl Iterator<Item = (&ObjId, ObjType, OpIter<'_>)> + '_ {
         let mut objs: Vec<_> = self
@@ -157,6 +187,14 @@ impl OpSetInternal {
             .and_then(|tree| tree.internal.seek_list_opid(id, encoding, clock, &self.osd))
     }
 
+    /// The op with ID `id` for `key` in `obj`, see [`OpTreeInternal::seek_op_at_key()`]
+    pub(crate) fn seek_op_at_key(&self, obj: &ObjId, key: &Key, id: OpId) -> Option<Op<'_>> {
+        let (_typ, encoding) = self.type_and_encoding(obj)?;
+        self.trees
+            .get(obj)
+            .and_then(|tree| tree.internal.seek_op_at_key(key, id, encoding, &self.osd))
+    }
+
     pub(crate) fn parent_object(&self, obj: &ObjId, clock: Option<&Clock>) -> Option<Parent> {
         let idx = self.trees.get(obj)?.parent?;
         let found = self.seek_idx(idx, clock)?;
@@ -182,6 +220,13 @@ impl OpSetInternal {
             .unwrap_or_default()
     }
 
+    pub(crate) fn has_map_key(&self, obj: &ObjId, key: &str, clock: Option<&Clock>) -> bool {
+        self.trees
+            .get(obj)
+            .map(|tree| tree.internal.has_map_key(&self.osd, key, clock))
+            .unwrap_or(false)
+    }
+
     pub(crate) fn op_iter<'a>(&'a self, obj: &ObjId) -> Option<OpIter<'a>> {
         self.trees.get(obj).map(|tree| OpIter {
             iter: tree.iter(),
@@ -222,12 +267,31 @@ impl OpSetInternal {
         }
     }
 
+    /// Like [`Self::find_op_without_patch_log()`] but for an op which is known to go at `pos`,
+    /// or after the ops it overwrites which start at `pos`
+    pub(crate) fn found_op_without_patch_log(
+        &self,
+        obj: &ObjId,
+        op: Op<'_>,
+        pred: &OpIds,
+        pos: usize,
+    ) -> FoundOpWithoutPatchLog {
+        if let Some(tree) = self.trees.get(obj) {
+            tree.internal
+                .found_op_without_patch_log(&self.osd, op, pred, pos)
+        } else {
+            Default::default()
+        }
+    }
+
     pub(crate) fn search<'a, 'b: 'a, Q>(&'b self, obj: &ObjId, mut query: Q) -> Q
     where
         Q: TreeQuery<'a>,
     {
         if let Some(tree) = self.trees.get(obj) {
             if query.can_shortcut_search(tree, &self.osd) {
+                #[cfg(feature = "query-tracing")]
+                self.osd.tracer.record_shortcut::<Q>();
                 query
             } else {
                 tree.internal.search(query, &self.osd)
@@ -375,7 +439,7 @@ impl OpSetInternal {
             self.length += 1;
             Ok(())
         } else {
-            Err(AutomergeError::NotAnObject)
+            Err(AutomergeError::NotAnObject(self.id_to_exid(obj.0)))
         }
     }
 
@@ -431,6 +495,178 @@ impl OpSetInternal {
         }
     }
 
+    pub(crate) fn object_stats(&self, obj: &ObjId, obj_type: ObjType) -> ObjectStats {
+        let mut stats = ObjectStats {
+            obj_type,
+            live: 0,
+            tombstones: 0,
+            conflicts: 0,
+            ops: 0,
+        };
+        // ops for the same key or element are stored next to each other, so count the visible
+        // ops in each run
+        let mut current: Option<(Key, usize)> = None;
+        let finish = |visible: usize, stats: &mut ObjectStats| match visible {
+            0 => stats.tombstones += 1,
+            1 => stats.live += 1,
+            _ => {
+                stats.live += 1;
+                stats.conflicts += 1;
+            }
+        };
+        for op in self.iter_ops(obj) {
+            stats.ops += 1;
+            if op.is_mark() {
+                continue;
+            }
+            let key = op.elemid_or_key();
+            let visible = usize::from(op.visible());
+            match &mut current {
+                Some((k, count)) if *k == key => *count += visible,
+                _ => {
+                    if let Some((_, count)) = current.replace((key, visible)) {
+                        finish(count, &mut stats);
+                    }
+                }
+            }
+        }
+        if let Some((_, count)) = current {
+            finish(count, &mut stats);
+        }
+        stats
+    }
+
+    /// The props of `obj` which currently have more than one visible value, in order
+    pub(crate) fn conflicted_props(&self, obj: &ObjId, encoding: ListEncoding) -> Vec<Prop> {
+        let mut props = Vec::new();
+        let mut index = 0;
+        // (key, number of visible ops, last visible op) for the current run of ops
+        let mut current: Option<(Key, usize, Option<Op<'_>>)> = None;
+        let mut finish = |visible: usize, winner: Option<Op<'_>>, key: Key| {
+            if let Some(winner) = winner {
+                if visible > 1 {
+                    props.push(winner.map_prop().unwrap_or(Prop::Seq(index)));
+                }
+                if matches!(key, Key::Seq(_)) {
+                    index += winner.width(encoding);
+                }
+            }
+        };
+        for op in self.iter_ops(obj) {
+            if op.is_mark() {
+                continue;
+            }
+            let key = op.elemid_or_key();
+            let visible = op.visible();
+            match &mut current {
+                Some((k, count, winner)) if *k == key => {
+                    if visible {
+                        *count += 1;
+                        *winner = Some(op);
+                    }
+                }
+                _ => {
+                    let next = (key, usize::from(visible), Some(op).filter(|_| visible));
+                    if let Some((key, count, winner)) = current.replace(next) {
+                        finish(count, winner, key);
+                    }
+                }
+            }
+        }
+        if let Some((key, count, winner)) = current {
+            finish(count, winner, key);
+        }
+        props
+    }
+
+    /// The elements of the sequence `obj` which were visible at `since` but are not visible now
+    pub(crate) fn deleted_elements(
+        &self,
+        obj: &ObjId,
+        encoding: ListEncoding,
+        since: &Clock,
+    ) -> Vec<DeletedElement<'_>> {
+        let mut deleted = vec![];
+        let mut index = 0;
+        let mut ops = self.iter_ops(obj).filter(|op| !op.is_mark()).peekable();
+        while let Some(first) = ops.next() {
+            let key = first.elemid_or_key();
+            let mut element = vec![first];
+            while let Some(op) = ops.next_if(|op| op.elemid_or_key() == key) {
+                element.push(op);
+            }
+            let width = match element
+                .iter()
+                .find(|op| !op.is_inc() && op.visible_or_mark(Some(since)))
+            {
+                Some(op) => op.width(encoding),
+                None => continue,
+            };
+            if !element.iter().any(|op| op.visible()) {
+                // ops on an element are ordered by ID so the last one is the most recent value
+                if let (Key::Seq(elemid), Some(last)) =
+                    (key, element.iter().rev().find(|op| !op.is_inc()))
+                {
+                    deleted.push(DeletedElement {
+                        index,
+                        id: self.id_to_exid(elemid.0),
+                        value: last.value(),
+                        value_id: last.exid(),
+                    });
+                }
+            }
+            index += width;
+        }
+        deleted
+    }
+
+    #[cfg(any(test, feature = "debug-invariants"))]
+    pub(crate) fn check_invariants(&self) -> Vec<crate::automerge::InvariantViolation> {
+        use crate::automerge::InvariantViolation;
+
+        let mut violations = vec![];
+        let mut count = 0;
+        for (obj, tree) in &self.trees {
+            count += tree.len();
+            for op in self.iter_ops(obj) {
+                for succ in op.succ() {
+                    if !succ.pred().any(|p| p.idx() == op.idx()) {
+                        violations.push(InvariantViolation::SuccPredMismatch {
+                            op: op.exid(),
+                            other: succ.exid(),
+                        });
+                    }
+                }
+                for pred in op.pred() {
+                    if !pred.succ().any(|s| s.idx() == op.idx()) {
+                        violations.push(InvariantViolation::SuccPredMismatch {
+                            op: op.exid(),
+                            other: pred.exid(),
+                        });
+                    }
+                }
+            }
+            if let Some(index) = tree.index(ListEncoding::List) {
+                let indexed = index.visible_len(ListEncoding::List);
+                let actual = self.top_ops(obj, None).count();
+                if indexed != actual {
+                    violations.push(InvariantViolation::IndexLength {
+                        obj: self.id_to_exid(obj.0),
+                        indexed,
+                        actual,
+                    });
+                }
+            }
+        }
+        if count != self.length {
+            violations.push(InvariantViolation::OpCount {
+                recorded: self.length,
+                actual: count,
+            });
+        }
+        violations
+    }
+
     pub(crate) fn text(&self, obj: &ObjId, clock: Option<Clock>) -> String {
         self.top_ops(obj, clock)
             .map(|top| top.op.as_str())
@@ -548,6 +784,8 @@ pub(crate) struct OpSetData {
     pub(crate) props: IndexedCache<String>,
     ops: Vec<OpRaw>,
     op_deps: Vec<OpDepRaw>,
+    #[cfg(feature = "query-tracing")]
+    pub(crate) tracer: crate::query_stats::QueryTracer,
 }
 
 impl Default for OpSetData {
@@ -557,6 +795,8 @@ impl Default for OpSetData {
             props: IndexedCache::new(),
             ops: Vec::new(),
             op_deps: Vec::new(),
+            #[cfg(feature = "query-tracing")]
+            tracer: Default::default(),
         }
     }
 }
@@ -632,6 +872,17 @@ impl<'a> DoubleEndedIterator for ChangeOpIter<'a> {
 }
 
 impl OpSetData {
+    /// The approximate number of bytes allocated for the ops and the links between them
+    pub(crate) fn ops_heap_size(&self) -> usize {
+        self.ops.capacity() * std::mem::size_of::<OpRaw>()
+            + self.op_deps.capacity() * std::mem::size_of::<OpDepRaw>()
+    }
+
+    /// The approximate number of bytes allocated for the actor and prop caches
+    pub(crate) fn caches_heap_size(&self) -> usize {
+        self.actors.heap_size(|_| 0) + self.props.heap_size(|p| p.capacity())
+    }
+
     pub(crate) fn start_range(&self) -> OpIdxRange {
         let len = self.ops.len() as u32;
         OpIdxRange {
@@ -792,6 +1043,8 @@ impl OpSetData {
             actors: actors.into_iter().collect(),
             ops: Vec::new(),
             op_deps: Vec::new(),
+            #[cfg(feature = "query-tracing")]
+            tracer: Default::default(),
         }
     }
 
@@ -171,6 +171,9 @@ impl<'a> Op<'a> {
         if let Some(clock) = clock {
             if self.is_inc() || self.is_mark() {
                 false
+            } else if self.is_counter() {
+                clock.covers(&self.op().id)
+                    && self.succ().all(|o| o.is_inc() || !clock.covers(o.id()))
             } else {
                 clock.covers(&self.op().id) && !self.succ().any(|i| clock.covers(i.id()))
             }
@@ -254,13 +257,13 @@ impl<'a> Op<'a> {
                     None
                 }
             })
-            .sum()
+            .fold(0, i64::wrapping_add)
     }
 
     pub(crate) fn value_at(&self, clock: Option<&Clock>) -> Value<'a> {
         if let Some(clock) = clock {
             if let OpType::Put(ScalarValue::Counter(c)) = &self.op().action {
-                return Value::counter(c.start + self.inc_at(clock));
+                return Value::counter(c.start.wrapping_add(self.inc_at(clock)));
             }
         }
         self.value()
@@ -401,7 +404,7 @@ pub(crate) struct OpBuilder {
 impl OpBuilder {
     pub(crate) fn increment(&mut self, n: i64) {
         if let OpType::Put(ScalarValue::Counter(c)) = &mut self.action {
-            c.current += n;
+            c.increment(n);
         }
     }
 
@@ -388,6 +388,30 @@ impl OpTreeInternal {
         })
     }
 
+    /// The op with ID `id` for `key`, which is either a map key or the ID of a sequence element
+    pub(crate) fn seek_op_at_key<'a>(
+        &'a self,
+        key: &Key,
+        id: OpId,
+        encoding: ListEncoding,
+        osd: &'a OpSetData,
+    ) -> Option<Op<'a>> {
+        match key {
+            Key::Map(_) => {
+                let pos = self.binary_search_by(osd, |o| o.key_cmp(key));
+                self.iter()
+                    .skip(pos)
+                    .map(|idx| idx.as_op(osd))
+                    .take_while(|o| o.key_cmp(key) == Ordering::Equal)
+                    .find(|o| *o.id() == id)
+            }
+            Key::Seq(_) => self
+                .seek_list_opid(id, encoding, None, osd)
+                .map(|found| found.op)
+                .filter(|op| op.elemid_or_key() == *key),
+        }
+    }
+
     pub(crate) fn find_op_with_patch_log<'a>(
         &'a self,
         op: Op<'a>,
@@ -470,6 +494,25 @@ impl OpTreeInternal {
         })
     }
 
+    /// Whether any op for `key_name` is visible, stopping at the first one found
+    pub(crate) fn has_map_key(
+        &self,
+        osd: &OpSetData,
+        key_name: &str,
+        clock: Option<&Clock>,
+    ) -> bool {
+        let key = match osd.props.lookup(key_name) {
+            Some(prop) => Key::Map(prop),
+            None => return false,
+        };
+        let pos = self.binary_search_by(osd, |o| o.key_cmp(&key));
+        self.iter()
+            .skip(pos)
+            .map(|op| op.as_op(osd))
+            .take_while(|op| op.key_cmp(&key) == Ordering::Equal)
+            .any(|op| op.visible_at(clock))
+    }
+
     pub(crate) fn seek_ops_by_index<'a>(
         &'a self,
         osd: &'a OpSetData,
@@ -503,10 +546,13 @@ impl OpTreeInternal {
         left
     }
 
-    pub(crate) fn search<'a, 'b: 'a, Q>(&'b self, mut query: Q, osd: &'a OpSetData) -> Q
+    pub(crate) fn search<'a, 'b: 'a, Q>(&'b self, query: Q, osd: &'a OpSetData) -> Q
     where
         Q: TreeQuery<'a>,
     {
+        #[cfg(feature = "query-tracing")]
+        let query = crate::query_stats::Traced::new(query);
+        let mut query = query;
         self.root_node.as_ref().map(|root| {
             if let Some(index) = root.index.as_ref() {
                 match query.query_node(root, index, osd) {
@@ -518,6 +564,8 @@ impl OpTreeInternal {
                 root.search(&mut query, osd)
             }
         });
+        #[cfg(feature = "query-tracing")]
+        let query = osd.tracer.record(query);
         query
     }
 
@@ -28,6 +28,20 @@ impl OpTreeNode {
         }
     }
 
+    /// The number of levels of nodes from this one down to the leaves, including this one
+    pub(crate) fn depth(&self) -> usize {
+        // the tree is balanced so every path to a leaf is the same length
+        1 + self.children.first().map_or(0, |c| c.depth())
+    }
+
+    /// The approximate number of bytes allocated by this node and its children
+    pub(crate) fn heap_size(&self) -> usize {
+        self.elements.capacity() * std::mem::size_of::<OpIdx>()
+            + self.children.capacity() * std::mem::size_of::<OpTreeNode>()
+            + self.index.as_ref().map_or(0, |i| i.heap_size())
+            + self.children.iter().map(|c| c.heap_size()).sum::<usize>()
+    }
+
     fn search_element<'a, 'b: 'a, Q>(
         &'b self,
         query: &mut Q,
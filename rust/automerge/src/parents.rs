@@ -111,4 +111,31 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn parent_object_and_path_to_object() {
+        let mut doc = crate::AutoCommit::new();
+        let config = doc
+            .put_object(crate::ROOT, "config", crate::ObjType::Map)
+            .unwrap();
+        let servers = doc
+            .put_object(&config, "servers", crate::ObjType::List)
+            .unwrap();
+        let server = doc.insert_object(&servers, 0, crate::ObjType::Map).unwrap();
+
+        assert_eq!(doc.parent_object(crate::ROOT).unwrap(), None);
+        assert_eq!(
+            doc.parent_object(&server).unwrap(),
+            Some((servers.clone(), Prop::Seq(0)))
+        );
+        assert_eq!(doc.path_to_object(crate::ROOT).unwrap(), vec![]);
+        assert_eq!(
+            doc.path_to_object(&server).unwrap(),
+            vec![
+                (crate::ROOT, Prop::Map("config".to_string())),
+                (config, Prop::Map("servers".to_string())),
+                (servers, Prop::Seq(0)),
+            ]
+        );
+    }
 }
@@ -1,7 +1,9 @@
 mod patch;
+mod patch_batch;
 mod patch_builder;
 mod patch_log;
 pub use patch::{Patch, PatchAction};
+pub use patch_batch::PatchBatch;
 pub(crate) use patch_builder::PatchBuilder;
 pub use patch_log::PatchLog;
 
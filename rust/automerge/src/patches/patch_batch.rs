@@ -0,0 +1,31 @@
+use crate::ChangeHash;
+
+use super::Patch;
+
+/// A sequence of [`Patch`]es emitted by [`crate::AutoCommit::diff_incremental_batch()`]
+///
+/// Every batch carries a sequence number which increases by one for each batch emitted by a
+/// document, along with the heads of the document before and after the patches were generated.
+/// Consumers which receive batches over an unreliable transport can use these to detect missed or
+/// re-ordered deliveries, see [`Self::follows()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchBatch {
+    /// The sequence number of this batch, the first batch emitted by a document is `1`
+    pub seq: u64,
+    /// The heads of the document before the patches in this batch were applied
+    pub before: Vec<ChangeHash>,
+    /// The heads of the document after the patches in this batch were applied
+    pub after: Vec<ChangeHash>,
+    /// The patches in this batch
+    pub patches: Vec<Patch>,
+}
+
+impl PatchBatch {
+    /// Whether this batch is the direct successor of `prev`
+    ///
+    /// This is true if the sequence number of this batch is one more than that of `prev` and the
+    /// `before` heads of this batch are the `after` heads of `prev`.
+    pub fn follows(&self, prev: &PatchBatch) -> bool {
+        self.seq == prev.seq + 1 && self.before == prev.after
+    }
+}
@@ -45,6 +45,7 @@ pub struct PatchLog {
     expose: HashSet<OpId>,
     active: bool,
     text_rep: TextRepresentation,
+    obj_types: Option<Vec<ObjType>>,
     pub(crate) heads: Option<Vec<ChangeHash>>,
 }
 
@@ -122,6 +123,7 @@ impl PatchLog {
             events: vec![],
             heads: None,
             text_rep,
+            obj_types: None,
         }
     }
 
@@ -143,6 +145,61 @@ impl PatchLog {
         Self::new(true, text_rep)
     }
 
+    /// Only generate patches for changes to objects of the given types
+    ///
+    /// Changes to other objects are dropped while the patches are generated, before any work is
+    /// done to work out their path or value, so an application which is only interested in part
+    /// of the document doesn't pay for patches it would throw away. For example, an editor which
+    /// only renders text can use `&[ObjType::Text]` to receive just the text deltas. The contents
+    /// of a newly created object are still reported if the object is of one of the given types,
+    /// even if its parent is not.
+    ///
+    /// ```
+    /// # use automerge::{Automerge, ObjType, PatchLog, ROOT, patches::TextRepresentation};
+    /// # use automerge::transaction::Transactable;
+    /// let mut doc = Automerge::new();
+    /// let text = doc
+    ///     .transact::<_, _, automerge::AutomergeError>(|tx| {
+    ///         tx.put_object(ROOT, "text", ObjType::Text)
+    ///     })
+    ///     .unwrap()
+    ///     .result;
+    ///
+    /// let mut other = doc.fork();
+    /// other
+    ///     .transact::<_, _, automerge::AutomergeError>(|tx| {
+    ///         tx.put(ROOT, "title", "notes")?;
+    ///         tx.splice_text(&text, 0, 0, "hello")
+    ///     })
+    ///     .unwrap();
+    ///
+    /// let mut patch_log =
+    ///     PatchLog::active(TextRepresentation::String).with_obj_types(&[ObjType::Text]);
+    /// doc.merge_and_log_patches(&mut other, &mut patch_log).unwrap();
+    /// let patches = doc.make_patches(&mut patch_log);
+    /// assert_eq!(patches.len(), 1);
+    /// assert_eq!(patches[0].obj, text);
+    /// ```
+    pub fn with_obj_types(mut self, obj_types: &[ObjType]) -> Self {
+        self.obj_types = Some(obj_types.to_vec());
+        self
+    }
+
+    pub(crate) fn set_obj_types(&mut self, obj_types: Option<Vec<ObjType>>) {
+        self.obj_types = obj_types;
+    }
+
+    pub(crate) fn obj_types(&self) -> Option<&[ObjType]> {
+        self.obj_types.as_deref()
+    }
+
+    /// A new log with the same settings as this one
+    pub(crate) fn fresh(&self, active: bool) -> Self {
+        let mut log = Self::new(active, self.text_rep);
+        log.obj_types = self.obj_types.clone();
+        log
+    }
+
     pub(crate) fn set_active(&mut self, setting: bool) {
         self.active = setting
     }
@@ -320,9 +377,9 @@ impl PatchLog {
         let expose = ExposeQueue(self.expose.iter().map(|id| doc.id_to_exid(*id)).collect());
         if let Some(heads) = self.heads.as_ref() {
             let read_doc = ReadDocAt { doc, heads };
-            Self::make_patches_inner(&self.events, expose, doc, &read_doc, self.text_rep)
+            Self::make_patches_inner(&self.events, expose, doc, &read_doc, self.filter())
         } else {
-            Self::make_patches_inner(&self.events, expose, doc, doc, self.text_rep)
+            Self::make_patches_inner(&self.events, expose, doc, doc, self.filter())
         }
     }
 
@@ -331,7 +388,7 @@ impl PatchLog {
         mut expose_queue: ExposeQueue,
         doc: &Automerge,
         read_doc: &R,
-        text_rep: TextRepresentation,
+        filter: Filter<'_>,
     ) -> Vec<Patch> {
         let mut patch_builder = PatchBuilder::default();
         for (obj, event) in events {
@@ -344,7 +401,10 @@ impl PatchLog {
                 continue;
             }
             // any objects exposed BEFORE exid get observed here
-            expose_queue.pump_queue(&exid, &mut patch_builder, doc, read_doc, text_rep);
+            expose_queue.pump_queue(&exid, &mut patch_builder, doc, read_doc, filter);
+            if !filter.accepts(doc, obj) {
+                continue;
+            }
             match event {
                 Event::PutMap {
                     key,
@@ -416,7 +476,7 @@ impl PatchLog {
             }
         }
         // any objects exposed AFTER all other events get exposed here
-        expose_queue.flush_queue(&mut patch_builder, doc, read_doc, text_rep);
+        expose_queue.flush_queue(&mut patch_builder, doc, read_doc, filter);
 
         patch_builder.take_patches()
     }
@@ -433,6 +493,7 @@ impl PatchLog {
             expose: HashSet::new(),
             events: Default::default(),
             text_rep: self.text_rep,
+            obj_types: self.obj_types.clone(),
             heads: None,
         }
     }
@@ -448,6 +509,33 @@ impl PatchLog {
     pub(crate) fn set_text_rep(&mut self, rep: TextRepresentation) {
         self.text_rep = rep;
     }
+
+    fn filter(&self) -> Filter<'_> {
+        Filter {
+            text_rep: self.text_rep,
+            obj_types: self.obj_types.as_deref(),
+        }
+    }
+}
+
+/// The settings which decide which patches are generated, and how
+#[derive(Clone, Copy)]
+struct Filter<'a> {
+    text_rep: TextRepresentation,
+    obj_types: Option<&'a [ObjType]>,
+}
+
+impl<'a> Filter<'a> {
+    fn accepts(&self, doc: &Automerge, obj: &ObjId) -> bool {
+        match self.obj_types {
+            Some(types) => doc
+                .ops()
+                .object_type(obj)
+                .map(|t| types.contains(&t))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
 }
 
 impl AsRef<OpId> for &(ObjId, Event) {
@@ -474,13 +562,13 @@ impl ExposeQueue {
         patch_builder: &mut PatchBuilder,
         doc: &Automerge,
         read_doc: &R,
-        text_rep: TextRepresentation,
+        filter: Filter<'_>,
     ) {
         while let Some(exposed) = self.0.first() {
             if exposed >= obj {
                 break;
             }
-            self.flush_obj(exposed.clone(), patch_builder, doc, read_doc, text_rep);
+            self.flush_obj(exposed.clone(), patch_builder, doc, read_doc, filter);
         }
     }
 
@@ -489,10 +577,10 @@ impl ExposeQueue {
         patch_builder: &mut PatchBuilder,
         doc: &Automerge,
         read_doc: &R,
-        text_rep: TextRepresentation,
+        filter: Filter<'_>,
     ) {
         while let Some(exposed) = self.0.first() {
-            self.flush_obj(exposed.clone(), patch_builder, doc, read_doc, text_rep);
+            self.flush_obj(exposed.clone(), patch_builder, doc, read_doc, filter);
         }
     }
 
@@ -510,15 +598,20 @@ impl ExposeQueue {
         patch_builder: &mut PatchBuilder,
         doc: &Automerge,
         read_doc: &R,
-        text_rep: TextRepresentation,
+        filter: Filter<'_>,
     ) -> Option<()> {
         let id = exid.to_internal_obj();
         self.remove(&exid);
+        // objects which are filtered out are still walked so that any children they have which
+        // are of interest are reported
+        let accepted = filter.accepts(doc, &id);
         match doc.ops().object_type(&id)? {
-            ObjType::Text if matches!(text_rep, TextRepresentation::String) => {
-                let text = read_doc.text(&exid).ok()?;
-                // TODO - need read_doc, text_spans()
-                patch_builder.splice_text(read_doc, exid, 0, &text, None);
+            ObjType::Text if matches!(filter.text_rep, TextRepresentation::String) => {
+                if accepted {
+                    let text = read_doc.text(&exid).ok()?;
+                    // TODO - need read_doc, text_spans()
+                    patch_builder.splice_text(read_doc, exid, 0, &text, None);
+                }
             }
             ObjType::List | ObjType::Text => {
                 for ListRangeItem {
@@ -532,14 +625,16 @@ impl ExposeQueue {
                     if value.is_object() {
                         self.insert(id.clone());
                     }
-                    patch_builder.insert(
-                        read_doc,
-                        exid.clone(),
-                        index,
-                        (value, id),
-                        conflict,
-                        marks,
-                    );
+                    if accepted {
+                        patch_builder.insert(
+                            read_doc,
+                            exid.clone(),
+                            index,
+                            (value, id),
+                            conflict,
+                            marks,
+                        );
+                    }
                 }
             }
             ObjType::Map | ObjType::Table => {
@@ -553,7 +648,15 @@ impl ExposeQueue {
                     if value.is_object() {
                         self.insert(id.clone());
                     }
-                    patch_builder.put(read_doc, exid.clone(), key.into(), (value, id), conflict);
+                    if accepted {
+                        patch_builder.put(
+                            read_doc,
+                            exid.clone(),
+                            key.into(),
+                            (value, id),
+                            conflict,
+                        );
+                    }
                 }
             }
         }
@@ -0,0 +1,103 @@
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ObjType, Prop, ReadDoc, ScalarValue, Value, ROOT};
+
+/// Split `path` into its segments
+///
+/// A path starting with `/` is a JSON Pointer (RFC 6901), in which `~1` stands for `/` and `~0`
+/// for `~` within a segment. Any other path is a list of segments separated by `.`. The empty
+/// path refers to the root of the document.
+pub(crate) fn segments(path: &str) -> Vec<String> {
+    if path.is_empty() {
+        Vec::new()
+    } else if let Some(pointer) = path.strip_prefix('/') {
+        pointer
+            .split('/')
+            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+            .collect()
+    } else {
+        path.split('.').map(|s| s.to_owned()).collect()
+    }
+}
+
+/// The prop `segment` refers to in an object of type `obj_type`
+///
+/// `len` is the length of the object, which `-` refers to the end of if it is a sequence.
+fn prop(path: &str, segment: &str, obj_type: ObjType, len: usize) -> Result<Prop, AutomergeError> {
+    if !obj_type.is_sequence() {
+        Ok(Prop::Map(segment.to_owned()))
+    } else if segment == "-" {
+        Ok(Prop::Seq(len))
+    } else {
+        segment
+            .parse()
+            .map(Prop::Seq)
+            .map_err(|_| AutomergeError::InvalidPath(path.to_owned()))
+    }
+}
+
+/// Resolve `path` to a value, see [`ReadDoc::value_path()`]
+pub(crate) fn get<'a, R: ReadDoc + ?Sized>(
+    doc: &'a R,
+    path: &str,
+) -> Result<Option<(Value<'a>, ExId)>, AutomergeError> {
+    let mut current = (Value::Object(ObjType::Map), ROOT);
+    for segment in segments(path) {
+        let obj = match current {
+            (Value::Object(obj_type), obj) => (obj_type, obj),
+            (Value::Scalar(_), _) => return Ok(None),
+        };
+        let prop = prop(path, &segment, obj.0, doc.length(&obj.1))?;
+        match doc.get(&obj.1, prop)? {
+            Some(value) => current = value,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(current))
+}
+
+/// Write `value` at `path`, see [`Transactable::set_path()`]
+pub(crate) fn set<T: Transactable + ?Sized>(
+    tx: &mut T,
+    path: &str,
+    value: ScalarValue,
+    create: bool,
+) -> Result<(), AutomergeError> {
+    let mut segments = segments(path);
+    let last = segments
+        .pop()
+        .ok_or_else(|| AutomergeError::InvalidPath(path.to_owned()))?;
+    let mut obj = (ObjType::Map, ROOT);
+    for (i, segment) in segments.iter().enumerate() {
+        let len = tx.length(&obj.1);
+        let prop = prop(path, segment, obj.0, len)?;
+        obj = match tx.get(&obj.1, prop.clone())? {
+            Some((Value::Object(obj_type), id)) => (obj_type, id),
+            Some((Value::Scalar(_), _)) => {
+                return Err(AutomergeError::InvalidPath(path.to_owned()))
+            }
+            None if create => {
+                // create a list if the segment after this one indexes into a sequence
+                let next = segments.get(i + 1).unwrap_or(&last);
+                let obj_type = if next == "-" || next.parse::<usize>().is_ok() {
+                    ObjType::List
+                } else {
+                    ObjType::Map
+                };
+                let id = match prop {
+                    Prop::Seq(index) if index == len => {
+                        tx.insert_object(&obj.1, index, obj_type)?
+                    }
+                    prop => tx.put_object(&obj.1, prop, obj_type)?,
+                };
+                (obj_type, id)
+            }
+            None => return Err(AutomergeError::InvalidPath(path.to_owned())),
+        };
+    }
+    let len = tx.length(&obj.1);
+    match prop(path, &last, obj.0, len)? {
+        Prop::Seq(index) if index == len => tx.insert(&obj.1, index, value),
+        prop => tx.put(&obj.1, prop, value),
+    }
+}
@@ -123,6 +123,14 @@ pub(crate) struct Index {
 }
 
 impl Index {
+    /// The approximate number of bytes allocated by the index
+    pub(crate) fn heap_size(&self) -> usize {
+        self.visible.capacity() * std::mem::size_of::<(Key, usize)>()
+            + self.ops.capacity() * std::mem::size_of::<OpId>()
+            + self.mark_begin.capacity() * std::mem::size_of::<(OpId, MarkData)>()
+            + self.mark_end.capacity() * std::mem::size_of::<OpId>()
+    }
+
     pub(crate) fn has_never_seen_puts(&self) -> bool {
         self.never_seen_puts
     }
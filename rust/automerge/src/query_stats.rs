@@ -0,0 +1,117 @@
+use std::sync::{Mutex, PoisonError};
+
+use crate::op_set::Op;
+use crate::op_tree::{OpSetData, OpTree, OpTreeNode};
+use crate::query::{Index, QueryResult, TreeQuery};
+
+/// What the most recent search of the op tree did, returned by
+/// [`crate::Automerge::last_query_stats()`]
+///
+/// Reads and writes which address a list or text object by index, or need to find an existing
+/// op, search the op tree of the object. Each node of the tree has an index which lets a query
+/// skip the whole node, so a search normally visits a few nodes on each level and examines a
+/// few ops. A search which examines a large fraction of the ops in the object is the sign of an
+/// access pattern the index doesn't help with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueryStats {
+    /// The name of the query type, for example `Nth` for finding the element at an index
+    pub query: &'static str,
+    /// Whether the query was answered without searching the tree, for example from a cache
+    pub shortcut: bool,
+    /// The number of nodes whose index was checked
+    pub nodes_visited: usize,
+    /// The number of nodes the query descended into
+    pub nodes_descended: usize,
+    /// The number of nodes the query skipped using their index
+    pub nodes_skipped: usize,
+    /// The number of ops the query examined one at a time
+    pub ops_visited: usize,
+}
+
+impl QueryStats {
+    fn new<Q>() -> Self {
+        let name = std::any::type_name::<Q>();
+        let name = name.split('<').next().unwrap_or(name);
+        QueryStats {
+            query: name.rsplit("::").next().unwrap_or(name),
+            ..Default::default()
+        }
+    }
+}
+
+/// Records the [`QueryStats`] of the last search of a document
+#[derive(Debug, Default)]
+pub(crate) struct QueryTracer(Mutex<Option<QueryStats>>);
+
+impl QueryTracer {
+    pub(crate) fn last(&self) -> Option<QueryStats> {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn record<Q>(&self, traced: Traced<Q>) -> Q {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(traced.stats);
+        traced.query
+    }
+
+    pub(crate) fn record_shortcut<Q>(&self) {
+        let stats = QueryStats {
+            shortcut: true,
+            ..QueryStats::new::<Q>()
+        };
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(stats);
+    }
+}
+
+impl Clone for QueryTracer {
+    /// A copy of a document starts without stats
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// Wraps a query to count what it does
+#[derive(Debug, Clone)]
+pub(crate) struct Traced<Q> {
+    query: Q,
+    stats: QueryStats,
+}
+
+impl<Q> Traced<Q> {
+    pub(crate) fn new(query: Q) -> Self {
+        Traced {
+            query,
+            stats: QueryStats::new::<Q>(),
+        }
+    }
+}
+
+impl<'a, Q: TreeQuery<'a>> TreeQuery<'a> for Traced<Q> {
+    fn equiv(&mut self, other: &Self) -> bool {
+        self.query.equiv(&other.query)
+    }
+
+    fn can_shortcut_search(&mut self, tree: &'a OpTree, osd: &'a OpSetData) -> bool {
+        self.query.can_shortcut_search(tree, osd)
+    }
+
+    fn query_node(
+        &mut self,
+        child: &'a OpTreeNode,
+        index: &'a Index,
+        osd: &'a OpSetData,
+    ) -> QueryResult {
+        let result = self.query.query_node(child, index, osd);
+        self.stats.nodes_visited += 1;
+        match result {
+            QueryResult::Descend => self.stats.nodes_descended += 1,
+            QueryResult::Next => self.stats.nodes_skipped += 1,
+            QueryResult::Finish => {}
+        }
+        result
+    }
+
+    fn query_element(&mut self, op: Op<'a>) -> QueryResult {
+        self.stats.ops_visited += 1;
+        self.query.query_element(op)
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{
+    blob::BlobReader,
     error::AutomergeError,
     exid::ExId,
     iter::{Keys, ListRange, MapRange, Values},
-    marks::{Mark, MarkSet},
-    parents::Parents,
-    Change, ChangeHash, Cursor, ObjType, Prop, Value,
+    marks::{self, Mark, MarkSet, Span},
+    parents::{Parent, Parents},
+    path, Change, ChangeHash, ConflictOrder, Conflicts, Cursor, ObjType, Prop, ScalarValue, Value,
 };
 
 use std::ops::RangeBounds;
@@ -40,6 +41,49 @@ pub trait ReadDoc {
         heads: &[ChangeHash],
     ) -> Result<Parents<'_>, AutomergeError>;
 
+    /// Get the object which contains `obj` and the property of `obj` within it, or [`None`] for
+    /// the root object
+    ///
+    /// # Errors
+    ///
+    /// The same as [`Self::parents()`]
+    fn parent_object<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+    ) -> Result<Option<(ExId, Prop)>, AutomergeError> {
+        Ok(self
+            .parents(obj)?
+            .next()
+            .map(|Parent { obj, prop, .. }| (obj, prop)))
+    }
+
+    /// Get the path from the root to `obj` as a list of `(object, property)` pairs, starting
+    /// with the property of the root which leads to `obj`
+    ///
+    /// The path to the root object is empty. Formatting the properties of the path gives a
+    /// readable location for an object ID, for example to show in a debugger. If `obj` has been
+    /// deleted, or lost a conflict, the path is to where it used to be, see
+    /// [`Parents::visible_path()`] to detect this.
+    ///
+    /// ```
+    /// # use automerge::{transaction::Transactable, AutoCommit, ObjType, Prop, ReadDoc, ROOT};
+    /// let mut doc = AutoCommit::new();
+    /// let list = doc.put_object(ROOT, "todos", ObjType::List).unwrap();
+    /// let todo = doc.insert_object(&list, 0, ObjType::Map).unwrap();
+    /// let path = doc.path_to_object(&todo).unwrap();
+    /// assert_eq!(
+    ///     path,
+    ///     vec![(ROOT, Prop::from("todos")), (list, Prop::from(0))]
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The same as [`Self::parents()`]
+    fn path_to_object<O: AsRef<ExId>>(&self, obj: O) -> Result<Vec<(ExId, Prop)>, AutomergeError> {
+        Ok(self.parents(obj)?.path())
+    }
+
     /// Get the keys of the object `obj`.
     ///
     /// For a map this returns the keys of the map.
@@ -57,6 +101,10 @@ pub trait ReadDoc {
     ///
     /// The returned iterator yields `(key, value, exid)` tuples, where the
     /// third element is the ID of the operation which created the value.
+    ///
+    /// Keys are visited in lexicographic order in a single pass over the object, stopping once the
+    /// end of `range` is reached, so this is cheaper than calling [`Self::get()`] for each of
+    /// [`Self::keys()`].
     fn map_range<'a, O: AsRef<ExId>, R: RangeBounds<String> + 'a>(
         &'a self,
         obj: O,
@@ -81,8 +129,12 @@ pub trait ReadDoc {
 
     /// Iterate over the indexes and values of the list or text `obj` in the given range.
     ///
-    /// The reuturned iterator yields `(index, value, exid)` tuples, where the third
+    /// The returned iterator yields `(index, value, exid)` tuples, where the third
     /// element is the ID of the operation which created the value.
+    ///
+    /// The elements are produced lazily from a single pass over the object, stopping once the end
+    /// of `range` is reached, so use `list_range(obj, ..)` rather than [`Self::get()`] for each
+    /// index to walk every element of a list.
     fn list_range<O: AsRef<ExId>, R: RangeBounds<usize>>(
         &self,
         obj: O,
@@ -128,6 +180,38 @@ pub trait ReadDoc {
     /// See [`Self::length()`]
     fn length_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> usize;
 
+    /// Whether the map `obj` has a value for `key`
+    ///
+    /// This is cheaper than checking the result of [`Self::get()`] as it stops at the first
+    /// visible operation for `key` and doesn't build a value. Returns `false` if `obj` is not a
+    /// map or is not in this document.
+    fn contains_key<O: AsRef<ExId>>(&self, obj: O, key: &str) -> bool {
+        matches!(self.get(obj, key), Ok(Some(_)))
+    }
+
+    /// Whether the map `obj` had a value for `key` as at `heads`
+    ///
+    /// See [`Self::contains_key()`]
+    fn contains_key_at<O: AsRef<ExId>>(&self, obj: O, key: &str, heads: &[ChangeHash]) -> bool {
+        matches!(self.get_at(obj, key, heads), Ok(Some(_)))
+    }
+
+    /// Whether the list or text `obj` has an element at `index`
+    ///
+    /// Returns `false` if `obj` is not a sequence or is not in this document.
+    fn has_index<O: AsRef<ExId>>(&self, obj: O, index: usize) -> bool {
+        matches!(self.object_type(obj.as_ref()), Ok(t) if t.is_sequence())
+            && index < self.length(obj)
+    }
+
+    /// Whether the list or text `obj` had an element at `index` as at `heads`
+    ///
+    /// See [`Self::has_index()`]
+    fn has_index_at<O: AsRef<ExId>>(&self, obj: O, index: usize, heads: &[ChangeHash]) -> bool {
+        matches!(self.object_type(obj.as_ref()), Ok(t) if t.is_sequence())
+            && index < self.length_at(obj, heads)
+    }
+
     /// Get the type of this object, if it is an object.
     fn object_type<O: AsRef<ExId>>(&self, obj: O) -> Result<ObjType, AutomergeError>;
 
@@ -148,6 +232,73 @@ pub trait ReadDoc {
         heads: Option<&[ChangeHash]>,
     ) -> Result<MarkSet, AutomergeError>;
 
+    /// The IDs of the rows in the table `obj`, along with the object ID of each row
+    ///
+    /// Rows are returned in order of their IDs. Entries in the table which are not maps (which
+    /// can only be created by writing to the table with the ordinary map methods) are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidObjType`] if `obj` is not a table
+    fn rows<O: AsRef<ExId>>(&self, obj: O) -> Result<Vec<(String, ExId)>, AutomergeError> {
+        let obj = obj.as_ref();
+        crate::handles::check_type(self, obj, ObjType::Table)?;
+        Ok(self
+            .map_range(obj, ..)
+            .filter(|item| matches!(item.value, Value::Object(ObjType::Map)))
+            .map(|item| (item.key.to_string(), item.id))
+            .collect())
+    }
+
+    /// The object ID of the row with ID `id` in the table `obj`, if there is one
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidObjType`] if `obj` is not a table
+    fn row<O: AsRef<ExId>>(&self, obj: O, id: &str) -> Result<Option<ExId>, AutomergeError> {
+        let obj = obj.as_ref();
+        crate::handles::check_type(self, obj, ObjType::Table)?;
+        match self.get(obj, id)? {
+            Some((Value::Object(ObjType::Map), row)) => Ok(Some(row)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Split the text object `obj` into runs of text which have the same marks
+    ///
+    /// Adjacent characters with the same marks are grouped into one [`Span`], so a rich text
+    /// editor can render the document with one styled run per span. Characters without any marks
+    /// are grouped into spans with an empty [`MarkSet`].
+    fn spans<O: AsRef<ExId>>(&self, obj: O) -> Result<Vec<Span>, AutomergeError> {
+        let obj = obj.as_ref();
+        let marks = self.marks(obj)?;
+        let elems = self.list_range(obj, ..).collect::<Vec<_>>();
+        Ok(marks::spans(
+            elems
+                .iter()
+                .filter_map(|e| Some((e.index, e.value.to_str()?))),
+            &marks,
+        ))
+    }
+
+    /// Split the text object `obj` into runs of text with the same marks as at `heads`, see
+    /// [`Self::spans()`]
+    fn spans_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        heads: &[ChangeHash],
+    ) -> Result<Vec<Span>, AutomergeError> {
+        let obj = obj.as_ref();
+        let marks = self.marks_at(obj, heads)?;
+        let elems = self.list_range_at(obj, .., heads).collect::<Vec<_>>();
+        Ok(marks::spans(
+            elems
+                .iter()
+                .filter_map(|e| Some((e.index, e.value.to_str()?))),
+            &marks,
+        ))
+    }
+
     /// Get the string represented by the given text object.
     fn text<O: AsRef<ExId>>(&self, obj: O) -> Result<String, AutomergeError>;
 
@@ -235,10 +386,213 @@ pub trait ReadDoc {
         heads: &[ChangeHash],
     ) -> Result<Vec<(Value<'_>, ExId)>, AutomergeError>;
 
+    /// Get the value of `prop` in `obj`, choosing between conflicting values using `order`
+    ///
+    /// The value returned is the one with the highest priority according to `order`. With
+    /// [`ConflictOrder::OpId`] this is the same as [`Self::get()`].
+    fn get_ordered<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+        order: &ConflictOrder,
+    ) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        Ok(self.get_all_ordered(obj, prop, order)?.pop())
+    }
+
+    /// Get all the conflicting values of `prop` in `obj`, ordered from the lowest to the highest
+    /// priority according to `order`
+    ///
+    /// See [`Self::get_all()`]
+    fn get_all_ordered<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+        order: &ConflictOrder,
+    ) -> Result<Vec<(Value<'_>, ExId)>, AutomergeError> {
+        let mut values = self.get_all(obj, prop)?;
+        values.sort_by(|(_, a), (_, b)| order.compare(a, b));
+        Ok(values)
+    }
+
+    /// Get the conflicting values of `prop` in `obj` keyed by the ID of the operation which set
+    /// each of them, with the value [`Self::get()`] returns marked as the winner
+    ///
+    /// This is the same shape as `getConflicts` in the JavaScript library. See [`Self::get_all()`]
+    fn get_conflicts<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Conflicts, AutomergeError> {
+        let obj = obj.as_ref();
+        let prop = prop.into();
+        let winner = self.get(obj, prop.clone())?.map(|(_, id)| id);
+        Ok(Conflicts::new(self.get_all(obj, prop)?, winner.as_ref()))
+    }
+
+    /// Get the string value of `prop` in `obj`, or [`None`] if there is no value
+    ///
+    /// This and the other typed getters such as [`Self::get_int()`] save matching on the
+    /// [`Value`] returned by [`Self::get()`]. Each one only accepts its own type, for example
+    /// [`Self::get_int()`] does not convert a float.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidValueType`] if the value is of a different type, as well
+    /// as the errors [`Self::get()`] returns
+    fn get_string<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<String>, AutomergeError> {
+        get_typed(self.get(obj, prop)?, "a string", |value| {
+            value.to_str().map(String::from)
+        })
+    }
+
+    /// Get the signed integer value of `prop` in `obj`, see [`Self::get_string()`]
+    fn get_int<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<i64>, AutomergeError> {
+        get_typed(self.get(obj, prop)?, "an int", |value| match value {
+            ScalarValue::Int(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    /// Get the unsigned integer value of `prop` in `obj`, see [`Self::get_string()`]
+    fn get_uint<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<u64>, AutomergeError> {
+        get_typed(self.get(obj, prop)?, "a uint", |value| match value {
+            ScalarValue::Uint(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    /// Get the floating point value of `prop` in `obj`, see [`Self::get_string()`]
+    fn get_f64<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<f64>, AutomergeError> {
+        get_typed(self.get(obj, prop)?, "a float", |value| match value {
+            ScalarValue::F64(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    /// Get the boolean value of `prop` in `obj`, see [`Self::get_string()`]
+    fn get_bool<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<bool>, AutomergeError> {
+        get_typed(self.get(obj, prop)?, "a boolean", |value| value.to_bool())
+    }
+
+    /// Get the bytes value of `prop` in `obj`, see [`Self::get_string()`]
+    fn get_bytes<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<Vec<u8>>, AutomergeError> {
+        get_typed(self.get(obj, prop)?, "bytes", |value| {
+            value.to_bytes().map(Vec::from)
+        })
+    }
+
+    /// Get the current value of the counter of `prop` in `obj`, see [`Self::get_string()`]
+    fn get_counter<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<i64>, AutomergeError> {
+        get_typed(self.get(obj, prop)?, "a counter", |value| match value {
+            ScalarValue::Counter(c) => Some(i64::from(c)),
+            _ => None,
+        })
+    }
+
+    /// Get the timestamp value of `prop` in `obj`, see [`Self::get_string()`]
+    fn get_timestamp<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<i64>, AutomergeError> {
+        get_typed(self.get(obj, prop)?, "a timestamp", |value| match value {
+            ScalarValue::Timestamp(t) => Some(*t),
+            _ => None,
+        })
+    }
+
+    /// Read the bytes of a blob written by
+    /// [`Transactable::set_blob()`](crate::transaction::Transactable::set_blob)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidObjType`] if `obj` is not a list. Reading fails with
+    /// [`std::io::ErrorKind::InvalidData`] if the list contains a value which is not bytes.
+    fn read_blob<O: AsRef<ExId>>(&self, obj: O) -> Result<BlobReader<'_, Self>, AutomergeError> {
+        BlobReader::new(self, obj.as_ref().clone())
+    }
+
+    /// Get the value at `path`, resolving each object along it in turn
+    ///
+    /// `path` is either a JSON Pointer, such as `/todos/3/title`, or the same segments separated
+    /// by dots, such as `todos.3.title`. Segments index into lists and text by position and into
+    /// maps by key, and the empty path refers to the root. Returns [`None`] if there is no value
+    /// at some point along the path.
+    ///
+    /// ```
+    /// # use automerge::{transaction::Transactable, AutoCommit, ReadDoc, Value};
+    /// let mut doc = AutoCommit::new();
+    /// doc.set_path("/todos/0/title", "buy milk", true).unwrap();
+    /// let (title, _) = doc.value_path("todos.0.title").unwrap().unwrap();
+    /// assert_eq!(title, Value::from("buy milk"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidPath`] if a segment which indexes into a list is not a
+    /// number.
+    fn value_path(&self, path: &str) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        path::get(self, path)
+    }
+
     /// Get the hashes of the changes in this document that aren't transitive dependencies of the
     /// given `heads`.
+    ///
+    /// The hashes are sorted.
     fn get_missing_deps(&self, heads: &[ChangeHash]) -> Vec<ChangeHash>;
 
     /// Get a change by its hash.
     fn get_change_by_hash(&self, hash: &ChangeHash) -> Option<&Change>;
 }
+
+/// Convert the result of [`ReadDoc::get()`] with `convert`, which returns [`None`] if the value is
+/// not of the `expected` type
+fn get_typed<T, F: FnOnce(&ScalarValue) -> Option<T>>(
+    value: Option<(Value<'_>, ExId)>,
+    expected: &str,
+    convert: F,
+) -> Result<Option<T>, AutomergeError> {
+    let value = match value {
+        Some((value, _)) => value,
+        None => return Ok(None),
+    };
+    let converted = match &value {
+        Value::Scalar(s) => convert(s),
+        Value::Object(_) => None,
+    };
+    match converted {
+        Some(v) => Ok(Some(v)),
+        None => Err(AutomergeError::InvalidValueType {
+            expected: expected.to_string(),
+            unexpected: value.to_string(),
+        }),
+    }
+}
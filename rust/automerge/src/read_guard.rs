@@ -0,0 +1,221 @@
+use std::ops::RangeBounds;
+
+use crate::{
+    error::AutomergeError,
+    exid::ExId,
+    iter::{Keys, ListRange, MapRange, Values},
+    marks::{Mark, MarkSet},
+    parents::Parents,
+    Change, ChangeHash, Cursor, ObjType, Prop, ReadDoc, Value,
+};
+
+/// A view of a document pinned to the heads it had when the guard was created
+///
+/// Created by [`crate::Automerge::read_guard()`] or [`crate::AutoCommit::read_guard()`]. Every
+/// method of [`ReadDoc`] on the guard without an explicit `heads` argument reads the document as
+/// at [`Self::heads()`], so a sequence of reads always sees a consistent snapshot. Methods which
+/// do take `heads` use the heads they are given.
+#[derive(Debug)]
+pub struct ReadGuard<'a, D: ?Sized> {
+    doc: &'a D,
+    heads: Vec<ChangeHash>,
+}
+
+impl<'a, D: ReadDoc + ?Sized> ReadGuard<'a, D> {
+    pub(crate) fn new(doc: &'a D, heads: Vec<ChangeHash>) -> Self {
+        Self { doc, heads }
+    }
+
+    /// The heads this guard reads at
+    pub fn heads(&self) -> &[ChangeHash] {
+        &self.heads
+    }
+}
+
+impl<'a, D: ReadDoc + ?Sized> ReadDoc for ReadGuard<'a, D> {
+    fn parents<O: AsRef<ExId>>(&self, obj: O) -> Result<Parents<'_>, AutomergeError> {
+        self.doc.parents_at(obj, &self.heads)
+    }
+
+    fn parents_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        heads: &[ChangeHash],
+    ) -> Result<Parents<'_>, AutomergeError> {
+        self.doc.parents_at(obj, heads)
+    }
+
+    fn keys<O: AsRef<ExId>>(&self, obj: O) -> Keys<'_> {
+        self.doc.keys_at(obj, &self.heads)
+    }
+
+    fn keys_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> Keys<'_> {
+        self.doc.keys_at(obj, heads)
+    }
+
+    fn map_range<'b, O: AsRef<ExId>, R: RangeBounds<String> + 'b>(
+        &'b self,
+        obj: O,
+        range: R,
+    ) -> MapRange<'b, R> {
+        self.doc.map_range_at(obj, range, &self.heads)
+    }
+
+    fn map_range_at<'b, O: AsRef<ExId>, R: RangeBounds<String> + 'b>(
+        &'b self,
+        obj: O,
+        range: R,
+        heads: &[ChangeHash],
+    ) -> MapRange<'b, R> {
+        self.doc.map_range_at(obj, range, heads)
+    }
+
+    fn list_range<O: AsRef<ExId>, R: RangeBounds<usize>>(
+        &self,
+        obj: O,
+        range: R,
+    ) -> ListRange<'_, R> {
+        self.doc.list_range_at(obj, range, &self.heads)
+    }
+
+    fn list_range_at<O: AsRef<ExId>, R: RangeBounds<usize>>(
+        &self,
+        obj: O,
+        range: R,
+        heads: &[ChangeHash],
+    ) -> ListRange<'_, R> {
+        self.doc.list_range_at(obj, range, heads)
+    }
+
+    fn values<O: AsRef<ExId>>(&self, obj: O) -> Values<'_> {
+        self.doc.values_at(obj, &self.heads)
+    }
+
+    fn values_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> Values<'_> {
+        self.doc.values_at(obj, heads)
+    }
+
+    fn length<O: AsRef<ExId>>(&self, obj: O) -> usize {
+        self.doc.length_at(obj, &self.heads)
+    }
+
+    fn length_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> usize {
+        self.doc.length_at(obj, heads)
+    }
+
+    fn contains_key<O: AsRef<ExId>>(&self, obj: O, key: &str) -> bool {
+        self.doc.contains_key_at(obj, key, &self.heads)
+    }
+
+    fn contains_key_at<O: AsRef<ExId>>(&self, obj: O, key: &str, heads: &[ChangeHash]) -> bool {
+        self.doc.contains_key_at(obj, key, heads)
+    }
+
+    fn has_index<O: AsRef<ExId>>(&self, obj: O, index: usize) -> bool {
+        self.doc.has_index_at(obj, index, &self.heads)
+    }
+
+    fn has_index_at<O: AsRef<ExId>>(&self, obj: O, index: usize, heads: &[ChangeHash]) -> bool {
+        self.doc.has_index_at(obj, index, heads)
+    }
+
+    fn object_type<O: AsRef<ExId>>(&self, obj: O) -> Result<ObjType, AutomergeError> {
+        self.doc.object_type(obj)
+    }
+
+    fn marks<O: AsRef<ExId>>(&self, obj: O) -> Result<Vec<Mark<'_>>, AutomergeError> {
+        self.doc.marks_at(obj, &self.heads)
+    }
+
+    fn marks_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        heads: &[ChangeHash],
+    ) -> Result<Vec<Mark<'_>>, AutomergeError> {
+        self.doc.marks_at(obj, heads)
+    }
+
+    fn get_marks<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        index: usize,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<MarkSet, AutomergeError> {
+        self.doc
+            .get_marks(obj, index, Some(heads.unwrap_or(&self.heads)))
+    }
+
+    fn text<O: AsRef<ExId>>(&self, obj: O) -> Result<String, AutomergeError> {
+        self.doc.text_at(obj, &self.heads)
+    }
+
+    fn text_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        heads: &[ChangeHash],
+    ) -> Result<String, AutomergeError> {
+        self.doc.text_at(obj, heads)
+    }
+
+    fn get_cursor<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        position: usize,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<Cursor, AutomergeError> {
+        self.doc
+            .get_cursor(obj, position, Some(at.unwrap_or(&self.heads)))
+    }
+
+    fn get_cursor_position<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        cursor: &Cursor,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<usize, AutomergeError> {
+        self.doc
+            .get_cursor_position(obj, cursor, Some(at.unwrap_or(&self.heads)))
+    }
+
+    fn get<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        self.doc.get_at(obj, prop, &self.heads)
+    }
+
+    fn get_at<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+        heads: &[ChangeHash],
+    ) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        self.doc.get_at(obj, prop, heads)
+    }
+
+    fn get_all<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Vec<(Value<'_>, ExId)>, AutomergeError> {
+        self.doc.get_all_at(obj, prop, &self.heads)
+    }
+
+    fn get_all_at<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+        heads: &[ChangeHash],
+    ) -> Result<Vec<(Value<'_>, ExId)>, AutomergeError> {
+        self.doc.get_all_at(obj, prop, heads)
+    }
+
+    fn get_missing_deps(&self, heads: &[ChangeHash]) -> Vec<ChangeHash> {
+        self.doc.get_missing_deps(heads)
+    }
+
+    fn get_change_by_hash(&self, hash: &ChangeHash) -> Option<&Change> {
+        self.doc.get_change_by_hash(hash)
+    }
+}
@@ -0,0 +1,84 @@
+use crate::columnar::Key as EncodedKey;
+use crate::exid::ExId;
+use crate::indexed_cache::IndexedCache;
+use crate::types::{ElemId, OpType};
+use crate::{ActorId, Change};
+
+/// An operation from a change, returned by [`crate::Automerge::change_ops()`] and
+/// [`Change::iter_decoded_ops()`]
+///
+/// Unlike the operations in an [`crate::ExpandedChange`], the object, key and operation IDs are
+/// [`ExId`]s which can be passed straight back to the methods of [`crate::ReadDoc`], so it is
+/// possible to describe what a change did in terms of the objects in the document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedOp {
+    /// The ID of the operation
+    pub id: ExId,
+    /// The object the operation applies to
+    pub obj: ExId,
+    /// The key in [`Self::obj`] the operation applies to
+    pub key: ResolvedKey,
+    /// Whether the operation inserts a new element after [`Self::key`], rather than updating it
+    pub insert: bool,
+    /// What the operation does
+    pub action: OpType,
+    /// The operations which this operation overwrites or deletes
+    pub pred: Vec<ExId>,
+}
+
+/// The key an operation in a [`ResolvedOp`] applies to
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedKey {
+    /// A key in a map
+    Map(String),
+    /// The start of a sequence, used by operations which insert at index 0
+    Head,
+    /// An element of a sequence, identified by the ID of the operation which inserted it
+    Elem(ExId),
+}
+
+/// Convert the operations of `change` into [`ResolvedOp`]s as they are iterated over
+///
+/// The actor indexes of the IDs are looked up in `actors`, the actors of the document the change
+/// belongs to. Without a document they are `0`, which is checked before an [`ExId`] is used.
+pub(crate) fn resolve_ops<'a>(
+    change: &'a Change,
+    actors: Option<&IndexedCache<ActorId>>,
+) -> impl Iterator<Item = ResolvedOp> + 'a {
+    let change_actors = std::iter::once(change.actor_id())
+        .chain(change.other_actor_ids())
+        .map(|actor| {
+            let index = actors.and_then(|actors| actors.lookup(actor));
+            (actor.clone(), index.unwrap_or_default())
+        })
+        .collect::<Vec<_>>();
+    let exid = move |counter: u64, actor: usize| {
+        let (actor, index) = change_actors[actor].clone();
+        ExId::Id(counter, actor, index)
+    };
+    let start_op = change.start_op().get();
+    change.iter_ops().enumerate().map(move |(i, op)| {
+        let key = match &op.key {
+            EncodedKey::Prop(prop) => ResolvedKey::Map(prop.to_string()),
+            EncodedKey::Elem(e) if e.is_head() => ResolvedKey::Head,
+            EncodedKey::Elem(ElemId(o)) => ResolvedKey::Elem(exid(o.counter(), o.actor())),
+        };
+        let obj = if op.obj.is_root() {
+            ExId::Root
+        } else {
+            exid(op.obj.opid().counter(), op.obj.opid().actor())
+        };
+        ResolvedOp {
+            id: exid(start_op + i as u64, 0),
+            obj,
+            key,
+            insert: op.insert,
+            pred: op
+                .pred
+                .iter()
+                .map(|p| exid(p.counter(), p.actor()))
+                .collect(),
+            action: OpType::from_action_and_value(op.action, op.val, op.mark_name, op.expand),
+        }
+    })
+}
@@ -0,0 +1,92 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::transaction::{self, Transaction};
+use crate::Automerge;
+
+/// A document which can be shared between threads, created with [`SharedAutomerge::new()`]
+///
+/// This is an [`Automerge`] behind an [`Arc`] and a [`RwLock`]. Cloning the handle is cheap and
+/// every clone refers to the same document. Any number of threads can read the document at once
+/// with [`Self::with_read()`], while [`Self::with_tx()`] and [`Self::with_mut()`] wait for
+/// exclusive access, so readers never see a transaction which is only partly applied.
+///
+/// ```
+/// # use automerge::{transaction::Transactable, AutomergeError, ReadDoc, SharedAutomerge, ROOT};
+/// let doc = SharedAutomerge::default();
+/// let writer = {
+///     let doc = doc.clone();
+///     std::thread::spawn(move || {
+///         doc.with_tx(|tx| tx.put(ROOT, "key", "value")).unwrap();
+///     })
+/// };
+/// writer.join().unwrap();
+/// let value = doc.with_read(|doc| doc.get_string(ROOT, "key")).unwrap();
+/// assert_eq!(value.as_deref(), Some("value"));
+/// ```
+///
+/// ## Panics
+///
+/// The lock is not poisoned by a closure which panics. The transaction of a [`Self::with_tx()`]
+/// closure which panics is rolled back, so the document is left as it was, but a
+/// [`Self::with_mut()`] closure which panics leaves the document as it was at the panic.
+#[derive(Debug, Clone, Default)]
+pub struct SharedAutomerge {
+    doc: Arc<RwLock<Automerge>>,
+}
+
+impl SharedAutomerge {
+    /// Share `doc` between threads
+    pub fn new(doc: Automerge) -> Self {
+        Self {
+            doc: Arc::new(RwLock::new(doc)),
+        }
+    }
+
+    /// Call `f` with read access to the document
+    ///
+    /// This waits for any [`Self::with_tx()`] or [`Self::with_mut()`] call on another thread to
+    /// finish, and blocks them until `f` returns.
+    pub fn with_read<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Automerge) -> R,
+    {
+        let doc = self.doc.read().unwrap_or_else(PoisonError::into_inner);
+        f(&doc)
+    }
+
+    /// Make a change to the document in a transaction, see [`Automerge::transact()`]
+    pub fn with_tx<F, O, E>(&self, f: F) -> transaction::Result<O, E>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> Result<O, E>,
+    {
+        self.with_mut(|doc| doc.transact(f))
+    }
+
+    /// Call `f` with exclusive access to the document
+    ///
+    /// This is for methods which change the document other than by a transaction, such as
+    /// [`Automerge::merge()`], [`Automerge::apply_changes()`] or receiving sync messages.
+    pub fn with_mut<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Automerge) -> R,
+    {
+        let mut doc = self.doc.write().unwrap_or_else(PoisonError::into_inner);
+        f(&mut doc)
+    }
+
+    /// Take the document out of the handle if this is the only handle to it
+    ///
+    /// Returns the handle unchanged if there are other handles to the document.
+    pub fn try_into_inner(self) -> Result<Automerge, Self> {
+        match Arc::try_unwrap(self.doc) {
+            Ok(lock) => Ok(lock.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(doc) => Err(Self { doc }),
+        }
+    }
+}
+
+impl From<Automerge> for SharedAutomerge {
+    fn from(doc: Automerge) -> Self {
+        Self::new(doc)
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{ActorId, Change};
+
+/// Marks the end of extra bytes which carry a signature, see [`append_signature()`]
+const MAGIC: &[u8] = b"\x00AMS";
+
+/// Signs the changes made on a document, see [`crate::Automerge::set_signer()`]
+pub trait Signer: Send + Sync {
+    /// Sign `payload`, the encoded change made by `actor`
+    fn sign(&self, actor: &ActorId, payload: &[u8]) -> Vec<u8>;
+}
+
+/// Checks the signatures of changes received by a document, see
+/// [`crate::Automerge::set_verifier()`]
+pub trait Verifier: Send + Sync {
+    /// Whether `signature` is a valid signature of `payload` by `actor`
+    ///
+    /// `signature` is [`None`] if the change is not signed.
+    fn verify(&self, actor: &ActorId, payload: &[u8], signature: Option<&[u8]>) -> bool;
+}
+
+#[derive(Clone)]
+pub(crate) struct SignerHandle(pub(crate) Arc<dyn Signer>);
+
+impl fmt::Debug for SignerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignerHandle").finish()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct VerifierHandle(pub(crate) Arc<dyn Verifier>);
+
+impl fmt::Debug for VerifierHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VerifierHandle").finish()
+    }
+}
+
+impl VerifierHandle {
+    pub(crate) fn verify(&self, change: &Change) -> bool {
+        self.0
+            .verify(change.actor_id(), &payload(change), change.signature())
+    }
+}
+
+/// Add `signature` to the end of `extra_bytes`
+///
+/// The signature is followed by its length and [`MAGIC`] so that it can be found again by
+/// [`split_signature()`] without knowing the format of the application's extra bytes.
+pub(crate) fn append_signature(extra_bytes: &mut Vec<u8>, signature: &[u8]) {
+    extra_bytes.extend_from_slice(signature);
+    extra_bytes.extend_from_slice(&(signature.len() as u32).to_be_bytes());
+    extra_bytes.extend_from_slice(MAGIC);
+}
+
+/// Split extra bytes written by [`append_signature()`] into the application's extra bytes and
+/// the signature
+pub(crate) fn split_signature(extra_bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let rest = extra_bytes.strip_suffix(MAGIC)?;
+    if rest.len() < 4 {
+        return None;
+    }
+    let (rest, len) = rest.split_at(rest.len() - 4);
+    let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    if rest.len() < len {
+        return None;
+    }
+    Some(rest.split_at(rest.len() - len))
+}
+
+/// The bytes which are signed for `change`: the change encoded without its signature
+pub(crate) fn payload(change: &Change) -> Vec<u8> {
+    let mut expanded = change.decode();
+    if let Some((extra_bytes, _)) = split_signature(change.extra_bytes()) {
+        expanded.extra_bytes = extra_bytes.to_vec();
+    }
+    Change::from(expanded).raw_bytes().to_vec()
+}
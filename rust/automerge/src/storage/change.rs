@@ -259,6 +259,10 @@ impl<'a, O: OpReadState> Change<'a, O> {
         self.header.checksum_valid()
     }
 
+    pub(crate) fn other_hash_algorithm(&self) -> Option<&'static str> {
+        self.header.other_algorithm()
+    }
+
     pub(crate) fn body_bytes(&self) -> &[u8] {
         &self.bytes[self.header.len()..]
     }
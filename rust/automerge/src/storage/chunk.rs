@@ -5,9 +5,8 @@ use std::{
     ops::Range,
 };
 
-use sha2::{Digest, Sha256};
-
 use super::{change::Unverified, parse, Change, Compressed, Document, MAGIC_BYTES};
+use crate::hash::{self, ChangeHasher, Sha256Hasher};
 use crate::{columnar::encoding::leb128::ulebsize, ChangeHash};
 
 pub(crate) enum Chunk<'a> {
@@ -99,6 +98,16 @@ impl<'a> Chunk<'a> {
         Ok((remaining, chunk))
     }
 
+    /// The name of the hash algorithm the chunk was written with, if its checksum is only valid
+    /// for an algorithm other than the one in use
+    pub(crate) fn other_hash_algorithm(&self) -> Option<&'static str> {
+        match self {
+            Self::Document(d) => d.other_hash_algorithm(),
+            Self::Change(c) => c.other_hash_algorithm(),
+            Self::CompressedChange(c, _) => c.other_hash_algorithm(),
+        }
+    }
+
     pub(crate) fn checksum_valid(&self) -> bool {
         match self {
             Self::Document(d) => d.checksum_valid(),
@@ -175,6 +184,9 @@ pub(crate) struct Header {
     data_len: usize,
     header_size: usize,
     hash: ChangeHash,
+    /// The name of another hash algorithm which the checksum matches, if it does not match
+    /// [`Self::hash`]
+    other_algorithm: Option<&'static str>,
 }
 
 impl Header {
@@ -182,6 +194,7 @@ impl Header {
         let hash = hash(chunk_type, data);
         Self {
             hash,
+            other_algorithm: None,
             checksum: hash.checksum().into(),
             data_len: data.len(),
             header_size: MAGIC_BYTES.len()
@@ -199,6 +212,7 @@ impl Header {
         let hash = hash(chunk_type, data);
         Self {
             hash,
+            other_algorithm: other_algorithm(chunk_type, data, hash, self.checksum),
             checksum: self.checksum,
             data_len: data.len(),
             header_size: MAGIC_BYTES.len()
@@ -253,10 +267,12 @@ impl Header {
 
         let (_, data) = parse::take_n(chunk_len as usize, i)?;
         let hash = hash(chunk_type, data);
+        let checksum = checksum_bytes.into();
         Ok((
             i,
             Header {
-                checksum: checksum_bytes.into(),
+                other_algorithm: other_algorithm(chunk_type, data, hash, checksum),
+                checksum,
                 chunk_type,
                 data_len: data.len(),
                 header_size: header.len(),
@@ -281,13 +297,40 @@ impl Header {
     pub(crate) fn checksum(&self) -> CheckSum {
         self.checksum
     }
+
+    /// The name of a hash algorithm other than the one in use which the checksum matches, which
+    /// means the chunk was written by a peer using that algorithm
+    pub(crate) fn other_algorithm(&self) -> Option<&'static str> {
+        self.other_algorithm
+    }
 }
 
 fn hash(typ: ChunkType, data: &[u8]) -> ChangeHash {
+    hash_with(hash::change_hasher(), typ, data)
+}
+
+fn hash_with(hasher: &dyn ChangeHasher, typ: ChunkType, data: &[u8]) -> ChangeHash {
     let mut out = vec![u8::from(typ)];
     leb128::write::unsigned(&mut out, data.len() as u64).unwrap();
     out.extend(data);
-    let hash_result = Sha256::digest(out);
-    let array: [u8; 32] = hash_result.into();
-    ChangeHash(array)
+    ChangeHash(hasher.digest(&out))
+}
+
+/// If `checksum` does not match `hash` because another hasher has been installed but does match
+/// the SHA-256 hash of the data, the name of SHA-256
+fn other_algorithm(
+    typ: ChunkType,
+    data: &[u8],
+    hash: ChangeHash,
+    checksum: CheckSum,
+) -> Option<&'static str> {
+    if CheckSum::from(hash) == checksum || hash::is_default() {
+        return None;
+    }
+    let sha256 = hash_with(&Sha256Hasher, typ, data);
+    if CheckSum::from(sha256) == checksum {
+        Some(Sha256Hasher.name())
+    } else {
+        None
+    }
 }
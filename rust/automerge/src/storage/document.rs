@@ -263,10 +263,14 @@ impl<'a> Document<'a> {
         }
 
         let header = Header::new(ChunkType::Document, &data);
-        let mut bytes = Vec::with_capacity(data.len() + header.len());
-        header.write(&mut bytes);
-        let header_len = bytes.len();
-        bytes.extend(&data);
+        let mut header_bytes = Vec::with_capacity(header.len());
+        header.write(&mut header_bytes);
+        let header_len = header_bytes.len();
+        // Insert the header in front of the data in place rather than copying the data into a
+        // new buffer, so that saving a large document does not need two copies of it
+        let mut bytes = data;
+        bytes.reserve_exact(header_len);
+        bytes.splice(0..0, header_bytes);
 
         let op_bytes = shift_range(ops_start..ops_end, header.len());
         let change_bytes = shift_range(change_start..change_end, header.len());
@@ -321,18 +325,22 @@ impl<'a> Document<'a> {
             .iter(&self.bytes[self.change_bytes.clone()])
     }
 
-    pub(crate) fn into_bytes(self) -> Vec<u8> {
-        if let Some(compressed) = self.compressed_bytes {
-            compressed.into_owned()
-        } else {
-            self.bytes.into_owned()
-        }
+    /// Write the encoded document to `writer`, compressed if it was compressed when it was
+    /// created, returning the number of bytes written
+    pub(crate) fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+        let bytes = self.compressed_bytes.as_ref().unwrap_or(&self.bytes);
+        writer.write_all(bytes)?;
+        Ok(bytes.len())
     }
 
     pub(crate) fn checksum_valid(&self) -> bool {
         self.header.checksum_valid()
     }
 
+    pub(crate) fn other_hash_algorithm(&self) -> Option<&'static str> {
+        self.header.other_algorithm()
+    }
+
     pub(crate) fn actors(&self) -> &[ActorId] {
         &self.actors
     }
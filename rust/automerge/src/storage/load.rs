@@ -25,6 +25,11 @@ pub enum Error {
     InflateDocument(Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error("bad checksum")]
     BadChecksum,
+    #[error("data was hashed with {found} but this process hashes changes with {expected}")]
+    HashAlgorithmMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
 }
 
 pub(crate) enum LoadedChanges<'a> {
@@ -50,8 +55,91 @@ pub(crate) enum LoadedChanges<'a> {
 /// or more changes. This means it is possible to partially load corrupted data if the first `n`
 /// chunks are valid. This function returns a `LoadedChanges` which you can examine to determine if
 /// this is the case.
+///
+/// With the `parallel` feature the chunks are decoded and verified on the rayon thread pool.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn load_changes(data: parse::Input<'_>) -> LoadedChanges<'_> {
+    load_changes_sequential(data)
+}
+
+#[cfg(feature = "parallel")]
+#[instrument(skip(data))]
+pub(crate) fn load_changes(data: parse::Input<'_>) -> LoadedChanges<'_> {
+    use rayon::prelude::*;
+
+    // finding the chunk boundaries only needs the headers, so is cheap to do up front
+    let bytes = data.unconsumed_bytes();
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+    while let Some(len) = chunk_len(&bytes[offset..]) {
+        chunks.push(offset..offset + len);
+        offset += len;
+    }
+    let results: Vec<_> = chunks
+        .par_iter()
+        .map(|range| {
+            let mut changes = Vec::new();
+            load_next_change(parse::Input::new(&bytes[range.clone()]), &mut changes)
+                .map(|_| changes)
+        })
+        .collect();
+    let mut changes = Vec::new();
+    for (range, result) in chunks.iter().zip(results) {
+        match result {
+            Ok(loaded) => changes.extend(loaded),
+            Err(error) => {
+                return LoadedChanges::Partial {
+                    loaded: changes,
+                    remaining: data.split(range.start).remaining,
+                    error,
+                }
+            }
+        }
+    }
+    // whatever is left could not be split into chunks, loading it reports the error
+    match load_changes_sequential(data.split(offset).remaining.reset()) {
+        LoadedChanges::Complete(loaded) => {
+            changes.extend(loaded);
+            LoadedChanges::Complete(changes)
+        }
+        LoadedChanges::Partial {
+            loaded,
+            remaining,
+            error,
+        } => {
+            changes.extend(loaded);
+            LoadedChanges::Partial {
+                loaded: changes,
+                remaining,
+                error,
+            }
+        }
+    }
+}
+
+/// The length of the chunk at the start of `bytes`, including its header, if `bytes` contains a
+/// whole chunk
+///
+/// This only reads the header, it doesn't check the magic bytes or the checksum.
+#[cfg(feature = "parallel")]
+fn chunk_len(bytes: &[u8]) -> Option<usize> {
+    // magic bytes, checksum and chunk type
+    const PREFIX: usize = 9;
+    let mut rest = bytes.get(PREFIX..)?;
+    let before = rest.len();
+    let data_len = usize::try_from(leb128::read::unsigned(&mut rest).ok()?).ok()?;
+    let len = PREFIX
+        .checked_add(before - rest.len())?
+        .checked_add(data_len)?;
+    if len <= bytes.len() {
+        Some(len)
+    } else {
+        None
+    }
+}
+
 #[instrument(skip(data))]
-pub(crate) fn load_changes<'a>(mut data: parse::Input<'a>) -> LoadedChanges<'a> {
+fn load_changes_sequential(mut data: parse::Input<'_>) -> LoadedChanges<'_> {
     let mut changes = Vec::new();
     while !data.is_empty() {
         let remaining = match load_next_change(data, &mut changes) {
@@ -69,14 +157,27 @@ pub(crate) fn load_changes<'a>(mut data: parse::Input<'a>) -> LoadedChanges<'a>
     LoadedChanges::Complete(changes)
 }
 
+/// Check that the checksum of `chunk` is valid, distinguishing chunks which were hashed with a
+/// different algorithm from those which are corrupt
+pub(crate) fn check_checksum(chunk: &storage::Chunk<'_>) -> Result<(), Error> {
+    if chunk.checksum_valid() {
+        return Ok(());
+    }
+    match chunk.other_hash_algorithm() {
+        Some(found) => Err(Error::HashAlgorithmMismatch {
+            expected: crate::hash::change_hasher().name(),
+            found,
+        }),
+        None => Err(Error::BadChecksum),
+    }
+}
+
 fn load_next_change<'a>(
     data: parse::Input<'a>,
     changes: &mut Vec<Change>,
 ) -> Result<parse::Input<'a>, Error> {
     let (remaining, chunk) = storage::Chunk::parse(data).map_err(|e| Error::Parse(Box::new(e)))?;
-    if !chunk.checksum_valid() {
-        return Err(Error::BadChecksum);
-    }
+    check_checksum(&chunk)?;
     match chunk {
         storage::Chunk::Document(d) => {
             tracing::trace!("loading document chunk");
@@ -11,6 +11,9 @@ use crate::{
     Change, ChangeHash,
 };
 
+/// Encode a document, returning the encoded [`Document`] rather than its bytes so that the
+/// caller can write the bytes somewhere without taking ownership of them
+///
 /// # Panics
 ///
 /// * If any of the `heads` are not in `changes`
@@ -18,14 +21,14 @@ use crate::{
 /// * If any of ops in `ops` reference a property which is not in `props`
 /// * If any of the changes reference a dependency index which is not in `changes`
 #[tracing::instrument(skip(changes, ops, actors, props, config))]
-pub(crate) fn save_document<'a, I, O>(
+pub(crate) fn encode_document<'a, I, O>(
     changes: I,
     ops: O,
     actors: &'a IndexedCache<ActorId>,
     props: &IndexedCache<String>,
     heads: &[ChangeHash],
     config: Option<CompressConfig>,
-) -> Vec<u8>
+) -> Document<'static>
 where
     I: Iterator<Item = &'a Change> + Clone + 'a,
     O: Iterator<Item = (&'a ObjId, Op<'a>)> + Clone + ExactSizeIterator,
@@ -54,14 +57,13 @@ where
         .filter(|actor| actors_with_ops.contains(actor))
         .collect::<Vec<_>>();
 
-    let doc = Document::new(
+    Document::new(
         actors_with_ops,
         hash_graph.heads_with_indices(heads.to_vec()),
         doc_ops,
         changes,
         config.unwrap_or(CompressConfig::Threshold(DEFLATE_MIN_SIZE)),
-    );
-    doc.into_bytes()
+    )
 }
 
 struct HashGraph {
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+
+use crate::exid::ExId;
+use crate::marks::{ExpandMark, Mark};
+use crate::transaction::Transactable;
+use crate::{hydrate, AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, ScalarValue, Value};
+
+/// A copy of an object and everything nested in it, returned by
+/// [`crate::Automerge::export_subtree()`]
+///
+/// A subtree can be written into any document with [`Transactable::import_subtree()`] or
+/// [`Transactable::insert_subtree()`], which recreate it with new object IDs. Unlike a
+/// [`crate::hydrate::Value`] a subtree keeps the difference between maps and tables, and the
+/// marks on text objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subtree {
+    obj_type: ObjType,
+    contents: Contents,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Contents {
+    Map(BTreeMap<String, Entry>),
+    List(Vec<Entry>),
+    Text {
+        text: String,
+        marks: Vec<Mark<'static>>,
+    },
+}
+
+/// A value in a [`Subtree`], this is also used to write other copies of values into a document
+/// so that they are all written the same way
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Entry {
+    Scalar(ScalarValue),
+    Object(Subtree),
+}
+
+impl Subtree {
+    /// The type of the object at the top of the subtree
+    pub fn obj_type(&self) -> ObjType {
+        self.obj_type
+    }
+
+    pub(crate) fn export<R: ReadDoc + ?Sized>(doc: &R, obj: &ExId) -> Result<Self, AutomergeError> {
+        Self::export_at(doc, obj, None)
+    }
+
+    /// Copy `obj` as at `heads`, or as it is now if `heads` is `None`
+    pub(crate) fn export_at<R: ReadDoc + ?Sized>(
+        doc: &R,
+        obj: &ExId,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Self, AutomergeError> {
+        let obj_type = doc.object_type(obj)?;
+        let contents = match obj_type {
+            ObjType::Map | ObjType::Table => {
+                let range = match heads {
+                    Some(heads) => doc.map_range_at(obj, .., heads),
+                    None => doc.map_range(obj, ..),
+                };
+                Contents::Map(
+                    range
+                        .map(|item| {
+                            Ok((
+                                item.key.to_string(),
+                                Entry::export(doc, item.value, &item.id, heads)?,
+                            ))
+                        })
+                        .collect::<Result<_, AutomergeError>>()?,
+                )
+            }
+            ObjType::List => {
+                let range = match heads {
+                    Some(heads) => doc.list_range_at(obj, .., heads),
+                    None => doc.list_range(obj, ..),
+                };
+                Contents::List(
+                    range
+                        .map(|item| Entry::export(doc, item.value, &item.id, heads))
+                        .collect::<Result<_, AutomergeError>>()?,
+                )
+            }
+            ObjType::Text => {
+                let (text, marks) = match heads {
+                    Some(heads) => (doc.text_at(obj, heads)?, doc.marks_at(obj, heads)?),
+                    None => (doc.text(obj)?, doc.marks(obj)?),
+                };
+                Contents::Text {
+                    text,
+                    marks: marks.into_iter().map(|mark| mark.into_owned()).collect(),
+                }
+            }
+        };
+        Ok(Subtree { obj_type, contents })
+    }
+
+    /// Recreate this subtree at `prop` in `obj`, inserting it if `insert` is true
+    pub(crate) fn write<T: Transactable>(
+        &self,
+        tx: &mut T,
+        obj: &ExId,
+        prop: Prop,
+        insert: bool,
+    ) -> Result<ExId, AutomergeError> {
+        let new = match prop.as_index() {
+            Some(index) if insert => tx.insert_object(obj, index, self.obj_type)?,
+            _ => tx.put_object(obj, prop, self.obj_type)?,
+        };
+        self.write_contents(tx, &new)?;
+        Ok(new)
+    }
+
+    /// Recreate the contents of this subtree in `obj`, which must be a new, empty object of the
+    /// same type
+    pub(crate) fn write_contents<T: Transactable>(
+        &self,
+        tx: &mut T,
+        obj: &ExId,
+    ) -> Result<(), AutomergeError> {
+        match &self.contents {
+            Contents::Map(entries) => {
+                for (key, entry) in entries {
+                    entry.write(tx, obj, key.as_str().into(), false)?;
+                }
+            }
+            Contents::List(entries) => {
+                for (index, entry) in entries.iter().enumerate() {
+                    entry.write(tx, obj, index.into(), true)?;
+                }
+            }
+            Contents::Text { text, marks } => {
+                if !text.is_empty() {
+                    tx.splice_text(obj, 0, 0, text)?;
+                }
+                for mark in marks {
+                    tx.mark(obj, mark.clone(), ExpandMark::default())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Entry {
+    pub(crate) fn scalar(value: ScalarValue) -> Self {
+        match value {
+            // only the start of a counter is encoded, so a copy starts from the current value
+            ScalarValue::Counter(c) => Entry::Scalar(ScalarValue::counter(c.current)),
+            value => Entry::Scalar(value),
+        }
+    }
+
+    /// `id` is the ID of the object if `value` is an object
+    fn export<R: ReadDoc + ?Sized>(
+        doc: &R,
+        value: Value<'_>,
+        id: &ExId,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Self, AutomergeError> {
+        match value {
+            Value::Scalar(s) => Ok(Entry::scalar(s.into_owned())),
+            Value::Object(_) => Ok(Entry::Object(Subtree::export_at(doc, id, heads)?)),
+        }
+    }
+
+    /// Write this value to `prop` in `obj`, inserting it before `prop` if `insert` is true
+    pub(crate) fn write<T: Transactable>(
+        &self,
+        tx: &mut T,
+        obj: &ExId,
+        prop: Prop,
+        insert: bool,
+    ) -> Result<(), AutomergeError> {
+        match self {
+            Entry::Scalar(s) => match prop.as_index() {
+                Some(index) if insert => tx.insert(obj, index, s.clone()),
+                _ => tx.put(obj, prop, s.clone()),
+            },
+            Entry::Object(subtree) => subtree.write(tx, obj, prop, insert).map(|_| ()),
+        }
+    }
+}
+
+impl From<&hydrate::Value> for Entry {
+    fn from(value: &hydrate::Value) -> Self {
+        let (obj_type, contents) = match value {
+            hydrate::Value::Scalar(s) => return Entry::scalar(s.clone()),
+            // sorted so that the same value always produces the same operations
+            hydrate::Value::Map(map) => (
+                ObjType::Map,
+                Contents::Map(
+                    map.iter()
+                        .map(|(key, value)| (key.clone(), Entry::from(value.value())))
+                        .collect(),
+                ),
+            ),
+            hydrate::Value::List(list) => (
+                ObjType::List,
+                Contents::List(list.iter().map(Entry::from).collect()),
+            ),
+            hydrate::Value::Text(text) => (
+                ObjType::Text,
+                Contents::Text {
+                    text: text.to_string(),
+                    marks: Vec::new(),
+                },
+            ),
+        };
+        Entry::Object(Subtree { obj_type, contents })
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use crate::{hydrate, ActorId, Change, ChangeHash};
+
+/// An overview of a document, returned by [`crate::Automerge::summary()`]
+///
+/// This is intended for screens which list many documents. Apart from the root keys requested by
+/// the caller it is computed from the change history alone, without building any values from the
+/// document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentSummary {
+    /// The current heads of the document
+    pub heads: Vec<ChangeHash>,
+    /// The latest timestamp of any change in the document, or `None` if there are no changes
+    ///
+    /// Timestamps are set by the peer which made each change so they are only as reliable as
+    /// that peer's clock.
+    pub last_modified: Option<i64>,
+    /// The number of actors which have made changes to the document
+    pub actors: usize,
+    /// The number of changes in the document
+    pub changes: usize,
+    /// The values of the root keys which were requested, keys with no value are omitted
+    pub root: HashMap<String, hydrate::Value>,
+}
+
+/// Who made one of the heads of a document and when, returned by
+/// [`crate::Automerge::heads_with_metadata()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadMetadata {
+    /// The hash of the change
+    pub hash: ChangeHash,
+    /// The actor which made the change
+    pub actor: ActorId,
+    /// The sequence number of the change among the changes made by [`Self::actor`]
+    pub seq: u64,
+    /// The timestamp of the change, set by the peer which made it
+    pub timestamp: i64,
+    /// The commit message of the change, if it has one
+    pub message: Option<String>,
+}
+
+impl From<&Change> for HeadMetadata {
+    fn from(change: &Change) -> Self {
+        HeadMetadata {
+            hash: change.hash(),
+            actor: change.actor_id().clone(),
+            seq: change.seq(),
+            timestamp: change.timestamp(),
+            message: change.message().cloned(),
+        }
+    }
+}
@@ -13,13 +13,19 @@
 //!   [`SyncDoc::generate_sync_message()`] to generate new sync message and sends
 //!   it to the receiving peer.
 //! * The receiving peer receives a message from the initiator, creates a new
-//!   [`State`], and calls [`SyncDoc::receive_sync_message()`] on it's view of the
+//!   [`State`], and calls [`SyncDoc::receive_sync_message()`] on its view of the
 //!   document
 //! * The receiving peer then calls [`SyncDoc::generate_sync_message()`] to generate
 //!   a new sync message and send it back to the initiator
 //! * From this point on each peer operates in a loop, receiving a sync message
 //!   from the other peer and then generating a new message to send back.
 //!
+//! Messages are encoded with [`Message::encode()`] and decoded with [`Message::decode()`]. The
+//! encoding is the same one used by the JavaScript implementation (which is built from this
+//! crate), so Rust and JavaScript peers can sync with each other. A [`State`] can be persisted
+//! between connections with [`State::encode()`] and [`State::decode()`], which avoids
+//! resending changes the other peer already has when reconnecting.
+//!
 //! ## Example
 //!
 //! ```
@@ -87,7 +93,7 @@ mod v1_compat_test;
 
 pub use bloom::{BloomFilter, DecodeError as DecodeBloomError};
 pub use state::DecodeError as DecodeStateError;
-pub use state::{Have, State};
+pub use state::{EphemeralHandler, Have, State, Stats};
 
 /// A document which can take part in the sync protocol
 ///
@@ -192,12 +198,11 @@ impl SyncDoc for Automerge {
                         need: Vec::new(),
                         have: vec![Have::default()],
                         changes: ChunkList::empty(),
-                        supported_capabilities: Some(vec![
-                            Capability::MessageV1,
-                            Capability::MessageV2,
-                        ]),
+                        supported_capabilities: Some(Capability::supported()),
                         version: MessageVersion::V1,
+                        ephemeral: sync_state.ephemeral.take(),
                     };
+                    sync_state.stats.record_sent(0, 0);
                     return Some(reset_msg);
                 }
             }
@@ -255,7 +260,9 @@ impl SyncDoc for Automerge {
             false
         };
 
-        if heads_unchanged && sync_state.have_responded {
+        // an ephemeral payload is always sent straight away
+        let ephemeral = sync_state.ephemeral.take();
+        if heads_unchanged && sync_state.have_responded && ephemeral.is_none() {
             if heads_equal && !message_builder.has_changes_to_send() {
                 return None;
             }
@@ -265,15 +272,17 @@ impl SyncDoc for Automerge {
         }
 
         // Only send the supported capabilities in the first message, the other end will store them
-        // in it's sync state and use them for subsequent messages
-        let supported_capabilities = if sync_state.have_responded {
+        // in it's sync state and use them for subsequent messages. They are also sent with an
+        // ephemeral payload, which is encoded after them.
+        let supported_capabilities = if sync_state.have_responded && ephemeral.is_none() {
             None
         } else {
-            Some(vec![Capability::MessageV1, Capability::MessageV2])
+            Some(Capability::supported())
         };
 
         sync_state.have_responded = true;
         sync_state.last_sent_heads = our_heads.clone();
+        let sent_changes = sent_hashes.len();
         sync_state.sent_hashes.extend(sent_hashes);
 
         let sync_message = message_builder
@@ -281,7 +290,11 @@ impl SyncDoc for Automerge {
             .have(our_have)
             .need(our_need)
             .supported_capabilities(supported_capabilities)
+            .ephemeral(ephemeral)
             .build();
+        sync_state
+            .stats
+            .record_sent(sent_changes, sync_message.changes.bytes_len());
 
         sync_state.in_flight = true;
         Some(sync_message)
@@ -394,6 +407,9 @@ impl Automerge {
         patch_log: &mut PatchLog,
     ) -> Result<(), AutomergeError> {
         sync_state.in_flight = false;
+        sync_state
+            .stats
+            .record_received(message.changes.bytes_len());
         let before_heads = self.get_heads();
 
         let Message {
@@ -402,12 +418,16 @@ impl Automerge {
             need: message_need,
             have: message_have,
             supported_capabilities,
+            ephemeral,
             ..
         } = message;
 
         if let Some(caps) = supported_capabilities {
             sync_state.their_capabilities = Some(caps);
         }
+        if let (Some(payload), Some(handler)) = (ephemeral, &sync_state.ephemeral_handler) {
+            (handler.0)(&payload);
+        }
 
         let changes_is_empty = message_changes.is_empty();
         if !changes_is_empty {
@@ -538,6 +558,13 @@ pub struct Message {
     pub supported_capabilities: Option<Vec<Capability>>,
     /// What version to encode this message as
     pub version: MessageVersion,
+    /// A payload which is passed to the recipient's ephemeral handler rather than being added to
+    /// the document, see [`State::set_ephemeral()`]
+    ///
+    /// This is encoded after the capabilities, so the capabilities are always encoded with it. If
+    /// `supported_capabilities` is `None` the capabilities of this implementation are encoded.
+    /// Peers which do not know about ephemeral payloads ignore it.
+    pub ephemeral: Option<Vec<u8>>,
 }
 
 /// An array of changes, each of which should be passed to [`Automerge::load_incremental()`]
@@ -557,6 +584,10 @@ impl From<Vec<u8>> for ChunkList {
 }
 
 impl ChunkList {
+    fn bytes_len(&self) -> usize {
+        self.0.iter().map(|c| c.len()).sum()
+    }
+
     fn parse(i: parse::Input<'_>) -> parse::ParseResult<'_, Self, ReadMessageError> {
         let change_parser = |i| {
             let (i, bytes) = parse::length_prefixed_bytes(i)?;
@@ -628,6 +659,12 @@ impl Message {
         } else {
             (i, None)
         };
+        let (i, ephemeral) = if !i.is_empty() {
+            let (i, payload) = parse::length_prefixed_bytes(i)?;
+            (i, Some(payload.to_vec()))
+        } else {
+            (i, None)
+        };
         Ok((
             i,
             Message {
@@ -637,6 +674,7 @@ impl Message {
                 changes,
                 supported_capabilities,
                 version: message_version,
+                ephemeral,
             },
         ))
     }
@@ -657,12 +695,22 @@ impl Message {
             buf.extend::<&[u8]>(change.as_ref())
         });
 
-        if let Some(supported_capabilities) = self.supported_capabilities {
+        // the capabilities have to be encoded before an ephemeral payload. An empty list would tell
+        // the recipient that we support nothing, so send the ones we actually support.
+        if self.supported_capabilities.is_some() || self.ephemeral.is_some() {
+            let supported_capabilities = self
+                .supported_capabilities
+                .unwrap_or_else(Capability::supported);
             encode_many(&mut buf, supported_capabilities.iter(), |buf, cap| {
                 cap.encode(buf);
             });
         }
 
+        if let Some(ephemeral) = self.ephemeral {
+            leb128::write::unsigned(&mut buf, ephemeral.len() as u64).unwrap();
+            buf.extend(ephemeral);
+        }
+
         buf
     }
 }
@@ -676,6 +724,11 @@ pub enum Capability {
 }
 
 impl Capability {
+    /// The capabilities of this implementation
+    fn supported() -> Vec<Capability> {
+        vec![Capability::MessageV1, Capability::MessageV2]
+    }
+
     fn encode(&self, out: &mut Vec<u8>) {
         match self {
             Capability::MessageV1 => out.push(0x01),
@@ -784,6 +837,7 @@ mod tests {
                 Just(Some(vec![Capability::MessageV2])),
                 Just(Some(vec![Capability::MessageV1, Capability::MessageV2])),
             ],
+            ephemeral in proptest::option::of(proptest::collection::vec(any::<u8>(), 0..20)),
         ) -> Message {
             Message {
                 heads,
@@ -791,6 +845,7 @@ mod tests {
                 have,
                 changes: changes.into_iter().map(|c| c.raw_bytes().to_vec()).collect::<Vec<Vec<u8>>>().into(),
                 supported_capabilities,
+                ephemeral,
                 version: MessageVersion::V1,
             }
         }
@@ -808,6 +863,7 @@ mod tests {
                 Just(Some(vec![Capability::MessageV2])),
                 Just(Some(vec![Capability::MessageV1, Capability::MessageV2])),
             ],
+            ephemeral in proptest::option::of(proptest::collection::vec(any::<u8>(), 0..20)),
         ) -> Message {
             Message {
                 heads,
@@ -815,6 +871,7 @@ mod tests {
                 have,
                 changes: ChunkList::from(raw),
                 supported_capabilities,
+                ephemeral,
                 version: MessageVersion::V2,
             }
         }
@@ -833,18 +890,42 @@ mod tests {
             changes: ChunkList::empty(),
             supported_capabilities: None,
             version: MessageVersion::V2,
+            ephemeral: None,
         };
         let encoded = msg.encode();
         Message::parse(Input::new(&encoded)).unwrap();
     }
 
+    #[test]
+    fn ephemeral_payload_without_capabilities_sends_supported_capabilities() {
+        let msg = Message {
+            heads: vec![],
+            need: vec![],
+            have: vec![],
+            changes: ChunkList::empty(),
+            supported_capabilities: None,
+            version: MessageVersion::V2,
+            ephemeral: Some(b"cursor".to_vec()),
+        };
+        let decoded = Message::decode(&msg.encode()).unwrap();
+        assert_eq!(
+            decoded.supported_capabilities,
+            Some(vec![Capability::MessageV1, Capability::MessageV2])
+        );
+        assert_eq!(decoded.ephemeral, Some(b"cursor".to_vec()));
+    }
+
     proptest! {
         #[test]
         fn encode_decode_message(msg in gen_sync_message()) {
             let encoded = msg.clone().encode();
             let (i, decoded) = Message::parse(Input::new(&encoded)).unwrap();
             assert!(i.is_empty());
-            assert_eq!(msg, decoded);
+            let mut expected = msg;
+            if expected.ephemeral.is_some() && expected.supported_capabilities.is_none() {
+                expected.supported_capabilities = Some(Capability::supported());
+            }
+            assert_eq!(expected, decoded);
         }
     }
 
@@ -1289,4 +1370,60 @@ mod tests {
         let (_, chunk) = Chunk::parse(Input::new(&changes.0[0])).unwrap();
         assert!(matches!(chunk, Chunk::Document(_)));
     }
+
+    #[test]
+    fn sync_stats_count_messages_and_bytes() {
+        let mut doc1 = crate::AutoCommit::new();
+        doc1.put(crate::ROOT, "key", "value").unwrap();
+        doc1.commit();
+        let mut doc2 = crate::AutoCommit::new();
+        let mut s1 = State::new();
+        let mut s2 = State::new();
+
+        sync(&mut doc1, &mut doc2, &mut s1, &mut s2);
+
+        assert!(s1.stats.messages_sent > 0);
+        assert_eq!(s1.stats.messages_sent, s2.stats.messages_received);
+        assert_eq!(s2.stats.messages_sent, s1.stats.messages_received);
+        assert!(s1.stats.changes_sent >= 1);
+        assert_eq!(s2.stats.changes_sent, 0);
+        assert_eq!(s1.stats.bytes_sent, s2.stats.bytes_received);
+        assert!(s1.stats.bytes_sent > 0);
+        assert_eq!(s2.stats.bytes_sent, 0);
+
+        // stats are not persisted
+        let decoded = State::decode(&s1.encode()).unwrap();
+        assert_eq!(decoded.stats, Stats::default());
+    }
+
+    #[test]
+    fn ephemeral_payloads_are_delivered_without_changing_the_document() {
+        let mut doc1 = crate::AutoCommit::new();
+        doc1.put(crate::ROOT, "key", "value").unwrap();
+        doc1.commit();
+        let mut doc2 = crate::AutoCommit::new();
+        let mut s1 = State::new();
+        let mut s2 = State::new();
+        sync(&mut doc1, &mut doc2, &mut s1, &mut s2);
+        let heads = doc2.get_heads();
+
+        let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = received.clone();
+        s2.set_ephemeral_handler(move |payload| log.lock().unwrap().push(payload.to_vec()));
+
+        // the documents are in sync, but a message is still sent for the payload
+        s1.set_ephemeral(b"cursor at 3".to_vec());
+        let msg = doc1.sync().generate_sync_message(&mut s1).unwrap();
+        assert!(msg.changes.is_empty());
+        let msg = Message::decode(&msg.encode()).unwrap();
+        doc2.sync().receive_sync_message(&mut s2, msg).unwrap();
+        assert_eq!(*received.lock().unwrap(), vec![b"cursor at 3".to_vec()]);
+        assert_eq!(doc2.get_heads(), heads);
+        // the capabilities sent with the payload do not change what the other end supports
+        assert!(s2.supports_v2_messages());
+
+        // the payload is only sent once
+        sync(&mut doc1, &mut doc2, &mut s1, &mut s2);
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
 }
@@ -9,6 +9,7 @@ pub(super) struct MessageBuilder {
     changes: Vec<Vec<u8>>,
     supported_capabilities: Option<Vec<Capability>>,
     version: MessageVersion,
+    ephemeral: Option<Vec<u8>>,
 }
 
 impl MessageBuilder {
@@ -20,6 +21,7 @@ impl MessageBuilder {
             changes: changes.map(|c| c.raw_bytes().to_vec()).collect(),
             supported_capabilities: None,
             version: MessageVersion::V1,
+            ephemeral: None,
         }
     }
 
@@ -35,6 +37,7 @@ impl MessageBuilder {
             have: Vec::new(),
             supported_capabilities: None,
             version: MessageVersion::V2,
+            ephemeral: None,
         }
     }
 
@@ -61,6 +64,11 @@ impl MessageBuilder {
         self
     }
 
+    pub(super) fn ephemeral(mut self, ephemeral: Option<Vec<u8>>) -> Self {
+        self.ephemeral = ephemeral;
+        self
+    }
+
     pub(super) fn build(self) -> Message {
         Message {
             heads: self.heads,
@@ -69,6 +77,7 @@ impl MessageBuilder {
             changes: super::ChunkList::from(self.changes),
             supported_capabilities: self.supported_capabilities,
             version: self.version,
+            ephemeral: self.ephemeral,
         }
     }
 
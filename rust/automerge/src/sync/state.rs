@@ -1,4 +1,7 @@
 use std::collections::BTreeSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 #[cfg(doc)]
 use super::SyncDoc;
@@ -60,6 +63,77 @@ pub struct State {
 
     /// The capabilities the other side has said they have
     pub their_capabilities: Option<Vec<Capability>>,
+
+    /// Counters for the messages exchanged in this session
+    pub stats: Stats,
+
+    /// The ephemeral payload to send with the next message, see [`Self::set_ephemeral()`]
+    pub ephemeral: Option<Vec<u8>>,
+
+    /// Called with ephemeral payloads from the other end, see [`Self::set_ephemeral_handler()`]
+    pub ephemeral_handler: Option<EphemeralHandler>,
+}
+
+/// The function set with [`State::set_ephemeral_handler()`]
+#[derive(Clone)]
+pub struct EphemeralHandler(pub(crate) Arc<EphemeralFn>);
+
+type EphemeralFn = dyn Fn(&[u8]) + Send + Sync;
+
+impl fmt::Debug for EphemeralHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EphemeralHandler").finish()
+    }
+}
+
+// The handler is not part of the state of the sync, so all handlers are considered equal
+impl PartialEq for EphemeralHandler {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for EphemeralHandler {}
+
+impl Hash for EphemeralHandler {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Counters describing the sync messages exchanged with a peer, see [`State::stats`]
+///
+/// These are not persisted by [`State::encode()`] and so only cover the current session. Byte
+/// counts are the size of the change data in the messages rather than of the encoded messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Stats {
+    /// The number of messages generated by [`SyncDoc::generate_sync_message()`]
+    pub messages_sent: u64,
+    /// The number of messages passed to [`SyncDoc::receive_sync_message()`]
+    pub messages_received: u64,
+    /// The number of changes included in the messages we have sent
+    pub changes_sent: u64,
+    /// The total size of the change data in the messages we have sent
+    pub bytes_sent: u64,
+    /// The total size of the change data in the messages we have received
+    pub bytes_received: u64,
+    /// The size of the change data in the last message we sent
+    pub last_sent_bytes: u64,
+    /// The size of the change data in the last message we received
+    pub last_received_bytes: u64,
+}
+
+impl Stats {
+    pub(crate) fn record_sent(&mut self, changes: usize, bytes: usize) {
+        self.messages_sent += 1;
+        self.changes_sent += changes as u64;
+        self.bytes_sent += bytes as u64;
+        self.last_sent_bytes = bytes as u64;
+    }
+
+    pub(crate) fn record_received(&mut self, bytes: usize) {
+        self.messages_received += 1;
+        self.bytes_received += bytes as u64;
+        self.last_received_bytes = bytes as u64;
+    }
 }
 
 /// A summary of the changes that the sender of the message already has.
@@ -116,10 +190,41 @@ impl State {
                 in_flight: false,
                 have_responded: false,
                 their_capabilities: None,
+                stats: Stats::default(),
+                ephemeral: None,
+                ephemeral_handler: None,
             },
         ))
     }
 
+    /// Send `payload` to the other end with the next sync message without adding it to the
+    /// document
+    ///
+    /// This is for data such as cursor positions or presence which is only of interest while
+    /// peers are connected. The next call to [`SyncDoc::generate_sync_message()`] returns a
+    /// message even if there are no changes to send, and the other end passes the payload to the
+    /// function set with [`Self::set_ephemeral_handler()`]. Setting a new payload before the
+    /// previous one has been sent replaces it. Payloads are not persisted by [`Self::encode()`].
+    pub fn set_ephemeral(&mut self, payload: Vec<u8>) {
+        self.ephemeral = Some(payload);
+    }
+
+    /// Set a function to be called with each ephemeral payload received from the other end, see
+    /// [`Self::set_ephemeral()`]
+    ///
+    /// Payloads received while no function is set are dropped.
+    pub fn set_ephemeral_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&[u8]) + Send + Sync + 'static,
+    {
+        self.ephemeral_handler = Some(EphemeralHandler(Arc::new(handler)));
+    }
+
+    /// Remove the function set with [`Self::set_ephemeral_handler()`]
+    pub fn clear_ephemeral_handler(&mut self) {
+        self.ephemeral_handler = None;
+    }
+
     pub(crate) fn supports_v2_messages(&self) -> bool {
         self.their_capabilities
             .as_ref()
@@ -0,0 +1,23 @@
+use std::ops::Range;
+
+use crate::ActorId;
+
+/// The merged content of a text object along with the regions where concurrent edits met,
+/// returned by [`crate::Automerge::text_with_conflicts()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextWithConflicts {
+    /// The merged text, the same as [`crate::ReadDoc::text()`]
+    pub text: String,
+    /// The conflicting regions of [`Self::text`], in order
+    pub conflicts: Vec<TextConflict>,
+}
+
+/// A region of merged text made up of text which several actors inserted concurrently at the
+/// same place
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextConflict {
+    /// The range of the merged text which the conflicting insertions cover
+    pub range: Range<usize>,
+    /// The text each actor inserted in the range, in the order the actors first appear
+    pub alternatives: Vec<(ActorId, String)>,
+}
@@ -2,11 +2,21 @@ use core::fmt::Debug;
 
 use crate::sequence_tree::SequenceTree;
 
-#[cfg(not(any(target_family = "wasm", feature = "utf8-indexing")))]
+// If both the `utf8-indexing` and `utf16-indexing` features are enabled, for example by two crates
+// in the same build, `utf8-indexing` takes precedence so that the features stay additive.
+
+#[cfg(not(any(
+    target_family = "wasm",
+    feature = "utf8-indexing",
+    feature = "utf16-indexing"
+)))]
 #[derive(Clone, PartialEq, Default)]
 pub struct TextValue(SequenceTree<char>);
 
-#[cfg(target_family = "wasm")]
+#[cfg(all(
+    any(target_family = "wasm", feature = "utf16-indexing"),
+    not(feature = "utf8-indexing")
+))]
 #[derive(Clone, PartialEq, Default)]
 pub struct TextValue(SequenceTree<u16>);
 
@@ -14,7 +24,11 @@ pub struct TextValue(SequenceTree<u16>);
 #[derive(Clone, PartialEq, Default)]
 pub struct TextValue(SequenceTree<u8>);
 
-#[cfg(not(any(target_family = "wasm", feature = "utf8-indexing")))]
+#[cfg(not(any(
+    target_family = "wasm",
+    feature = "utf8-indexing",
+    feature = "utf16-indexing"
+)))]
 impl TextValue {
     pub(crate) fn new(s: &str) -> Self {
         let mut v = SequenceTree::new();
@@ -49,7 +63,10 @@ impl TextValue {
     }
 }
 
-#[cfg(target_family = "wasm")]
+#[cfg(all(
+    any(target_family = "wasm", feature = "utf16-indexing"),
+    not(feature = "utf8-indexing")
+))]
 impl TextValue {
     pub(crate) fn new(s: &str) -> Self {
         let mut v = SequenceTree::new();
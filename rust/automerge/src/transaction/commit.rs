@@ -3,6 +3,8 @@
 pub struct CommitOptions {
     pub message: Option<String>,
     pub time: Option<i64>,
+    /// Application defined bytes stored with the change, see [`crate::Change::extra_bytes()`]
+    pub extra_bytes: Option<Vec<u8>>,
 }
 
 impl CommitOptions {
@@ -29,4 +31,19 @@ impl CommitOptions {
         self.time = Some(time);
         self
     }
+
+    /// Add extra bytes to the commit.
+    ///
+    /// Automerge does not interpret these, so they can be used to attach application metadata
+    /// such as audit information to a change.
+    pub fn with_extra_bytes<B: Into<Vec<u8>>>(mut self, extra_bytes: B) -> Self {
+        self.extra_bytes = Some(extra_bytes.into());
+        self
+    }
+
+    /// Add extra bytes to the commit.
+    pub fn set_extra_bytes<B: Into<Vec<u8>>>(&mut self, extra_bytes: B) -> &mut Self {
+        self.extra_bytes = Some(extra_bytes.into());
+        self
+    }
 }
@@ -3,10 +3,12 @@ use std::sync::Arc;
 
 use crate::exid::ExId;
 use crate::marks::{ExpandMark, Mark, MarkSet};
-use crate::op_set::{ChangeOpIter, OpIdx, OpIdxRange};
+use crate::op_set::{ChangeOpIter, Op, OpIdx, OpIdxRange};
 use crate::patches::{PatchLog, TextRepresentation};
 use crate::query::{self, OpIdSearch};
+use crate::signing;
 use crate::storage::Change as StoredChange;
+use crate::transaction::CommitOptions;
 use crate::types::{Clock, Key, ListEncoding, ObjId, OpId};
 use crate::{op_tree::OpSetData, types::OpBuilder, Automerge, Change, ChangeHash, Prop};
 use crate::{AutomergeError, ObjType, OpType, ScalarValue};
@@ -18,6 +20,7 @@ pub(crate) struct TransactionInner {
     start_op: NonZeroU64,
     time: i64,
     message: Option<String>,
+    extra_bytes: Vec<u8>,
     deps: Vec<ChangeHash>,
     scope: Option<Clock>,
     idx_range: OpIdxRange,
@@ -57,6 +60,7 @@ impl TransactionInner {
             start_op,
             time: 0,
             message: None,
+            extra_bytes: Vec::new(),
             idx_range,
             deps,
             scope,
@@ -67,10 +71,9 @@ impl TransactionInner {
     pub(crate) fn empty(
         doc: &mut Automerge,
         args: TransactionArgs,
-        message: Option<String>,
-        time: Option<i64>,
+        options: CommitOptions,
     ) -> ChangeHash {
-        Self::new(args).commit_impl(doc, message, time)
+        Self::new(args).commit_impl(doc, options)
     }
 
     pub(crate) fn pending_ops(&self) -> usize {
@@ -82,32 +85,34 @@ impl TransactionInner {
     ///
     /// Returns `None` if there were no operations to commit
     #[tracing::instrument(skip(self, doc))]
-    pub(crate) fn commit(
-        self,
-        doc: &mut Automerge,
-        message: Option<String>,
-        time: Option<i64>,
-    ) -> Option<ChangeHash> {
+    pub(crate) fn commit(self, doc: &mut Automerge, options: CommitOptions) -> Option<ChangeHash> {
         if self.pending_ops() == 0 {
             return None;
         }
-        Some(self.commit_impl(doc, message, time))
+        Some(self.commit_impl(doc, options))
     }
 
-    pub(crate) fn commit_impl(
-        mut self,
-        doc: &mut Automerge,
-        message: Option<String>,
-        time: Option<i64>,
-    ) -> ChangeHash {
-        if message.is_some() {
-            self.message = message;
+    pub(crate) fn commit_impl(mut self, doc: &mut Automerge, options: CommitOptions) -> ChangeHash {
+        if options.message.is_some() {
+            self.message = options.message;
         }
 
-        if let Some(t) = time {
+        if let Some(t) = options.time.or_else(|| doc.timestamp()) {
             self.time = t;
         }
 
+        if let Some(extra_bytes) = options.extra_bytes {
+            self.extra_bytes = extra_bytes;
+        }
+
+        if let Some(signer) = doc.signer().cloned() {
+            let unsigned = self.clone().export(doc.osd());
+            let signature = signer
+                .0
+                .sign(unsigned.actor_id(), &signing::payload(&unsigned));
+            signing::append_signature(&mut self.extra_bytes, &signature);
+        }
+
         let num_ops = self.pending_ops();
         let change = self.export(doc.osd());
         let hash = change.hash();
@@ -140,6 +145,7 @@ impl TransactionInner {
             .with_message(self.message.clone())
             .with_dependencies(deps)
             .with_timestamp(self.time)
+            .with_extra_bytes(self.extra_bytes.clone())
             .build(self.operations(osd).map(op_as_actor_id))
         {
             Ok(s) => s,
@@ -158,6 +164,38 @@ impl TransactionInner {
         Change::new(stored)
     }
 
+    /// Whether a draft saved as `change` was created from the same state as this transaction
+    pub(crate) fn matches_draft(&self, change: &Change) -> bool {
+        let mut deps = self.deps.clone();
+        deps.sort();
+        let mut draft_deps = change.deps().to_vec();
+        draft_deps.sort();
+        self.pending_ops() == 0
+            && self.seq == change.seq()
+            && self.start_op == change.start_op()
+            && deps == draft_deps
+    }
+
+    /// Add the operations of a draft saved as `change` to this transaction, returning the number
+    /// of operations added.
+    pub(crate) fn restore_draft(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        change: &Change,
+    ) -> Result<usize, AutomergeError> {
+        doc.check_frozen()?;
+        let ops = doc.import_draft(change)?;
+        let num_ops = ops.len();
+        for (obj, op, pred) in ops {
+            let idx = doc.ops_mut().load_with_range(obj, op, &mut self.idx_range);
+            doc.insert_loaded_op(&obj, idx, &pred, patch_log)?;
+        }
+        self.message = change.message().cloned();
+        self.extra_bytes = change.extra_bytes().to_vec();
+        Ok(num_ops)
+    }
+
     /// Undo the operations added in this transaction, returning the number of cancelled
     /// operations.
     pub(crate) fn rollback(self, doc: &mut Automerge) -> usize {
@@ -219,15 +257,18 @@ impl TransactionInner {
         prop: P,
         value: V,
     ) -> Result<(), AutomergeError> {
+        doc.check_frozen()?;
         let obj = doc.exid_to_obj(ex_obj)?;
         let value = value.into();
         let prop = prop.into();
         match (&prop, obj.typ) {
-            (Prop::Map(_), ObjType::Map) => Ok(()),
+            (Prop::Map(_), ObjType::Map | ObjType::Table) => Ok(()),
             (Prop::Seq(_), ObjType::List) => Ok(()),
             (Prop::Seq(_), ObjType::Text) => Ok(()),
             _ => Err(AutomergeError::InvalidOp(obj.typ)),
         }?;
+        doc.write_limits().check_prop(&prop)?;
+        doc.write_limits().check_value(&value)?;
         self.local_op(doc, patch_log, obj.id, prop, value.into())?;
         Ok(())
     }
@@ -253,13 +294,16 @@ impl TransactionInner {
         prop: P,
         value: ObjType,
     ) -> Result<ExId, AutomergeError> {
+        doc.check_frozen()?;
         let obj = doc.exid_to_obj(ex_obj)?;
         let prop = prop.into();
         match (&prop, obj.typ) {
-            (Prop::Map(_), ObjType::Map) => Ok(()),
+            (Prop::Map(_), ObjType::Map | ObjType::Table) => Ok(()),
             (Prop::Seq(_), ObjType::List) => Ok(()),
             _ => Err(AutomergeError::InvalidOp(obj.typ)),
         }?;
+        doc.write_limits().check_prop(&prop)?;
+        doc.write_limits().check_depth(doc.object_depth(&obj.id))?;
         self.local_op(doc, patch_log, obj.id, prop, value.into())
             .map(|val| val.unwrap().as_op(doc.osd()).exid())
     }
@@ -268,10 +312,10 @@ impl TransactionInner {
         OpId::new(self.start_op.get() + self.pending_ops() as u64, self.actor)
     }
 
-    fn next_insert(&mut self, key: Key, value: ScalarValue) -> OpBuilder {
+    fn next_insert(&mut self, key: Key, action: OpType) -> OpBuilder {
         OpBuilder {
             id: self.next_id(),
-            action: OpType::Put(value),
+            action,
             key,
             insert: true,
         }
@@ -315,11 +359,13 @@ impl TransactionInner {
         index: usize,
         value: V,
     ) -> Result<(), AutomergeError> {
+        doc.check_frozen()?;
         let obj = doc.exid_to_obj(ex_obj)?;
         if !matches!(obj.typ, ObjType::List | ObjType::Text) {
             return Err(AutomergeError::InvalidOp(obj.typ));
         }
         let value = value.into();
+        doc.write_limits().check_value(&value)?;
         tracing::trace!(obj=?obj, value=?value, "inserting value");
         self.do_insert(
             doc,
@@ -340,10 +386,12 @@ impl TransactionInner {
         index: usize,
         value: ObjType,
     ) -> Result<ExId, AutomergeError> {
+        doc.check_frozen()?;
         let obj = doc.exid_to_obj(ex_obj)?;
         if !matches!(obj.typ, ObjType::List | ObjType::Text) {
             return Err(AutomergeError::InvalidOp(obj.typ));
         }
+        doc.write_limits().check_depth(doc.object_depth(&obj.id))?;
         let idx = self.do_insert(
             doc,
             patch_log,
@@ -438,6 +486,9 @@ impl TransactionInner {
         if matches!(action, OpType::Increment(_)) && query.ops.iter().all(|op| !op.is_counter()) {
             return Err(AutomergeError::MissingCounter);
         }
+        if let OpType::Increment(n) = action {
+            check_counter_overflow(&query.ops, n, self.scope.as_ref())?;
+        }
 
         let op = OpBuilder {
             id,
@@ -483,6 +534,9 @@ impl TransactionInner {
         if matches!(action, OpType::Increment(_)) && query.ops.iter().all(|op| !op.is_counter()) {
             return Err(AutomergeError::MissingCounter);
         }
+        if let OpType::Increment(n) = action {
+            check_counter_overflow(&query.ops, n, self.scope.as_ref())?;
+        }
 
         let op = OpBuilder {
             id,
@@ -517,6 +571,7 @@ impl TransactionInner {
         prop: P,
         value: i64,
     ) -> Result<(), AutomergeError> {
+        doc.check_frozen()?;
         let obj = doc.exid_to_obj(obj)?;
         self.local_op(
             doc,
@@ -535,6 +590,7 @@ impl TransactionInner {
         ex_obj: &ExId,
         prop: P,
     ) -> Result<(), AutomergeError> {
+        doc.check_frozen()?;
         let obj = doc.exid_to_obj(ex_obj)?;
         let prop = prop.into();
         if obj.typ == ObjType::Text {
@@ -567,11 +623,15 @@ impl TransactionInner {
         del: isize,
         vals: impl IntoIterator<Item = ScalarValue>,
     ) -> Result<(), AutomergeError> {
+        doc.check_frozen()?;
         let obj = doc.exid_to_obj(ex_obj)?;
         if !matches!(obj.typ, ObjType::List | ObjType::Text) {
             return Err(AutomergeError::InvalidOp(obj.typ));
         }
-        let values = vals.into_iter().collect();
+        let values = vals.into_iter().collect::<Vec<_>>();
+        for value in &values {
+            doc.write_limits().check_value(value)?;
+        }
         self.inner_splice(
             doc,
             patch_log,
@@ -579,13 +639,49 @@ impl TransactionInner {
                 obj: obj.id,
                 index,
                 del,
-                values,
+                values: values.into_iter().map(OpType::Put).collect(),
                 splice_type: SpliceType::List,
             },
         )?;
         Ok(())
     }
 
+    /// Splice new, empty objects into the given sequence. Returns the IDs of the new objects
+    pub(crate) fn splice_objects(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        index: usize,
+        del: isize,
+        objs: impl IntoIterator<Item = ObjType>,
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        doc.check_frozen()?;
+        let obj = doc.exid_to_obj(ex_obj)?;
+        if !matches!(obj.typ, ObjType::List | ObjType::Text) {
+            return Err(AutomergeError::InvalidOp(obj.typ));
+        }
+        let values = objs.into_iter().map(OpType::Make).collect::<Vec<_>>();
+        if !values.is_empty() {
+            doc.write_limits().check_depth(doc.object_depth(&obj.id))?;
+        }
+        let inserted = self.inner_splice(
+            doc,
+            patch_log,
+            SpliceArgs {
+                obj: obj.id,
+                index,
+                del,
+                values,
+                splice_type: SpliceType::List,
+            },
+        )?;
+        Ok(inserted
+            .into_iter()
+            .map(|idx| idx.as_op(doc.osd()).exid())
+            .collect())
+    }
+
     /// Splice string into a text object
     pub(crate) fn splice_text(
         &mut self,
@@ -596,11 +692,15 @@ impl TransactionInner {
         del: isize,
         text: &str,
     ) -> Result<(), AutomergeError> {
+        doc.check_frozen()?;
         let obj = doc.exid_to_obj(ex_obj)?;
         if obj.typ != ObjType::Text {
             return Err(AutomergeError::InvalidOp(obj.typ));
         }
-        let values = text.chars().map(ScalarValue::from).collect();
+        let values = text
+            .chars()
+            .map(|c| OpType::Put(ScalarValue::from(c)))
+            .collect();
         self.inner_splice(
             doc,
             patch_log,
@@ -611,9 +711,11 @@ impl TransactionInner {
                 values,
                 splice_type: SpliceType::Text(text),
             },
-        )
+        )?;
+        Ok(())
     }
 
+    /// Delete and insert the elements described by `args`, returning the ops which were inserted
     fn inner_splice(
         &mut self,
         doc: &mut Automerge,
@@ -625,7 +727,7 @@ impl TransactionInner {
             values,
             splice_type,
         }: SpliceArgs<'_>,
-    ) -> Result<(), AutomergeError> {
+    ) -> Result<Vec<OpIdx>, AutomergeError> {
         if del < 0 {
             if let Some(n) = index.checked_add_signed(del) {
                 index = n;
@@ -676,6 +778,7 @@ impl TransactionInner {
 
         // do the insert query for the first item and then
         // insert the remaining ops one after the other
+        let mut inserted = Vec::with_capacity(values.len());
         if !values.is_empty() {
             let query = doc.ops().search(
                 &obj,
@@ -687,8 +790,8 @@ impl TransactionInner {
             let mut cursor = index;
             let mut width = 0;
 
-            for v in &values {
-                let op = self.next_insert(key, v.clone());
+            for v in values {
+                let op = self.next_insert(key, v);
 
                 key = op.id.into();
 
@@ -698,6 +801,7 @@ impl TransactionInner {
                 width = idx.as_op(doc.osd()).width(encoding);
                 cursor += width;
                 pos += 1;
+                inserted.push(idx);
             }
 
             doc.ops_mut()
@@ -711,14 +815,13 @@ impl TransactionInner {
                         patch_log.splice(obj, index, text, marks.clone());
                     }
                     SpliceType::List | SpliceType::Text(..) => {
-                        let mut opid = self.next_id().minus(values.len());
-                        for (offset, v) in values.iter().enumerate() {
-                            opid = opid.next();
+                        for (offset, idx) in inserted.iter().enumerate() {
+                            let op = idx.as_op(doc.osd());
                             patch_log.insert(
                                 obj,
                                 index + offset,
-                                v.clone().into(),
-                                opid,
+                                op.value().into(),
+                                *op.id(),
                                 false,
                                 marks.clone(),
                             );
@@ -727,7 +830,7 @@ impl TransactionInner {
                 }
             }
         }
-        Ok(())
+        Ok(inserted)
     }
 
     pub(crate) fn mark(
@@ -738,6 +841,7 @@ impl TransactionInner {
         mark: Mark<'_>,
         expand: ExpandMark,
     ) -> Result<(), AutomergeError> {
+        doc.check_frozen()?;
         let obj = doc.exid_to_obj(ex_obj)?;
         let action = OpType::MarkBegin(expand.before(), mark.data.clone().into_owned());
 
@@ -767,6 +871,7 @@ impl TransactionInner {
         end: usize,
         expand: ExpandMark,
     ) -> Result<(), AutomergeError> {
+        doc.check_frozen()?;
         let mark = Mark::new(name.to_string(), ScalarValue::Null, start, end);
         self.mark(doc, patch_log, ex_obj, mark, expand)
     }
@@ -849,10 +954,29 @@ struct SpliceArgs<'a> {
     obj: ObjId,
     index: usize,
     del: isize,
-    values: Vec<ScalarValue>,
+    values: Vec<OpType>,
     splice_type: SpliceType<'a>,
 }
 
+/// Local increments which would overflow any of the counters they apply to are rejected rather
+/// than wrapping, see [`crate::value::Counter::increment`]
+fn check_counter_overflow(
+    ops: &[Op<'_>],
+    n: i64,
+    scope: Option<&Clock>,
+) -> Result<(), AutomergeError> {
+    let overflows = ops
+        .iter()
+        .filter(|op| op.is_counter())
+        .filter_map(|op| op.value_at(scope).to_i64())
+        .any(|current| current.checked_add(n).is_none());
+    if overflows {
+        Err(AutomergeError::CounterOverflow)
+    } else {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{transaction::Transactable, ReadDoc, ROOT};
@@ -28,15 +28,26 @@ pub struct Transaction<'a> {
     // rolled back during drop.
     inner: Option<TransactionInner>,
     patch_log: PatchLog,
+    // whether the patch log was only activated to notify the document's op observer
+    observed_only: bool,
     doc: &'a mut Automerge,
 }
 
 impl<'a> Transaction<'a> {
-    pub(crate) fn new(doc: &'a mut Automerge, args: TransactionArgs, patch_log: PatchLog) -> Self {
+    pub(crate) fn new(
+        doc: &'a mut Automerge,
+        args: TransactionArgs,
+        mut patch_log: PatchLog,
+    ) -> Self {
+        let observed_only = doc.has_op_observer() && !patch_log.is_active();
+        if observed_only {
+            patch_log.set_active(true);
+        }
         Self {
             inner: Some(TransactionInner::new(args)),
             doc,
             patch_log,
+            observed_only,
         }
     }
 
@@ -57,7 +68,7 @@ impl<'a> Transaction<'a> {
         args: TransactionArgs,
         opts: CommitOptions,
     ) -> ChangeHash {
-        TransactionInner::empty(doc, args, opts.message, opts.time)
+        TransactionInner::empty(doc, args, opts)
     }
 }
 
@@ -69,11 +80,8 @@ impl<'a> Transaction<'a> {
 
     /// Commit the operations performed in this transaction, returning the hashes corresponding to
     /// the new heads.
-    pub fn commit(mut self) -> (Option<ChangeHash>, PatchLog) {
-        let tx = self.inner.take().unwrap();
-        let hash = tx.commit(self.doc, None, None);
-        // TODO - remove this clone
-        (hash, self.patch_log.clone())
+    pub fn commit(self) -> (Option<ChangeHash>, PatchLog) {
+        self.commit_with(CommitOptions::default())
     }
 
     /// Commit the operations in this transaction with some options.
@@ -94,9 +102,19 @@ impl<'a> Transaction<'a> {
     /// ```
     pub fn commit_with(mut self, options: CommitOptions) -> (Option<ChangeHash>, PatchLog) {
         let tx = self.inner.take().unwrap();
-        let hash = tx.commit(self.doc, options.message, options.time);
+        let hash = tx.commit(self.doc, options);
         // TODO - remove this clone
-        (hash, self.patch_log.clone())
+        let mut patch_log = self.patch_log.clone();
+        if self.doc.has_op_observer() {
+            let mut observed = patch_log.clone();
+            observed.set_obj_types(None);
+            self.doc.notify_op_observer(&mut observed, true);
+        }
+        if self.observed_only {
+            patch_log.truncate();
+            patch_log.set_active(false);
+        }
+        (hash, patch_log)
     }
 
     /// Undo the operations added in this transaction, returning the number of cancelled
@@ -187,6 +205,26 @@ impl<'a> ReadDoc for Transaction<'a> {
             .length_for(obj.as_ref(), self.get_scope(Some(heads)))
     }
 
+    fn contains_key<O: AsRef<ExId>>(&self, obj: O, key: &str) -> bool {
+        self.doc
+            .contains_key_for(obj.as_ref(), key, self.get_scope(None))
+    }
+
+    fn contains_key_at<O: AsRef<ExId>>(&self, obj: O, key: &str, heads: &[ChangeHash]) -> bool {
+        self.doc
+            .contains_key_for(obj.as_ref(), key, self.get_scope(Some(heads)))
+    }
+
+    fn has_index<O: AsRef<ExId>>(&self, obj: O, index: usize) -> bool {
+        self.doc
+            .has_index_for(obj.as_ref(), index, self.get_scope(None))
+    }
+
+    fn has_index_at<O: AsRef<ExId>>(&self, obj: O, index: usize, heads: &[ChangeHash]) -> bool {
+        self.doc
+            .has_index_for(obj.as_ref(), index, self.get_scope(Some(heads)))
+    }
+
     fn object_type<O: AsRef<ExId>>(&self, obj: O) -> Result<ObjType, AutomergeError> {
         self.doc.object_type(obj)
     }
@@ -386,6 +424,16 @@ impl<'a> Transactable for Transaction<'a> {
         Ok(())
     }
 
+    fn splice_objects<O: AsRef<ExId>, V: IntoIterator<Item = ObjType>>(
+        &mut self,
+        obj: O,
+        pos: usize,
+        del: isize,
+        objs: V,
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        self.do_tx(|tx, doc, hist| tx.splice_objects(doc, hist, obj.as_ref(), pos, del, objs))
+    }
+
     fn splice_text<O: AsRef<ExId>>(
         &mut self,
         obj: O,
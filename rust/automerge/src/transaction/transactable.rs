@@ -1,6 +1,10 @@
+use crate::blob;
 use crate::exid::ExId;
+use crate::handles::{Kv, List, Map, OrderedMap, Table, Text};
 use crate::marks::{ExpandMark, Mark};
-use crate::{AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, ScalarValue};
+use crate::{
+    hydrate, path, AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, ScalarValue, Subtree, Value,
+};
 
 /// A way of mutating a document within a single change.
 pub trait Transactable: ReadDoc {
@@ -58,6 +62,12 @@ pub trait Transactable: ReadDoc {
     ) -> Result<ExId, AutomergeError>;
 
     /// Increment the counter at the prop in the object by `value`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::MissingCounter`] if the value at `prop` is not a counter and
+    /// [`AutomergeError::CounterOverflow`] if adding `value` would overflow an `i64`. Note that
+    /// increments from concurrent changes wrap on overflow when they are merged.
     fn increment<O: AsRef<ExId>, P: Into<Prop>>(
         &mut self,
         obj: O,
@@ -65,6 +75,46 @@ pub trait Transactable: ReadDoc {
         value: i64,
     ) -> Result<(), AutomergeError>;
 
+    /// Increment the counter at the prop in the object by `delta`, keeping the result within
+    /// `min..=max`
+    ///
+    /// The increment actually made is the difference between the current value and the clamped
+    /// result, which is returned. If the counter is already outside the bounds it is moved back
+    /// inside them. The bounds are only enforced for this increment: concurrent increments made
+    /// by other actors can still take the merged value outside of them.
+    ///
+    /// # Errors
+    ///
+    /// - [`AutomergeError::InvalidCounterBounds`] if `min` is greater than `max`
+    /// - [`AutomergeError::MissingCounter`] if the value at `prop` is not a counter
+    /// - [`AutomergeError::CounterOverflow`] if the required increment does not fit in an `i64`
+    fn inc_clamped<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        delta: i64,
+        min: i64,
+        max: i64,
+    ) -> Result<i64, AutomergeError> {
+        if min > max {
+            return Err(AutomergeError::InvalidCounterBounds { min, max });
+        }
+        let prop = prop.into();
+        let current = match self.get(obj.as_ref(), prop.clone())? {
+            Some((Value::Scalar(v), _)) if v.is_counter() => v.to_i64(),
+            _ => None,
+        }
+        .ok_or(AutomergeError::MissingCounter)?;
+        let target = current.saturating_add(delta).clamp(min, max);
+        let inc = target
+            .checked_sub(current)
+            .ok_or(AutomergeError::CounterOverflow)?;
+        if inc != 0 {
+            self.increment(obj, prop, inc)?;
+        }
+        Ok(target)
+    }
+
     /// Delete the value at prop in the object.
     fn delete<O: AsRef<ExId>, P: Into<Prop>>(
         &mut self,
@@ -83,6 +133,33 @@ pub trait Transactable: ReadDoc {
         vals: V,
     ) -> Result<(), AutomergeError>;
 
+    /// Insert `values` into the list `obj`, starting at `index`
+    ///
+    /// The position of `index` is found once and the values are placed one after the other from
+    /// there, so this is much faster than calling [`Self::insert()`] for each value when
+    /// importing a large list. It is the same as [`Self::splice()`] without deleting anything.
+    fn insert_batch<O: AsRef<ExId>, V: Into<ScalarValue>, I: IntoIterator<Item = V>>(
+        &mut self,
+        obj: O,
+        index: usize,
+        values: I,
+    ) -> Result<(), AutomergeError> {
+        self.splice(obj, index, 0, values.into_iter().map(Into::into))
+    }
+
+    /// Like [`Self::splice`] but inserts a new, empty object of each type in `objs`, returning the
+    /// IDs of the new objects in order
+    ///
+    /// This is useful for importing rows of data, where each row is a map: the maps are all
+    /// created in one call and then filled in using the returned IDs.
+    fn splice_objects<O: AsRef<ExId>, V: IntoIterator<Item = ObjType>>(
+        &mut self,
+        obj: O,
+        pos: usize,
+        del: isize,
+        objs: V,
+    ) -> Result<Vec<ExId>, AutomergeError>;
+
     /// Like [`Self::splice`] but for text.
     fn splice_text<O: AsRef<ExId>>(
         &mut self,
@@ -92,6 +169,136 @@ pub trait Transactable: ReadDoc {
         text: &str,
     ) -> Result<(), AutomergeError>;
 
+    /// Create a new text object at `prop` in `obj` containing `initial`
+    ///
+    /// This is equivalent to calling [`Self::put_object()`] followed by [`Self::splice_text()`],
+    /// so the initial content is inserted in a single batch rather than a character at a time.
+    fn set_text<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        initial: &str,
+    ) -> Result<ExId, AutomergeError> {
+        let text = self.put_object(obj, prop, ObjType::Text)?;
+        if !initial.is_empty() {
+            self.splice_text(&text, 0, 0, initial)?;
+        }
+        Ok(text)
+    }
+
+    /// Write the JSON-like `value` to `prop` in `obj`, creating maps, lists and text objects for
+    /// the objects it contains
+    ///
+    /// Map entries are written in key order. A [`hydrate::Value`] can be deserialized from any
+    /// serde format, for example with `serde_json::from_value()`.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`Self::put()`] and [`Self::put_object()`]
+    fn set_json<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        value: &hydrate::Value,
+    ) -> Result<(), AutomergeError>
+    where
+        Self: Sized,
+    {
+        hydrate::write(self, obj.as_ref(), prop.into(), false, value)
+    }
+
+    /// Write a copy of `subtree` to `prop` in `obj`, returning the ID of the new object
+    ///
+    /// The copy has new object IDs, so later changes to the object `subtree` was exported from
+    /// do not affect it. See [`crate::Automerge::export_subtree()`].
+    ///
+    /// # Errors
+    ///
+    /// The same as [`Self::put_object()`]
+    fn import_subtree<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        subtree: &Subtree,
+    ) -> Result<ExId, AutomergeError>
+    where
+        Self: Sized,
+    {
+        subtree.write(self, obj.as_ref(), prop.into(), false)
+    }
+
+    /// Insert a copy of `subtree` at `index` in the list `obj`, see [`Self::import_subtree()`]
+    ///
+    /// # Errors
+    ///
+    /// The same as [`Self::insert_object()`]
+    fn insert_subtree<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        index: usize,
+        subtree: &Subtree,
+    ) -> Result<ExId, AutomergeError>
+    where
+        Self: Sized,
+    {
+        subtree.write(self, obj.as_ref(), index.into(), true)
+    }
+
+    /// Write everything `reader` produces to a new blob at `prop` in `obj`
+    ///
+    /// A blob is a list of [`ScalarValue::Bytes`] chunks of 64 KiB,
+    /// so a large binary value is not stored as a single operation and can be read back a chunk
+    /// at a time with [`ReadDoc::read_blob()`]. Returns the ID of the list.
+    ///
+    /// ```
+    /// # use std::io::Read;
+    /// # use automerge::{transaction::Transactable, AutoCommit, ReadDoc, ROOT};
+    /// let data = vec![7_u8; 100_000];
+    /// let mut doc = AutoCommit::new();
+    /// let blob = doc.set_blob(ROOT, "image", &data[..]).unwrap();
+    /// assert_eq!(doc.length(&blob), 2);
+    ///
+    /// let mut read = Vec::new();
+    /// doc.read_blob(&blob).unwrap().read_to_end(&mut read).unwrap();
+    /// assert_eq!(read, data);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::BlobRead`] if `reader` fails, as well as the errors of
+    /// [`Self::put_object()`]. The chunks read before the failure are left in the blob.
+    fn set_blob<O: AsRef<ExId>, P: Into<Prop>, R: std::io::Read>(
+        &mut self,
+        obj: O,
+        prop: P,
+        reader: R,
+    ) -> Result<ExId, AutomergeError>
+    where
+        Self: Sized,
+    {
+        blob::write(self, obj.as_ref(), prop.into(), reader)
+    }
+
+    /// Write `value` at `path`, see [`ReadDoc::value_path()`] for the format of paths
+    ///
+    /// The last segment of the path is the prop to write. In a list it can be an existing index,
+    /// the length of the list or `-` to append. If `create_missing` is true, maps are created for
+    /// missing objects along the path, or lists if the segment after them is a number or `-`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidPath`] if the path is empty, passes through a scalar
+    /// value or, unless `create_missing` is true, a missing object. Otherwise the same as
+    /// [`Self::put()`] and [`Self::insert()`].
+    fn set_path<V: Into<ScalarValue>>(
+        &mut self,
+        path: &str,
+        value: V,
+        create_missing: bool,
+    ) -> Result<(), AutomergeError> {
+        path::set(self, path, value.into(), create_missing)
+    }
+
     /// Mark a sequence
     fn mark<O: AsRef<ExId>>(
         &mut self,
@@ -122,4 +329,114 @@ pub trait Transactable: ReadDoc {
     /// can do.
     fn update_text<S: AsRef<str>>(&mut self, obj: &ExId, new_text: S)
         -> Result<(), AutomergeError>;
+
+    /// Get a [`Text`] handle for the text object `obj`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidObjType`] if `obj` is not a text object
+    fn text_obj<O: AsRef<ExId>>(&mut self, obj: O) -> Result<Text<'_, Self>, AutomergeError>
+    where
+        Self: Sized,
+    {
+        Text::new(self, obj.as_ref().clone())
+    }
+
+    /// Get a [`List`] handle for the list object `obj`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidObjType`] if `obj` is not a list
+    fn list<O: AsRef<ExId>>(&mut self, obj: O) -> Result<List<'_, Self>, AutomergeError>
+    where
+        Self: Sized,
+    {
+        List::new(self, obj.as_ref().clone())
+    }
+
+    /// Get a [`Map`] handle for the map object `obj`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidObjType`] if `obj` is not a map
+    fn map<O: AsRef<ExId>>(&mut self, obj: O) -> Result<Map<'_, Self>, AutomergeError>
+    where
+        Self: Sized,
+    {
+        Map::new(self, obj.as_ref().clone())
+    }
+
+    /// Get a [`Kv`] handle for using the root map as a flat key-value store
+    fn kv(&mut self) -> Kv<'_, Self>
+    where
+        Self: Sized,
+    {
+        Kv::new(self)
+    }
+
+    /// Get a [`Table`] handle for the table object `obj`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidObjType`] if `obj` is not a table
+    fn table<O: AsRef<ExId>>(&mut self, obj: O) -> Result<Table<'_, Self>, AutomergeError>
+    where
+        Self: Sized,
+    {
+        Table::new(self, obj.as_ref().clone())
+    }
+
+    /// Add a row to the table `table` and return the ID of the new row
+    ///
+    /// The row is a new map containing `fields`, stored in the table under a new random ID. The
+    /// object ID of the row can be found with [`ReadDoc::row()`]. See [`Table`] for how rows
+    /// behave under concurrent edits.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidObjType`] if `table` is not a table
+    fn add_row<O, I, K, V>(&mut self, table: O, fields: I) -> Result<String, AutomergeError>
+    where
+        O: AsRef<ExId>,
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<ScalarValue>,
+    {
+        let table = table.as_ref();
+        crate::handles::check_type(self, table, ObjType::Table)?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let row = self.put_object(table, id.as_str(), ObjType::Map)?;
+        for (key, value) in fields {
+            self.put(&row, key.as_ref(), value)?;
+        }
+        Ok(id)
+    }
+
+    /// Create a new, empty ordered map at `prop` in `obj`, see [`OrderedMap`]
+    fn put_ordered_map<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+    ) -> Result<ExId, AutomergeError>
+    where
+        Self: Sized,
+    {
+        OrderedMap::create(self, obj.as_ref(), prop)
+    }
+
+    /// Get an [`OrderedMap`] handle for the ordered map `obj`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::NotAnOrderedMap`] if `obj` was not created with
+    /// [`Self::put_ordered_map()`]
+    fn ordered_map<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+    ) -> Result<OrderedMap<'_, Self>, AutomergeError>
+    where
+        Self: Sized,
+    {
+        OrderedMap::new(self, obj.as_ref().clone())
+    }
 }
@@ -157,6 +157,7 @@ impl fmt::Display for ActorId {
 
 /// The type of an object
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Copy, Hash)]
+#[cfg_attr(feature = "derive-arbitrary", derive(arbitrary::Arbitrary))]
 #[serde(rename_all = "camelCase", untagged)]
 pub enum ObjType {
     /// A map
@@ -530,11 +531,6 @@ impl OpId {
         OpId(self.0 - 1, self.1)
     }
 
-    #[inline]
-    pub(crate) fn minus(&self, n: usize) -> OpId {
-        OpId(self.0 - n as u32, self.1)
-    }
-
     #[inline]
     pub(crate) fn next(&self) -> OpId {
         OpId(self.0 + 1, self.1)
@@ -638,8 +634,9 @@ pub(crate) struct Peer {}
 /// The number of bytes in a change hash.
 pub(crate) const HASH_SIZE: usize = 32; // 256 bits = 32 bytes
 
-/// The sha256 hash of a change.
+/// The hash of a change, computed with SHA-256 unless another [`crate::ChangeHasher`] is installed.
 #[derive(Eq, PartialEq, Hash, Clone, PartialOrd, Ord, Copy)]
+#[cfg_attr(feature = "derive-arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ChangeHash(pub [u8; HASH_SIZE]);
 
 impl ChangeHash {
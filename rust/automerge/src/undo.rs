@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+
+use crate::exid::ExId;
+use crate::subtree::Entry;
+use crate::transaction::Transactable;
+use crate::{
+    AutoCommit, AutomergeError, ChangeHash, ObjType, OpType, Prop, ReadDoc, ResolvedKey,
+    ResolvedOp, Subtree, Value,
+};
+
+/// Local undo and redo for an [`AutoCommit`]
+///
+/// Changes are recorded with [`Self::commit()`] or [`Self::record()`]. [`Self::undo()`] then
+/// makes a new change which reverses the most recently recorded change: values which the change
+/// overwrote are put back, elements which it inserted are deleted, elements and keys which it
+/// deleted are restored and counters are decremented by as much as the change incremented them.
+/// [`Self::redo()`] reverses the most recent undo in the same way.
+///
+/// Because undoing makes a new change rather than rewriting history, it only reverses the parts
+/// of the change which have not been overwritten since. If another actor has changed a value
+/// which the recorded change set then that value is left alone, so undoing never discards
+/// someone else's work. Marks are not undone.
+///
+/// ```
+/// # use automerge::{transaction::Transactable, AutoCommit, ReadDoc, UndoManager, ROOT};
+/// let mut doc = AutoCommit::new();
+/// let mut undo = UndoManager::new();
+/// doc.put(ROOT, "title", "draft").unwrap();
+/// undo.commit(&mut doc);
+/// doc.put(ROOT, "title", "final").unwrap();
+/// undo.commit(&mut doc);
+///
+/// undo.undo(&mut doc).unwrap();
+/// assert_eq!(doc.get(ROOT, "title").unwrap().unwrap().0.to_str(), Some("draft"));
+/// undo.redo(&mut doc).unwrap();
+/// assert_eq!(doc.get(ROOT, "title").unwrap().unwrap().0.to_str(), Some("final"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UndoManager {
+    undo_stack: Vec<ChangeHash>,
+    redo_stack: Vec<ChangeHash>,
+}
+
+impl UndoManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Commit the current transaction of `doc` and record it as a step which can be undone
+    ///
+    /// Returns [`None`] if there were no operations to commit
+    pub fn commit(&mut self, doc: &mut AutoCommit) -> Option<ChangeHash> {
+        let hash = doc.commit()?;
+        self.record(hash);
+        Some(hash)
+    }
+
+    /// Record the local change `hash` as a step which can be undone
+    ///
+    /// This discards any steps which could be redone.
+    pub fn record(&mut self, hash: ChangeHash) {
+        self.undo_stack.push(hash);
+        self.redo_stack.clear();
+    }
+
+    /// Whether there is a step to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Whether there is a step to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Undo the most recently recorded step which still has an effect, returning the hash of the
+    /// change which undid it
+    ///
+    /// Any outstanding transaction is committed first. Steps which have been entirely overwritten
+    /// by later changes are discarded. Returns [`None`] if there was nothing to undo.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a recorded step is not a change in `doc`, the step is kept so the
+    /// undo can be retried.
+    pub fn undo(&mut self, doc: &mut AutoCommit) -> Result<Option<ChangeHash>, AutomergeError> {
+        let undone = reverse_step(doc, &mut self.undo_stack)?;
+        self.redo_stack.extend(undone);
+        Ok(undone)
+    }
+
+    /// Redo the most recently undone step, returning the hash of the change which redid it
+    ///
+    /// Returns [`None`] if there was nothing to redo, see [`Self::undo()`]
+    pub fn redo(&mut self, doc: &mut AutoCommit) -> Result<Option<ChangeHash>, AutomergeError> {
+        let redone = reverse_step(doc, &mut self.redo_stack)?;
+        self.undo_stack.extend(redone);
+        Ok(redone)
+    }
+}
+
+/// Reverse the change on the top of `stack`, skipping changes which no longer have any effect
+fn reverse_step(
+    doc: &mut AutoCommit,
+    stack: &mut Vec<ChangeHash>,
+) -> Result<Option<ChangeHash>, AutomergeError> {
+    doc.commit();
+    while let Some(hash) = stack.pop() {
+        if let Err(e) = reverse_change(doc, &hash) {
+            doc.rollback();
+            stack.push(hash);
+            return Err(e);
+        }
+        if let Some(reversed) = doc.commit() {
+            return Ok(Some(reversed));
+        }
+    }
+    Ok(None)
+}
+
+fn reverse_change(doc: &mut AutoCommit, hash: &ChangeHash) -> Result<(), AutomergeError> {
+    let ops = doc.change_ops(hash)?;
+    let before = doc
+        .get_change_by_hash(hash)
+        .ok_or(AutomergeError::MissingHash(*hash))?
+        .deps()
+        .to_vec();
+    let created = ops.iter().map(|op| op.id.clone()).collect::<HashSet<_>>();
+    // Operations are reversed in the order they were made so that elements deleted one after the
+    // other are restored one after the other, each one after the one before it.
+    for op in &ops {
+        // objects the change created did not exist before it, so there is nothing to restore in
+        // them and reversing the operation which created them is enough
+        if created.contains(&op.obj) {
+            continue;
+        }
+        match &op.key {
+            _ if op.insert => reverse_insert(doc, op)?,
+            ResolvedKey::Map(key) => reverse_map_op(doc, hash, op, key, &before)?,
+            ResolvedKey::Elem(elem) => reverse_seq_op(doc, hash, op, elem, &before)?,
+            ResolvedKey::Head => {}
+        }
+    }
+    Ok(())
+}
+
+fn reverse_map_op(
+    doc: &mut AutoCommit,
+    hash: &ChangeHash,
+    op: &ResolvedOp,
+    key: &str,
+    before: &[ChangeHash],
+) -> Result<(), AutomergeError> {
+    let current = doc
+        .get(&op.obj, key)?
+        .map(|(value, id)| (value.is_counter(), id));
+    match (&op.action, current) {
+        (OpType::Increment(n), Some((true, _))) => doc.increment(&op.obj, key, -n),
+        (OpType::Put(_) | OpType::Make(_), Some((_, id))) if id == op.id => {
+            match doc.get_at(&op.obj, key, before)? {
+                Some((value, source)) => {
+                    let value = value.into_owned();
+                    restore(doc, &op.obj, key.into(), false, value, &source, before)
+                }
+                None => doc.delete(&op.obj, key),
+            }
+        }
+        (OpType::Delete, None) => {
+            let (value, source) = doc
+                .get_at(&op.obj, key, before)?
+                .ok_or_else(|| missing_pred(hash, op))?;
+            let value = value.into_owned();
+            restore(doc, &op.obj, key.into(), false, value, &source, before)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn reverse_insert(doc: &mut AutoCommit, op: &ResolvedOp) -> Result<(), AutomergeError> {
+    let (index, visible) = doc.doc.element_index(&op.obj, &op.id, None)?;
+    if visible {
+        doc.delete(&op.obj, index)?;
+    }
+    Ok(())
+}
+
+fn reverse_seq_op(
+    doc: &mut AutoCommit,
+    hash: &ChangeHash,
+    op: &ResolvedOp,
+    elem: &ExId,
+    before: &[ChangeHash],
+) -> Result<(), AutomergeError> {
+    let (index, visible) = doc.doc.element_index(&op.obj, elem, None)?;
+    let clock = doc.doc.clock_at(before);
+    let (old_index, existed) = doc.doc.element_index(&op.obj, elem, Some(clock))?;
+    match &op.action {
+        OpType::Increment(n) if visible => doc.increment(&op.obj, index, -n),
+        OpType::Put(_) | OpType::Make(_) if visible && existed => {
+            if doc.get(&op.obj, index)?.map(|(_, id)| id) != Some(op.id.clone()) {
+                return Ok(());
+            }
+            let (value, source) = doc
+                .get_at(&op.obj, old_index, before)?
+                .ok_or_else(|| missing_pred(hash, op))?;
+            let value = value.into_owned();
+            restore(doc, &op.obj, index.into(), false, value, &source, before)
+        }
+        OpType::Delete if !visible && existed => {
+            let (value, source) = doc
+                .get_at(&op.obj, old_index, before)?
+                .ok_or_else(|| missing_pred(hash, op))?;
+            let value = value.into_owned();
+            restore(doc, &op.obj, index.into(), true, value, &source, before)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The error for an op in the change `hash` which overwrote or deleted a value that can't be
+/// found as at the dependencies of the change
+fn missing_pred(hash: &ChangeHash, op: &ResolvedOp) -> AutomergeError {
+    match op.pred.first().unwrap_or(&op.id) {
+        ExId::Id(counter, actor, _) => AutomergeError::InvalidOpReference {
+            change: *hash,
+            counter: *counter,
+            actor: actor.clone(),
+        },
+        ExId::Root => AutomergeError::MissingHash(*hash),
+    }
+}
+
+/// Put `value` back at `prop` in `obj`, or insert it there if `insert` is true
+///
+/// If `value` is an object a new object is created and the contents of `source` as at `heads`
+/// are copied into it.
+fn restore(
+    doc: &mut AutoCommit,
+    obj: &ExId,
+    prop: Prop,
+    insert: bool,
+    value: Value<'static>,
+    source: &ExId,
+    heads: &[ChangeHash],
+) -> Result<(), AutomergeError> {
+    let entry = match value {
+        Value::Object(_) => Entry::Object(Subtree::export_at(&*doc, source, Some(heads))?),
+        Value::Scalar(value) => match prop.as_index() {
+            Some(index) if insert && doc.object_type(obj)? == ObjType::Text => {
+                let text = value.to_str().unwrap_or_default().to_string();
+                return doc.splice_text(obj, index, 0, &text);
+            }
+            _ => Entry::scalar(value.into_owned()),
+        },
+    };
+    entry.write(doc, obj, prop, insert)
+}
@@ -1,9 +1,11 @@
 use crate::error;
+use crate::exid::ExId;
 use crate::types::ObjType;
 use serde::{Deserialize, Serialize, Serializer};
 use smol_str::SmolStr;
 use std::borrow::Cow;
 use std::fmt;
+use std::time::{Duration, SystemTime};
 
 /// The type of values in an automerge document
 #[derive(Debug, Clone, PartialEq)]
@@ -240,6 +242,24 @@ impl<'a> Value<'a> {
             _ => None,
         }
     }
+
+    /// If this value is a timestamp, return it as a [`SystemTime`], see
+    /// [`ScalarValue::to_system_time()`]
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        match self {
+            Value::Scalar(s) => s.to_system_time(),
+            _ => None,
+        }
+    }
+
+    /// If this value is a timestamp, return it as a UTC [`chrono::DateTime`]
+    #[cfg(feature = "chrono")]
+    pub fn to_datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self {
+            Value::Scalar(s) => s.to_datetime(),
+            _ => None,
+        }
+    }
 }
 
 impl<'a> fmt::Display for Value<'a> {
@@ -329,6 +349,19 @@ impl<'a> From<()> for Value<'a> {
     }
 }
 
+impl<'a> From<SystemTime> for Value<'a> {
+    fn from(t: SystemTime) -> Self {
+        Value::Scalar(Cow::Owned(t.into()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<'a> From<chrono::DateTime<chrono::Utc>> for Value<'a> {
+    fn from(t: chrono::DateTime<chrono::Utc>) -> Self {
+        Value::Scalar(Cow::Owned(t.into()))
+    }
+}
+
 impl<'a> From<ObjType> for Value<'a> {
     fn from(o: ObjType) -> Self {
         Value::Object(o)
@@ -347,6 +380,81 @@ impl<'a> From<&'a ScalarValue> for Value<'a> {
     }
 }
 
+/// A [`Value`] together with the ID which identifies it in the document
+///
+/// Methods such as [`crate::ReadDoc::get()`] and [`crate::ReadDoc::values()`] return a tuple of
+/// `(Value, ExId)`, where the ID is the object ID if the value is an object. Converting that tuple
+/// into a `ValueRef` keeps the object ID with the object so it can be used to recurse into nested
+/// objects directly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueRef<'a> {
+    /// A composite object of type `typ` with the object ID `id`
+    Object { typ: ObjType, id: ExId },
+    /// A non composite value, `id` is the ID of the operation which set it
+    Scalar {
+        value: Cow<'a, ScalarValue>,
+        id: ExId,
+    },
+}
+
+impl<'a> ValueRef<'a> {
+    /// The object ID if this is an object, or the ID of the operation which set the value if it
+    /// is a scalar
+    pub fn id(&self) -> &ExId {
+        match self {
+            ValueRef::Object { id, .. } => id,
+            ValueRef::Scalar { id, .. } => id,
+        }
+    }
+
+    /// The object ID if this is an object
+    pub fn obj_id(&self) -> Option<&ExId> {
+        match self {
+            ValueRef::Object { id, .. } => Some(id),
+            ValueRef::Scalar { .. } => None,
+        }
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, ValueRef::Object { .. })
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        matches!(self, ValueRef::Scalar { .. })
+    }
+
+    /// The value without its ID
+    pub fn value(&self) -> Value<'_> {
+        match self {
+            ValueRef::Object { typ, .. } => Value::Object(*typ),
+            ValueRef::Scalar { value, .. } => Value::Scalar(Cow::Borrowed(value.as_ref())),
+        }
+    }
+
+    /// Split this into a [`Value`] and an ID, the inverse of `From<(Value, ExId)>`
+    pub fn into_parts(self) -> (Value<'a>, ExId) {
+        match self {
+            ValueRef::Object { typ, id } => (Value::Object(typ), id),
+            ValueRef::Scalar { value, id } => (Value::Scalar(value), id),
+        }
+    }
+}
+
+impl<'a> From<(Value<'a>, ExId)> for ValueRef<'a> {
+    fn from((value, id): (Value<'a>, ExId)) -> Self {
+        match value {
+            Value::Object(typ) => ValueRef::Object { typ, id },
+            Value::Scalar(value) => ValueRef::Scalar { value, id },
+        }
+    }
+}
+
+impl<'a> fmt::Display for ValueRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value().fmt(f)
+    }
+}
+
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy)]
 pub(crate) enum DataType {
     #[serde(rename = "counter")]
@@ -372,8 +480,14 @@ pub struct Counter {
 }
 
 impl Counter {
+    /// Counter arithmetic wraps on overflow.
+    ///
+    /// Increments from remote changes must always apply and must produce the same value on every
+    /// peer whatever order they arrive in, so they cannot saturate or fail. Local increments which
+    /// would overflow are rejected with [`AutomergeError::CounterOverflow`](crate::AutomergeError::CounterOverflow)
+    /// before they are made.
     pub(crate) fn increment(&mut self, inc: i64) {
-        self.current += inc;
+        self.current = self.current.wrapping_add(inc);
     }
 }
 
@@ -401,6 +515,13 @@ impl From<i64> for Counter {
     }
 }
 
+#[cfg(feature = "derive-arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Counter {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        i64::arbitrary(u).map(Counter::from)
+    }
+}
+
 impl From<&i64> for Counter {
     fn from(n: &i64) -> Self {
         Counter {
@@ -436,6 +557,7 @@ impl From<&Counter> for f64 {
 
 /// A value which is not a composite value
 #[derive(Serialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "derive-arbitrary", derive(arbitrary::Arbitrary))]
 #[serde(untagged)]
 pub enum ScalarValue {
     Bytes(Vec<u8>),
@@ -656,6 +778,35 @@ impl ScalarValue {
     pub fn counter(n: i64) -> ScalarValue {
         ScalarValue::Counter(n.into())
     }
+
+    /// If this value is a timestamp, return it as a [`SystemTime`]
+    ///
+    /// Timestamps are stored as milliseconds since the unix epoch, the same as a javascript
+    /// `Date`. Returns `None` for any other type of value, or a timestamp which the platform's
+    /// `SystemTime` can't represent.
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        match self {
+            ScalarValue::Timestamp(ms) => {
+                let offset = Duration::from_millis(ms.unsigned_abs());
+                if *ms < 0 {
+                    SystemTime::UNIX_EPOCH.checked_sub(offset)
+                } else {
+                    SystemTime::UNIX_EPOCH.checked_add(offset)
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// If this value is a timestamp, return it as a UTC [`chrono::DateTime`]
+    #[cfg(feature = "chrono")]
+    pub fn to_datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        use chrono::TimeZone;
+        match self {
+            ScalarValue::Timestamp(ms) => chrono::Utc.timestamp_millis_opt(*ms).single(),
+            _ => None,
+        }
+    }
 }
 
 impl From<&str> for ScalarValue {
@@ -724,6 +875,26 @@ impl From<()> for ScalarValue {
     }
 }
 
+/// A timestamp with millisecond precision, times which are further than `i64::MAX`
+/// milliseconds from the unix epoch are clamped
+impl From<SystemTime> for ScalarValue {
+    fn from(t: SystemTime) -> Self {
+        let millis = |d: Duration| i64::try_from(d.as_millis()).unwrap_or(i64::MAX);
+        match t.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(after) => ScalarValue::Timestamp(millis(after)),
+            Err(before) => ScalarValue::Timestamp(-millis(before.duration())),
+        }
+    }
+}
+
+/// A timestamp with millisecond precision
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for ScalarValue {
+    fn from(t: chrono::DateTime<chrono::Utc>) -> Self {
+        ScalarValue::Timestamp(t.timestamp_millis())
+    }
+}
+
 impl From<char> for ScalarValue {
     fn from(c: char) -> Self {
         ScalarValue::Str(SmolStr::new(c.to_string()))
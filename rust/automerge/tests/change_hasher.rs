@@ -0,0 +1,55 @@
+//! Installing a change hasher affects the whole process, so these tests live in their own test
+//! binary
+
+use automerge::{
+    set_change_hasher, transaction::Transactable, verify_change, AutoCommit, ChangeHasher,
+    HasherAlreadySet, LoadChangeError, ReadDoc, Sha256Hasher, ROOT,
+};
+
+/// SHA-256 with the bytes of the digest reversed
+struct ReversedSha256;
+
+impl ChangeHasher for ReversedSha256 {
+    fn name(&self) -> &'static str {
+        "reversed-sha256"
+    }
+
+    fn digest(&self, data: &[u8]) -> [u8; 32] {
+        let mut digest = Sha256Hasher.digest(data);
+        digest.reverse();
+        digest
+    }
+}
+
+#[test]
+fn installed_hasher_is_used_and_sha256_data_is_detected() {
+    let mut sha256_doc = AutoCommit::new();
+    sha256_doc.put(&ROOT, "key", "value").unwrap();
+    let sha256_saved = sha256_doc.save();
+    let sha256_change = sha256_doc.get_last_local_change().unwrap().clone();
+
+    set_change_hasher(&ReversedSha256).unwrap();
+    assert_eq!(set_change_hasher(&Sha256Hasher), Err(HasherAlreadySet));
+
+    let err = AutoCommit::load(&sha256_saved).unwrap_err();
+    assert!(err.to_string().contains("hashed with sha256"), "{}", err);
+    assert!(matches!(
+        verify_change(sha256_change.raw_bytes()),
+        Err(LoadChangeError::HashAlgorithmMismatch {
+            expected: "reversed-sha256",
+            found: "sha256"
+        })
+    ));
+
+    let mut doc = AutoCommit::new().with_actor(sha256_doc.get_actor().clone());
+    doc.put(&ROOT, "key", "value").unwrap();
+    let change = doc.get_last_local_change().unwrap().clone();
+    assert_ne!(change.hash(), sha256_change.hash());
+    assert_eq!(verify_change(change.raw_bytes()).unwrap(), change.hash());
+
+    let loaded = AutoCommit::load(&doc.save()).unwrap();
+    assert_eq!(
+        loaded.get(&ROOT, "key").unwrap().unwrap().0.to_str(),
+        Some("value")
+    );
+}
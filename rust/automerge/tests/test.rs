@@ -1,10 +1,11 @@
 use automerge::marks::{ExpandMark, Mark};
 use automerge::op_tree::B;
 use automerge::patches::TextRepresentation;
-use automerge::transaction::Transactable;
+use automerge::transaction::{CommitOptions, Transactable};
 use automerge::{
-    ActorId, AutoCommit, Automerge, AutomergeError, Change, ExpandedChange, ObjId, ObjType, Patch,
-    PatchAction, PatchLog, Prop, ReadDoc, ScalarValue, SequenceTree, Value, ROOT,
+    ActorId, AutoCommit, AutoSerde, Automerge, AutomergeError, Change, ChangeHash, ConflictOrder,
+    ExpandedChange, ObjId, ObjType, Patch, PatchAction, PatchLog, Prop, ReadDoc, SaveOptions,
+    ScalarValue, SequenceTree, SharedAutomerge, UndoManager, Value, ValueRef, WriteLimits, ROOT,
 };
 use std::fs;
 
@@ -1994,3 +1995,2469 @@ fn save_with_ops_which_reference_actors_only_via_delete() {
     // This will panic if we failed to encode the referenced actor ID
     let _ = Automerge::load(&saved).unwrap();
 }
+
+#[test]
+fn list_and_map_handles() {
+    let mut doc = AutoCommit::new();
+    let list_id = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+
+    let mut list = doc.list(&list_id).unwrap();
+    assert!(list.is_empty());
+    list.push(1).unwrap();
+    list.push(3).unwrap();
+    list.insert(1, 2).unwrap();
+    let nested = list.push_object(ObjType::Map).unwrap();
+    list.delete(0).unwrap();
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.get(0).unwrap().unwrap().0, Value::int(2));
+    assert_eq!(
+        list.iter().map(|item| item.index).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+
+    let mut map = doc.map(&nested).unwrap();
+    map.put("a", "b").unwrap();
+    map.put("count", ScalarValue::counter(1)).unwrap();
+    map.increment("count", 2).unwrap();
+    assert!(map.contains_key("a").unwrap());
+    assert_eq!(map.keys().collect::<Vec<_>>(), vec!["a", "count"]);
+    assert_eq!(
+        map.entries().map(|item| item.key).collect::<Vec<_>>(),
+        vec!["a", "count"]
+    );
+    map.delete("a").unwrap();
+    assert_eq!(map.len(), 1);
+
+    assert_eq!(
+        doc.get(&nested, "count").unwrap().unwrap().0,
+        Value::counter(3)
+    );
+    assert!(matches!(
+        doc.map(&list_id),
+        Err(AutomergeError::InvalidObjType {
+            expected: ObjType::Map,
+            unexpected: ObjType::List
+        })
+    ));
+    assert!(doc.list(ROOT).is_err());
+}
+
+#[test]
+fn value_refs_carry_object_ids() {
+    fn count_scalars(doc: &AutoCommit, obj: &ObjId) -> usize {
+        doc.values(obj)
+            .map(ValueRef::from)
+            .map(|value| match value {
+                ValueRef::Object { id, .. } => count_scalars(doc, &id),
+                ValueRef::Scalar { .. } => 1,
+            })
+            .sum()
+    }
+
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, 1).unwrap();
+    let inner = doc.insert_object(&list, 1, ObjType::Map).unwrap();
+    doc.put(&inner, "a", "b").unwrap();
+    doc.put(ROOT, "c", 3).unwrap();
+    assert_eq!(count_scalars(&doc, &ROOT), 3);
+
+    let item = doc.list_range(&list, 1..).next().unwrap();
+    let value = item.value_ref();
+    assert_eq!(value.obj_id(), Some(&inner));
+    assert_eq!(value.value(), Value::map());
+
+    let item = doc
+        .map_range(ROOT, ..)
+        .find(|item| item.key == "c")
+        .unwrap();
+    let value = item.value_ref();
+    assert!(value.is_scalar());
+    assert_eq!(value.obj_id(), None);
+    assert_eq!(value.clone().into_parts(), (Value::int(3), item.id));
+}
+
+#[test]
+fn export_paths_serializes_only_requested_values() {
+    let mut doc = AutoCommit::new();
+    let config = doc.put_object(ROOT, "config", ObjType::Map).unwrap();
+    doc.put(&config, "theme", "dark").unwrap();
+    let items = doc.put_object(ROOT, "items", ObjType::List).unwrap();
+    doc.insert(&items, 0, "a").unwrap();
+    let second = doc.insert_object(&items, 1, ObjType::Map).unwrap();
+    doc.put(&second, "count", 2_u64).unwrap();
+    doc.put(ROOT, "other", "ignored").unwrap();
+
+    let paths = vec![
+        vec![Prop::from("config")],
+        vec![Prop::from("items"), Prop::from(1), Prop::from("count")],
+        vec![
+            Prop::from("config"),
+            Prop::from("theme"),
+            Prop::from("nested"),
+        ],
+        vec![Prop::from("missing")],
+    ];
+    let exported = serde_json::to_value(doc.export_paths(&paths)).unwrap();
+    assert_eq!(
+        exported,
+        serde_json::json!([{"theme": "dark"}, 2, null, null])
+    );
+
+    let whole = serde_json::to_value(doc.document().export_paths(&[vec![]])).unwrap();
+    assert_eq!(
+        whole[0],
+        serde_json::to_value(AutoSerde::from(&doc)).unwrap()
+    );
+}
+
+#[test]
+fn counter_overflow_and_clamped_increments() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "stock", ScalarValue::counter(i64::MAX - 1))
+        .unwrap();
+    doc1.put(ROOT, "name", "widget").unwrap();
+    let mut doc2 = doc1.fork();
+
+    // local increments which would overflow are rejected
+    assert_eq!(
+        doc1.increment(ROOT, "stock", 2),
+        Err(AutomergeError::CounterOverflow)
+    );
+    assert_eq!(
+        doc1.get(ROOT, "stock").unwrap().unwrap().0,
+        Value::counter(i64::MAX - 1)
+    );
+
+    // but concurrent increments always apply, wrapping on overflow
+    doc1.increment(ROOT, "stock", 1).unwrap();
+    doc2.increment(ROOT, "stock", 1).unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    assert_eq!(
+        doc1.get(ROOT, "stock").unwrap().unwrap().0,
+        Value::counter(i64::MIN)
+    );
+
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "stock", ScalarValue::counter(5)).unwrap();
+    assert_eq!(doc.inc_clamped(ROOT, "stock", -10, 0, 10), Ok(0));
+    assert_eq!(doc.inc_clamped(ROOT, "stock", 4, 0, 10), Ok(4));
+    assert_eq!(doc.inc_clamped(ROOT, "stock", i64::MAX, 0, 10), Ok(10));
+    assert_eq!(
+        doc.get(ROOT, "stock").unwrap().unwrap().0,
+        Value::counter(10)
+    );
+    assert_eq!(
+        doc.inc_clamped(ROOT, "stock", 1, 10, 0),
+        Err(AutomergeError::InvalidCounterBounds { min: 10, max: 0 })
+    );
+    doc.put(ROOT, "name", "widget").unwrap();
+    assert_eq!(
+        doc.inc_clamped(ROOT, "name", 1, 0, 10),
+        Err(AutomergeError::MissingCounter)
+    );
+}
+
+#[test]
+fn verify_change_checks_stored_change_bytes() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, &"hello world ".repeat(100))
+        .unwrap();
+    doc.commit();
+    let mut change = doc.get_last_local_change().unwrap().clone();
+
+    assert_eq!(change.checksum(), change.hash().0[..4]);
+    assert_eq!(
+        automerge::verify_change(change.raw_bytes()).unwrap(),
+        change.hash()
+    );
+    let compressed = change.bytes().into_owned();
+    assert_ne!(compressed, change.raw_bytes());
+    assert_eq!(
+        automerge::verify_change(&compressed).unwrap(),
+        change.hash()
+    );
+
+    // the checksum lives just after the four magic bytes
+    let mut corrupted = change.raw_bytes().to_vec();
+    corrupted[4] ^= 0xff;
+    assert!(matches!(
+        automerge::verify_change(&corrupted),
+        Err(automerge::LoadChangeError::BadChecksum)
+    ));
+
+    // a corrupted body no longer hashes to the stored checksum
+    let mut corrupted = change.raw_bytes().to_vec();
+    let body = corrupted.windows(5).position(|w| w == b"hello").unwrap();
+    corrupted[body] = b'j';
+    assert_eq!(corrupted[4..8], change.raw_bytes()[4..8]);
+    assert!(matches!(
+        automerge::verify_change(&corrupted),
+        Err(automerge::LoadChangeError::BadChecksum)
+    ));
+
+    assert!(matches!(
+        automerge::verify_change(&doc.save()),
+        Err(automerge::LoadChangeError::WrongChunkType)
+    ));
+}
+
+#[test]
+fn with_changes_previews_changes_without_applying_them() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "status", "draft").unwrap();
+    let mut doc2 = doc1.fork();
+    doc2.put(ROOT, "status", "published").unwrap();
+    doc2.put(ROOT, "reviewer", "bob").unwrap();
+    let heads = doc1.get_heads();
+    let changes = doc2
+        .get_changes(&heads)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let preview = doc1
+        .with_changes(changes, |view| {
+            (
+                view.get(ROOT, "status").unwrap().unwrap().0.into_owned(),
+                view.length(ROOT),
+            )
+        })
+        .unwrap();
+    assert_eq!(preview, (Value::from("published").into_owned(), 2));
+
+    assert_eq!(doc1.get_heads(), heads);
+    assert_eq!(
+        doc1.get(ROOT, "status").unwrap().unwrap().0,
+        Value::from("draft")
+    );
+    assert_eq!(doc1.length(ROOT), 1);
+}
+
+#[test]
+fn ordered_map_keeps_user_controlled_key_order() {
+    let mut doc = AutoCommit::new();
+    let columns = doc.put_ordered_map(ROOT, "columns").unwrap();
+    {
+        let mut map = doc.ordered_map(&columns).unwrap();
+        map.put("zeta", 1).unwrap();
+        map.put("alpha", 2).unwrap();
+        map.put("mid", 3).unwrap();
+        map.put("zeta", 4).unwrap();
+        assert_eq!(map.keys(), vec!["zeta", "alpha", "mid"]);
+        assert_eq!(map.len(), 3);
+
+        assert!(map.move_key("mid", 0).unwrap());
+        assert!(!map.move_key("missing", 0).unwrap());
+        map.insert(1, "beta", 5).unwrap();
+        assert_eq!(map.keys(), vec!["mid", "beta", "zeta", "alpha"]);
+        assert_eq!(
+            map.insert(5, "gamma", 6),
+            Err(AutomergeError::InvalidIndex(5))
+        );
+        assert!(!map.contains_key("gamma").unwrap());
+
+        map.delete("zeta").unwrap();
+        assert_eq!(map.keys(), vec!["mid", "beta", "alpha"]);
+        assert_eq!(map.index_of("alpha"), Some(2));
+        let values = map
+            .entries()
+            .into_iter()
+            .map(|(k, v, _)| (k, v.to_i64().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                ("mid".to_string(), 3),
+                ("beta".to_string(), 5),
+                ("alpha".to_string(), 2)
+            ]
+        );
+    }
+
+    // concurrently adding the same key doesn't duplicate it and a concurrent move and delete
+    // doesn't resurrect the key
+    let mut doc2 = doc.fork();
+    doc.ordered_map(&columns).unwrap().put("new", 1).unwrap();
+    doc.ordered_map(&columns)
+        .unwrap()
+        .move_key("beta", 0)
+        .unwrap();
+    doc2.ordered_map(&columns).unwrap().put("new", 2).unwrap();
+    doc2.ordered_map(&columns).unwrap().delete("beta").unwrap();
+    doc.merge(&mut doc2).unwrap();
+    let keys = doc.ordered_map(&columns).unwrap().keys();
+    assert_eq!(keys, vec!["mid", "alpha", "new"]);
+
+    assert_eq!(
+        doc.ordered_map(ROOT).unwrap_err(),
+        AutomergeError::NotAnOrderedMap(ROOT)
+    );
+}
+
+#[test]
+fn table_rows() {
+    let mut doc = AutoCommit::new();
+    let table = doc.put_object(ROOT, "people", ObjType::Table).unwrap();
+    let alice = doc
+        .add_row(
+            &table,
+            vec![("name", ScalarValue::from("alice")), ("age", 30.into())],
+        )
+        .unwrap();
+    let bob = {
+        let mut people = doc.table(&table).unwrap();
+        people.add_row(vec![("name", "bob")]).unwrap()
+    };
+    assert_ne!(alice, bob);
+
+    let alice_row = doc.row(&table, &alice).unwrap().unwrap();
+    assert_eq!(
+        doc.get(&alice_row, "name").unwrap().unwrap().0,
+        Value::from("alice")
+    );
+    let mut ids = doc
+        .rows(&table)
+        .unwrap()
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    ids.sort();
+    let mut expected = vec![alice.clone(), bob.clone()];
+    expected.sort();
+    assert_eq!(ids, expected);
+    assert_eq!(doc.row(&table, "missing").unwrap(), None);
+
+    // removing a row wins over a concurrent update to it
+    let mut doc2 = doc.fork();
+    assert!(doc.table(&table).unwrap().remove_row(&alice).unwrap());
+    assert!(!doc.table(&table).unwrap().remove_row(&alice).unwrap());
+    doc2.put(&alice_row, "age", 31).unwrap();
+    doc.merge(&mut doc2).unwrap();
+    assert_eq!(doc.table(&table).unwrap().len(), 1);
+    assert_eq!(doc.row(&table, &alice).unwrap(), None);
+
+    assert!(matches!(
+        doc.rows(ROOT),
+        Err(AutomergeError::InvalidObjType { .. })
+    ));
+    assert!(doc.add_row(ROOT, vec![("name", "carol")]).is_err());
+    assert_eq!(doc.length(ROOT), 1);
+}
+
+#[test]
+fn contains_key_and_has_index() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "a", 1).unwrap();
+    doc.put(ROOT, "count", ScalarValue::counter(1)).unwrap();
+    doc.increment(ROOT, "count", 2).unwrap();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "x").unwrap();
+    doc.insert(&list, 1, "y").unwrap();
+    let heads = doc.get_heads();
+
+    assert!(doc.contains_key(ROOT, "a"));
+    assert!(doc.contains_key(ROOT, "count"));
+    assert!(!doc.contains_key(ROOT, "b"));
+    assert!(!doc.contains_key(&list, "a"));
+    assert!(doc.has_index(&list, 1));
+    assert!(!doc.has_index(&list, 2));
+    assert!(!doc.has_index(ROOT, 0));
+
+    doc.delete(ROOT, "a").unwrap();
+    doc.delete(&list, 1).unwrap();
+    assert!(!doc.contains_key(ROOT, "a"));
+    assert!(!doc.has_index(&list, 1));
+    assert!(doc.contains_key_at(ROOT, "a", &heads));
+    assert!(doc.has_index_at(&list, 1, &heads));
+
+    let doc = doc.document();
+    assert!(doc.contains_key(ROOT, "count"));
+    assert!(!doc.contains_key(ROOT, "a"));
+    assert!(doc.contains_key_at(ROOT, "a", &heads));
+    assert!(!doc.read_guard().contains_key(ROOT, "a"));
+}
+
+#[test]
+fn actor_frontier_and_changes_by_actor() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let mut doc2 = AutoCommit::new().with_actor(ActorId::from(b"bbbb"));
+    doc1.put(ROOT, "a", 1).unwrap();
+    doc1.commit();
+    doc1.put(ROOT, "b", 2).unwrap();
+    doc1.put(ROOT, "c", 3).unwrap();
+    doc1.commit();
+    doc2.merge(&mut doc1).unwrap();
+    doc2.put(ROOT, "d", 4).unwrap();
+    doc2.commit();
+    doc1.put(ROOT, "e", 5).unwrap();
+    doc1.commit();
+
+    let frontier1 = doc1.actor_frontier();
+    let a = &frontier1[&ActorId::from(b"aaaa")];
+    assert_eq!((a.seq, a.max_op), (3, 4));
+    assert_eq!(a.hash, doc1.get_heads()[0]);
+    assert!(!frontier1.contains_key(&ActorId::from(b"bbbb")));
+
+    let frontier2 = doc2.actor_frontier();
+    assert_eq!(frontier2[&ActorId::from(b"aaaa")].seq, 2);
+    assert_eq!(frontier2[&ActorId::from(b"bbbb")].seq, 1);
+
+    // ask doc1 for the changes from aaaa which doc2 is missing
+    let missing = doc1
+        .get_changes_by_actor(
+            &ActorId::from(b"aaaa"),
+            frontier2[&ActorId::from(b"aaaa")].seq,
+        )
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].seq(), 3);
+    doc2.apply_changes(missing).unwrap();
+    assert_eq!(doc2.actor_frontier()[&ActorId::from(b"aaaa")], *a);
+
+    assert_eq!(
+        doc1.get_changes_by_actor(&ActorId::from(b"aaaa"), 0).len(),
+        3
+    );
+    assert!(doc1
+        .get_changes_by_actor(&ActorId::from(b"cccc"), 0)
+        .is_empty());
+}
+
+#[test]
+fn ancestry_queries() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    let base = doc1.commit().unwrap();
+    let mut doc2 = doc1.fork();
+    doc1.put(ROOT, "b", 2).unwrap();
+    let left = doc1.commit().unwrap();
+    doc2.put(ROOT, "c", 3).unwrap();
+    let right = doc2.commit().unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    assert!(doc1.is_ancestor(&base, &left).unwrap());
+    assert!(!doc1.is_ancestor(&left, &right).unwrap());
+    assert_eq!(doc1.common_ancestors(&[left, right]).unwrap(), vec![base]);
+
+    let unknown = ChangeHash([1; 32]);
+    assert_eq!(
+        doc2.is_ancestor(&base, &left),
+        Err(AutomergeError::MissingHash(left))
+    );
+    assert_eq!(
+        doc1.common_ancestors(&[left, unknown]),
+        Err(AutomergeError::MissingHash(unknown))
+    );
+}
+
+#[test]
+fn document_summary() {
+    let mut doc = AutoCommit::new();
+    assert_eq!(doc.summary(&["title"]).last_modified, None);
+
+    doc.put(ROOT, "title", "Notes").unwrap();
+    let tags = doc.put_object(ROOT, "tags", ObjType::List).unwrap();
+    doc.insert(&tags, 0, "work").unwrap();
+    doc.put(ROOT, "body", "a long body").unwrap();
+    doc.commit_with(CommitOptions::default().with_time(100));
+    let mut doc2 = doc.fork();
+    doc2.put(ROOT, "title", "Meeting notes").unwrap();
+    doc2.commit_with(CommitOptions::default().with_time(200));
+    doc.merge(&mut doc2).unwrap();
+
+    let summary = doc.summary(&["title", "tags", "missing"]);
+    assert_eq!(summary.heads, doc.get_heads());
+    assert_eq!(summary.last_modified, Some(200));
+    assert_eq!(summary.actors, 2);
+    assert_eq!(summary.changes, 2);
+    assert_eq!(summary.root.len(), 2);
+    assert_eq!(
+        summary.root["title"],
+        automerge::hydrate::Value::from("Meeting notes")
+    );
+    assert_eq!(
+        summary.root["tags"],
+        automerge::hydrate::Value::from(vec![automerge::hydrate::Value::from("work")])
+    );
+}
+
+#[test]
+fn conflicts_can_be_read_with_a_custom_order() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let mut doc2 = AutoCommit::new().with_actor(ActorId::from(b"bbbb"));
+    let mut doc3 = AutoCommit::new().with_actor(ActorId::from(b"cccc"));
+    doc1.put(ROOT, "color", "red").unwrap();
+    doc2.put(ROOT, "color", "green").unwrap();
+    doc3.put(ROOT, "color", "blue").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    doc1.merge(&mut doc3).unwrap();
+    doc1.put(ROOT, "single", "only").unwrap();
+
+    let value = |order: &ConflictOrder| {
+        doc1.get_ordered(ROOT, "color", order)
+            .unwrap()
+            .unwrap()
+            .0
+            .into_owned()
+    };
+    assert_eq!(
+        value(&ConflictOrder::OpId),
+        doc1.get(ROOT, "color").unwrap().unwrap().0
+    );
+    assert_eq!(value(&ConflictOrder::OpId), Value::from("blue"));
+    assert_eq!(
+        value(&ConflictOrder::PreferActor(ActorId::from(b"aaaa"))),
+        Value::from("red")
+    );
+    assert_eq!(
+        value(&ConflictOrder::PreferActor(ActorId::from(b"dddd"))),
+        Value::from("blue")
+    );
+    assert_eq!(
+        value(&ConflictOrder::custom(|a, b| b.cmp(a))),
+        Value::from("red")
+    );
+
+    let all = doc1
+        .get_all_ordered(
+            ROOT,
+            "color",
+            &ConflictOrder::PreferActor(ActorId::from(b"bbbb")),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|(v, _)| v.into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        all,
+        vec![
+            Value::from("red"),
+            Value::from("blue"),
+            Value::from("green")
+        ]
+    );
+    assert_eq!(
+        doc1.get_ordered(ROOT, "single", &ConflictOrder::custom(|a, b| b.cmp(a)))
+            .unwrap()
+            .unwrap()
+            .0,
+        Value::from("only")
+    );
+    assert_eq!(
+        doc1.get_ordered(ROOT, "missing", &ConflictOrder::default())
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn splice_objects_creates_objects_in_a_list() {
+    let mut doc = AutoCommit::new();
+    let rows = doc.put_object(ROOT, "rows", ObjType::List).unwrap();
+    doc.splice(&rows, 0, 0, vec!["a".into(), "b".into(), "c".into()])
+        .unwrap();
+
+    let ids = doc
+        .splice_objects(&rows, 1, 1, vec![ObjType::Map, ObjType::Map, ObjType::List])
+        .unwrap();
+    assert_eq!(ids.len(), 3);
+    for (i, id) in ids.iter().take(2).enumerate() {
+        doc.put(id, "row", i as u64).unwrap();
+    }
+    assert_eq!(doc.length(&rows), 5);
+    assert_eq!(doc.get(&rows, 0).unwrap().unwrap().0, Value::from("a"));
+    for (i, id) in ids.iter().enumerate() {
+        assert_eq!(&doc.get(&rows, i + 1).unwrap().unwrap().1, id);
+    }
+    assert_eq!(doc.object_type(&ids[2]).unwrap(), ObjType::List);
+    assert_eq!(doc.get(&rows, 4).unwrap().unwrap().0, Value::from("c"));
+    assert_eq!(
+        doc.get(&ids[1], "row").unwrap().unwrap().0,
+        Value::from(1_u64)
+    );
+
+    // a negative delete removes elements before `pos`
+    doc.update_diff_cursor();
+    let ids = doc
+        .splice_objects(&rows, 2, -2, vec![ObjType::Text, ObjType::Map])
+        .unwrap();
+    assert_eq!(doc.length(&rows), 5);
+    assert_eq!(doc.get(&rows, 0).unwrap().unwrap().1, ids[0]);
+    assert_eq!(doc.get(&rows, 1).unwrap().unwrap().1, ids[1]);
+    let inserted = doc
+        .diff_incremental()
+        .into_iter()
+        .find_map(|p| match p.action {
+            PatchAction::Insert { index, values, .. } => Some((index, values)),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(inserted.0, 0);
+    assert_eq!(
+        inserted.1.iter().map(|v| v.1.clone()).collect::<Vec<_>>(),
+        ids
+    );
+
+    assert_eq!(
+        doc.splice_objects(ROOT, 0, 0, vec![ObjType::Map]),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    );
+}
+
+#[test]
+fn splice_patches_have_the_ids_of_the_inserted_elements() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.update_diff_cursor();
+    doc.splice(&list, 0, 0, vec!["a".into(), "b".into()])
+        .unwrap();
+    let ids = doc
+        .list_range(&list, ..)
+        .map(|item| item.id)
+        .collect::<Vec<_>>();
+    let patches = doc.diff_incremental();
+    match &patches[0].action {
+        PatchAction::Insert { values, .. } => {
+            assert_eq!(values.iter().map(|v| v.1.clone()).collect::<Vec<_>>(), ids)
+        }
+        other => panic!("unexpected patch {:?}", other),
+    }
+}
+
+#[test]
+fn conflicts_lists_every_conflicted_prop() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from([1]));
+    let map = doc1.put_object(ROOT, "map", ObjType::Map).unwrap();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    let deleted = doc1.put_object(ROOT, "deleted", ObjType::Map).unwrap();
+    doc1.put(&map, "a", 1).unwrap();
+    doc1.splice(&list, 0, 0, vec![1.into(), 2.into(), 3.into()])
+        .unwrap();
+    assert_eq!(doc1.conflicts().count(), 0);
+
+    let mut doc2 = doc1.fork().with_actor(ActorId::from([2]));
+    doc1.put(ROOT, "title", "one").unwrap();
+    doc1.put(&map, "a", 2).unwrap();
+    doc1.put(&list, 2, 4).unwrap();
+    doc1.put(&deleted, "x", 1).unwrap();
+    doc2.put(ROOT, "title", "two").unwrap();
+    doc2.put(&map, "a", 3).unwrap();
+    doc2.put(&map, "b", 3).unwrap();
+    doc2.put(&list, 2, 5).unwrap();
+    doc2.put(&deleted, "x", 2).unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    doc1.delete(ROOT, "deleted").unwrap();
+
+    let list_ids = doc1
+        .get_all(&list, 2)
+        .unwrap()
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect::<Vec<_>>();
+    let conflicts = doc1.conflicts().collect::<Vec<_>>();
+    assert_eq!(
+        conflicts
+            .iter()
+            .map(|(obj, prop, values)| (obj.clone(), prop.clone(), values.len()))
+            .collect::<Vec<_>>(),
+        vec![
+            (ROOT, Prop::from("title"), 2),
+            (map.clone(), Prop::from("a"), 2),
+            (list.clone(), Prop::from(2), 2),
+        ]
+    );
+    assert_eq!(
+        conflicts[1]
+            .2
+            .iter()
+            .map(|(v, _)| v.clone())
+            .collect::<Vec<_>>(),
+        vec![Value::int(2), Value::int(3)]
+    );
+    assert_eq!(
+        conflicts[2]
+            .2
+            .iter()
+            .map(|(_, id)| id.clone())
+            .collect::<Vec<_>>(),
+        list_ids
+    );
+
+    doc1.put(&map, "a", 4).unwrap();
+    assert_eq!(doc1.conflicts().count(), 2);
+}
+
+#[test]
+fn timestamp_source_is_used_for_local_changes() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "a", 1).unwrap();
+    doc.commit();
+    assert_eq!(doc.get_last_local_change().unwrap().timestamp(), 0);
+
+    let now = std::sync::Arc::new(std::sync::atomic::AtomicI64::new(1000));
+    let clock = now.clone();
+    doc.set_timestamp_source(move || clock.fetch_add(1, std::sync::atomic::Ordering::SeqCst));
+    doc.put(ROOT, "a", 2).unwrap();
+    doc.commit();
+    assert_eq!(doc.get_last_local_change().unwrap().timestamp(), 1000);
+
+    // an explicit time takes precedence
+    doc.put(ROOT, "a", 3).unwrap();
+    doc.commit_with(CommitOptions::default().with_time(5));
+    assert_eq!(doc.get_last_local_change().unwrap().timestamp(), 5);
+
+    // the source is shared with forks
+    let mut fork = doc.fork();
+    fork.put(ROOT, "a", 4).unwrap();
+    fork.commit();
+    assert_eq!(fork.get_last_local_change().unwrap().timestamp(), 1001);
+
+    doc.clear_timestamp_source();
+    doc.put(ROOT, "a", 5).unwrap();
+    doc.commit();
+    assert_eq!(doc.get_last_local_change().unwrap().timestamp(), 0);
+}
+
+#[test]
+fn timestamp_source_is_kept_when_loading_into_an_empty_document() {
+    let mut source = AutoCommit::new();
+    source.put(ROOT, "a", 1).unwrap();
+    let bytes = source.save();
+
+    let mut doc = AutoCommit::new();
+    doc.set_timestamp_source(|| 1000);
+    doc.load_incremental(&bytes).unwrap();
+    doc.put(ROOT, "a", 2).unwrap();
+    doc.commit();
+    assert_eq!(doc.get_last_local_change().unwrap().timestamp(), 1000);
+}
+
+#[test]
+fn map_range_yields_only_keys_in_range() {
+    fn collect<R: std::ops::RangeBounds<String>>(
+        items: automerge::iter::MapRange<'_, R>,
+    ) -> Vec<(&str, i64)> {
+        items
+            .map(|item| (item.key, item.value.to_i64().unwrap()))
+            .collect()
+    }
+
+    let mut doc = AutoCommit::new();
+    for key in ["apple", "banana", "cherry", "date", "elderberry"] {
+        doc.put(ROOT, key, key.len() as i64).unwrap();
+    }
+    doc.delete(ROOT, "cherry").unwrap();
+    let heads = doc.get_heads();
+    doc.put(ROOT, "cherry", 1).unwrap();
+
+    assert_eq!(
+        collect(doc.map_range(ROOT, "banana".to_string().."date".to_string())),
+        vec![("banana", 6), ("cherry", 1)]
+    );
+    assert_eq!(
+        collect(doc.map_range(ROOT, "b".to_string()..="date".to_string())),
+        vec![("banana", 6), ("cherry", 1), ("date", 4)]
+    );
+    assert_eq!(
+        collect(doc.map_range(ROOT, ..="b".to_string())),
+        vec![("apple", 5)]
+    );
+    assert_eq!(
+        collect(doc.map_range_at(ROOT, "c".to_string().., &heads)),
+        vec![("date", 4), ("elderberry", 10)]
+    );
+}
+
+#[test]
+fn list_range_yields_only_indexes_in_range() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.splice(&list, 0, 0, (0..10).map(ScalarValue::from))
+        .unwrap();
+    doc.delete(&list, 2).unwrap();
+
+    let items = |range: std::ops::Range<usize>, doc: &AutoCommit| {
+        doc.list_range(&list, range)
+            .map(|item| (item.index, item.value.to_i64().unwrap()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(items(1..4, &doc), vec![(1, 1), (2, 3), (3, 4)]);
+    assert_eq!(items(8..20, &doc), vec![(8, 9)]);
+    assert_eq!(doc.list_range(&list, ..=1).count(), 2);
+    assert_eq!(doc.list_range(&list, ..).count(), 9);
+
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+    let chars = doc
+        .list_range(&text, 1..3)
+        .map(|item| item.value.to_str().unwrap().to_string())
+        .collect::<String>();
+    assert_eq!(chars, "el");
+}
+
+#[test]
+fn drafts_can_be_saved_and_restored() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.commit();
+    let saved = doc.save();
+    assert_eq!(doc.save_draft(), None);
+
+    doc.splice_text(&text, 0, 0, "unsaved").unwrap();
+    doc.put(ROOT, "title", "draft").unwrap();
+    let draft = doc.save_draft().unwrap();
+
+    let mut restored = AutoCommit::load(&saved).unwrap();
+    assert_eq!(restored.load_draft(&draft).unwrap(), 8);
+    assert_eq!(restored.get_actor(), doc.get_actor());
+    assert_eq!(restored.pending_ops(), 8);
+    assert_eq!(restored.text(&text).unwrap(), "unsaved");
+    assert_eq!(
+        restored.get(ROOT, "title").unwrap().unwrap().0,
+        Value::from("draft")
+    );
+
+    // the draft can be continued and committed as if it had never been saved
+    restored.splice_text(&text, 7, 0, "!").unwrap();
+    doc.splice_text(&text, 7, 0, "!").unwrap();
+    assert_eq!(restored.commit(), doc.commit());
+
+    // or rolled back
+    let mut restored = AutoCommit::load(&saved).unwrap();
+    restored.load_draft(&draft).unwrap();
+    assert_eq!(restored.rollback(), 8);
+    assert_eq!(restored.text(&text).unwrap(), "");
+
+    // a draft can only be restored onto the state it was saved from
+    let actor = restored.get_actor().clone();
+    restored.put(ROOT, "title", "other").unwrap();
+    assert_eq!(
+        restored.load_draft(&draft),
+        Err(AutomergeError::InvalidDraft)
+    );
+    assert_eq!(restored.get_actor(), &actor);
+    assert_eq!(restored.pending_ops(), 0);
+}
+
+#[test]
+fn change_membership() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    let first = doc1.commit().unwrap();
+    let mut doc2 = doc1.fork();
+    doc2.put(ROOT, "b", 2).unwrap();
+    let second = doc2.commit().unwrap();
+    doc2.put(ROOT, "c", 3).unwrap();
+    let third = doc2.commit().unwrap();
+
+    assert!(doc1.has_change(&first));
+    assert!(!doc1.has_change(&second));
+    assert_eq!(
+        doc1.filter_unknown_hashes(vec![third, first, second]),
+        vec![third, second]
+    );
+
+    // changes waiting for their dependencies are not known
+    let change = doc2.get_change_by_hash(&third).unwrap().clone();
+    doc1.apply_changes(vec![change]).unwrap();
+    assert!(!doc1.has_change(&third));
+    doc1.merge(&mut doc2).unwrap();
+    assert!(doc1.filter_unknown_hashes(doc2.get_heads()).is_empty());
+}
+
+#[test]
+fn reading_a_historical_version_through_read_doc() {
+    fn describe<R: ReadDoc>(doc: &R, text: &ObjId) -> (Vec<String>, String, usize) {
+        (
+            doc.keys(ROOT).collect(),
+            doc.text(text).unwrap(),
+            doc.length(text),
+        )
+    }
+
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+    let heads = doc.get_heads();
+    doc.put(ROOT, "title", "greeting").unwrap();
+    doc.splice_text(&text, 5, 0, " world").unwrap();
+
+    let old = describe(&doc.at(&heads).unwrap(), &text);
+    assert_eq!(old, (vec!["text".to_string()], "hello".to_string(), 5));
+    assert_eq!(
+        describe(doc.document(), &text),
+        (
+            vec!["text".to_string(), "title".to_string()],
+            "hello world".to_string(),
+            11
+        )
+    );
+    assert_eq!(describe(&doc.document().at(&heads).unwrap(), &text), old);
+
+    let missing = ChangeHash([0; 32]);
+    assert_eq!(
+        doc.at(&[missing]).err(),
+        Some(AutomergeError::MissingHash(missing))
+    );
+}
+
+#[test]
+fn commit_options_can_set_extra_bytes() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "a", 1).unwrap();
+    let hash = doc
+        .commit_with(
+            CommitOptions::default()
+                .with_message("audit")
+                .with_time(10)
+                .with_extra_bytes(b"user=alice".to_vec()),
+        )
+        .unwrap();
+    let change = doc.get_change_by_hash(&hash).unwrap();
+    assert_eq!(change.message().map(String::as_str), Some("audit"));
+    assert_eq!(change.timestamp(), 10);
+    assert_eq!(change.extra_bytes(), b"user=alice");
+
+    let mut tx_doc = Automerge::new();
+    let mut tx = tx_doc.transaction();
+    tx.put(ROOT, "b", 2).unwrap();
+    let mut options = CommitOptions::default();
+    options.set_extra_bytes(vec![1, 2, 3]);
+    let (hash, _) = tx.commit_with(options);
+    assert_eq!(
+        tx_doc
+            .get_change_by_hash(&hash.unwrap())
+            .unwrap()
+            .extra_bytes(),
+        &[1, 2, 3]
+    );
+
+    let heads = doc.get_heads();
+    let loaded = AutoCommit::load(&doc.save()).unwrap();
+    assert_eq!(
+        loaded.get_change_by_hash(&heads[0]).unwrap().extra_bytes(),
+        b"user=alice"
+    );
+}
+
+#[test]
+fn frozen_documents_reject_changes() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.put(ROOT, "count", ScalarValue::counter(1)).unwrap();
+    let mut other = doc.fork();
+    other.put(ROOT, "other", 1).unwrap();
+
+    // pending operations are committed when the document is frozen
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+    doc.freeze();
+    assert!(doc.is_frozen());
+    assert_eq!(doc.pending_ops(), 0);
+    let heads = doc.get_heads();
+
+    assert_eq!(doc.put(ROOT, "a", 1), Err(AutomergeError::Frozen));
+    assert_eq!(
+        doc.put_object(ROOT, "a", ObjType::Map).err(),
+        Some(AutomergeError::Frozen)
+    );
+    assert_eq!(doc.insert(&list, 0, 1), Err(AutomergeError::Frozen));
+    assert_eq!(doc.increment(ROOT, "count", 1), Err(AutomergeError::Frozen));
+    assert_eq!(doc.delete(ROOT, "count"), Err(AutomergeError::Frozen));
+    assert_eq!(
+        doc.splice_text(&text, 0, 0, "oh "),
+        Err(AutomergeError::Frozen)
+    );
+    assert_eq!(doc.merge(&mut other).err(), Some(AutomergeError::Frozen));
+    assert_eq!(
+        doc.load_incremental(&other.save()).err(),
+        Some(AutomergeError::Frozen)
+    );
+    assert_eq!(doc.get_heads(), heads);
+    assert_eq!(doc.text(&text).unwrap(), "hello");
+
+    // forks are not frozen
+    let mut fork = doc.fork();
+    assert!(!fork.is_frozen());
+    fork.put(ROOT, "a", 1).unwrap();
+
+    doc.thaw();
+    doc.merge(&mut other).unwrap();
+    doc.put(ROOT, "a", 1).unwrap();
+    assert_eq!(doc.get(ROOT, "other").unwrap().unwrap().0, Value::int(1));
+}
+
+#[test]
+fn ordering_heads_and_changes() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from([1]));
+    doc1.put(ROOT, "a", 1).unwrap();
+    let first = doc1.commit().unwrap();
+    let mut doc2 = doc1.fork().with_actor(ActorId::from([2]));
+    doc1.put(ROOT, "b", 1).unwrap();
+    let left = doc1.commit().unwrap();
+    doc2.put(ROOT, "b", 2).unwrap();
+    let right = doc2.commit().unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    let heads = doc1.get_heads();
+    assert_eq!(Automerge::order_heads(&[right, left, right]), heads);
+    assert_eq!(Automerge::order_heads(&[left, right]), heads);
+
+    use std::cmp::Ordering;
+    assert_eq!(doc1.cmp_changes(&first, &left).unwrap(), Ordering::Less);
+    // concurrent changes with the same max op are ordered by actor
+    assert_eq!(doc1.cmp_changes(&right, &left).unwrap(), Ordering::Greater);
+    assert_eq!(doc1.cmp_changes(&left, &left).unwrap(), Ordering::Equal);
+    // an empty change has the max op of its dependencies, so it can be ordered before them
+    let mut doc0 = doc1.fork().with_actor(ActorId::from([0]));
+    let empty = doc0.empty_change(CommitOptions::default());
+    assert_eq!(doc0.cmp_changes(&empty, &left).unwrap(), Ordering::Less);
+    let missing = ChangeHash([0; 32]);
+    assert_eq!(
+        doc1.cmp_changes(&missing, &left),
+        Err(AutomergeError::MissingHash(missing))
+    );
+}
+
+#[test]
+fn save_to_writer() {
+    struct FailingWriter;
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "disk full"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+
+    let mut saved = Vec::new();
+    let written = doc.save_to_writer(&mut saved).unwrap();
+    assert_eq!(written, saved.len());
+    assert_eq!(saved, doc.save());
+
+    doc.splice_text(&text, 5, 0, " world").unwrap();
+    assert!(doc.save_incremental_to_writer(&mut FailingWriter).is_err());
+    // the failed write did not count as a save
+    let mut incremental = Vec::new();
+    doc.save_incremental_to_writer(&mut incremental).unwrap();
+    assert!(!incremental.is_empty());
+    let mut nothing = Vec::new();
+    assert_eq!(doc.save_incremental_to_writer(&mut nothing).unwrap(), 0);
+
+    let mut loaded = AutoCommit::load(&saved).unwrap();
+    loaded.load_incremental(&incremental).unwrap();
+    assert_eq!(loaded.text(&text).unwrap(), "hello world");
+}
+
+#[test]
+fn save_incremental_once_enough_has_changed() {
+    let mut doc = AutoCommit::new();
+    doc.save();
+    assert_eq!(doc.unsaved_bytes_estimate(), 0);
+
+    doc.put(ROOT, "a", 1).unwrap();
+    // the open transaction is not counted
+    assert_eq!(doc.unsaved_bytes_estimate(), 0);
+    doc.commit();
+    let small = doc.unsaved_bytes_estimate();
+    assert!(small > 0);
+    assert_eq!(doc.save_incremental_if_larger_than(small), None);
+
+    doc.put(ROOT, "b", "a longer string value").unwrap();
+    doc.commit();
+    let total = doc.unsaved_bytes_estimate();
+    assert!(total > small);
+    let saved = doc.save_incremental_if_larger_than(small).unwrap();
+    assert_eq!(saved.len(), total);
+    assert_eq!(doc.unsaved_bytes_estimate(), 0);
+    assert!(doc.save_incremental().is_empty());
+}
+
+#[test]
+fn get_conflicts_marks_the_winner() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let mut doc2 = AutoCommit::new().with_actor(ActorId::from(b"bbbb"));
+    doc1.put(ROOT, "color", "red").unwrap();
+    doc2.put(ROOT, "color", "green").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    doc1.put(ROOT, "single", "only").unwrap();
+
+    let conflicts = doc1.get_conflicts(ROOT, "color").unwrap();
+    assert!(conflicts.is_conflicted());
+    assert_eq!(
+        conflicts.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+        vec!["1@61616161", "1@62626262"]
+    );
+    let red = conflicts.get("1@61616161").unwrap();
+    assert_eq!(red.value, Value::from("red"));
+    assert_eq!(red.actor, ActorId::from(b"aaaa"));
+    assert!(!red.winner);
+    let winner = conflicts.winner().unwrap();
+    assert_eq!(winner.value, Value::from("green"));
+    assert!(winner.winner);
+    assert_eq!(
+        Some(&winner.id),
+        doc1.get(ROOT, "color").unwrap().map(|(_, id)| id).as_ref()
+    );
+
+    let single = doc1.get_conflicts(ROOT, "single").unwrap();
+    assert_eq!(single.len(), 1);
+    assert!(!single.is_conflicted());
+    assert!(doc1.get_conflicts(ROOT, "missing").unwrap().is_empty());
+}
+
+#[test]
+fn heads_with_metadata() {
+    let mut alice = AutoCommit::new().with_actor(ActorId::from(b"alice"));
+    alice.put(ROOT, "a", 1).unwrap();
+    alice.commit_with(CommitOptions::default().with_time(100));
+    let mut bob = alice.fork().with_actor(ActorId::from(b"bob"));
+    alice.put(ROOT, "a", 2).unwrap();
+    alice.commit_with(CommitOptions::default().with_time(200).with_message("edit"));
+    bob.put(ROOT, "b", 1).unwrap();
+    bob.commit_with(CommitOptions::default().with_time(300));
+    alice.merge(&mut bob).unwrap();
+
+    let heads = alice.heads_with_metadata();
+    assert_eq!(
+        heads.iter().map(|h| h.hash).collect::<Vec<_>>(),
+        alice.get_heads()
+    );
+    let from_alice = heads
+        .iter()
+        .find(|h| h.actor == ActorId::from(b"alice"))
+        .unwrap();
+    assert_eq!(from_alice.seq, 2);
+    assert_eq!(from_alice.timestamp, 200);
+    assert_eq!(from_alice.message.as_deref(), Some("edit"));
+    let from_bob = heads
+        .iter()
+        .find(|h| h.actor == ActorId::from(b"bob"))
+        .unwrap();
+    assert_eq!(from_bob.seq, 1);
+    assert_eq!(from_bob.timestamp, 300);
+    assert_eq!(from_bob.message, None);
+    assert!(AutoCommit::new().heads_with_metadata().is_empty());
+}
+
+#[test]
+fn undo_and_redo_local_changes() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let mut undo = UndoManager::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello world").unwrap();
+    let todos = doc.put_object(ROOT, "todos", ObjType::List).unwrap();
+    let todo = doc.insert_object(&todos, 0, ObjType::Map).unwrap();
+    doc.put(&todo, "title", "write tests").unwrap();
+    doc.put(ROOT, "count", ScalarValue::counter(1)).unwrap();
+    doc.commit();
+    assert!(!undo.can_undo());
+
+    doc.splice_text(&text, 5, 6, "").unwrap();
+    doc.splice_text(&text, 0, 0, ">").unwrap();
+    undo.commit(&mut doc).unwrap();
+    doc.delete(&todos, 0).unwrap();
+    doc.increment(ROOT, "count", 5).unwrap();
+    undo.commit(&mut doc).unwrap();
+    assert_eq!(doc.text(&text).unwrap(), ">hello");
+    assert_eq!(doc.length(&todos), 0);
+
+    undo.undo(&mut doc).unwrap().unwrap();
+    assert_eq!(doc.length(&todos), 1);
+    let (_, restored) = doc.get(&todos, 0).unwrap().unwrap();
+    assert_eq!(
+        doc.get(&restored, "title").unwrap().unwrap().0,
+        Value::from("write tests")
+    );
+    assert_eq!(
+        doc.get(ROOT, "count").unwrap().unwrap().0,
+        Value::counter(1)
+    );
+    undo.undo(&mut doc).unwrap().unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "hello world");
+    assert!(!undo.can_undo());
+    assert_eq!(undo.undo(&mut doc).unwrap(), None);
+
+    undo.redo(&mut doc).unwrap().unwrap();
+    assert_eq!(doc.text(&text).unwrap(), ">hello");
+    undo.undo(&mut doc).unwrap().unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "hello world");
+
+    // recording a new step discards the steps which could be redone
+    doc.put(ROOT, "title", "mine").unwrap();
+    undo.commit(&mut doc).unwrap();
+    assert!(!undo.can_redo());
+
+    // values which another actor has overwritten since are left alone
+    let mut other = doc.fork().with_actor(ActorId::from(b"bbbb"));
+    other.put(ROOT, "title", "theirs").unwrap();
+    other.commit();
+    doc.merge(&mut other).unwrap();
+    assert_eq!(undo.undo(&mut doc).unwrap(), None);
+    assert_eq!(
+        doc.get(ROOT, "title").unwrap().unwrap().0,
+        Value::from("theirs")
+    );
+}
+
+#[test]
+fn undo_restores_incremented_counters() {
+    let mut doc = AutoCommit::new();
+    let mut undo = UndoManager::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.put(ROOT, "deleted", ScalarValue::counter(1)).unwrap();
+    doc.put(ROOT, "overwritten", ScalarValue::counter(1))
+        .unwrap();
+    doc.insert(&list, 0, ScalarValue::counter(1)).unwrap();
+    doc.insert(&list, 1, ScalarValue::counter(1)).unwrap();
+    doc.commit();
+    doc.increment(ROOT, "deleted", 5).unwrap();
+    doc.increment(ROOT, "overwritten", 5).unwrap();
+    doc.increment(&list, 0, 5).unwrap();
+    doc.increment(&list, 1, 5).unwrap();
+    undo.commit(&mut doc).unwrap();
+
+    doc.delete(ROOT, "deleted").unwrap();
+    doc.put(ROOT, "overwritten", "text").unwrap();
+    doc.delete(&list, 0).unwrap();
+    doc.put(&list, 0, "text").unwrap();
+    undo.commit(&mut doc).unwrap();
+
+    undo.undo(&mut doc).unwrap().unwrap();
+    for loaded in [doc.clone(), AutoCommit::load(&doc.save()).unwrap()] {
+        assert_eq!(loaded.get_counter(ROOT, "deleted").unwrap(), Some(6));
+        assert_eq!(loaded.get_counter(ROOT, "overwritten").unwrap(), Some(6));
+        assert_eq!(loaded.length(&list), 2);
+        assert_eq!(loaded.get_counter(&list, 0).unwrap(), Some(6));
+        assert_eq!(loaded.get_counter(&list, 1).unwrap(), Some(6));
+    }
+
+    // the increments are still a step which can be undone
+    assert!(undo.can_undo());
+    undo.undo(&mut doc).unwrap().unwrap();
+    let loaded = AutoCommit::load(&doc.save()).unwrap();
+    assert_eq!(loaded.get_counter(ROOT, "deleted").unwrap(), Some(1));
+    assert_eq!(loaded.get_counter(&list, 1).unwrap(), Some(1));
+}
+
+#[test]
+fn incremented_counters_are_visible_at_earlier_heads() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "count", ScalarValue::counter(1)).unwrap();
+    doc.commit();
+    doc.increment(ROOT, "count", 5).unwrap();
+    doc.commit();
+    let heads = doc.get_heads();
+    doc.delete(ROOT, "count").unwrap();
+    doc.commit();
+
+    assert_eq!(
+        doc.get_at(ROOT, "count", &heads).unwrap().unwrap().0,
+        Value::counter(6)
+    );
+    assert_eq!(doc.keys_at(ROOT, &heads).count(), 1);
+    assert_eq!(doc.get(ROOT, "count").unwrap(), None);
+}
+
+#[test]
+fn write_limits_reject_local_operations() {
+    let mut doc = AutoCommit::new();
+    doc.set_write_limits(
+        WriteLimits::default()
+            .with_max_depth(2)
+            .with_max_key_len(5)
+            .with_max_string_len(10),
+    );
+
+    let outer = doc.put_object(ROOT, "outer", ObjType::Map).unwrap();
+    let inner = doc.put_object(&outer, "inner", ObjType::List).unwrap();
+    assert_eq!(
+        doc.insert_object(&inner, 0, ObjType::Map),
+        Err(AutomergeError::TooDeep { depth: 3, max: 2 })
+    );
+    assert_eq!(
+        doc.put(&outer, "toolong", 1),
+        Err(AutomergeError::KeyTooLong { len: 7, max: 5 })
+    );
+    assert_eq!(
+        doc.insert(&inner, 0, "a long string"),
+        Err(AutomergeError::StringTooLong { len: 13, max: 10 })
+    );
+    assert!(doc
+        .splice(&inner, 0, 0, ["ok".into(), "far too long".into()])
+        .is_err());
+    assert_eq!(doc.length(&inner), 0);
+    // text objects are not limited by the string length
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "a text longer than ten bytes")
+        .unwrap();
+    doc.commit();
+
+    // changes from elsewhere are not checked
+    let mut other = AutoCommit::new();
+    other
+        .put(ROOT, "a long key", "a long string value")
+        .unwrap();
+    doc.merge(&mut other).unwrap();
+    assert!(doc.get(ROOT, "a long key").unwrap().is_some());
+}
+
+#[test]
+fn write_limits_apply_after_loading_into_an_empty_document() {
+    let mut source = AutoCommit::new();
+    source.put(ROOT, "key", "value").unwrap();
+    let bytes = source.save();
+
+    let mut doc = AutoCommit::new();
+    doc.set_write_limits(WriteLimits::default().with_max_key_len(5));
+    doc.load_incremental(&bytes).unwrap();
+    assert_eq!(
+        doc.write_limits(),
+        &WriteLimits::default().with_max_key_len(5)
+    );
+    assert_eq!(
+        doc.put(ROOT, "toolong", 1),
+        Err(AutomergeError::KeyTooLong { len: 7, max: 5 })
+    );
+}
+
+#[test]
+fn materialize_whole_document() {
+    let mut doc = AutoCommit::new();
+    let config = doc.put_object(ROOT, "config", ObjType::Map).unwrap();
+    doc.put(&config, "theme", "dark").unwrap();
+    doc.put(&ROOT, "count", ScalarValue::counter(1)).unwrap();
+    let heads = doc.get_heads();
+
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, 1.5).unwrap();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+    doc.increment(&ROOT, "count", 2).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(doc.materialize()).unwrap(),
+        serde_json::json!({
+            "config": {"theme": "dark"},
+            "count": 3,
+            "list": [1.5],
+            "text": "hello",
+        })
+    );
+    assert_eq!(
+        serde_json::to_value(doc.materialize_at(&heads)).unwrap(),
+        serde_json::json!({"config": {"theme": "dark"}, "count": 1})
+    );
+}
+
+#[test]
+fn construct_document_from_json() {
+    let json = serde_json::json!({
+        "name": "config",
+        "retries": 3,
+        "ratio": 0.5,
+        "enabled": true,
+        "missing": null,
+        "servers": [{"host": "a"}, {"host": "b"}],
+    });
+    let value: automerge::hydrate::Value = serde_json::from_value(json.clone()).unwrap();
+    let mut doc = AutoCommit::from_json(&value).unwrap();
+    assert_eq!(doc.get_changes(&[]).len(), 1);
+    assert_eq!(serde_json::to_value(doc.materialize()).unwrap(), json);
+    let servers = doc.get(ROOT, "servers").unwrap().unwrap().1;
+    assert_eq!(doc.object_type(&servers).unwrap(), ObjType::List);
+
+    let mut doc = Automerge::new();
+    let mut tx = doc.transaction();
+    let nested = serde_json::from_value(serde_json::json!({"a": [1, [2]]})).unwrap();
+    tx.set_json(ROOT, "nested", &nested).unwrap();
+    tx.commit();
+    assert_eq!(
+        serde_json::to_value(doc.materialize()).unwrap(),
+        serde_json::json!({"nested": {"a": [1, [2]]}})
+    );
+
+    let list = serde_json::from_value(serde_json::json!([1])).unwrap();
+    assert!(matches!(
+        Automerge::from_json(&list),
+        Err(AutomergeError::InvalidValueType { .. })
+    ));
+
+    let mut counted = AutoCommit::new();
+    counted.put(ROOT, "count", ScalarValue::counter(1)).unwrap();
+    counted.increment(ROOT, "count", 5).unwrap();
+    let copy = Automerge::from_json(&counted.materialize()).unwrap();
+    let loaded = Automerge::load(&copy.save()).unwrap();
+    assert_eq!(loaded.get_counter(ROOT, "count").unwrap(), Some(6));
+}
+
+#[test]
+fn kv_handle_reads_and_writes_typed_values() {
+    let mut doc = AutoCommit::new();
+    let mut kv = doc.kv();
+    kv.put("theme", "dark").unwrap();
+    kv.put("retries", 3_u64).unwrap();
+    kv.put("ratio", 0.5).unwrap();
+    kv.put("enabled", true).unwrap();
+    assert_eq!(kv.len(), 4);
+    assert_eq!(kv.get::<String>("theme").unwrap(), Some("dark".to_string()));
+    assert_eq!(kv.get::<i64>("retries").unwrap(), Some(3));
+    assert_eq!(kv.get::<f64>("ratio").unwrap(), Some(0.5));
+    assert_eq!(kv.get::<bool>("enabled").unwrap(), Some(true));
+    match kv.get::<i64>("theme") {
+        Err(automerge::handles::KvError::WrongType { key, expected, .. }) => {
+            assert_eq!(key, "theme");
+            assert_eq!(expected, "an integer");
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    kv.delete("theme").unwrap();
+    assert!(!kv.contains_key("theme"));
+    assert_eq!(kv.get::<String>("theme").unwrap(), None);
+
+    doc.put_object(ROOT, "nested", ObjType::Map).unwrap();
+    assert!(doc.kv().get::<String>("nested").is_err());
+}
+
+#[test]
+fn debug_bundle_reproduces_document_and_queue() {
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "key", "value").unwrap();
+    let mut other = doc.fork();
+    other.put(&ROOT, "missing", 1).unwrap();
+    other.commit();
+    other.put(&ROOT, "queued", 2).unwrap();
+    let queued = other.get_last_local_change().unwrap().clone();
+    doc.apply_changes(vec![queued]).unwrap();
+
+    let bundle = automerge::DebugBundle::from_bytes(&doc.export_debug_bundle()).unwrap();
+    assert_eq!(bundle.stats.heads, doc.get_heads());
+    assert_eq!(bundle.stats.changes, 1);
+    assert_eq!(bundle.stats.queued, 1);
+    assert!(bundle.actors.contains(doc.get_actor()));
+    assert_eq!(bundle.patches.len(), 1);
+
+    let restored = bundle.load().unwrap();
+    assert_eq!(restored.get_heads(), doc.get_heads());
+    assert_eq!(restored.export_queue(), doc.export_queue());
+
+    assert!(automerge::DebugBundle::from_bytes(b"not a bundle").is_err());
+}
+
+#[test]
+fn typed_getters() {
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "str", "hello").unwrap();
+    doc.put(&ROOT, "int", -1).unwrap();
+    doc.put(&ROOT, "uint", 1_u64).unwrap();
+    doc.put(&ROOT, "float", 1.5).unwrap();
+    doc.put(&ROOT, "bool", true).unwrap();
+    doc.put(&ROOT, "bytes", vec![1_u8, 2]).unwrap();
+    doc.put(&ROOT, "counter", ScalarValue::counter(3)).unwrap();
+    doc.increment(&ROOT, "counter", 2).unwrap();
+    doc.put(&ROOT, "timestamp", ScalarValue::Timestamp(10))
+        .unwrap();
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "item").unwrap();
+
+    assert_eq!(
+        doc.get_string(&ROOT, "str").unwrap(),
+        Some("hello".to_string())
+    );
+    assert_eq!(doc.get_int(&ROOT, "int").unwrap(), Some(-1));
+    assert_eq!(doc.get_uint(&ROOT, "uint").unwrap(), Some(1));
+    assert_eq!(doc.get_f64(&ROOT, "float").unwrap(), Some(1.5));
+    assert_eq!(doc.get_bool(&ROOT, "bool").unwrap(), Some(true));
+    assert_eq!(doc.get_bytes(&ROOT, "bytes").unwrap(), Some(vec![1, 2]));
+    assert_eq!(doc.get_counter(&ROOT, "counter").unwrap(), Some(5));
+    assert_eq!(doc.get_timestamp(&ROOT, "timestamp").unwrap(), Some(10));
+    assert_eq!(doc.get_string(&list, 0).unwrap(), Some("item".to_string()));
+    assert_eq!(doc.get_string(&ROOT, "missing").unwrap(), None);
+
+    assert_eq!(
+        doc.get_int(&ROOT, "str"),
+        Err(AutomergeError::InvalidValueType {
+            expected: "an int".to_string(),
+            unexpected: "\"hello\"".to_string(),
+        })
+    );
+    assert!(doc.get_string(&ROOT, "list").is_err());
+    assert!(doc.get_int(&ROOT, "float").is_err());
+}
+
+#[test]
+fn op_observer_sees_local_and_remote_changes() {
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl automerge::OpObserver for Recorder {
+        fn put(&self, _obj: &ObjId, prop: &Prop, value: &Value<'_>, local: bool) {
+            let event = format!("put {} {} {}", prop, value, local);
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn insert(&self, _obj: &ObjId, index: usize, value: &Value<'_>, local: bool) {
+            let event = format!("insert {} {} {}", index, value, local);
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn delete(&self, _obj: &ObjId, prop: &Prop, local: bool) {
+            let event = format!("delete {} {}", prop, local);
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut doc = AutoCommit::new();
+    doc.set_op_observer(Recorder(events.clone()));
+    doc.put(&ROOT, "a", 1).unwrap();
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "x").unwrap();
+    assert!(events.lock().unwrap().is_empty());
+    doc.commit();
+    assert_eq!(
+        std::mem::take(&mut *events.lock().unwrap()),
+        vec!["put a 1 true", "put list list true", "insert 0 \"x\" true"]
+    );
+
+    let mut other = doc.fork();
+    other.clear_op_observer();
+    other.delete(&ROOT, "a").unwrap();
+    other.insert(&list, 1, "y").unwrap();
+    doc.merge(&mut other).unwrap();
+    assert_eq!(
+        std::mem::take(&mut *events.lock().unwrap()),
+        vec!["delete a false", "insert 1 \"y\" false"]
+    );
+
+    let mut plain = Automerge::new();
+    plain.set_op_observer(Recorder(events.clone()));
+    let mut tx = plain.transaction();
+    tx.put(&ROOT, "b", true).unwrap();
+    tx.commit();
+    assert_eq!(
+        std::mem::take(&mut *events.lock().unwrap()),
+        vec!["put b true true"]
+    );
+}
+
+#[test]
+fn load_reports_progress() {
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "a", 1).unwrap();
+    doc.commit();
+    let mut saved = doc.save();
+    for i in 0..3 {
+        doc.put(&ROOT, "b", i).unwrap();
+        doc.commit();
+        saved.extend(doc.save_incremental());
+    }
+
+    let mut reports = Vec::new();
+    let loaded = Automerge::load_with_options(
+        &saved,
+        automerge::LoadOptions::new().on_progress(|p| reports.push(p)),
+    )
+    .unwrap();
+    assert_eq!(loaded.get_heads(), doc.get_heads());
+    let counts: Vec<_> = reports
+        .iter()
+        .map(|p| (p.changes_processed, p.total_changes, p.ops_applied))
+        .collect();
+    assert_eq!(counts, vec![(1, 4, 1), (2, 4, 2), (3, 4, 3), (4, 4, 4)]);
+}
+
+#[test]
+fn iterate_decoded_ops_of_a_change() {
+    use automerge::{OpType, ResolvedKey};
+
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aa"));
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "a").unwrap();
+    doc.commit_with(CommitOptions::default().with_message("first").with_time(10));
+    doc.delete(&list, 0).unwrap();
+    doc.commit();
+
+    let first = doc.get_changes(&[]).remove(0).clone();
+    assert_eq!(first.message().map(String::as_str), Some("first"));
+    assert_eq!(first.timestamp(), 10);
+    let ops: Vec<_> = first.iter_decoded_ops().collect();
+    assert_eq!(ops.len(), 2);
+    assert_eq!(ops[0].id, list);
+    assert_eq!(ops[0].obj, ROOT);
+    assert_eq!(ops[0].key, ResolvedKey::Map("list".to_string()));
+    assert_eq!(ops[0].action, OpType::Make(ObjType::List));
+    assert_eq!(ops[1].obj, list);
+    assert_eq!(ops[1].key, ResolvedKey::Head);
+    assert!(ops[1].insert);
+    // the IDs can be used with the document
+    assert_eq!(doc.object_type(&ops[1].obj).unwrap(), ObjType::List);
+
+    let second = doc.get_last_local_change().unwrap().clone();
+    let ops: Vec<_> = second.iter_decoded_ops().collect();
+    assert_eq!(ops[0].key, ResolvedKey::Elem(ops[0].pred[0].clone()));
+    assert_eq!(ops[0].action, OpType::Delete);
+    assert_eq!(ops, doc.change_ops(&second.hash()).unwrap());
+    assert_eq!(second.deps(), &[first.hash()]);
+}
+
+#[test]
+fn history_filters_changes() {
+    let alice = ActorId::from(b"alice");
+    let bob = ActorId::from(b"bob");
+    let mut doc = AutoCommit::new().with_actor(alice.clone());
+    doc.put(&ROOT, "a", 1).unwrap();
+    doc.commit_with(
+        CommitOptions::default()
+            .with_message("fix typo")
+            .with_time(10),
+    );
+    let mut other = doc.fork().with_actor(bob.clone());
+    other.put(&ROOT, "b", 2).unwrap();
+    other.commit_with(CommitOptions::default().with_message("add b").with_time(20));
+    doc.put(&ROOT, "c", 3).unwrap();
+    doc.commit_with(
+        CommitOptions::default()
+            .with_message("fix layout")
+            .with_time(30),
+    );
+    doc.merge(&mut other).unwrap();
+
+    let all: Vec<_> = doc.history().map(|(hash, _)| hash).collect();
+    assert_eq!(all.len(), 3);
+    for (hash, change) in doc.history() {
+        assert_eq!(hash, change.hash());
+        let position = all.iter().position(|h| h == &hash).unwrap();
+        for dep in change.deps() {
+            assert!(all.iter().position(|h| h == dep).unwrap() < position);
+        }
+    }
+
+    let messages = |history: automerge::iter::History<'_>| {
+        history
+            .map(|(_, c)| c.message().cloned().unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(messages(doc.history().by_actor(&bob)), vec!["add b"]);
+    assert_eq!(
+        messages(doc.history().message_contains("fix")),
+        vec!["fix typo", "fix layout"]
+    );
+    assert_eq!(
+        messages(doc.history().in_time_range(10..30)),
+        vec!["fix typo", "add b"]
+    );
+    assert_eq!(
+        messages(
+            doc.history()
+                .by_actor(&alice)
+                .in_time_range(15..)
+                .message_contains("fix")
+        ),
+        vec!["fix layout"]
+    );
+}
+
+#[test]
+fn compact_keeps_current_state_only() {
+    use automerge::marks::{ExpandMark, Mark};
+
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    for i in 0..10 {
+        doc.insert(&list, 0, i).unwrap();
+        doc.commit();
+    }
+    for _ in 0..5 {
+        doc.delete(&list, 0).unwrap();
+        doc.commit();
+    }
+    let nested = doc.insert_object(&list, 1, ObjType::Map).unwrap();
+    doc.put(&nested, "counter", ScalarValue::counter(1))
+        .unwrap();
+    doc.increment(&nested, "counter", 4).unwrap();
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello world").unwrap();
+    doc.mark(
+        &text,
+        Mark::new("bold".into(), true, 0, 5),
+        ExpandMark::After,
+    )
+    .unwrap();
+    doc.put(&ROOT, "gone", "soon").unwrap();
+    doc.delete(&ROOT, "gone").unwrap();
+
+    let mut compacted = doc.compact().unwrap();
+    assert_eq!(compacted.get_changes(&[]).len(), 1);
+    assert_ne!(compacted.get_actor(), doc.get_actor());
+    assert_eq!(compacted.materialize(), doc.materialize());
+    let compacted_text = compacted.get(&ROOT, "text").unwrap().unwrap().1;
+    assert_eq!(
+        compacted.marks(&compacted_text).unwrap(),
+        doc.marks(&text).unwrap()
+    );
+    assert!(compacted.save().len() < doc.save().len());
+
+    let loaded = AutoCommit::load(&compacted.save()).unwrap();
+    assert_eq!(loaded.materialize(), doc.materialize());
+    let loaded_list = loaded.get(&ROOT, "list").unwrap().unwrap().1;
+    let loaded_nested = loaded.get(&loaded_list, 1).unwrap().unwrap().1;
+    assert_eq!(
+        loaded.get_counter(&loaded_nested, "counter").unwrap(),
+        Some(5)
+    );
+
+    // the compacted change must not clash with the original changes of this actor
+    let mut original = doc.fork();
+    original.merge(&mut compacted).unwrap();
+}
+
+#[test]
+fn signed_changes_are_verified_on_apply() {
+    use automerge::{Signer, Verifier};
+
+    // signs with the actor ID as the key, so a change claiming another actor's ID fails
+    struct ActorKey;
+
+    impl Signer for ActorKey {
+        fn sign(&self, actor: &ActorId, payload: &[u8]) -> Vec<u8> {
+            let key = actor.to_bytes();
+            payload
+                .iter()
+                .enumerate()
+                .fold(vec![0; 4], |mut sig, (i, b)| {
+                    sig[i % 4] ^= b ^ key[i % key.len()];
+                    sig
+                })
+        }
+    }
+
+    impl Verifier for ActorKey {
+        fn verify(&self, actor: &ActorId, payload: &[u8], signature: Option<&[u8]>) -> bool {
+            signature == Some(self.sign(actor, payload).as_slice())
+        }
+    }
+
+    let mut author = AutoCommit::new();
+    author.set_signer(ActorKey);
+    author.put(&ROOT, "a", 1).unwrap();
+    author.commit_with(CommitOptions::default().with_extra_bytes(vec![1, 2, 3]));
+    let change = author.get_last_local_change().unwrap().clone();
+    assert_eq!(change.signature().map(<[u8]>::len), Some(4));
+    assert!(change.extra_bytes().starts_with(&[1, 2, 3]));
+
+    let mut server = AutoCommit::new();
+    server.set_verifier(ActorKey);
+    server.apply_changes(vec![change.clone()]).unwrap();
+    assert_eq!(server.get_int(&ROOT, "a").unwrap(), Some(1));
+
+    // tampering with any part of the change, here the application's extra bytes, is detected
+    let mut forged = change.decode();
+    forged.hash = None;
+    forged.extra_bytes[0] = 9;
+    let forged = Change::from(forged);
+    assert_eq!(
+        server.apply_changes(vec![forged.clone()]),
+        Err(AutomergeError::InvalidSignature(forged.hash()))
+    );
+    assert!(!server.document().has_change(&forged.hash()));
+
+    let mut unsigned = AutoCommit::new();
+    unsigned.put(&ROOT, "b", 2).unwrap();
+    assert!(server.merge(&mut unsigned).is_err());
+    assert_eq!(server.get(&ROOT, "b").unwrap(), None);
+}
+
+#[test]
+#[cfg(all(feature = "utf16-indexing", not(feature = "utf8-indexing")))]
+fn text_is_indexed_by_utf16_code_units() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "a😀b").unwrap();
+    assert_eq!(doc.length(&text), 4);
+    doc.splice_text(&text, 3, 1, "c").unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "a😀c");
+}
+
+#[test]
+fn blobs_are_chunked_and_read_back() {
+    use std::io::Read;
+
+    let data: Vec<u8> = (0..200_000_u32).map(|i| (i % 251) as u8).collect();
+    let mut doc = AutoCommit::new();
+    let blob = doc.set_blob(&ROOT, "file", &data[..]).unwrap();
+    assert_eq!(doc.length(&blob), 4);
+    let empty = doc.set_blob(&ROOT, "empty", std::io::empty()).unwrap();
+    assert_eq!(doc.length(&empty), 0);
+
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    let blob = loaded.get(&ROOT, "file").unwrap().unwrap().1;
+    let mut read = Vec::new();
+    let mut reader = loaded.read_blob(&blob).unwrap();
+    let mut buf = [0; 1000];
+    loop {
+        let n = reader.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        read.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(read, data);
+
+    let mut read = Vec::new();
+    let empty = loaded.get(&ROOT, "empty").unwrap().unwrap().1;
+    loaded
+        .read_blob(&empty)
+        .unwrap()
+        .read_to_end(&mut read)
+        .unwrap();
+    assert!(read.is_empty());
+
+    assert!(loaded.read_blob(&ROOT).is_err());
+    let bad = doc.put_object(&ROOT, "bad", ObjType::List).unwrap();
+    doc.insert(&bad, 0, "not bytes").unwrap();
+    assert!(doc.read_blob(&bad).unwrap().read_to_end(&mut read).is_err());
+}
+
+#[test]
+fn load_many_change_chunks_with_a_corrupt_tail() {
+    let mut doc = AutoCommit::new();
+    let mut saved = Vec::new();
+    for i in 0..50 {
+        doc.put(&ROOT, "count", i).unwrap();
+        doc.commit();
+        saved.extend(doc.save_incremental());
+    }
+    let loaded = Automerge::load(&saved).unwrap();
+    assert_eq!(loaded.get_heads(), doc.get_heads());
+
+    doc.put(&ROOT, "count", 50).unwrap();
+    let last = doc.save_incremental();
+    saved.extend(&last[..last.len() - 1]);
+    assert!(Automerge::load(&saved).is_err());
+    assert!(Automerge::load_with_options(
+        &saved,
+        automerge::LoadOptions::new().on_partial_load(automerge::OnPartialLoad::Ignore),
+    )
+    .is_ok());
+
+    // load_incremental into a non empty document keeps the chunks before the corrupt one
+    let mut incremental = AutoCommit::new();
+    incremental.put(&ROOT, "other", true).unwrap();
+    incremental.load_incremental(&saved).unwrap();
+    assert_eq!(incremental.get_int(&ROOT, "count").unwrap(), Some(49));
+}
+
+#[test]
+fn applying_typed_runs_matches_applying_with_patches() {
+    let mut base = AutoCommit::new();
+    let text = base.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    base.splice_text(&text, 0, 0, "hello world").unwrap();
+    base.commit();
+
+    let mut doc1 = base.fork().with_actor(ActorId::from([1]));
+    let mut doc2 = base.fork().with_actor(ActorId::from([2]));
+    doc1.splice_text(&text, 5, 0, " there, typing a run")
+        .unwrap();
+    doc1.splice_text(&text, 8, 3, "").unwrap();
+    doc1.splice_text(&text, 2, 0, "XY").unwrap();
+    doc1.commit();
+    doc2.splice_text(&text, 5, 0, " concurrently typed")
+        .unwrap();
+    doc2.splice_text(&text, 0, 1, "J").unwrap();
+    doc2.commit();
+
+    let changes = doc1
+        .get_changes(&base.get_heads())
+        .into_iter()
+        .chain(doc2.get_changes(&base.get_heads()))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut fast = Automerge::load(&base.save()).unwrap();
+    fast.apply_changes(changes.clone()).unwrap();
+    let mut logged = Automerge::load(&base.save()).unwrap();
+    let mut patch_log = PatchLog::active(TextRepresentation::String);
+    logged
+        .apply_changes_log_patches(changes, &mut patch_log)
+        .unwrap();
+
+    assert_eq!(fast.text(&text).unwrap(), logged.text(&text).unwrap());
+    assert_eq!(fast.save(), logged.save());
+    doc1.merge(&mut doc2).unwrap();
+    assert_eq!(fast.text(&text).unwrap(), doc1.text(&text).unwrap());
+}
+
+#[test]
+fn document_stats() {
+    let mut doc = AutoCommit::new();
+    let empty = doc.stats();
+    assert_eq!(empty.ops, 0);
+    assert_eq!(empty.objects, 1);
+    assert_eq!(empty.tree_depth, 0);
+
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    for i in 0..1000 {
+        doc.insert(&list, i, i as i64).unwrap();
+    }
+    doc.commit();
+    let mut other = doc.fork();
+    other.put(&ROOT, "other", true).unwrap();
+    other.commit();
+    doc.merge(&mut other).unwrap();
+
+    let stats = doc.stats();
+    assert_eq!(stats.ops, 1002);
+    assert_eq!(stats.objects, 2);
+    assert_eq!(stats.actors, 2);
+    assert_eq!(stats.queued, 0);
+    assert!(stats.tree_depth > 1);
+    assert!(stats.heap.ops > 0);
+    assert!(stats.heap.op_trees > 0);
+    assert!(stats.heap.history > 0);
+    assert!(stats.heap.change_graph > 0);
+    assert_eq!(stats.heap.queue, 0);
+    assert!(stats.heap.total() > empty.heap.total());
+
+    let mut missing_deps = AutoCommit::new();
+    missing_deps
+        .apply_changes(other.get_changes(&[]).into_iter().skip(1).cloned())
+        .unwrap();
+    let stats = missing_deps.stats();
+    assert_eq!(stats.queued, 1);
+    assert!(stats.heap.queue > 0);
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn encrypted_saves_round_trip() {
+    let key = [7; 32];
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "secret", "value").unwrap();
+    let mut saved = doc.save_encrypted(&key);
+    assert!(!saved.windows(5).any(|w| w == b"value"));
+    assert!(Automerge::load(&saved).is_err());
+
+    doc.put(&ROOT, "more", 1).unwrap();
+    saved.extend(doc.save_incremental_encrypted(&key));
+    assert!(doc.save_incremental_encrypted(&key).is_empty());
+
+    let mut loaded = AutoCommit::load_encrypted(&saved, &key).unwrap();
+    assert_eq!(loaded.get_heads(), doc.get_heads());
+    assert!(matches!(
+        Automerge::load_encrypted(&saved, &[8; 32]),
+        Err(AutomergeError::Decryption)
+    ));
+    let mut tampered = saved.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    assert!(matches!(
+        Automerge::load_encrypted(&tampered, &key),
+        Err(AutomergeError::Decryption)
+    ));
+
+    let mut other = Automerge::new();
+    other.load_incremental_encrypted(&saved, &key).unwrap();
+    assert_eq!(other.get_heads(), doc.get_heads());
+}
+
+#[test]
+fn read_and_write_values_by_path() {
+    let mut doc = AutoCommit::new();
+    assert!(matches!(
+        doc.set_path("/todos/0/title", "buy milk", false),
+        Err(AutomergeError::InvalidPath(_))
+    ));
+    doc.set_path("/todos/0/title", "buy milk", true).unwrap();
+    doc.set_path("/todos/-/title", "walk dog", true).unwrap();
+    doc.set_path("todos.1.done", true, false).unwrap();
+    doc.set_path("/a~1b/c~0d", 1, true).unwrap();
+
+    let (todos, todos_id) = doc.value_path("/todos").unwrap().unwrap();
+    assert_eq!(todos, Value::Object(ObjType::List));
+    assert_eq!(doc.length(&todos_id), 2);
+    assert_eq!(
+        doc.value_path("todos.1.title").unwrap().unwrap().0,
+        Value::from("walk dog")
+    );
+    assert_eq!(
+        doc.value_path("/todos/1/done").unwrap().unwrap().0,
+        Value::from(true)
+    );
+    assert_eq!(
+        doc.get_int(doc.value_path("/a~1b").unwrap().unwrap().1, "c~d")
+            .unwrap(),
+        Some(1)
+    );
+    assert_eq!(doc.value_path("").unwrap().unwrap().1, ROOT);
+    assert!(doc.value_path("/todos/5/title").unwrap().is_none());
+    assert!(doc.value_path("/todos/0/title/x").unwrap().is_none());
+    assert!(matches!(
+        doc.value_path("/todos/first"),
+        Err(AutomergeError::InvalidPath(_))
+    ));
+    assert!(matches!(
+        doc.set_path("/todos/0/title/x", 1, true),
+        Err(AutomergeError::InvalidPath(_))
+    ));
+    assert!(matches!(
+        doc.set_path("", 1, true),
+        Err(AutomergeError::InvalidPath(_))
+    ));
+}
+
+#[test]
+fn insert_batch_into_list() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    doc.insert_batch(&list, 0, 0..10_000_i64).unwrap();
+    doc.insert_batch(&list, 1, vec!["a", "b"]).unwrap();
+    assert_eq!(doc.length(&list), 10_002);
+    assert_eq!(doc.get(&list, 0).unwrap().unwrap().0, Value::from(0_i64));
+    assert_eq!(doc.get(&list, 2).unwrap().unwrap().0, Value::from("b"));
+    assert_eq!(
+        doc.get(&list, 10_001).unwrap().unwrap().0,
+        Value::from(9_999_i64)
+    );
+    assert!(matches!(
+        doc.insert_batch(&ROOT, 0, vec![1]),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    ));
+}
+
+#[test]
+fn counter_increment_history() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from([1]));
+    doc1.put(&ROOT, "visits", ScalarValue::counter(10)).unwrap();
+    doc1.put(&ROOT, "name", "site").unwrap();
+    doc1.commit_with(CommitOptions::default().with_time(100));
+    let mut doc2 = doc1.fork().with_actor(ActorId::from([2]));
+
+    doc1.increment(&ROOT, "visits", 5).unwrap();
+    doc1.commit_with(CommitOptions::default().with_time(200));
+    doc2.increment(&ROOT, "visits", -2).unwrap();
+    doc2.commit_with(CommitOptions::default().with_time(300));
+    doc1.merge(&mut doc2).unwrap();
+
+    assert_eq!(doc1.get_counter(&ROOT, "visits").unwrap(), Some(13));
+    let increments = doc1.counter_increments(&ROOT, "visits").unwrap();
+    assert_eq!(
+        increments
+            .iter()
+            .map(|i| (i.actor.clone(), i.by, i.time))
+            .collect::<Vec<_>>(),
+        vec![(ActorId::from([1]), 5, 200), (ActorId::from([2]), -2, 300)]
+    );
+    assert_eq!(
+        doc1.hash_for_opid(&increments[1].id),
+        Some(increments[1].change)
+    );
+
+    assert!(doc1
+        .counter_increments(&ROOT, "missing")
+        .unwrap()
+        .is_empty());
+    assert!(matches!(
+        doc1.counter_increments(&ROOT, "name"),
+        Err(AutomergeError::InvalidValueType { .. })
+    ));
+}
+
+#[test]
+fn timestamps_round_trip_and_search_by_range() {
+    use std::time::{Duration, SystemTime};
+
+    let day = Duration::from_secs(24 * 60 * 60);
+    let start = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+    let before_epoch = SystemTime::UNIX_EPOCH - Duration::from_millis(1500);
+    assert_eq!(
+        ScalarValue::from(start),
+        ScalarValue::Timestamp(1_700_000_000_123)
+    );
+    assert_eq!(
+        ScalarValue::from(before_epoch),
+        ScalarValue::Timestamp(-1500)
+    );
+    assert_eq!(
+        ScalarValue::Timestamp(-1500).to_system_time(),
+        Some(before_epoch)
+    );
+    assert_eq!(ScalarValue::Int(5).to_system_time(), None);
+
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "created", start).unwrap();
+    let (created, _) = doc.get(&ROOT, "created").unwrap().unwrap();
+    assert_eq!(created.to_system_time(), Some(start));
+
+    let events = doc.put_object(&ROOT, "events", ObjType::List).unwrap();
+    doc.insert(&events, 0, start + day * 3).unwrap();
+    doc.insert(&events, 1, "not a date").unwrap();
+    doc.insert(&events, 2, start).unwrap();
+    doc.insert(&events, 3, start + day * 10).unwrap();
+    doc.insert(&events, 4, start + day).unwrap();
+
+    assert_eq!(
+        doc.timestamps_in_range(&events, start..start + day * 7)
+            .unwrap(),
+        vec![(0, start + day * 3), (2, start), (4, start + day)]
+    );
+    assert_eq!(
+        doc.timestamps_in_range(&events, start + day * 7..).unwrap(),
+        vec![(3, start + day * 10)]
+    );
+    assert!(matches!(
+        doc.timestamps_in_range(&ROOT, ..),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    ));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn timestamps_convert_to_chrono() {
+    use chrono::TimeZone;
+
+    let time = chrono::Utc.timestamp_millis_opt(1_700_000_000_123).unwrap();
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "created", time).unwrap();
+    let (created, _) = doc.get(&ROOT, "created").unwrap().unwrap();
+    assert_eq!(created, Value::timestamp(1_700_000_000_123));
+    assert_eq!(created.to_datetime(), Some(time));
+}
+
+#[test]
+fn object_id_errors_say_which_id_is_wrong() {
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "key", "value").unwrap();
+    let (_, scalar) = doc.get(&ROOT, "key").unwrap().unwrap();
+    match doc.get(&scalar, "key") {
+        Err(AutomergeError::NotAnObject(id)) => assert_eq!(id, scalar),
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    let mut other = AutoCommit::new();
+    let list = other.put_object(&ROOT, "list", ObjType::List).unwrap();
+    match doc.insert(&list, 0, 1) {
+        Err(AutomergeError::UnknownActor(actor)) => assert_eq!(&actor, other.get_actor()),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn fork_with_actor_and_merge_a_draft() {
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "title", "first").unwrap();
+    doc.commit();
+    let base = doc.get_heads();
+    doc.put(&ROOT, "title", "second").unwrap();
+    doc.commit();
+
+    let draft_actor = ActorId::from(b"draft-1".to_vec());
+    let mut draft = doc.fork_with_actor(draft_actor.clone());
+    assert_eq!(draft.get_actor(), &draft_actor);
+    assert_eq!(draft.get_heads(), doc.get_heads());
+
+    let mut historical = doc
+        .fork_at(&base)
+        .unwrap()
+        .with_actor(ActorId::from(b"draft-2".to_vec()));
+    assert_eq!(
+        historical.get_string(&ROOT, "title").unwrap(),
+        Some("first".to_string())
+    );
+    historical.put(&ROOT, "body", "from the past").unwrap();
+    historical.commit();
+    doc.merge(&mut historical).unwrap();
+    assert_eq!(
+        doc.get_string(&ROOT, "body").unwrap(),
+        Some("from the past".to_string())
+    );
+
+    let plain = Automerge::new().fork_with_actor(draft_actor.clone());
+    assert_eq!(plain.get_actor(), &draft_actor);
+}
+
+#[test]
+fn merge_preview_reports_changes_and_conflicts() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa".to_vec()));
+    doc.put(&ROOT, "title", "draft").unwrap();
+    doc.put(&ROOT, "body", "text").unwrap();
+    doc.commit();
+    let base = doc.get_heads();
+    let mut other = doc.fork().with_actor(ActorId::from(b"bbbb".to_vec()));
+
+    doc.put(&ROOT, "title", "ours").unwrap();
+    doc.commit();
+    other.put(&ROOT, "title", "theirs").unwrap();
+    other.put(&ROOT, "author", "bob").unwrap();
+    other.commit();
+
+    let ours = doc.get_heads();
+    let before = doc.save();
+    let preview = doc.merge_preview(&mut other).unwrap();
+    assert_eq!(doc.save(), before);
+    assert_eq!(doc.get_heads(), ours);
+
+    assert_eq!(preview.base, base);
+    assert_eq!(preview.incoming, other.get_heads());
+    assert_eq!(preview.ours.len(), 1);
+    assert_eq!(preview.theirs.len(), 2);
+    assert_eq!(preview.conflicts, vec![(ROOT, Prop::from("title"))]);
+    assert!(preview.has_conflicts());
+
+    let mut merged = doc.fork();
+    merged.merge(&mut other).unwrap();
+    let merged_heads = merged.get_heads();
+    let expected = merged.diff(&ours, &merged_heads);
+    assert_eq!(preview.patches, expected);
+
+    doc.merge(&mut other).unwrap();
+    let preview = doc.merge_preview(&mut other).unwrap();
+    assert!(preview.is_empty());
+    assert!(preview.patches.is_empty());
+    assert!(!preview.has_conflicts());
+
+    // a frozen document can still preview a merge
+    other.put(&ROOT, "body", "more text").unwrap();
+    other.commit();
+    doc.freeze();
+    let preview = doc.merge_preview(&mut other).unwrap();
+    assert_eq!(preview.incoming, other.get_heads());
+    assert_eq!(preview.patches.len(), 1);
+}
+
+#[test]
+fn apply_changes_atomic_applies_nothing_on_error() {
+    let actor = ActorId::from(b"aaaa".to_vec());
+    let mut first = AutoCommit::new().with_actor(actor.clone());
+    first.put(&ROOT, "key", "first").unwrap();
+    first.commit();
+    let mut second = AutoCommit::new().with_actor(actor);
+    second.put(&ROOT, "key", "second").unwrap();
+    second.commit();
+    let mut other = AutoCommit::new();
+    other.put(&ROOT, "other", 1).unwrap();
+    other.commit();
+
+    let changes = vec![
+        other.get_last_local_change().cloned().unwrap(),
+        first.get_last_local_change().cloned().unwrap(),
+        second.get_last_local_change().cloned().unwrap(),
+    ];
+
+    let mut doc = AutoCommit::new();
+    let result = doc.apply_changes_atomic(changes.clone());
+    assert!(matches!(
+        result,
+        Err(AutomergeError::DuplicateSeqNumber(1, _))
+    ));
+    assert!(doc.get_heads().is_empty());
+    assert_eq!(doc.get(&ROOT, "other").unwrap(), None);
+    assert!(doc.diff_incremental().is_empty());
+
+    let mut partial = AutoCommit::new();
+    assert!(partial.apply_changes(changes.clone()).is_err());
+    assert_eq!(partial.get_heads().len(), 2);
+
+    doc.apply_changes_atomic(changes[..2].to_vec()).unwrap();
+    assert_eq!(doc.get_heads().len(), 2);
+    assert_eq!(doc.diff_incremental().len(), 2);
+}
+
+#[test]
+fn save_with_deflate_threshold() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "a".repeat(200)).unwrap();
+    let uncompressed = doc.save_nocompress();
+    let default = doc.save();
+    let never = doc.save_with_options(SaveOptions {
+        deflate_threshold: usize::MAX,
+        ..Default::default()
+    });
+    let always = doc.save_with_options(SaveOptions {
+        deflate_threshold: 0,
+        ..Default::default()
+    });
+    // every column is smaller than the default threshold
+    assert_eq!(default, uncompressed);
+    assert_eq!(never, uncompressed);
+    assert!(always.len() < uncompressed.len());
+
+    for bytes in [default, never, always] {
+        let loaded = Automerge::load(&bytes).unwrap();
+        assert_eq!(
+            loaded.get(&list, 0).unwrap().unwrap().0.to_str(),
+            Some("a".repeat(200).as_str())
+        );
+    }
+}
+
+#[test]
+fn export_and_import_a_subtree() {
+    let mut src = AutoCommit::new();
+    let card = src.put_object(&ROOT, "card", ObjType::Map).unwrap();
+    src.put(&card, "votes", ScalarValue::counter(3)).unwrap();
+    src.increment(&card, "votes", 3).unwrap();
+    let table = src.put_object(&card, "table", ObjType::Table).unwrap();
+    src.put(&table, "row", "one").unwrap();
+    let tags = src.put_object(&card, "tags", ObjType::List).unwrap();
+    src.insert(&tags, 0, "a").unwrap();
+    src.insert(&tags, 1, "b").unwrap();
+    let title = src.put_object(&card, "title", ObjType::Text).unwrap();
+    src.splice_text(&title, 0, 0, "hello world").unwrap();
+    src.mark(
+        &title,
+        Mark::new("bold".to_string(), true, 0, 5),
+        ExpandMark::None,
+    )
+    .unwrap();
+
+    let subtree = src.export_subtree(&card).unwrap();
+    assert_eq!(subtree.obj_type(), ObjType::Map);
+
+    let mut dst = AutoCommit::new();
+    let copy = dst.import_subtree(&ROOT, "copy", &subtree).unwrap();
+    let cards = dst.put_object(&ROOT, "cards", ObjType::List).unwrap();
+    dst.insert(&cards, 0, "first").unwrap();
+    let inserted = dst.insert_subtree(&cards, 0, &subtree).unwrap();
+    assert_eq!(dst.length(&cards), 2);
+    assert_eq!(dst.get(&cards, 0).unwrap().unwrap().1, inserted);
+    assert_ne!(copy, card);
+
+    assert_eq!(
+        dst.get(&copy, "votes").unwrap().unwrap().0,
+        Value::counter(6)
+    );
+    let loaded = AutoCommit::load(&dst.save()).unwrap();
+    assert_eq!(loaded.get_counter(&copy, "votes").unwrap(), Some(6));
+    let (_, table_copy) = dst.get(&copy, "table").unwrap().unwrap();
+    assert_eq!(dst.object_type(&table_copy).unwrap(), ObjType::Table);
+    assert_ne!(table_copy, table);
+    let (_, tags_copy) = dst.get(&copy, "tags").unwrap().unwrap();
+    assert_eq!(dst.length(&tags_copy), 2);
+    let (_, title_copy) = dst.get(&copy, "title").unwrap().unwrap();
+    assert_eq!(dst.text(&title_copy).unwrap(), "hello world");
+    assert_eq!(dst.marks(&title_copy).unwrap(), src.marks(&title).unwrap());
+
+    // the copy is independent of the original
+    src.splice_text(&title, 0, 5, "goodbye").unwrap();
+    assert_eq!(dst.text(&title_copy).unwrap(), "hello world");
+    assert_eq!(dst.export_subtree(&copy).unwrap(), subtree);
+
+    assert!(matches!(
+        dst.export_subtree(&card),
+        Err(AutomergeError::UnknownActor(_))
+    ));
+}
+
+#[test]
+fn shared_document_across_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Automerge>();
+    assert_send_sync::<SharedAutomerge>();
+
+    let doc = SharedAutomerge::new(Automerge::new());
+    let writers = (0..4)
+        .map(|i| {
+            let doc = doc.clone();
+            std::thread::spawn(move || {
+                for j in 0..10 {
+                    doc.with_tx(|tx| tx.put(ROOT, format!("{}-{}", i, j), j as u64))
+                        .unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    assert_eq!(doc.with_read(|doc| doc.length(ROOT)), 40);
+
+    let mut other = Automerge::new();
+    other
+        .transact::<_, _, AutomergeError>(|tx| tx.put(ROOT, "other", true))
+        .unwrap();
+    doc.with_mut(|doc| doc.merge(&mut other)).unwrap();
+
+    let failed = doc.with_tx(|tx| {
+        tx.put(ROOT, "rolled back", 1)?;
+        Err::<(), _>(AutomergeError::InvalidPath("test".to_string()))
+    });
+    assert!(failed.is_err());
+
+    let copy = doc.clone();
+    let doc = doc.try_into_inner().unwrap_err();
+    drop(copy);
+    let doc = doc.try_into_inner().unwrap();
+    assert_eq!(doc.length(ROOT), 41);
+    assert_eq!(doc.get(ROOT, "rolled back").unwrap(), None);
+}
+
+#[cfg(feature = "query-tracing")]
+#[test]
+fn query_stats_for_list_access() {
+    let mut doc = AutoCommit::new();
+    assert_eq!(doc.last_query_stats(), None);
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    doc.splice(&list, 0, 0, (0..10_000).map(ScalarValue::from))
+        .unwrap();
+    doc.commit();
+
+    assert_eq!(doc.get(&list, 5_000).unwrap().unwrap().0, Value::int(5_000));
+    let stats = doc.last_query_stats().unwrap();
+    assert_eq!(stats.query, "Nth");
+    assert!(!stats.shortcut);
+    assert!(stats.nodes_skipped > 0);
+    assert_eq!(
+        stats.nodes_visited,
+        stats.nodes_descended + stats.nodes_skipped
+    );
+    // the index lets the search skip most of the list
+    assert!(stats.ops_visited < 1_000, "{:?}", stats);
+
+    assert_eq!(doc.fork().last_query_stats(), None);
+}
+
+#[cfg(feature = "derive-arbitrary")]
+#[test]
+fn arbitrary_changes_round_trip_and_apply() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let mut state = 1_u64;
+    let mut doc = Automerge::new();
+    doc.set_strict_validation(true);
+    for _ in 0..500 {
+        let bytes = (0..512)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (state >> 56) as u8
+            })
+            .collect::<Vec<_>>();
+        let mut u = Unstructured::new(&bytes);
+        let change = Change::arbitrary(&mut u).unwrap();
+        // op IDs in an arbitrary change can have counters which are too large to load, those
+        // changes must be rejected rather than panicking
+        let decoded = match Change::try_from(change.raw_bytes()) {
+            Ok(decoded) => decoded,
+            Err(_) => continue,
+        };
+        assert_eq!(decoded.hash(), change.hash());
+        assert_eq!(
+            ExpandedChange::from(&decoded),
+            ExpandedChange::from(&change)
+        );
+        // most arbitrary changes are invalid, with strict validation they are rejected rather than
+        // panicking
+        let _ = doc.apply_changes(vec![change]);
+    }
+}
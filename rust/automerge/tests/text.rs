@@ -1,6 +1,11 @@
+use std::ops::Bound;
 use std::str::FromStr;
 
-use automerge::{transaction::Transactable, ActorId, AutoCommit, ObjType, ReadDoc, ROOT};
+use automerge::marks::{ExpandMark, Mark};
+use automerge::{
+    transaction::Transactable, ActorId, AutoCommit, AutomergeError, ObjType, ReadDoc, ScalarValue,
+    ROOT,
+};
 use test_log::test;
 
 #[test]
@@ -41,3 +46,181 @@ fn update_text_big_ole_graphemes() {
     // should render as a family of three followed by a family of four
     assert_eq!(doc.text(&text).unwrap(), "left👨‍👩‍👧👨‍👩‍👦‍👦right");
 }
+
+#[test]
+fn text_handle() {
+    let mut doc = AutoCommit::new();
+    let text_id = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+
+    let mut text = doc.text_obj(&text_id).unwrap();
+    assert!(text.is_empty());
+    text.push_str("hello").unwrap();
+    text.splice(5, 0, " world").unwrap();
+    assert_eq!(text.len(), 11);
+    assert_eq!(text.get(6..).unwrap(), "world");
+    assert_eq!(text.get(..=4).unwrap(), "hello");
+    assert!(text.get(3..20).is_err());
+    assert!(matches!(
+        text.get(..=usize::MAX),
+        Err(AutomergeError::InvalidIndex(usize::MAX))
+    ));
+    assert!(matches!(
+        text.get((Bound::Excluded(usize::MAX), Bound::Unbounded)),
+        Err(AutomergeError::InvalidIndex(usize::MAX))
+    ));
+    assert_eq!(text.iter().count(), 11);
+
+    let cursor = text.get_cursor(6, None).unwrap();
+    text.splice(0, 0, ">> ").unwrap();
+    assert_eq!(text.get_cursor_position(&cursor, None).unwrap(), 9);
+
+    text.mark(
+        Mark::new("bold".to_string(), ScalarValue::Boolean(true), 3, 8),
+        ExpandMark::None,
+    )
+    .unwrap();
+    assert_eq!(text.marks().unwrap().len(), 1);
+    text.update(">> hello there").unwrap();
+    assert_eq!(text.text().unwrap(), ">> hello there");
+
+    assert_eq!(doc.text(&text_id).unwrap(), ">> hello there");
+    assert!(matches!(
+        doc.text_obj(&map),
+        Err(AutomergeError::InvalidObjType {
+            expected: ObjType::Text,
+            unexpected: ObjType::Map
+        })
+    ));
+}
+
+#[test]
+fn set_text_creates_text_with_initial_content() {
+    let mut doc = AutoCommit::new();
+    let content = "fn main() {\n    println!(\"héllo 🐻\");\n}\n";
+    let text = doc.set_text(ROOT, "file", content).unwrap();
+    assert_eq!(doc.object_type(&text).unwrap(), ObjType::Text);
+    assert_eq!(doc.text(&text).unwrap(), content);
+    assert_eq!(doc.get(ROOT, "file").unwrap().unwrap().1, text);
+
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "placeholder").unwrap();
+    let empty = doc.set_text(&list, 0, "").unwrap();
+    assert_eq!(doc.text(&empty).unwrap(), "");
+    assert_eq!(doc.length(&list), 1);
+
+    let heads = doc.get_heads();
+    let mut doc2 = AutoCommit::load(&doc.save()).unwrap();
+    assert_eq!(doc2.get_heads(), heads);
+    assert_eq!(doc2.text(&text).unwrap(), content);
+}
+
+#[test]
+fn cursors_are_stable_across_merges() {
+    let mut doc1 = AutoCommit::new();
+    let text = doc1.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "hello world").unwrap();
+    let mut doc2 = doc1.fork();
+
+    // a caret just before "world"
+    let cursor = doc1.get_cursor(&text, 6, None).unwrap();
+
+    doc1.splice_text(&text, 0, 0, "oh, ").unwrap();
+    doc2.splice_text(&text, 5, 0, " big").unwrap();
+    doc2.splice_text(&text, 15, 0, "!").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    assert_eq!(doc1.text(&text).unwrap(), "oh, hello big world!");
+    assert_eq!(doc1.get_cursor_position(&text, &cursor, None).unwrap(), 14);
+
+    // the position of a cursor whose element was deleted is where the element would have been
+    doc2.merge(&mut doc1).unwrap();
+    doc2.splice_text(&text, 14, 2, "").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    assert_eq!(doc1.text(&text).unwrap(), "oh, hello big rld!");
+    assert_eq!(doc1.get_cursor_position(&text, &cursor, None).unwrap(), 14);
+}
+
+#[test]
+fn spans_group_text_by_marks() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello world").unwrap();
+    let before = doc.get_heads();
+    doc.mark(
+        &text,
+        Mark::new("bold".to_string(), true, 0, 5),
+        ExpandMark::None,
+    )
+    .unwrap();
+
+    let spans = doc.spans(&text).unwrap();
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0].text, "hello");
+    assert_eq!(
+        spans[0].marks.iter().collect::<Vec<_>>(),
+        vec![("bold", &ScalarValue::Boolean(true))]
+    );
+    assert_eq!(spans[1].text, " world");
+    assert!(spans[1].marks.is_empty());
+
+    let old = doc.spans_at(&text, &before).unwrap();
+    assert_eq!(old.len(), 1);
+    assert_eq!(old[0].text, "hello world");
+    assert!(old[0].marks.is_empty());
+}
+
+#[test]
+fn text_with_conflicts() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from([1]));
+    let text = doc1.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "hello world, bye world")
+        .unwrap();
+    let mut doc2 = doc1.fork().with_actor(ActorId::from([2]));
+
+    // both replace the first "world", a character at a time on one side
+    doc1.splice_text(&text, 6, 5, "").unwrap();
+    for (i, c) in "there".chars().enumerate() {
+        doc1.splice_text(&text, 6 + i, 0, &c.to_string()).unwrap();
+        doc1.commit();
+    }
+    doc2.splice_text(&text, 6, 5, "everyone").unwrap();
+    // an edit which doesn't touch any concurrent edit
+    doc2.splice_text(&text, 16, 3, "good").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    let merged = doc1.text_with_conflicts(&text).unwrap();
+    assert_eq!(merged.text, doc1.text(&text).unwrap());
+    assert_eq!(merged.conflicts.len(), 1);
+    let conflict = &merged.conflicts[0];
+    assert_eq!(conflict.range, 6..19);
+    assert_eq!(
+        &merged.text[conflict.range.clone()],
+        conflict
+            .alternatives
+            .iter()
+            .map(|(_, text)| text.as_str())
+            .collect::<String>()
+    );
+    let mut alternatives = conflict.alternatives.clone();
+    alternatives.sort();
+    assert_eq!(
+        alternatives,
+        vec![
+            (ActorId::from([1]), "there".to_string()),
+            (ActorId::from([2]), "everyone".to_string())
+        ]
+    );
+
+    // once one side has seen the other's edits, further edits don't conflict
+    doc2.merge(&mut doc1).unwrap();
+    doc2.splice_text(&text, 6, 0, "x").unwrap();
+    let merged = doc2.text_with_conflicts(&text).unwrap();
+    assert_eq!(merged.conflicts.len(), 1);
+    assert_eq!(merged.conflicts[0].range, 7..20);
+
+    assert!(matches!(
+        doc1.text_with_conflicts(ROOT),
+        Err(AutomergeError::InvalidObjType { .. })
+    ));
+}